@@ -0,0 +1,61 @@
+//! Build a flat tree of text nodes under `<body>` with repeated pre-insertion.
+//!
+//! ```text
+//! cargo bench --bench insert
+//! ```
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use ohim::{Document, Node, browsing_context::BrowsingContext, user_agent::UserAgent};
+use wasmtime::{Engine, Store};
+
+const SIZES: [usize; 3] = [1_000, 5_000, 10_000];
+
+/// Where each new child is inserted.
+#[derive(Clone, Copy, Debug)]
+enum Position {
+    /// Before the first child.
+    Front,
+    /// Before the last child.
+    BeforeLast,
+}
+
+fn build(size: usize, position: Position) {
+    let mut user_agent = UserAgent::new(Engine::default());
+    let mut store = Store::<()>::default();
+    let (_, _, document) = BrowsingContext::new_top_browsing_context(&mut user_agent, &mut store);
+    let body = Node::from(document.body(&store).expect("document has a body"));
+    for _ in 0..size {
+        let node = text(&document, &mut store);
+        let child = match position {
+            Position::Front => body.first_child(&store),
+            Position::BeforeLast => body.last_child(&store),
+        };
+        body.pre_insert(node, child.as_ref(), &mut store)
+            .expect("failed to insert a child");
+    }
+}
+
+fn text(document: &Document, store: &mut Store<()>) -> Node {
+    document
+        .create_text_node("x".into(), store)
+        .expect("failed to create a text node")
+        .into()
+}
+
+fn insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("pre_insert");
+    group.sample_size(10);
+    for position in [Position::Front, Position::BeforeLast] {
+        for size in SIZES {
+            group.bench_with_input(
+                BenchmarkId::new(format!("{position:?}"), size),
+                &size,
+                |b, &size| b.iter(|| build(size, position)),
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, insert);
+criterion_main!(benches);
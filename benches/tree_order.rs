@@ -0,0 +1,98 @@
+//! Sort nodes scattered over a large tree in tree order, with `sort_in_tree_order` and with pairwise
+//! `tree_order_cmp` comparisons as the baseline.
+//!
+//! ```text
+//! cargo bench --bench tree_order
+//! ```
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use ohim::{
+    Document, Node, browsing_context::BrowsingContext, sort_in_tree_order, tree_order_cmp,
+    user_agent::UserAgent,
+};
+use wasmtime::{Engine, Store};
+
+/// The number of nodes in the tree.
+const TREE_SIZE: usize = 100_000;
+/// The number of children of each element.
+const FAN_OUT: usize = 10;
+const SIZES: [usize; 2] = [1_000, 10_000];
+
+/// Build a tree of `TREE_SIZE` elements under `<body>`, where each element has `FAN_OUT`
+/// children, and return its nodes in tree order.
+fn build(document: &Document, store: &mut Store<()>) -> Vec<Node> {
+    let body = Node::from(document.body(&*store).expect("document has a body"));
+    let mut nodes = Vec::with_capacity(TREE_SIZE);
+    let mut parents = std::collections::VecDeque::from([body]);
+    while nodes.len() < TREE_SIZE {
+        let parent = parents.pop_front().expect("tree has a parent");
+        for _ in 0..FAN_OUT {
+            let element = document
+                .create_element_ns(
+                    Some("http://www.w3.org/1999/xhtml".into()),
+                    "div".into(),
+                    &mut *store,
+                )
+                .expect("failed to create an element");
+            let node = Node::from(element);
+            parent.append_child(node.clone(), &mut *store);
+            parents.push_back(node.clone());
+            nodes.push(node);
+        }
+    }
+    nodes
+}
+
+/// Pick `size` nodes spread over `nodes`, in an order unrelated to tree order.
+fn scatter(nodes: &[Node], size: usize) -> Vec<Node> {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    (0..size)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            nodes[(state % nodes.len() as u64) as usize].clone()
+        })
+        .collect()
+}
+
+fn tree_order(c: &mut Criterion) {
+    let mut user_agent = UserAgent::new(Engine::default());
+    let mut store = Store::<()>::default();
+    let (_, _, document) = BrowsingContext::new_top_browsing_context(&mut user_agent, &mut store);
+    let nodes = build(&document, &mut store);
+
+    let mut group = c.benchmark_group("tree_order");
+    group.sample_size(10);
+    for size in SIZES {
+        let scattered = scatter(&nodes, size);
+        group.bench_with_input(
+            BenchmarkId::new("sort_in_tree_order", size),
+            &scattered,
+            |b, scattered| {
+                b.iter(|| {
+                    let mut nodes = scattered.clone();
+                    sort_in_tree_order(&mut nodes, &store);
+                    nodes
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("tree_order_cmp", size),
+            &scattered,
+            |b, scattered| {
+                b.iter(|| {
+                    let mut nodes = scattered.clone();
+                    nodes.sort_by(|a, b| {
+                        tree_order_cmp(a, b, &store).expect("nodes are in the same tree")
+                    });
+                    nodes
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, tree_order);
+criterion_main!(benches);
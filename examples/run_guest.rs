@@ -0,0 +1,118 @@
+//! Run a guest component in a new window.
+//!
+//! ```text
+//! cargo run --example run_guest -- <component.wasm> [entry-point]
+//! ```
+//!
+//! Without an entry point, the guest's `run` export is called if it has one, and the legacy `test`
+//! export otherwise.
+
+use anyhow::{Context, bail};
+use ohim::{
+    ohim::dom::{node, structured_clone, window},
+    user_agent::UserAgent,
+};
+use std::{env, fs, path::Path};
+
+use wasmtime::{
+    Config, Engine, Result,
+    component::{Component, HasSelf, Linker, Val},
+};
+
+const USAGE: &str = "usage: cargo run --example run_guest -- <component.wasm> [entry-point]";
+
+/// Read the guest component at `path`.
+///
+/// Generally embeddings should not be expected to convert a core module into a component
+/// programmatically, but instead language specific tooling should be used, for example in Rust
+/// `cargo component` is a good way of doing that: https://github.com/bytecodealliance/cargo-component
+///
+/// So a core module is rejected with a hint to componentize it first.
+fn read_component(path: &Path) -> Result<Vec<u8>> {
+    let bytes = fs::read(path).with_context(|| {
+        format!(
+            "failed to read {}, build a guest first as described in README.md",
+            path.display()
+        )
+    })?;
+    // The preamble is the magic number, a 2-byte version and a 2-byte layer, which is 0 for a core
+    // module and 1 for a component.
+    match bytes.get(..8) {
+        Some([0x00, b'a', b's', b'm', _, _, 0x01, 0x00]) => Ok(bytes),
+        Some([0x00, b'a', b's', b'm', _, _, 0x00, 0x00]) => bail!(
+            "{} is a core wasm module, not a component. Componentize it first, for example with \
+             `wasm-tools component new`, or build it for the wasm32-wasip2 target",
+            path.display()
+        ),
+        _ => bail!("{} is not a wasm binary", path.display()),
+    }
+}
+
+/// Choose the export to call, probing the exports of `component` if `requested` is `None`.
+fn entry_point(
+    engine: &Engine,
+    component: &Component,
+    requested: Option<String>,
+) -> Result<String> {
+    let exports: Vec<String> = component
+        .component_type()
+        .exports(engine)
+        .map(|(name, _)| name.to_string())
+        .collect();
+    let name = match requested {
+        Some(name) => name,
+        None if exports.iter().any(|export| export == "run") => "run".to_string(),
+        None => "test".to_string(),
+    };
+    if !exports.contains(&name) {
+        bail!(
+            "the component does not export `{name}`, its exports are: {}",
+            exports.join(", ")
+        );
+    }
+    Ok(name)
+}
+
+fn main() -> Result<()> {
+    let mut args = env::args().skip(1);
+    let Some(path) = args.next() else {
+        bail!(USAGE);
+    };
+    let requested = args.next();
+
+    // Create an engine with the component model enabled (disabled by default).
+    let engine = Engine::new(Config::new().wasm_component_model(true))?;
+    let mut linker = Linker::new(&engine);
+
+    // Add the host functions the guest imports.
+    wasmtime_wasi::p2::add_to_linker_sync(&mut linker)?;
+    node::add_to_linker::<_, HasSelf<_>>(&mut linker, |state| state)?;
+    window::add_to_linker::<_, HasSelf<_>>(&mut linker, |state| state)?;
+    structured_clone::add_to_linker::<_, HasSelf<_>>(&mut linker, |state| state)?;
+
+    let component = Component::new(&engine, read_component(Path::new(&path))?)?;
+    let name = entry_point(&engine, &component, requested)?;
+
+    // Open a window and instantiate the guest in it.
+    let mut user_agent = UserAgent::new(engine);
+    let window = user_agent.open_window();
+    let store = user_agent
+        .window_mut(window)
+        .context("failed to get the window")?;
+    let instance = linker.instantiate(&mut *store, &component)?;
+
+    let func = instance
+        .get_func(&mut *store, &name)
+        .with_context(|| format!("`{name}` is not a function"))?;
+    if !func.params(&*store).is_empty() {
+        bail!("`{name}` takes parameters, only entry points without parameters can be called");
+    }
+    let mut results = vec![Val::Bool(false); func.results(&*store).len()];
+    func.call(&mut *store, &[], &mut results)?;
+    func.post_return(&mut *store)?;
+    for result in results {
+        println!("`{name}` returned: {result:?}");
+    }
+
+    Ok(())
+}
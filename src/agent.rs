@@ -1,22 +1,17 @@
 //! User-Agent related types
 
-use std::{
-    collections::HashMap,
-    ops::Deref,
-    sync::{
-        Arc, LazyLock, Mutex,
-        atomic::{AtomicUsize, Ordering},
-    },
-};
+use html5ever::{Namespace, ns};
+use malloc_size_of_derive::MallocSizeOf;
 
 use crate::{
     Window, WindowProxy,
     browsing_context::{BrowsingContextID, IsolationMode},
     url::{DOMUrl, ImmutableOrigin},
+    user_agent::UserAgent,
 };
 
 /// <https://tc39.es/ecma262/#sec-agent-clusters>
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct AgentCluster {
     /// <https://html.spec.whatwg.org/multipage/#agent-cluster-cross-origin-isolation>
     pub isolation_mode: IsolationMode,
@@ -27,7 +22,7 @@ pub struct AgentCluster {
 }
 
 /// <https://tc39.es/ecma262/#sec-agents>
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct Agent {
     id: AgentID,
     _block: bool,
@@ -35,46 +30,35 @@ pub struct Agent {
 
 impl Agent {
     /// <https://html.spec.whatwg.org/multipage/#create-an-agent>
-    pub fn create(block: bool) -> AgentID {
-        let id = AgentID::default();
+    ///
+    /// The agent is kept in `user_agent`, use the returned ID to look it up.
+    pub fn create(user_agent: &mut UserAgent, block: bool) -> AgentID {
+        let id = AgentID::new();
         let agent = Self { id, _block: block };
-        RELEVANT_AGENT.lock().unwrap().insert(id, agent);
+        user_agent.agents.insert(id, agent);
         id
     }
 
+    /// Remove the agent `id` from `user_agent`.
+    ///
+    /// This is called when the browsing context group holding its agent cluster is removed.
+    pub fn destroy(user_agent: &mut UserAgent, id: AgentID) -> Option<Agent> {
+        user_agent.agents.remove(&id)
+    }
+
     /// Get the ID of `Agent`
     pub fn id(&self) -> AgentID {
         self.id
     }
 }
 
-/// <https://html.spec.whatwg.org/multipage/#relevant-agent>
-static RELEVANT_AGENT: LazyLock<Arc<Mutex<HashMap<AgentID, Agent>>>> =
-    LazyLock::new(|| Arc::new(Mutex::new(HashMap::new())));
-
-/// ID of `Agent`.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct AgentID(pub usize);
-
-impl Default for AgentID {
-    fn default() -> Self {
-        static COUNT: LazyLock<AtomicUsize> = LazyLock::new(|| AtomicUsize::new(0));
-        let id = Self(COUNT.load(Ordering::Relaxed));
-        COUNT.fetch_add(1, Ordering::Relaxed);
-        id
-    }
-}
-
-impl Deref for AgentID {
-    type Target = usize;
-
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
+define_id! {
+    /// ID of `Agent`.
+    AgentID
 }
 
 /// <https://tc39.es/ecma262/#sec-code-realms>
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct Realm {
     id: RealmID,
     _agent: AgentID,
@@ -88,13 +72,13 @@ impl Realm {
     ///
     /// # Note
     /// This returns `Realm` because there are more steps outside of this method to complete. Please
-    /// make sure to run those steps and insert the `Realm` into `RELEVANT_REALM`
+    /// make sure to run those steps and insert the `Realm` into the `UserAgent`
     pub fn create(
         agent: AgentID,
         global_object: Option<Window>,
         global_this: Option<WindowProxy>,
     ) -> Realm {
-        let id = RealmID::default();
+        let id = RealmID::new();
         Self {
             id,
             _agent: agent,
@@ -112,6 +96,7 @@ impl Realm {
     /// <https://html.spec.whatwg.org/multipage/#set-up-a-window-environment-settings-object>
     pub fn set_window_settings_object(
         mut self,
+        user_agent: &mut UserAgent,
         creation_url: DOMUrl,
         top_url: DOMUrl,
         top_origin: ImmutableOrigin,
@@ -122,88 +107,153 @@ impl Realm {
             Some(e) => (e.id, e.browsing_context),
             // 5. Otherwise, set settings object's id to a new unique opaque string, settings object's target
             // browsing context to null, and settings object's active service worker to null.
-            None => (EnvironmentID::default(), None),
+            None => (EnvironmentID::new(), None),
         };
         // 6. Set settings object's creation URL to creationURL, settings object's top-level creation URL to
         // topLevelCreationURL, and settings object's top-level origin to topLevelOrigin.
         let settings_object = Environment {
             id,
-            _creation_url: creation_url,
-            _top_url: Some(top_url),
-            _top_origin: Some(top_origin),
+            creation_url,
+            top_url: Some(top_url),
+            top_origin: Some(top_origin),
             browsing_context,
             ready: false,
         };
         // 7. Set realm's [[HostDefined]] field to settings object.
         self.settings_object = Some(settings_object);
         let id = self.id;
-        RELEVANT_REALM.lock().unwrap().insert(id, self);
+        user_agent.realms.insert(id, self);
     }
-}
-
-/// <https://html.spec.whatwg.org/multipage/#concept-relevant-realm>
-pub static RELEVANT_REALM: LazyLock<Arc<Mutex<HashMap<RealmID, Realm>>>> =
-    LazyLock::new(|| Arc::new(Mutex::new(HashMap::new())));
 
-/// ID of `Realm`.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct RealmID(pub usize);
-
-impl Default for RealmID {
-    fn default() -> Self {
-        static COUNT: LazyLock<AtomicUsize> = LazyLock::new(|| AtomicUsize::new(0));
-        let id = Self(COUNT.load(Ordering::Relaxed));
-        COUNT.fetch_add(1, Ordering::Relaxed);
-        id
+    /// Remove the realm `id` from `user_agent`.
+    ///
+    /// This is called when the document whose relevant realm it is gets discarded.
+    pub fn destroy(user_agent: &mut UserAgent, id: RealmID) -> Option<Realm> {
+        user_agent.realms.remove(&id)
     }
 }
 
-impl Deref for RealmID {
-    type Target = usize;
-
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
+define_id! {
+    /// ID of `Realm`.
+    RealmID
 }
 
 /// <https://html.spec.whatwg.org/multipage/#environment>
 #[derive(Debug)]
 pub struct Environment {
     id: EnvironmentID,
-    _creation_url: DOMUrl,
-    _top_url: Option<DOMUrl>,
-    _top_origin: Option<ImmutableOrigin>,
+    creation_url: DOMUrl,
+    top_url: Option<DOMUrl>,
+    top_origin: Option<ImmutableOrigin>,
     browsing_context: Option<BrowsingContextID>,
     pub(crate) ready: bool,
     // TODO: An active service worker
 }
 
-/// ID of `Environment`.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct EnvironmentID(pub usize);
+impl Environment {
+    /// <https://html.spec.whatwg.org/multipage/#concept-environment-id>
+    pub fn id(&self) -> EnvironmentID {
+        self.id
+    }
 
-impl Default for EnvironmentID {
-    fn default() -> Self {
-        static COUNT: LazyLock<AtomicUsize> = LazyLock::new(|| AtomicUsize::new(0));
-        let id = Self(COUNT.load(Ordering::Relaxed));
-        COUNT.fetch_add(1, Ordering::Relaxed);
-        id
+    /// <https://html.spec.whatwg.org/multipage/#concept-environment-creation-url>
+    pub fn creation_url(&self) -> &DOMUrl {
+        &self.creation_url
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#concept-environment-top-level-creation-url>
+    pub fn top_level_creation_url(&self) -> Option<&DOMUrl> {
+        self.top_url.as_ref()
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#concept-environment-top-level-origin>
+    pub fn top_level_origin(&self) -> Option<&ImmutableOrigin> {
+        self.top_origin.as_ref()
     }
-}
 
-impl Deref for EnvironmentID {
-    type Target = usize;
+    /// <https://html.spec.whatwg.org/multipage/#concept-environment-target-browsing-context>
+    pub fn target_browsing_context(&self) -> Option<BrowsingContextID> {
+        self.browsing_context
+    }
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
+    /// <https://html.spec.whatwg.org/multipage/#concept-environment-execution-ready-flag>
+    pub fn is_ready(&self) -> bool {
+        self.ready
     }
 }
 
+define_id! {
+    /// ID of `Environment`.
+    EnvironmentID
+}
+
 /// <https://infra.spec.whatwg.org/#namespaces>
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, MallocSizeOf)]
 pub enum NameSpace {
     /// <https://infra.spec.whatwg.org/#html-namespace>
     HTML,
+    /// <https://infra.spec.whatwg.org/#mathml-namespace>
+    MathML,
+    /// <https://infra.spec.whatwg.org/#svg-namespace>
+    SVG,
+    /// <https://infra.spec.whatwg.org/#xlink-namespace>
+    XLink,
+    /// <https://infra.spec.whatwg.org/#xml-namespace>
+    XML,
+    /// <https://infra.spec.whatwg.org/#xmlns-namespace>
+    XMLNS,
     /// None
     None,
 }
+
+impl NameSpace {
+    /// Get the namespace URL. `NameSpace::None` is the null namespace and returns the empty string.
+    pub fn url(&self) -> &'static str {
+        match self {
+            NameSpace::HTML => "http://www.w3.org/1999/xhtml",
+            NameSpace::MathML => "http://www.w3.org/1998/Math/MathML",
+            NameSpace::SVG => "http://www.w3.org/2000/svg",
+            NameSpace::XLink => "http://www.w3.org/1999/xlink",
+            NameSpace::XML => "http://www.w3.org/XML/1998/namespace",
+            NameSpace::XMLNS => "http://www.w3.org/2000/xmlns/",
+            NameSpace::None => "",
+        }
+    }
+
+    /// Get the namespace whose URL is `url`. The empty string is the null namespace. This returns
+    /// `None` if `url` is not one of the namespaces listed in Infra.
+    pub fn from_url(url: &str) -> Option<NameSpace> {
+        [
+            NameSpace::HTML,
+            NameSpace::MathML,
+            NameSpace::SVG,
+            NameSpace::XLink,
+            NameSpace::XML,
+            NameSpace::XMLNS,
+            NameSpace::None,
+        ]
+        .into_iter()
+        .find(|name_space| name_space.url() == url)
+    }
+}
+
+impl From<NameSpace> for Namespace {
+    fn from(name_space: NameSpace) -> Namespace {
+        match name_space {
+            NameSpace::HTML => ns!(html),
+            NameSpace::MathML => ns!(mathml),
+            NameSpace::SVG => ns!(svg),
+            NameSpace::XLink => ns!(xlink),
+            NameSpace::XML => ns!(xml),
+            NameSpace::XMLNS => ns!(xmlns),
+            NameSpace::None => ns!(),
+        }
+    }
+}
+
+/// Namespaces other than the ones listed in Infra are mapped to `NameSpace::None`.
+impl From<&Namespace> for NameSpace {
+    fn from(namespace: &Namespace) -> NameSpace {
+        NameSpace::from_url(namespace).unwrap_or(NameSpace::None)
+    }
+}
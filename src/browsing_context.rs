@@ -2,12 +2,7 @@
 
 use std::{
     collections::{HashMap, HashSet},
-    ops::Deref,
-    sync::{
-        Arc, LazyLock, Mutex,
-        atomic::{AtomicUsize, Ordering},
-    },
-    time::Instant,
+    fmt,
 };
 
 use bitflags::bitflags;
@@ -15,9 +10,13 @@ use headers::ContentType;
 use wasmtime::AsContextMut;
 
 use crate::{
-    Document, DocumentMode, Window, WindowProxy,
+    CustomElementRegistry, Document, DocumentLoadTimingInfo, DocumentMode, Element, Node, Window,
+    WindowProxy,
     agent::{Agent, AgentCluster, AgentID, Realm},
-    url::{DOMUrl, ImmutableOrigin},
+    permissions_policy::PermissionsPolicy,
+    time::{coarsen_time, unsafe_shared_current_time},
+    url::{DOMUrl, Host, ImmutableOrigin, OpaqueOrigin},
+    user_agent::UserAgent,
 };
 
 /// <https://html.spec.whatwg.org/multipage/document-sequences.html#browsing-context>
@@ -27,42 +26,96 @@ pub struct BrowsingContext {
     group: Option<BrowsingContextGroupID>,
     /// <https://html.spec.whatwg.org/multipage/#popup-sandboxing-flag-set>
     popup_flag: SandboxingFlag,
+    /// <https://html.spec.whatwg.org/multipage/#opener-browsing-context>
+    opener: Option<BrowsingContextID>,
     pub(crate) window: Option<Window>,
 }
 
-/// <https://html.spec.whatwg.org/multipage/#browsing-context-set>
-static BROWSING_CONTEXT_SET: LazyLock<Arc<Mutex<HashMap<BrowsingContextID, BrowsingContext>>>> =
-    LazyLock::new(|| Arc::new(Mutex::new(HashMap::new())));
-
 impl BrowsingContext {
     /// <https://html.spec.whatwg.org/multipage/document-sequences.html#creating-a-new-top-level-browsing-context>
-    pub fn new_top_browsing_context(store: impl AsContextMut) -> (BrowsingContextID, Document) {
+    ///
+    /// The group is kept in the user agent's browsing context group set and the browsing context in
+    /// the browsing context set. Their IDs are returned to look them up.
+    pub fn new_top_browsing_context(
+        user_agent: &mut UserAgent,
+        store: impl AsContextMut,
+    ) -> (BrowsingContextGroupID, BrowsingContextID, Document) {
         // 1. Let group and document be the result of creating a new browsing context group and document.
-        let (context, document) =
-            BrowsingContextGroup::new_browsing_context_group_and_document(store);
+        let (group, context, document) =
+            BrowsingContextGroup::new_browsing_context_group_and_document(user_agent, store);
+        // 2. Return group's browsing context set[0] and document.
         let id = context.id();
-        BROWSING_CONTEXT_SET.lock().unwrap().insert(id, context);
+        user_agent.browsing_context_set.insert(id, context);
+        (group, id, document)
+    }
 
+    /// <https://html.spec.whatwg.org/multipage/document-sequences.html#creating-a-new-auxiliary-browsing-context>
+    ///
+    /// The browsing context is kept in the user agent's browsing context set, in the browsing
+    /// context group of `opener`. Its ID is returned to look it up.
+    pub fn new_auxiliary_browsing_context(
+        user_agent: &mut UserAgent,
+        opener: BrowsingContextID,
+        store: impl AsContextMut,
+    ) -> (BrowsingContextID, Document) {
+        // 1. Let openerTopLevelBrowsingContext be opener's top-level traversable's active browsing
+        // context.
+        // Every window is a top-level traversable, so opener is its own top-level browsing context.
+        // 2. Let group be openerTopLevelBrowsingContext's group.
+        // 3. Assert: group is non-null, as navigating invokes this directly.
+        let group_id = user_agent
+            .browsing_context_set
+            .get(&opener)
+            .and_then(BrowsingContext::group)
+            .expect("opener must be in a browsing context group");
+        // The group is taken out of the set while the browsing context is created in it.
+        let mut group = user_agent
+            .browsing_context_group_set
+            .remove(&group_id)
+            .expect("browsing context group of opener must be in the set");
+        // 4. Let browsingContext and document be the result of creating a new browsing context and
+        // document with opener's active document, null, and group.
+        // TODO: The active document of opener lives in the store of its window, so it can't be the
+        // creator yet.
+        let (mut context, document) =
+            Self::new_browsing_context(user_agent, None, None, &mut group, store);
+        // 5. TODO: Set browsingContext's is popup to true.
+        // 6. Append browsingContext to group.
+        group.browsing_context.insert(context.id());
+        context.group = Some(group_id);
+        user_agent
+            .browsing_context_group_set
+            .insert(group_id, group);
+        // 7. Set browsingContext's opener browsing context to opener.
+        context.opener = Some(opener);
+        // 8. TODO: Set browsingContext's virtual browsing context group ID to
+        // openerTopLevelBrowsingContext's virtual browsing context group ID.
+        // 9. TODO: Set browsingContext's opener origin at creation to opener's active document's
+        // origin.
+        // 10. Return browsingContext and document.
+        let id = context.id();
+        user_agent.browsing_context_set.insert(id, context);
         (id, document)
     }
 
     /// <https://html.spec.whatwg.org/multipage/document-sequences.html#creating-a-new-browsing-context>
-    /// TODO: implement embedder
     pub fn new_browsing_context(
+        user_agent: &mut UserAgent,
         _creator: Option<Document>,
-        embedder: Option<bool>,
+        embedder: Option<&Element>,
         group: &mut BrowsingContextGroup,
         mut store: impl AsContextMut,
     ) -> (Self, Document) {
         // 1. Let browsingContext be a new browsing context.
         let mut context = BrowsingContext {
-            id: BrowsingContextID::default(),
+            id: BrowsingContextID::new(),
             group: None,
             popup_flag: SandboxingFlag::empty(),
+            opener: None,
             window: None,
         };
         // 2. Let unsafeContextCreationTime be the unsafe shared current time.
-        let _time = Instant::now();
+        let time = unsafe_shared_current_time();
         // 3. Let creatorOrigin be null.
         let creator_origin: Option<ImmutableOrigin> = None;
         // 4. Let creatorBaseURL be null.
@@ -71,32 +124,42 @@ impl BrowsingContext {
 
         // 6. Let sandboxFlags be the result of determining the creation sandboxing flags given browsingContext and
         // embedder.
-        let flags = context.determine_creation_sandbox_flags(&embedder);
+        let flags = context.determine_creation_sandbox_flags(embedder);
         // 7. Let origin be the result of determining the origin given about:blank, sandboxFlags, and creatorOrigin.
         let origin = determin_origin(
             Some(&DOMUrl::parse("about:blank").unwrap()),
             flags,
             creator_origin,
         );
-        // 8. TODO: Let permissionsPolicy be the result of creating a permissions policy given embedder and origin.
-        let policy = false;
+        // 8. Let permissionsPolicy be the result of creating a permissions policy given embedder and origin.
+        let policy = PermissionsPolicy::create(embedder, origin.clone(), &store);
         // 9. Let agent be the result of obtaining a similar-origin window agent given origin, group, and false.
-        let agent = group.window_agent(&origin, false);
+        let agent = group.window_agent(user_agent, &origin, false);
         // 10. Let realm execution context be the result of creating a new realm given agent and the following customizations:
-        let realm = Realm::create(
-            agent,
-            Some(Window::new(&mut store).expect("Failed to create window")),
-            Some(WindowProxy {}),
-        );
+        let window = Window::new(&mut store).expect("Failed to create window");
+        window.set_origin_keyed(group.is_origin_keyed(agent), &mut store);
+        let realm = Realm::create(agent, Some(window.clone()), Some(WindowProxy {}));
         let realm_id = realm.id();
-        // 11. Let topLevelCreationURL be about:blank if embedder is null; TODO: otherwise embedder's relevant settings
+        // The relevant settings object of embedder is the one of its node document.
+        let embedder_settings = embedder.and_then(|embedder| {
+            Node::from(embedder.clone())
+                .owner_document(&store)
+                .and_then(|document| document.relevant_settings_object(user_agent, &store))
+        });
+        // 11. Let topLevelCreationURL be about:blank if embedder is null; otherwise embedder's relevant settings
         // object's top-level creation URL.
-        let top_url = DOMUrl::parse("about:blank").unwrap();
-        // 12. Let topLevelOrigin be origin if embedder is null; TODO: otherwise embedder's relevant settings object's top-level origin.
-        let top_origin = origin.clone();
+        let top_url = embedder_settings
+            .and_then(|settings| settings.top_level_creation_url().cloned())
+            .unwrap_or_else(|| DOMUrl::parse("about:blank").unwrap());
+        // 12. Let topLevelOrigin be origin if embedder is null; otherwise embedder's relevant settings object's
+        // top-level origin.
+        let top_origin = embedder_settings
+            .and_then(|settings| settings.top_level_origin().cloned())
+            .unwrap_or_else(|| origin.clone());
         // 13. Set up a window environment settings object with about:blank, realm execution context, null,
         // topLevelCreationURL, and topLevelOrigin.
         realm.set_window_settings_object(
+            user_agent,
             DOMUrl::parse("about:blank").unwrap(),
             top_url,
             top_origin,
@@ -106,9 +169,14 @@ impl BrowsingContext {
         // 14. Let loadTimingInfo be a new document load timing info with its navigation start time set to the result
         // of calling coarsen time with unsafeContextCreationTime and the new environment settings object's
         // cross-origin isolated capability.
-        // TODO: implement load time info
-        let load_time_info = false;
+        // TODO: The cross-origin isolated capability of environment settings objects.
+        let load_time_info = DocumentLoadTimingInfo {
+            navigation_start_time: coarsen_time(time, false),
+            ..Default::default()
+        };
         // 15. Let document be a new Document
+        let registry =
+            CustomElementRegistry::new(&window, &mut store).expect("Failed to create registry");
         let document = Document::new(
             true,
             ContentType::html(),
@@ -122,10 +190,12 @@ impl BrowsingContext {
             creator_url,
             realm_id,
             true,
-            // TODO: Define CustomElementRegistry
+            Some(registry),
             &mut store,
         )
         .expect("Failed to create document");
+        // Set window's associated Document to document.
+        window.set_document(document.clone(), &mut store);
         // 16. TODO: If creator is non-null, then:
         // 18. Mark document as ready for post-load tasks.
         // XXX: Unimplemented because this is only used for printing.
@@ -135,14 +205,14 @@ impl BrowsingContext {
             .populate_hhb(&mut store)
             .expect("Failed to create Elements");
         // 20. Make active document.
-        document.active(&mut context, false, &store);
+        document.active(user_agent, &mut context, false, &store);
         // 21. TODO: Completely finish loading document.
         // 22. Return browsingContext and document.
         (context, document)
     }
 
     /// <https://html.spec.whatwg.org/multipage/browsers.html#determining-the-creation-sandboxing-flags>
-    pub fn determine_creation_sandbox_flags(&self, embedder: &Option<bool>) -> SandboxingFlag {
+    pub fn determine_creation_sandbox_flags(&self, embedder: Option<&Element>) -> SandboxingFlag {
         match embedder {
             // If embedder is null, then: the flags set on browsing context's popup sandboxing flag set.
             None => self.popup_flag,
@@ -152,135 +222,173 @@ impl BrowsingContext {
         }
     }
 
+    /// <https://html.spec.whatwg.org/multipage/#bcg-remove>
+    ///
+    /// The browsing context is also removed from the user agent's browsing context set.
+    pub fn remove(user_agent: &mut UserAgent, id: BrowsingContextID) {
+        let Some(mut context) = user_agent.browsing_context_set.remove(&id) else {
+            return;
+        };
+        // 1. Assert: browsingContext's group is non-null.
+        // 2. Let group be browsingContext's group.
+        // 3. Set browsingContext's group to null.
+        let Some(group_id) = context.group.take() else {
+            return;
+        };
+        let Some(group) = user_agent.browsing_context_group_set.get_mut(&group_id) else {
+            return;
+        };
+        // 4. Remove browsingContext from group's browsing context set.
+        group.browsing_context.remove(&id);
+        // 5. If group's browsing context set is empty, then remove group from the user agent's
+        // browsing context group set.
+        if group.browsing_context.is_empty() {
+            BrowsingContextGroup::remove(user_agent, group_id);
+        }
+    }
+
     /// Get the ID of the BrowsingContext.
     pub fn id(&self) -> BrowsingContextID {
         self.id
     }
-}
 
-/// <https://html.spec.whatwg.org/multipage/#browsing-context-group-set>
-static BROWSING_CONTEXT_GROUP_SET: LazyLock<
-    Arc<Mutex<HashMap<BrowsingContextGroupID, BrowsingContextGroup>>>,
-> = LazyLock::new(|| Arc::new(Mutex::new(HashMap::new())));
+    /// <https://html.spec.whatwg.org/multipage/#tlbc-group>
+    pub fn group(&self) -> Option<BrowsingContextGroupID> {
+        self.group
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#opener-browsing-context>
+    pub fn opener(&self) -> Option<BrowsingContextID> {
+        self.opener
+    }
+}
 
 /// <https://html.spec.whatwg.org/multipage/document-sequences.html#browsing-context-group>
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct BrowsingContextGroup {
     id: BrowsingContextGroupID,
     browsing_context: HashSet<BrowsingContextID>,
-    agent_cluster: HashMap<ImmutableOrigin, AgentCluster>,
-    historical_agent_cluster: HashMap<ImmutableOrigin, ImmutableOrigin>,
+    agent_cluster: HashMap<AgentClusterKey, AgentCluster>,
+    historical_agent_cluster: HashMap<ImmutableOrigin, AgentClusterKey>,
     isolation_mode: IsolationMode,
 }
 
 impl BrowsingContextGroup {
     /// <https://html.spec.whatwg.org/multipage/document-sequences.html#creating-a-new-browsing-context-group-and-document>
+    ///
+    /// The group is moved into the user agent's browsing context group set, so only its ID is
+    /// returned. The browsing context is returned for the caller to store it.
     pub fn new_browsing_context_group_and_document(
+        user_agent: &mut UserAgent,
         store: impl AsContextMut,
-    ) -> (BrowsingContext, Document) {
+    ) -> (BrowsingContextGroupID, BrowsingContext, Document) {
         // 1. Let group be a new browsing context group.
-        let mut group = BrowsingContextGroup::default();
+        let mut group = BrowsingContextGroup {
+            id: BrowsingContextGroupID::new(),
+            browsing_context: HashSet::new(),
+            agent_cluster: HashMap::new(),
+            historical_agent_cluster: HashMap::new(),
+            isolation_mode: IsolationMode::default(),
+        };
         // 3. Let browsingContext and document be the result of creating a new browsing context and document with null,
         // null, and group.
         let (mut context, document) =
-            BrowsingContext::new_browsing_context(None, None, &mut group, store);
+            BrowsingContext::new_browsing_context(user_agent, None, None, &mut group, store);
         // 4. Append browsingContext to group.
         group.browsing_context.insert(context.id());
         context.group = Some(group.id());
         // 2. Append group to the user agent's browsing context group set.
         let id = group.id();
-        BROWSING_CONTEXT_GROUP_SET.lock().unwrap().insert(id, group);
+        user_agent.browsing_context_group_set.insert(id, group);
         // 5. Return group and document.
-        (context, document)
+        (id, context, document)
     }
 
     /// <https://html.spec.whatwg.org/multipage/#obtain-similar-origin-window-agent>
-    pub fn window_agent(&mut self, origin: &ImmutableOrigin, oac: bool) -> AgentID {
+    pub fn window_agent(
+        &mut self,
+        user_agent: &mut UserAgent,
+        origin: &ImmutableOrigin,
+        oac: bool,
+    ) -> AgentID {
         // 3. If group's cross-origin isolation mode is not "none", then set key to origin.
-        let key = if self.isolation_mode == IsolationMode::None {
-            origin
+        let key = if self.isolation_mode != IsolationMode::None {
+            AgentClusterKey::Origin(origin.clone())
             // 4. Otherwise, if group's historical agent cluster key map[origin] exists,
             // then set key to group's historical agent cluster key map[origin].
-        } else if let Some(k) = self.historical_agent_cluster.get(origin) {
-            k
+        } else if let Some(key) = self.historical_agent_cluster.get(origin) {
+            key.clone()
         } else {
             // 5.1 If requestsOAC is true, then set key to origin.
-            let k = if oac {
-                origin.clone()
+            let key = if oac {
+                AgentClusterKey::Origin(origin.clone())
             } else {
                 // 1. Let site be the result of obtaining a site with origin.
                 // 2. Let key be site.
-                obtain_site(origin)
+                AgentClusterKey::Site(obtain_site(origin))
             };
             // 5.2 Set group's historical agent cluster key map[origin] to key.
-            self.historical_agent_cluster.insert(origin.clone(), k);
-            self.historical_agent_cluster.get(origin).unwrap()
+            self.historical_agent_cluster
+                .insert(origin.clone(), key.clone());
+            key
         };
 
         // 6. If group's agent cluster map[key] does not exist, then:
-        if !self.agent_cluster.contains_key(key) {
+        let isolation_mode = self.isolation_mode;
+        let agent_cluster = self.agent_cluster.entry(key).or_insert_with_key(|key| {
             // 6.1. Let agentCluster be a new agent cluster.
-            let agent_cluster = AgentCluster {
+            // 6.5. Set group's agent cluster map[key] to agentCluster.
+            AgentCluster {
                 // 6.2. Set agentCluster's cross-origin isolation mode to group's cross-origin isolation mode.
-                isolation_mode: self.isolation_mode,
+                isolation_mode,
                 // 6.3. If key is an origin: Set agentCluster's is origin-keyed to true.
-                origin_keyed: key == origin,
+                origin_keyed: matches!(key, AgentClusterKey::Origin(_)),
                 // 6.4. Add the result of creating an agent, given false, to agentCluster.
-                agent: Agent::create(false),
-            };
-            // 6.5. Set group's agent cluster map[key] to agentCluster.
-            self.agent_cluster.insert(key.clone(), agent_cluster);
-        }
+                agent: Agent::create(user_agent, false),
+            }
+        });
         // 7. Return the single similar-origin window agent contained in group's agent cluster map[key].
-        self.agent_cluster.get(key).unwrap().agent
+        agent_cluster.agent
     }
 
-    /// Get the ID of the `BrowsingContextGroup`.
-    pub fn id(&self) -> BrowsingContextGroupID {
-        self.id
+    /// Whether the agent cluster of `agent` in the agent cluster map is origin-keyed.
+    pub fn is_origin_keyed(&self, agent: AgentID) -> bool {
+        self.agent_cluster
+            .values()
+            .any(|cluster| cluster.agent == agent && cluster.origin_keyed)
     }
-}
-
-/// ID of `BrowsingContext`.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct BrowsingContextID(pub usize);
 
-impl Default for BrowsingContextID {
-    fn default() -> Self {
-        static COUNT: LazyLock<AtomicUsize> = LazyLock::new(|| AtomicUsize::new(0));
-        let id = Self(COUNT.load(Ordering::Relaxed));
-        COUNT.fetch_add(1, Ordering::Relaxed);
-        id
+    /// Get the keys of the agent cluster map, for diagnostics.
+    pub fn agent_cluster_keys(&self) -> impl Iterator<Item = &AgentClusterKey> {
+        self.agent_cluster.keys()
     }
-}
-
-impl Deref for BrowsingContextID {
-    type Target = usize;
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
+    /// Remove the group `id` from the user agent's browsing context group set.
+    ///
+    /// The agents of its agent clusters are destroyed along with it.
+    pub fn remove(user_agent: &mut UserAgent, id: BrowsingContextGroupID) {
+        let Some(group) = user_agent.browsing_context_group_set.remove(&id) else {
+            return;
+        };
+        for agent_cluster in group.agent_cluster.into_values() {
+            Agent::destroy(user_agent, agent_cluster.agent);
+        }
     }
-}
 
-/// ID of `BrowsingContext`.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct BrowsingContextGroupID(pub usize);
-
-impl Default for BrowsingContextGroupID {
-    fn default() -> Self {
-        static COUNT: LazyLock<AtomicUsize> = LazyLock::new(|| AtomicUsize::new(0));
-        let id = Self(COUNT.load(Ordering::Relaxed));
-        COUNT.fetch_add(1, Ordering::Relaxed);
-        id
+    /// Get the ID of the `BrowsingContextGroup`.
+    pub fn id(&self) -> BrowsingContextGroupID {
+        self.id
     }
 }
 
-impl Deref for BrowsingContextGroupID {
-    type Target = usize;
+define_id! {
+    /// ID of `BrowsingContext`.
+    BrowsingContextID
+}
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
+define_id! {
+    /// ID of `BrowsingContextGroup`.
+    BrowsingContextGroupID
 }
 
 /// <https://html.spec.whatwg.org/multipage/document-sequences.html#cross-origin-isolation-mode>
@@ -351,8 +459,7 @@ pub fn determin_origin(
         // 3. If url is about:srcdoc, then:
         // 4. If url matches about:blank and sourceOrigin is non-null, then return sourceOrigin.
         (Some(u), Some(o)) => {
-            // TODO: Implement matches URL
-            if u.as_str() == "about:srcdoc" || u.as_str() == "about:blank" {
+            if u.is_about_srcdoc() || u.matches_about_blank() {
                 o
             } else {
                 // 5. Return url's origin.
@@ -365,15 +472,57 @@ pub fn determin_origin(
 }
 
 /// <https://html.spec.whatwg.org/multipage/#obtain-a-site>
-pub fn obtain_site(origin: &ImmutableOrigin) -> ImmutableOrigin {
+pub fn obtain_site(origin: &ImmutableOrigin) -> Site {
     // 1. If origin is an opaque origin, then return origin.
     match origin {
-        ImmutableOrigin::Opaque(_) => origin.clone(),
+        ImmutableOrigin::Opaque(opaque) => Site::Opaque(opaque.clone()),
         ImmutableOrigin::Tuple(scheme, host, _) => {
             // 2. If origin's host's registrable domain is null, then return (origin's scheme, origin's host).
             // 3. Return (origin's scheme, origin's host's registrable domain).
             // TODO: implement registrable_domain (This requires a list of public domain)
-            ImmutableOrigin::Tuple(scheme.clone(), host.clone(), u16::MAX)
+            Site::SchemeAndHost(scheme.clone(), host.clone())
+        }
+    }
+}
+
+/// <https://html.spec.whatwg.org/multipage/#site>
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Site {
+    /// An opaque origin.
+    Opaque(OpaqueOrigin),
+    /// <https://html.spec.whatwg.org/multipage/#scheme-and-host>
+    SchemeAndHost(String, Host),
+}
+
+impl fmt::Display for Site {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            // The serialization of an opaque origin is "null".
+            Site::Opaque(_) => f.write_str("null"),
+            Site::SchemeAndHost(scheme, host) => write!(f, "{scheme}://{host}"),
+        }
+    }
+}
+
+/// The key of the agent cluster map of a browsing context group. Sites and origins never compare
+/// equal, even for the same host.
+///
+/// <https://html.spec.whatwg.org/multipage/#agent-cluster-key>
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AgentClusterKey {
+    /// The cluster is site-keyed.
+    Site(Site),
+    /// The cluster is origin-keyed.
+    Origin(ImmutableOrigin),
+}
+
+impl fmt::Display for AgentClusterKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AgentClusterKey::Site(site) => write!(f, "site: {site}"),
+            AgentClusterKey::Origin(origin) => {
+                write!(f, "origin: {}", origin.ascii_serialization())
+            }
         }
     }
 }
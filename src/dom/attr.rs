@@ -0,0 +1,194 @@
+use malloc_size_of_derive::MallocSizeOf;
+use wasmtime::{AsContextMut, Result, component::Resource};
+
+use crate::{WindowStates, agent::NameSpace, ohim::dom::node::HostAttr, string::DOMString};
+
+use super::Element;
+
+/// <https://dom.spec.whatwg.org/#attr>
+///
+/// Attributes are stored by value in the attribute list of their element, and the element sets
+/// itself as the owner element of the attributes it hands out.
+///
+/// TODO: This should be a node with a node document.
+#[derive(Clone, Debug, MallocSizeOf)]
+pub struct Attr {
+    /// <https://dom.spec.whatwg.org/#concept-attribute-namespace>
+    pub(crate) name_space: NameSpace,
+    /// <https://dom.spec.whatwg.org/#concept-attribute-namespace-prefix>
+    pub(crate) prefix: Option<DOMString>,
+    /// <https://dom.spec.whatwg.org/#concept-attribute-local-name>
+    pub(crate) local_name: DOMString,
+    /// <https://dom.spec.whatwg.org/#concept-attribute-value>
+    pub(crate) value: DOMString,
+    /// <https://dom.spec.whatwg.org/#concept-attribute-element>
+    ///
+    /// This is always `None` for the attributes kept in an attribute list.
+    #[ignore_malloc_size_of = "owned by the GC heap"]
+    pub(crate) owner_element: Option<Element>,
+}
+
+impl Attr {
+    /// Create an attribute in the null namespace.
+    pub fn new(local_name: DOMString, value: DOMString) -> Self {
+        Self {
+            name_space: NameSpace::None,
+            prefix: None,
+            local_name,
+            value,
+            owner_element: None,
+        }
+    }
+
+    /// Set the namespace and namespace prefix.
+    pub fn with_namespace(mut self, name_space: NameSpace, prefix: Option<DOMString>) -> Self {
+        self.name_space = name_space;
+        self.prefix = prefix;
+        self
+    }
+
+    /// Set the owner element.
+    pub(crate) fn with_owner_element(mut self, owner_element: Option<Element>) -> Self {
+        self.owner_element = owner_element;
+        self
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-attr-namespaceuri>
+    pub fn namespace_uri(&self) -> Option<&'static str> {
+        match self.name_space {
+            NameSpace::None => None,
+            name_space => Some(name_space.url()),
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-attr-prefix>
+    pub fn prefix(&self) -> Option<&str> {
+        self.prefix.as_deref()
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-attr-localname>
+    pub fn local_name(&self) -> &str {
+        &self.local_name
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-attr-name>
+    pub fn name(&self) -> DOMString {
+        // An attribute’s qualified name is its local name if its namespace prefix is null, and its
+        // namespace prefix, followed by ":", followed by its local name, otherwise.
+        match &self.prefix {
+            Some(prefix) => DOMString::from(format!("{prefix}:{}", self.local_name)),
+            None => self.local_name.clone(),
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-attr-value>
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-attr-value>
+    pub fn set_value(&mut self, value: &str, mut store: impl AsContextMut) {
+        // The value setter steps are to set an existing attribute value with this and the given
+        // value.
+        // 1. If attribute’s element is null, then set attribute’s value to value and return.
+        self.value = DOMString::from(value);
+        let Some(element) = &self.owner_element else {
+            return;
+        };
+        // 2. Change attribute to value.
+        let old_value = element
+            .data_mut(&mut store)
+            .as_element_mut()
+            .attribute_list
+            .iter_mut()
+            .find(|attr| attr.name_space == self.name_space && attr.local_name == self.local_name)
+            .map(|attr| std::mem::replace(&mut attr.value, DOMString::from(value)));
+        if let Some(old_value) = old_value.filter(|_| self.name_space == NameSpace::None) {
+            element.run_attribute_change_steps(
+                &self.local_name,
+                Some(&*old_value),
+                Some(value),
+                store,
+            );
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-attr-ownerelement>
+    pub fn owner_element(&self) -> Option<&Element> {
+        self.owner_element.as_ref()
+    }
+
+    /// Get the attribute’s serialized name.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#attribute's-serialised-name>
+    pub(crate) fn serialized_name(&self) -> DOMString {
+        match self.name_space {
+            // If the attribute has no namespace: the attribute’s serialized name is the attribute’s
+            // local name.
+            NameSpace::None => self.local_name.clone(),
+            // If the attribute is in the XML namespace: the attribute’s serialized name is the
+            // string "xml:" followed by the attribute’s local name.
+            NameSpace::XML => DOMString::from(format!("xml:{}", self.local_name)),
+            // If the attribute is in the XMLNS namespace and the attribute’s local name is xmlns:
+            // the attribute’s serialized name is the string "xmlns".
+            NameSpace::XMLNS if self.local_name == "xmlns" => DOMString::from("xmlns"),
+            // If the attribute is in the XMLNS namespace and the attribute’s local name is not
+            // xmlns: the attribute’s serialized name is the string "xmlns:" followed by the
+            // attribute’s local name.
+            NameSpace::XMLNS => DOMString::from(format!("xmlns:{}", self.local_name)),
+            // If the attribute is in the XLink namespace: the attribute’s serialized name is the
+            // string "xlink:" followed by the attribute’s local name.
+            NameSpace::XLink => DOMString::from(format!("xlink:{}", self.local_name)),
+            // If the attribute is in some other namespace: the attribute’s serialized name is the
+            // attribute’s qualified name.
+            _ => self.name(),
+        }
+    }
+}
+
+impl HostAttr for WindowStates {
+    fn local_name(&mut self, self_: Resource<Attr>) -> Result<String> {
+        let self_ = self.table.get(&self_)?;
+        Ok(self_.local_name().into())
+    }
+
+    fn name(&mut self, self_: Resource<Attr>) -> Result<String> {
+        let self_ = self.table.get(&self_)?;
+        Ok(self_.name().into())
+    }
+
+    fn namespace_uri(&mut self, self_: Resource<Attr>) -> Result<Option<String>> {
+        let self_ = self.table.get(&self_)?;
+        Ok(self_.namespace_uri().map(Into::into))
+    }
+
+    fn prefix(&mut self, self_: Resource<Attr>) -> Result<Option<String>> {
+        let self_ = self.table.get(&self_)?;
+        Ok(self_.prefix().map(Into::into))
+    }
+
+    fn value(&mut self, self_: Resource<Attr>) -> Result<String> {
+        let self_ = self.table.get(&self_)?;
+        Ok(self_.value().into())
+    }
+
+    fn set_value(&mut self, self_: Resource<Attr>, value: String) -> Result<()> {
+        let mut attr = self.table.get(&self_)?.clone();
+        attr.set_value(&value, &mut self.store);
+        *self.table.get_mut(&self_)? = attr;
+        Ok(())
+    }
+
+    fn owner_element(&mut self, self_: Resource<Attr>) -> Result<Option<Resource<Element>>> {
+        let owner_element = self.table.get(&self_)?.owner_element().cloned();
+        match owner_element {
+            Some(e) => Ok(Some(self.push_resource(e)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn drop(&mut self, rep: Resource<Attr>) -> Result<()> {
+        self.delete_resource(rep)?;
+        Ok(())
+    }
+}
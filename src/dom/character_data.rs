@@ -0,0 +1,375 @@
+use std::ops::Deref;
+
+use wasmtime::{AsContext, AsContextMut, ExternRef, Rooted};
+
+use crate::{
+    DomException, NodeImpl, NodeTypeData, Object,
+    string::{DOMString, check_surrogate_boundaries},
+};
+
+use super::{Comment, Node, Text, mutation_observer::queue_character_data_mutation_record};
+
+/// <https://dom.spec.whatwg.org/#characterdata>
+///
+/// Offsets and counts are measured in UTF-16 code units like the spec, while the data is stored as
+/// UTF-8. An offset or count that would split a surrogate pair returns an "IndexSizeError", as
+/// described on [`DOMString`].
+#[derive(Clone, Debug)]
+pub struct CharacterData(pub(crate) Object<NodeImpl>);
+
+impl CharacterData {
+    /// <https://dom.spec.whatwg.org/#dom-characterdata-data>
+    pub fn data(&self, store: impl AsContext) -> DOMString {
+        self.0.data(&store).as_character_data().clone()
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-characterdata-data>
+    pub fn set_data(&self, data: &str, store: impl AsContextMut) {
+        // The data setter steps are to replace data with node this, offset 0, count this’s length,
+        // and data the given value.
+        let length = self.length(&store);
+        // This can not fail since offset is 0.
+        let _ = self.replace_data(0, length, data, store);
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-node-length>
+    pub fn length(&self, store: impl AsContext) -> usize {
+        self.0.data(&store).as_character_data().len_utf16()
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-cd-substring>
+    pub fn substring_data(
+        &self,
+        offset: usize,
+        count: usize,
+        store: impl AsContext,
+    ) -> Result<DOMString, DomException> {
+        // 1. Let length be node’s length.
+        // 2. If offset is greater than length, then throw an "IndexSizeError" DOMException.
+        // 3. If offset plus count is greater than length, return a string whose value is the code
+        // units from the offsetth code unit to the end of node’s data, and then return.
+        // 4. Return a string whose value is the code units from the offsetth code unit to the
+        // offset+countth code unit in node’s data.
+        self.0
+            .data(&store)
+            .as_character_data()
+            .substring_utf16(offset, count)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-characterdata-appenddata>
+    pub fn append_data(&self, data: &str, store: impl AsContextMut) {
+        // The appendData(data) method steps are to replace data with node this, offset this’s length,
+        // count 0, and data data.
+        let length = self.length(&store);
+        // This can not fail since offset is the length.
+        let _ = self.replace_data(length, 0, data, store);
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-characterdata-insertdata>
+    pub fn insert_data(
+        &self,
+        offset: usize,
+        data: &str,
+        store: impl AsContextMut,
+    ) -> Result<(), DomException> {
+        // The insertData(offset, data) method steps are to replace data with node this, offset
+        // offset, count 0, and data data.
+        self.replace_data(offset, 0, data, store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-characterdata-deletedata>
+    pub fn delete_data(
+        &self,
+        offset: usize,
+        count: usize,
+        store: impl AsContextMut,
+    ) -> Result<(), DomException> {
+        // The deleteData(offset, count) method steps are to replace data with node this, offset
+        // offset, count count, and data the empty string.
+        self.replace_data(offset, count, "", store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-cd-replace>
+    pub fn replace_data(
+        &self,
+        offset: usize,
+        count: usize,
+        data: &str,
+        mut store: impl AsContextMut,
+    ) -> Result<(), DomException> {
+        let mut node_data = self.0.data(&store).as_character_data().to_utf16();
+        // 1. Let length be node’s length.
+        let length = node_data.len();
+        // 2. If offset is greater than length, then throw an "IndexSizeError" DOMException.
+        if offset > length {
+            return Err(index_size_error(offset, length));
+        }
+        // 3. If offset plus count is greater than length, then set count to length minus offset.
+        let count = count.min(length - offset);
+        check_surrogate_boundaries(&node_data, offset, offset + count)?;
+        // 4. Queue a mutation record of "characterData" for node with null, null, node’s data, « »,
+        // « », null, and null.
+        let old_value = self.data(&store);
+        queue_character_data_mutation_record(&Node::from(self.clone()), old_value, &mut store);
+        // 5. Insert data into node’s data after offset code units.
+        // 6. Let delete offset be offset + data’s length.
+        // 7. Starting from delete offset code units, remove count code units from node’s data.
+        let data_length = data.encode_utf16().count();
+        node_data.splice(offset..offset + count, data.encode_utf16());
+        *self.0.data_mut(&mut store).as_character_data_mut() =
+            DOMString::from_utf16_lossy(&node_data);
+        let node = Node::from(self.clone());
+        let updated = |boundary_offset: usize| {
+            if boundary_offset > offset && boundary_offset <= offset + count {
+                offset
+            } else if boundary_offset > offset + count {
+                boundary_offset + data_length - count
+            } else {
+                boundary_offset
+            }
+        };
+        for range in node.data(&store).live_ranges.clone() {
+            let (start, end) = (range.start(&store), range.end(&store));
+            // 8. For each live range whose start node is node and start offset is greater than
+            // offset but less than or equal to offset plus count, set its start offset to offset.
+            // 10. For each live range whose start node is node and start offset is greater than
+            // offset plus count, increase its start offset by data’s length and decrease it by
+            // count.
+            if start.node.is_same_node(&node, &store) {
+                range.set_start_offset(updated(start.offset), &mut store);
+            }
+            // 9. For each live range whose end node is node and end offset is greater than offset
+            // but less than or equal to offset plus count, set its end offset to offset.
+            // 11. For each live range whose end node is node and end offset is greater than offset
+            // plus count, increase its end offset by data’s length and decrease it by count.
+            if end.node.is_same_node(&node, &store) {
+                range.set_end_offset(updated(end.offset), &mut store);
+            }
+        }
+        // 12. TODO: If node’s parent is non-null, then run the children changed steps for node’s
+        // parent.
+        Ok(())
+    }
+
+    /// Get `Rooted<ExternRef>` reference of the `Node`.
+    pub fn as_root(&self) -> &Rooted<ExternRef> {
+        self
+    }
+}
+
+impl NodeImpl {
+    /// Get the data of a `CharacterData` node, or `None` if the node is not a `CharacterData`.
+    pub fn try_as_character_data(&self) -> Option<&DOMString> {
+        match self.data {
+            NodeTypeData::Text(ref text) => Some(&text.data),
+            NodeTypeData::Comment(ref comment) => Some(&comment.data),
+            _ => None,
+        }
+    }
+
+    /// Get the data of a `CharacterData` node.
+    pub(crate) fn as_character_data(&self) -> &DOMString {
+        self.try_as_character_data()
+            .expect("node is not a CharacterData")
+    }
+
+    /// Get the exclusive reference to the data of a `CharacterData` node, or `None` if the node is
+    /// not a `CharacterData`.
+    pub fn try_as_character_data_mut(&mut self) -> Option<&mut DOMString> {
+        match self.data {
+            NodeTypeData::Text(ref mut text) => Some(&mut text.data),
+            NodeTypeData::Comment(ref mut comment) => Some(&mut comment.data),
+            _ => None,
+        }
+    }
+
+    /// Get the exclusive reference to the data of a `CharacterData` node.
+    pub(crate) fn as_character_data_mut(&mut self) -> &mut DOMString {
+        self.try_as_character_data_mut()
+            .expect("node is not a CharacterData")
+    }
+}
+
+impl Deref for CharacterData {
+    type Target = Object<NodeImpl>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<Text> for CharacterData {
+    fn from(value: Text) -> Self {
+        Self(value.0)
+    }
+}
+
+impl From<Comment> for CharacterData {
+    fn from(value: Comment) -> Self {
+        Self(value.0)
+    }
+}
+
+impl From<CharacterData> for Node {
+    fn from(value: CharacterData) -> Self {
+        Self(value.0)
+    }
+}
+
+fn index_size_error(offset: usize, length: usize) -> DomException {
+    DomException::IndexSizeError(format!(
+        "offset {offset} is greater than the length {length}"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        MutationCallback, MutationObserver, MutationObserverInit, Range,
+        testing::{self, text},
+    };
+
+    #[test]
+    fn offsets_are_utf16_code_units() {
+        let (_user_agent, mut store, document) = testing::document();
+        let data = CharacterData(text(&document, "a😀b", &mut store).0);
+        assert_eq!(data.length(&store), 4);
+        assert_eq!(data.substring_data(1, 2, &store).unwrap(), "😀");
+        assert_eq!(data.substring_data(3, 10, &store).unwrap(), "b");
+        data.insert_data(3, "é", &mut store).unwrap();
+        assert_eq!(data.data(&store), "a😀éb");
+        data.delete_data(1, 2, &mut store).unwrap();
+        assert_eq!(data.data(&store), "aéb");
+        data.replace_data(1, 100, "c", &mut store).unwrap();
+        assert_eq!(data.data(&store), "ac");
+        data.append_data("d", &mut store);
+        assert_eq!(data.data(&store), "acd");
+    }
+
+    #[test]
+    fn offsets_past_the_end_are_rejected() {
+        let (_user_agent, mut store, document) = testing::document();
+        let data = CharacterData(text(&document, "abc", &mut store).0);
+        assert!(matches!(
+            data.substring_data(4, 0, &store),
+            Err(DomException::IndexSizeError(_))
+        ));
+        assert!(matches!(
+            data.insert_data(4, "x", &mut store),
+            Err(DomException::IndexSizeError(_))
+        ));
+        assert_eq!(data.data(&store), "abc");
+    }
+
+    #[test]
+    fn splitting_a_surrogate_pair_is_rejected() {
+        let (_user_agent, mut store, document) = testing::document();
+        let data = CharacterData(text(&document, "a😀b", &mut store).0);
+        assert!(matches!(
+            data.substring_data(2, 1, &store),
+            Err(DomException::IndexSizeError(_))
+        ));
+        assert!(matches!(
+            data.substring_data(0, 2, &store),
+            Err(DomException::IndexSizeError(_))
+        ));
+        assert!(matches!(
+            data.insert_data(2, "x", &mut store),
+            Err(DomException::IndexSizeError(_))
+        ));
+        assert!(matches!(
+            data.delete_data(1, 1, &mut store),
+            Err(DomException::IndexSizeError(_))
+        ));
+        assert_eq!(data.data(&store), "a😀b");
+    }
+
+    /// Create a live range from (`node`, `start`) to (`node`, `end`).
+    fn range(node: &Node, start: usize, end: usize, store: &mut wasmtime::Store<()>) -> Range {
+        let document = node.owner_document(&*store).unwrap();
+        let range = document.create_range(&mut *store).unwrap();
+        range.set_start(node, start, &mut *store).unwrap();
+        range.set_end(node, end, &mut *store).unwrap();
+        range
+    }
+
+    fn offsets(range: &Range, store: &wasmtime::Store<()>) -> (usize, usize) {
+        (range.start(store).offset, range.end(store).offset)
+    }
+
+    #[test]
+    fn replace_data_updates_live_ranges() {
+        let (_user_agent, mut store, document) = testing::document();
+        let node = text(&document, "abcdefgh", &mut store);
+        let data = CharacterData(node.0.clone());
+        let before = range(&node, 0, 2, &mut store);
+        let inside = range(&node, 3, 6, &mut store);
+        let after = range(&node, 7, 8, &mut store);
+        let spanning = range(&node, 1, 7, &mut store);
+
+        // Replace "cdef" with "XY".
+        data.replace_data(2, 4, "XY", &mut store).unwrap();
+        assert_eq!(data.data(&store), "abXYgh");
+        // Boundary points up to the offset stay, the ones in the replaced data move to the
+        // offset, and the ones after it move by the change in length.
+        assert_eq!(offsets(&before, &store), (0, 2));
+        assert_eq!(offsets(&inside, &store), (2, 2));
+        assert!(inside.collapsed(&store));
+        assert_eq!(offsets(&after, &store), (5, 6));
+        assert_eq!(offsets(&spanning, &store), (1, 5));
+
+        // Inserting at a boundary point leaves it before the inserted data.
+        data.insert_data(0, "__", &mut store).unwrap();
+        assert_eq!(offsets(&before, &store), (0, 4));
+        assert_eq!(offsets(&after, &store), (7, 8));
+    }
+
+    #[test]
+    fn replace_data_only_updates_ranges_of_the_node() {
+        let (_user_agent, mut store, document) = testing::document();
+        let node = text(&document, "abc", &mut store);
+        let other = text(&document, "abc", &mut store);
+        let range = range(&other, 1, 3, &mut store);
+        CharacterData(node.0.clone())
+            .delete_data(0, 3, &mut store)
+            .unwrap();
+        assert_eq!(offsets(&range, &store), (1, 3));
+    }
+
+    #[test]
+    fn replace_data_queues_records_with_the_old_value() {
+        let (_user_agent, mut store, document) = testing::document();
+        let node = text(&document, "abc", &mut store);
+        let data = CharacterData(node.0.clone());
+        let observer = MutationObserver::new(MutationCallback(0), &mut store).unwrap();
+        let options = MutationObserverInit {
+            character_data_old_value: Some(true),
+            ..Default::default()
+        };
+        observer.observe(&node, options, &mut store).unwrap();
+
+        data.append_data("d", &mut store);
+        data.insert_data(0, "x", &mut store).unwrap();
+        data.delete_data(1, 1, &mut store).unwrap();
+        data.replace_data(0, 1, "y", &mut store).unwrap();
+        // A rejected offset doesn't queue a record.
+        assert!(data.insert_data(100, "z", &mut store).is_err());
+        assert_eq!(data.data(&store), "ybcd");
+
+        let records = observer.take_records(&mut store);
+        let old_values: Vec<_> = records
+            .iter()
+            .map(|record| record.old_value().map(|value| &**value))
+            .collect();
+        assert_eq!(
+            old_values,
+            [Some("abc"), Some("abcd"), Some("xabcd"), Some("xbcd")]
+        );
+        assert!(
+            records
+                .iter()
+                .all(|record| record.target().is_same_node(&node, &store))
+        );
+    }
+}
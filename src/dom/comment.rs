@@ -0,0 +1,203 @@
+use std::ops::Deref;
+
+use malloc_size_of_derive::MallocSizeOf;
+use wasmtime::{AsContext, AsContextMut, Error, ExternRef, Result, Rooted, component::Resource};
+
+use crate::{
+    NodeImpl, NodeTypeData, Object, WindowStates,
+    ohim::dom::node::{DomException as WitDomException, HostComment},
+    string::DOMString,
+};
+
+use super::{CharacterData, Document, Node};
+
+/// <https://dom.spec.whatwg.org/#comment>
+#[derive(Clone, Debug)]
+pub struct Comment(pub(crate) Object<NodeImpl>);
+
+impl Comment {
+    /// Create a new `Comment` node whose data is `data` and node document is `document`.
+    ///
+    /// <https://dom.spec.whatwg.org/#dom-comment-comment>
+    pub fn new(document: &Document, data: DOMString, mut store: impl AsContextMut) -> Result<Self> {
+        let comment = Comment(Object::new(
+            &mut store,
+            NodeImpl::new_with_type(NodeTypeData::Comment(CommentImpl { data })),
+        )?);
+        comment
+            .data_mut(&mut store)
+            .set_node_document(Some(document.clone()));
+        Ok(comment)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-characterdata-data>
+    pub fn data(&self, store: impl AsContext) -> DOMString {
+        CharacterData::from(self.clone()).data(store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-characterdata-data>
+    pub fn set_data(&self, data: &str, store: impl AsContextMut) {
+        CharacterData::from(self.clone()).set_data(data, store)
+    }
+
+    /// Get `Rooted<ExternRef>` reference of the `Node`.
+    pub fn as_root(&self) -> &Rooted<ExternRef> {
+        self
+    }
+}
+
+impl Deref for Comment {
+    type Target = Object<NodeImpl>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<Comment> for Node {
+    fn from(value: Comment) -> Self {
+        Self(value.0)
+    }
+}
+
+/// Implementation of acutal `Comment` object. This can be accessed from `NodeImpl`.
+#[derive(Debug, MallocSizeOf)]
+pub struct CommentImpl {
+    /// <https://dom.spec.whatwg.org/#concept-cd-data>
+    pub(crate) data: DOMString,
+}
+
+impl WindowStates {
+    /// Get the `Comment` of `resource`.
+    ///
+    /// This returns an error instead of panicking if the object is not a `Comment`.
+    fn get_comment(&self, resource: &Resource<Comment>) -> Result<Comment> {
+        let comment = self.table.get(resource)?;
+        if !comment.try_data(&self.store)?.is_comment() {
+            return Err(Error::msg("object is not a Comment"));
+        }
+        Ok(comment.clone())
+    }
+}
+
+impl HostComment for WindowStates {
+    fn data(&mut self, self_: Resource<Comment>) -> Result<String> {
+        let self_ = self.get_comment(&self_)?;
+        Ok(self_.data(&self.store).into())
+    }
+
+    fn set_data(&mut self, self_: Resource<Comment>, data: String) -> Result<()> {
+        let self_ = self.get_comment(&self_)?;
+        self_.set_data(&data, &mut self.store);
+        Ok(())
+    }
+
+    fn length(&mut self, self_: Resource<Comment>) -> Result<u32> {
+        let self_ = CharacterData::from(self.get_comment(&self_)?);
+        Ok(self_.length(&self.store) as u32)
+    }
+
+    fn substring_data(
+        &mut self,
+        self_: Resource<Comment>,
+        offset: u32,
+        count: u32,
+    ) -> Result<Result<String, WitDomException>> {
+        let self_ = CharacterData::from(self.get_comment(&self_)?);
+        Ok(self_
+            .substring_data(offset as usize, count as usize, &self.store)
+            .map(Into::into)
+            .map_err(Into::into))
+    }
+
+    fn append_data(&mut self, self_: Resource<Comment>, data: String) -> Result<()> {
+        let self_ = CharacterData::from(self.get_comment(&self_)?);
+        self_.append_data(&data, &mut self.store);
+        Ok(())
+    }
+
+    fn insert_data(
+        &mut self,
+        self_: Resource<Comment>,
+        offset: u32,
+        data: String,
+    ) -> Result<Result<(), WitDomException>> {
+        let self_ = CharacterData::from(self.get_comment(&self_)?);
+        Ok(self_
+            .insert_data(offset as usize, &data, &mut self.store)
+            .map_err(Into::into))
+    }
+
+    fn delete_data(
+        &mut self,
+        self_: Resource<Comment>,
+        offset: u32,
+        count: u32,
+    ) -> Result<Result<(), WitDomException>> {
+        let self_ = CharacterData::from(self.get_comment(&self_)?);
+        Ok(self_
+            .delete_data(offset as usize, count as usize, &mut self.store)
+            .map_err(Into::into))
+    }
+
+    fn replace_data(
+        &mut self,
+        self_: Resource<Comment>,
+        offset: u32,
+        count: u32,
+        data: String,
+    ) -> Result<Result<(), WitDomException>> {
+        let self_ = CharacterData::from(self.get_comment(&self_)?);
+        Ok(self_
+            .replace_data(offset as usize, count as usize, &data, &mut self.store)
+            .map_err(Into::into))
+    }
+
+    fn drop(&mut self, rep: Resource<Comment>) -> Result<()> {
+        self.delete_resource(rep)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wasmtime::Engine;
+
+    use super::*;
+    use crate::{ohim::dom::node::DomExceptionName, user_agent::UserAgent};
+
+    #[test]
+    fn guests_edit_comments_in_utf16_code_units() {
+        let mut states = WindowStates::create(&mut UserAgent::new(Engine::default()));
+        let document = states.window.document(&states.store).unwrap();
+        let comment = Comment::new(&document, "a😀b".into(), &mut states.store).unwrap();
+        let comment = states.push_resource(comment).unwrap();
+        let borrow = || Resource::<Comment>::new_borrow(comment.rep());
+
+        assert_eq!(HostComment::length(&mut states, borrow()).unwrap(), 4);
+        let data = HostComment::substring_data(&mut states, borrow(), 1, 2).unwrap();
+        assert_eq!(data.unwrap(), "😀");
+        // Offsets inside the surrogate pair and past the end are rejected.
+        for (offset, count) in [(2, 1), (5, 0)] {
+            let error = HostComment::substring_data(&mut states, borrow(), offset, count)
+                .unwrap()
+                .unwrap_err();
+            assert!(matches!(error.name, DomExceptionName::IndexSizeError));
+        }
+        HostComment::insert_data(&mut states, borrow(), 3, "é".into())
+            .unwrap()
+            .unwrap();
+        HostComment::replace_data(&mut states, borrow(), 1, 2, "-".into())
+            .unwrap()
+            .unwrap();
+        HostComment::append_data(&mut states, borrow(), "!".into()).unwrap();
+        assert_eq!(HostComment::data(&mut states, borrow()).unwrap(), "a-éb!");
+        HostComment::delete_data(&mut states, borrow(), 0, 2)
+            .unwrap()
+            .unwrap();
+        assert_eq!(HostComment::data(&mut states, borrow()).unwrap(), "éb!");
+
+        HostComment::drop(&mut states, comment).unwrap();
+        assert_eq!(states.resource_count(), 0);
+    }
+}
@@ -0,0 +1,493 @@
+use wasmtime::{AsContext, AsContextMut, Result, component::Resource};
+
+use crate::{
+    DomException, WindowStates,
+    agent::NameSpace,
+    ohim::dom::{node::DomException as WitDomException, window::HostCustomElementRegistry},
+    string::DOMString,
+};
+
+use super::{Element, Node, Object, Window};
+
+/// <https://html.spec.whatwg.org/multipage/#customelementregistry>
+#[derive(Clone, Debug)]
+pub struct CustomElementRegistry(Object<CustomElementRegistryImpl>);
+
+impl CustomElementRegistry {
+    /// Create a `CustomElementRegistry` whose relevant global object is `window`.
+    pub fn new(window: &Window, store: impl AsContextMut) -> Result<Self> {
+        Ok(CustomElementRegistry(Object::new(
+            store,
+            CustomElementRegistryImpl {
+                window: window.clone(),
+                definitions: Vec::new(),
+                when_defined: Vec::new(),
+                resolved: Vec::new(),
+            },
+        )?))
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-customelementregistry-define>
+    ///
+    /// TODO: options with extends and lifecycle callbacks.
+    pub fn define(
+        &self,
+        name: &str,
+        constructor: CustomElementConstructor,
+        mut store: impl AsContextMut,
+    ) -> Result<(), DomException> {
+        // 1. TODO: If IsConstructor(constructor) is false, then throw a TypeError.
+        // 2. If name is not a valid custom element name, then throw a "SyntaxError" DOMException.
+        if !is_valid_custom_element_name(name) {
+            return Err(DomException::SyntaxError(format!(
+                "\"{name}\" is not a valid custom element name"
+            )));
+        }
+        let definitions = &self.0.data(&store).definitions;
+        // 3. If this’s custom element definition set contains an item with name name, then throw a
+        // "NotSupportedError" DOMException.
+        if definitions.iter().any(|definition| definition.name == name) {
+            return Err(DomException::NotSupportedError(format!(
+                "\"{name}\" is already defined"
+            )));
+        }
+        // 4. If this’s custom element definition set contains an item with constructor constructor,
+        // then throw a "NotSupportedError" DOMException.
+        if definitions
+            .iter()
+            .any(|definition| definition.constructor == constructor)
+        {
+            return Err(DomException::NotSupportedError(format!(
+                "constructor {} is already defined",
+                constructor.0
+            )));
+        }
+        // 5. Let localName be name.
+        let local_name = DOMString::from(name);
+        // 6 ~ 13. TODO: Handle extends and get the lifecycle callbacks from constructor.
+        // 14. Let definition be a new custom element definition with name name, local name
+        // localName, and constructor constructor.
+        let definition = CustomElementDefinition {
+            name: DOMString::from(name),
+            local_name,
+            constructor,
+        };
+        // 15. Append definition to this’s custom element definition set.
+        self.0
+            .data_mut(&mut store)
+            .definitions
+            .push(definition.clone());
+        // 16. Let document be this’s relevant global object’s associated Document.
+        let window = self.0.data(&store).window.clone();
+        let Some(document) = window.document(&store) else {
+            return Ok(());
+        };
+        // 17. Let upgradeCandidates be all elements that are shadow-including descendants of
+        // document, whose namespace is the HTML namespace and whose local name is localName, in
+        // shadow-including tree order.
+        // The document itself is not an element, so its inclusive descendants can be used.
+        let upgrade_candidates: Vec<Element> = Node::from(document)
+            .shadow_including_inclusive_descendants(&store)
+            .into_iter()
+            .filter(|node| node.data(&store).is_element())
+            .map(|node| Element(node.0))
+            .filter(|element| {
+                element.is_html_namespace(&store)
+                    && element.local_name(&store) == definition.local_name
+            })
+            .collect();
+        // 18. For each element element of upgradeCandidates, enqueue a custom element upgrade
+        // reaction given element and definition.
+        // TODO: There is no custom element reaction queue yet, so the element is upgraded
+        // immediately.
+        for element in upgrade_candidates {
+            element.upgrade(&definition, &mut store);
+        }
+        // 19. If this’s when-defined promise map[name] exists:
+        let data = self.0.data_mut(&mut store);
+        if let Some(index) = data.when_defined.iter().position(|pending| pending == name) {
+            // 19.1 Resolve this’s when-defined promise map[name] with constructor.
+            data.resolved.push((definition.name, constructor));
+            // 19.2 Remove this’s when-defined promise map[name].
+            data.when_defined.remove(index);
+        }
+        Ok(())
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-customelementregistry-whendefined>
+    ///
+    /// Promises can't be handed to guests yet. `Some` stands for a promise already resolved with
+    /// the constructor of `name`, and `None` for a pending one. Pending promises are resolved when
+    /// `name` is defined, and the embedder takes them with
+    /// [`CustomElementRegistry::take_resolved`].
+    pub fn when_defined(
+        &self,
+        name: &str,
+        mut store: impl AsContextMut,
+    ) -> Result<Option<CustomElementConstructor>, DomException> {
+        // 1. If name is not a valid custom element name, then return a promise rejected with a
+        // "SyntaxError" DOMException.
+        if !is_valid_custom_element_name(name) {
+            return Err(DomException::SyntaxError(format!(
+                "\"{name}\" is not a valid custom element name"
+            )));
+        }
+        // 2. If this’s custom element definition set contains an item with name name, then return
+        // a promise resolved with that item’s constructor.
+        if let Some(constructor) = self.get(name, &store) {
+            return Ok(Some(constructor));
+        }
+        // 3. If this’s when-defined promise map[name] does not exist, then set this’s when-defined
+        // promise map[name] to a new promise.
+        let when_defined = &mut self.0.data_mut(&mut store).when_defined;
+        if !when_defined.iter().any(|pending| pending == name) {
+            when_defined.push(DOMString::from(name));
+        }
+        // 4. Return this’s when-defined promise map[name].
+        Ok(None)
+    }
+
+    /// Take the when-defined promises resolved since the last call, as the names and constructors
+    /// they are resolved with, in the order the names were defined.
+    ///
+    /// The embedder's event loop should call this and resolve the promises of the guest.
+    pub fn take_resolved(
+        &self,
+        mut store: impl AsContextMut,
+    ) -> Vec<(DOMString, CustomElementConstructor)> {
+        std::mem::take(&mut self.0.data_mut(&mut store).resolved)
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-customelementregistry-get>
+    pub fn get(&self, name: &str, store: impl AsContext) -> Option<CustomElementConstructor> {
+        // 1. If this’s custom element definition set contains an item with name name, then return
+        // that item’s constructor.
+        // 2. Return undefined.
+        self.0
+            .data(&store)
+            .definitions
+            .iter()
+            .find(|definition| definition.name == name)
+            .map(|definition| definition.constructor)
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#look-up-a-custom-element-definition>
+    pub(crate) fn look_up(
+        &self,
+        name_space: NameSpace,
+        local_name: &str,
+        is: Option<&str>,
+        store: impl AsContext,
+    ) -> Option<CustomElementDefinition> {
+        // 1. If registry is null, then return null.
+        // 2. If namespace is not the HTML namespace, then return null.
+        if name_space != NameSpace::HTML {
+            return None;
+        }
+        let definitions = &self.0.data(&store).definitions;
+        // 3. If registry’s custom element definition set contains an item with name and local name
+        // both equal to localName, then return that item.
+        // 4. If registry’s custom element definition set contains an item with name equal to is and
+        // local name equal to localName, then return that item.
+        // 5. Return null.
+        definitions
+            .iter()
+            .find(|definition| definition.name == local_name && definition.local_name == local_name)
+            .or_else(|| {
+                definitions.iter().find(|definition| {
+                    is.is_some_and(|is| definition.name == is)
+                        && definition.local_name == local_name
+                })
+            })
+            .cloned()
+    }
+}
+
+/// Implementation of acutal `CustomElementRegistry` object.
+#[derive(Debug)]
+struct CustomElementRegistryImpl {
+    /// <https://html.spec.whatwg.org/multipage/#concept-relevant-global>
+    window: Window,
+    /// <https://html.spec.whatwg.org/multipage/#custom-element-definition-set>
+    definitions: Vec<CustomElementDefinition>,
+    /// <https://html.spec.whatwg.org/multipage/#when-defined-promise-map>
+    ///
+    /// The names whose promise is still pending.
+    when_defined: Vec<DOMString>,
+    /// The when-defined promises resolved by `define`, waiting for the embedder.
+    resolved: Vec<(DOMString, CustomElementConstructor)>,
+}
+
+/// <https://html.spec.whatwg.org/multipage/#custom-element-definition>
+#[derive(Clone, Debug)]
+pub struct CustomElementDefinition {
+    /// <https://html.spec.whatwg.org/multipage/#concept-custom-element-definition-name>
+    pub(crate) name: DOMString,
+    /// <https://html.spec.whatwg.org/multipage/#concept-custom-element-definition-local-name>
+    pub(crate) local_name: DOMString,
+    /// <https://html.spec.whatwg.org/multipage/#concept-custom-element-definition-constructor>
+    pub(crate) constructor: CustomElementConstructor,
+}
+
+/// A handle to the constructor of a custom element, provided by the guest.
+///
+/// The constructor can't be invoked until guest callbacks are supported, similar to
+/// [`EventListener`](super::EventListener).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CustomElementConstructor(pub u32);
+
+/// <https://html.spec.whatwg.org/multipage/#valid-custom-element-name>
+pub fn is_valid_custom_element_name(name: &str) -> bool {
+    // A string name is a valid custom element name if all of the following are true:
+    // - name matches the PotentialCustomElementName production:
+    //   [a-z] (PCENChar)* '-' (PCENChar)*
+    let mut chars = name.chars();
+    let starts_with_lower_alpha = chars.next().is_some_and(|c| c.is_ascii_lowercase());
+    let is_potential_custom_element_name = starts_with_lower_alpha
+        && name.contains('-')
+        && chars.all(|c| {
+            matches!(c,
+                '-' | '.' | '0'..='9' | '_' | 'a'..='z' | '\u{B7}' | '\u{C0}'..='\u{D6}'
+                | '\u{D8}'..='\u{F6}' | '\u{F8}'..='\u{37D}' | '\u{37F}'..='\u{1FFF}'
+                | '\u{200C}'..='\u{200D}' | '\u{203F}'..='\u{2040}' | '\u{2070}'..='\u{218F}'
+                | '\u{2C00}'..='\u{2FEF}' | '\u{3001}'..='\u{D7FF}' | '\u{F900}'..='\u{FDCF}'
+                | '\u{FDF0}'..='\u{FFFD}' | '\u{10000}'..='\u{EFFFF}')
+        });
+    // - name is not any of the following: annotation-xml, color-profile, font-face,
+    //   font-face-src, font-face-uri, font-face-format, font-face-name, missing-glyph
+    is_potential_custom_element_name
+        && !matches!(
+            name,
+            "annotation-xml"
+                | "color-profile"
+                | "font-face"
+                | "font-face-src"
+                | "font-face-uri"
+                | "font-face-format"
+                | "font-face-name"
+                | "missing-glyph"
+        )
+}
+
+impl WindowStates {
+    /// Get the `CustomElementRegistry` of `resource`.
+    ///
+    /// This returns an error instead of panicking if the object is not a `CustomElementRegistry`.
+    fn get_custom_element_registry(
+        &self,
+        resource: &Resource<CustomElementRegistry>,
+    ) -> Result<CustomElementRegistry> {
+        let registry = self.table.get(resource)?;
+        registry.0.try_data(&self.store)?;
+        Ok(registry.clone())
+    }
+}
+
+impl HostCustomElementRegistry for WindowStates {
+    fn define(
+        &mut self,
+        self_: Resource<CustomElementRegistry>,
+        name: String,
+        constructor: u32,
+    ) -> Result<Result<(), WitDomException>> {
+        let self_ = self.get_custom_element_registry(&self_)?;
+        Ok(self_
+            .define(
+                &name,
+                CustomElementConstructor(constructor),
+                &mut self.store,
+            )
+            .map_err(Into::into))
+    }
+
+    fn get(&mut self, self_: Resource<CustomElementRegistry>, name: String) -> Result<Option<u32>> {
+        let self_ = self.get_custom_element_registry(&self_)?;
+        Ok(self_
+            .get(&name, &self.store)
+            .map(|constructor| constructor.0))
+    }
+
+    fn when_defined(
+        &mut self,
+        self_: Resource<CustomElementRegistry>,
+        name: String,
+    ) -> Result<Result<Option<u32>, WitDomException>> {
+        let self_ = self.get_custom_element_registry(&self_)?;
+        Ok(self_
+            .when_defined(&name, &mut self.store)
+            .map(|constructor| constructor.map(|constructor| constructor.0))
+            .map_err(Into::into))
+    }
+
+    fn drop(&mut self, rep: Resource<CustomElementRegistry>) -> Result<()> {
+        self.delete_resource(rep)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wasmtime::Store;
+
+    use super::*;
+    use crate::{
+        CustomElementState, Document, ShadowRootInit, ShadowRootMode, SlotAssignmentMode,
+        testing::{self, element},
+    };
+
+    fn registry(document: &Document, store: &Store<()>) -> CustomElementRegistry {
+        document.custom_element_registry(store).unwrap()
+    }
+
+    /// Create an element named `name` and append it to `parent`.
+    fn append(parent: impl Into<Node>, name: &str, store: &mut Store<()>) -> Element {
+        let parent: Node = parent.into();
+        let document = parent.owner_document(&*store).unwrap();
+        let child = element(&document, name, store);
+        parent
+            .pre_insert(child.clone().into(), None, store)
+            .unwrap();
+        child
+    }
+
+    #[test]
+    fn invalid_names_are_syntax_errors() {
+        let (_user_agent, mut store, document) = testing::document();
+        let registry = registry(&document, &store);
+        for name in [
+            "",
+            "thing",
+            "My-thing",
+            "1-thing",
+            "-thing",
+            "my thing",
+            "font-face",
+        ] {
+            let error = registry
+                .define(name, CustomElementConstructor(1), &mut store)
+                .unwrap_err();
+            assert!(matches!(error, DomException::SyntaxError(_)), "{name:?}");
+            let error = registry.when_defined(name, &mut store).unwrap_err();
+            assert!(matches!(error, DomException::SyntaxError(_)), "{name:?}");
+        }
+        assert!(
+            registry
+                .define("my-thing", CustomElementConstructor(1), &mut store)
+                .is_ok()
+        );
+        assert!(
+            registry
+                .define("math-\u{3B1}", CustomElementConstructor(2), &mut store)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn redefinitions_are_not_supported() {
+        let (_user_agent, mut store, document) = testing::document();
+        let registry = registry(&document, &store);
+        registry
+            .define("my-thing", CustomElementConstructor(1), &mut store)
+            .unwrap();
+
+        // The same name with another constructor.
+        let error = registry
+            .define("my-thing", CustomElementConstructor(2), &mut store)
+            .unwrap_err();
+        assert!(matches!(error, DomException::NotSupportedError(_)));
+        // The same constructor with another name.
+        let error = registry
+            .define("my-other-thing", CustomElementConstructor(1), &mut store)
+            .unwrap_err();
+        assert!(matches!(error, DomException::NotSupportedError(_)));
+
+        assert_eq!(
+            registry.get("my-thing", &store),
+            Some(CustomElementConstructor(1))
+        );
+        assert_eq!(registry.get("my-other-thing", &store), None);
+    }
+
+    #[test]
+    fn define_upgrades_the_elements_of_the_document() {
+        let (_user_agent, mut store, document) = testing::document();
+        let body = document.body(&store).unwrap();
+        let thing = append(body.clone(), "my-thing", &mut store);
+        let other = append(body.clone(), "my-other-thing", &mut store);
+        // Elements in shadow trees are shadow-including descendants of the document.
+        let host = append(body, "div", &mut store);
+        let init = ShadowRootInit {
+            mode: ShadowRootMode::Closed,
+            delegates_focus: false,
+            slot_assignment: SlotAssignmentMode::Named,
+        };
+        let shadow = host.attach_shadow(init, &mut store).unwrap();
+        let shadowed = append(shadow, "my-thing", &mut store);
+        // Elements out of the document are not upgraded.
+        let detached = element(&document, "my-thing", &mut store);
+        for element in [&thing, &other, &shadowed, &detached] {
+            assert_eq!(
+                element.custom_element_state(&store),
+                CustomElementState::Undefined
+            );
+        }
+
+        registry(&document, &store)
+            .define("my-thing", CustomElementConstructor(1), &mut store)
+            .unwrap();
+        assert_eq!(
+            thing.custom_element_state(&store),
+            CustomElementState::Custom
+        );
+        assert_eq!(
+            shadowed.custom_element_state(&store),
+            CustomElementState::Custom
+        );
+        assert_eq!(
+            other.custom_element_state(&store),
+            CustomElementState::Undefined
+        );
+        assert_eq!(
+            detached.custom_element_state(&store),
+            CustomElementState::Undefined
+        );
+    }
+
+    #[test]
+    fn when_defined_is_resolved_by_define() {
+        let (_user_agent, mut store, document) = testing::document();
+        let registry = registry(&document, &store);
+        registry
+            .define("my-thing", CustomElementConstructor(1), &mut store)
+            .unwrap();
+        // A defined name is resolved already.
+        assert_eq!(
+            registry.when_defined("my-thing", &mut store).unwrap(),
+            Some(CustomElementConstructor(1))
+        );
+        assert!(registry.take_resolved(&mut store).is_empty());
+
+        // Pending promises are resolved once, in the order the names are defined.
+        for name in ["my-a", "my-b", "my-a"] {
+            assert_eq!(registry.when_defined(name, &mut store).unwrap(), None);
+        }
+        registry
+            .define("my-b", CustomElementConstructor(2), &mut store)
+            .unwrap();
+        registry
+            .define("my-c", CustomElementConstructor(3), &mut store)
+            .unwrap();
+        registry
+            .define("my-a", CustomElementConstructor(4), &mut store)
+            .unwrap();
+        let resolved = registry.take_resolved(&mut store);
+        assert_eq!(
+            resolved,
+            [
+                (DOMString::from("my-b"), CustomElementConstructor(2)),
+                (DOMString::from("my-a"), CustomElementConstructor(4)),
+            ]
+        );
+        assert!(registry.take_resolved(&mut store).is_empty());
+    }
+}
@@ -4,17 +4,34 @@ use std::{
 };
 
 use headers::ContentType;
-use wasmtime::{AsContext, AsContextMut, ExternRef, Result, Rooted, component::Resource};
+use malloc_size_of::{MallocSizeOf, MallocSizeOfOps};
+use wasmtime::{AsContext, AsContextMut, Error, ExternRef, Result, Rooted, component::Resource};
 
 use crate::{
-    Element, NodeImpl, NodeTypeData, Object, WindowStates,
-    agent::{NameSpace, RELEVANT_REALM, RealmID},
+    DomException, Element, NodeImpl, NodeTypeData, Object, Window, WindowStates,
+    agent::{Environment, NameSpace, Realm, RealmID},
     browsing_context::{BrowsingContext, BrowsingContextID, SandboxingFlag},
-    ohim::dom::node::HostDocument,
-    url::{DOMUrl, ImmutableOrigin},
+    ohim::dom::node::{
+        DomException as WitDomException, HostDocument, NodeOrString as WitNodeOrString,
+    },
+    path::{Path, PathError},
+    permissions_policy::{Feature, PermissionsPolicy},
+    string::DOMString,
+    time::{DOMHighResTimeStamp, coarsen_time, unsafe_shared_current_time},
+    url::{
+        DOMUrl, Host, ImmutableOrigin, MutableOrigin,
+        is_registrable_domain_suffix_of_or_is_equal_to,
+    },
+    user_agent::UserAgent,
 };
 
-use super::{ElementLocal, Node};
+use super::{
+    Comment, CustomElementRegistry, DocumentType, HTMLSlotElement, MemoryReport, Node,
+    NodeOrString, Range, TagName, Text, parser::parse_document,
+};
+
+/// The default maximum depth of a node tree. See [`Document::max_tree_depth`].
+pub const DEFAULT_MAX_TREE_DEPTH: usize = 4096;
 
 /// <https://dom.spec.whatwg.org/#document>
 #[derive(Clone, Debug)]
@@ -29,13 +46,14 @@ impl Document {
         mode: DocumentMode,
         origin: ImmutableOrigin,
         browsing_context: BrowsingContextID,
-        policy: bool,
+        policy: PermissionsPolicy,
         flags: SandboxingFlag,
-        time_info: bool,
+        time_info: DocumentLoadTimingInfo,
         is_blank: bool,
         base_url: Option<DOMUrl>,
         realm: RealmID,
         allow_shadow: bool,
+        custom_element_registry: Option<CustomElementRegistry>,
         mut store: impl AsContextMut,
     ) -> Result<Self> {
         let document = Document(Object::new(
@@ -53,6 +71,7 @@ impl Document {
                 base_url,
                 realm,
                 allow_shadow,
+                custom_element_registry,
             ))),
         )?);
 
@@ -63,53 +82,663 @@ impl Document {
         Ok(document)
     }
 
+    /// <https://html.spec.whatwg.org/multipage/#navigate-html>
+    ///
+    /// Create a new HTML `Document` for `url` from the bytes of a response body, and parse them
+    /// into it. The new document is created in the browsing context and realm of this document,
+    /// as loading a document replacing this one would do. Its mode is set from the parsed doctype.
+    ///
+    /// Only UTF-8 is supported for now. Other encodings are a "NotSupportedError" returned as a
+    /// [`DomException`] wrapped in the error.
+    /// TODO: Encoding sniffing and incremental parsing.
+    pub fn parse_html_document(
+        &self,
+        bytes: &[u8],
+        url: DOMUrl,
+        mut store: impl AsContextMut,
+    ) -> Result<Document> {
+        // The input byte stream is decoded as UTF-8 with the byte order mark removed.
+        let input = std::str::from_utf8(bytes)
+            .map_err(|_| not_supported!("decoding an encoding other than UTF-8"))?;
+        let input = input.strip_prefix('\u{FEFF}').unwrap_or(input);
+        let browsing_context = self
+            .browsing_context(&store)
+            .ok_or_else(|| Error::msg("document has no browsing context"))?;
+        let data = self.data(&store).as_document();
+        let (flags, realm, allow_shadow, registry) = (
+            data.flags,
+            data.realm,
+            data.allow_shadow,
+            data.custom_element_registry.clone(),
+        );
+        // Create and initialize a Document object with "html", "text/html" and navigationParams.
+        let document = Document::new(
+            true,
+            ContentType::html(),
+            DocumentMode::NoQuirks,
+            url.origin(),
+            browsing_context,
+            PermissionsPolicy::create(None, url.origin(), &store),
+            flags,
+            DocumentLoadTimingInfo {
+                navigation_start_time: coarsen_time(unsafe_shared_current_time(), false),
+                ..Default::default()
+            },
+            false,
+            None,
+            realm,
+            allow_shadow,
+            registry,
+            &mut store,
+        )?;
+        document.set_url(url, &mut store);
+        // Create an HTML parser associated with document and run it with the input byte stream.
+        parse_document(&document, input, &mut store)?;
+        Ok(document)
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-document-mode>
+    pub fn mode(&self, store: impl AsContext) -> DocumentMode {
+        self.data(&store).as_document().mode
+    }
+
+    /// Set the mode of the document, which is done by the parser.
+    pub(crate) fn set_mode(&self, mode: DocumentMode, mut store: impl AsContextMut) {
+        self.data_mut(&mut store).as_document_mut().mode = mode;
+    }
+
+    /// <https://dom.spec.whatwg.org/#document-custom-element-registry>
+    pub fn custom_element_registry(&self, store: impl AsContext) -> Option<CustomElementRegistry> {
+        self.data(&store)
+            .as_document()
+            .custom_element_registry
+            .clone()
+    }
+
     /// <https://dom.spec.whatwg.org/#concept-document-origin>
     pub fn origin(&self, store: impl AsContext) -> ImmutableOrigin {
+        self.data(&store).as_document().origin.immutable().clone()
+    }
+
+    /// The origin of the document along with its domain, which is set by [`Document::set_domain`].
+    pub fn mutable_origin(&self, store: impl AsContext) -> MutableOrigin {
         self.data(&store).as_document().origin.clone()
     }
 
+    /// <https://html.spec.whatwg.org/multipage/#dom-document-domain>
+    pub fn domain(&self, store: impl AsContext) -> DOMString {
+        // 1. Let effectiveDomain be this’s origin’s effective domain.
+        // 2. If effectiveDomain is null, then return the empty string.
+        // 3. Return effectiveDomain, serialized.
+        self.mutable_origin(&store)
+            .effective_domain()
+            .map(|domain| DOMString::from(domain.to_string()))
+            .unwrap_or_default()
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-document-domain>
+    ///
+    /// `window` is the global object of the running script, whose agent is the surrounding agent.
+    pub fn set_domain(
+        &self,
+        value: &str,
+        window: &Window,
+        store: impl AsContext,
+    ) -> Result<(), DomException> {
+        // 1. If this’s browsing context is null, then throw a "SecurityError" DOMException.
+        if self.browsing_context(&store).is_none() {
+            return Err(DomException::SecurityError(
+                "document has no browsing context".into(),
+            ));
+        }
+        // 2. If this’s active sandboxing flag set has its sandboxed document.domain browsing
+        // context flag set, then throw a "SecurityError" DOMException.
+        let data = self.data(&store).as_document();
+        if data
+            .flags
+            .contains(SandboxingFlag::DOCUMENT_DOMAIN_BROWSING_CONTEXT)
+        {
+            return Err(DomException::SecurityError(
+                "document.domain is sandboxed".into(),
+            ));
+        }
+        // 3. If this is not allowed to use the "document-domain" feature, then throw a
+        // "SecurityError" DOMException.
+        let origin = data.origin.clone();
+        if !self.is_feature_enabled(Feature::DocumentDomain, origin.immutable(), &store) {
+            return Err(DomException::SecurityError(
+                "document-domain is disabled by the permissions policy".into(),
+            ));
+        }
+        // 4. Let effectiveDomain be this’s origin’s effective domain.
+        // 5. If effectiveDomain is null, then throw a "SecurityError" DOMException.
+        let Some(effective_domain) = origin.effective_domain() else {
+            return Err(DomException::SecurityError(
+                "document has an opaque origin".into(),
+            ));
+        };
+        // 6. If the given value is not a registrable domain suffix of and is not equal to
+        // effectiveDomain, then throw a "SecurityError" DOMException.
+        if !is_registrable_domain_suffix_of_or_is_equal_to(value, &effective_domain) {
+            return Err(DomException::SecurityError(format!(
+                "{value:?} is not a registrable domain suffix of \"{effective_domain}\""
+            )));
+        }
+        // 7. If the surrounding agent’s agent cluster’s is origin-keyed is true, then return.
+        if window.is_origin_keyed(&store) {
+            return Ok(());
+        }
+        // 8. Set this’s origin’s domain to the result of parsing the given value.
+        let domain = Host::parse(value).expect("the value is checked to be a valid host");
+        origin.set_domain(domain);
+        Ok(())
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#concept-document-permissions-policy>
+    pub fn permissions_policy(&self, store: impl AsContext) -> PermissionsPolicy {
+        self.data(&store).as_document().policy.clone()
+    }
+
+    /// <https://w3c.github.io/webappsec-permissions-policy/#is-feature-enabled>
+    pub fn is_feature_enabled(
+        &self,
+        feature: Feature,
+        origin: &ImmutableOrigin,
+        store: impl AsContext,
+    ) -> bool {
+        self.data(&store)
+            .as_document()
+            .policy
+            .is_feature_enabled(feature, origin)
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#load-timing-info>
+    pub fn load_timing(&self, store: impl AsContext) -> DocumentLoadTimingInfo {
+        self.data(&store).as_document().time_info
+    }
+
     /// <https://html.spec.whatwg.org/multipage/#concept-document-about-base-url>
     pub fn about_base_url(&self, store: impl AsContext) -> Option<DOMUrl> {
         self.data(&store).as_document().about_base_url.clone()
     }
 
+    /// <https://html.spec.whatwg.org/multipage/#fallback-base-url>
+    pub fn fallback_base_url(&self, store: impl AsContext) -> DOMUrl {
+        let url = self.url(&store);
+        let about_base_url = self.about_base_url(&store);
+        // 1. If document is an iframe srcdoc document, then:
+        if url.is_about_srcdoc() {
+            // 1.1 Assert: document’s about base URL is non-null.
+            // 1.2 Return document’s about base URL.
+            return about_base_url.expect("iframe srcdoc document has an about base URL");
+        }
+        // 2. If document’s URL matches about:blank and document’s about base URL is non-null, then
+        // return document’s about base URL.
+        if let Some(about_base_url) = about_base_url.filter(|_| url.matches_about_blank()) {
+            return about_base_url;
+        }
+        // 3. Return document’s URL.
+        url
+    }
+
     /// <https://dom.spec.whatwg.org/#dom-document-url>
     pub fn url(&self, store: impl AsContext) -> DOMUrl {
         self.data(&store).as_document().url.clone()
     }
 
+    /// Set the document's URL.
+    pub(crate) fn set_url(&self, url: DOMUrl, mut store: impl AsContextMut) {
+        self.data_mut(&mut store).as_document_mut().url = url;
+    }
+
+    /// Get the maximum depth of the node tree of this document. A document has a depth of 0 and its
+    /// children have a depth of 1.
+    ///
+    /// Inserting a node deeper than this fails with a "HierarchyRequestError", so guests and parsed
+    /// content can not build trees deep enough to exhaust the host.
+    pub fn max_tree_depth(&self, store: impl AsContext) -> usize {
+        self.data(&store).as_document().max_tree_depth
+    }
+
+    /// Set the maximum depth of the node tree of this document. Existing trees deeper than `depth`
+    /// are kept, but no node can be inserted beyond it.
+    pub fn set_max_tree_depth(&self, depth: usize, mut store: impl AsContextMut) {
+        self.data_mut(&mut store).as_document_mut().max_tree_depth = depth;
+    }
+
+    /// Append `slot` to the signal slots, unless it is already there.
+    pub(crate) fn append_signal_slot(&self, slot: &HTMLSlotElement, mut store: impl AsContextMut) {
+        let node = Node::from(slot.clone());
+        let signaled = self
+            .data(&store)
+            .as_document()
+            .signal_slots
+            .iter()
+            .any(|signaled| node.is_same_node(&signaled.clone().into(), &store));
+        if !signaled {
+            self.data_mut(&mut store)
+                .as_document_mut()
+                .signal_slots
+                .push(slot.clone());
+        }
+    }
+
+    /// Take the slots whose assigned nodes changed, in the order their changes were signaled. A
+    /// slot is taken once however many times its change was signaled.
+    ///
+    /// Events can't be dispatched to guests yet, so this stands for the step of
+    /// [notify mutation observers](https://dom.spec.whatwg.org/#notify-mutation-observers) that
+    /// fires a "slotchange" event at each signal slot. The embedder's event loop should call this
+    /// and deliver the events to the guest.
+    pub fn take_signal_slots(&self, mut store: impl AsContextMut) -> Vec<HTMLSlotElement> {
+        std::mem::take(&mut self.data_mut(&mut store).as_document_mut().signal_slots)
+    }
+
+    /// <https://dom.spec.whatwg.org/#html-document>
+    pub fn is_html(&self, store: impl AsContext) -> bool {
+        self.data(&store).as_document().is_html
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-document-getelementsbytagname>
+    ///
+    /// This returns a snapshot instead of a live `HTMLCollection`.
+    pub fn get_elements_by_tag_name(
+        &self,
+        qualified_name: &str,
+        store: impl AsContext,
+    ) -> Vec<Element> {
+        Node::from(self.clone()).get_elements_by_tag_name(qualified_name, store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-parentnode-queryselector>
+    pub fn query_selector(
+        &self,
+        selectors: &str,
+        store: impl AsContext,
+    ) -> Result<Option<Element>, DomException> {
+        Node::from(self.clone()).query_selector(selectors, store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-parentnode-queryselectorall>
+    pub fn query_selector_all(
+        &self,
+        selectors: &str,
+        store: impl AsContext,
+    ) -> Result<Vec<Element>, DomException> {
+        Node::from(self.clone()).query_selector_all(selectors, store)
+    }
+
+    /// Find the node addressed by `path`. See [`path`](crate::path) for the syntax.
+    pub fn query_path(&self, path: &str, store: impl AsContext) -> Result<Option<Node>, PathError> {
+        let path = path.parse::<Path>()?;
+        Ok(path.evaluate(self, store))
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-parentnode-children>
+    ///
+    /// This returns a snapshot instead of a live `HTMLCollection`.
+    pub fn children(&self, store: impl AsContext) -> Vec<Element> {
+        Node::from(self.clone()).children(store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-parentnode-firstelementchild>
+    pub fn first_element_child(&self, store: impl AsContext) -> Option<Element> {
+        Node::from(self.clone()).first_element_child(store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-parentnode-lastelementchild>
+    pub fn last_element_child(&self, store: impl AsContext) -> Option<Element> {
+        Node::from(self.clone()).last_element_child(store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-parentnode-childelementcount>
+    pub fn child_element_count(&self, store: impl AsContext) -> usize {
+        Node::from(self.clone()).child_element_count(store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-parentnode-prepend>
+    pub fn prepend(
+        &self,
+        nodes: Vec<NodeOrString>,
+        store: impl AsContextMut,
+    ) -> Result<(), DomException> {
+        Node::from(self.clone()).prepend(nodes, store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-parentnode-append>
+    pub fn append(
+        &self,
+        nodes: Vec<NodeOrString>,
+        store: impl AsContextMut,
+    ) -> Result<(), DomException> {
+        Node::from(self.clone()).append(nodes, store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#internal-createelementns-steps>
+    ///
+    /// "InvalidCharacterError" and "NamespaceError" are returned as a [`DomException`] wrapped in
+    /// the error. A namespace other than the ones listed in Infra returns a "NotSupportedError"
+    /// since [`NameSpace`] can not represent it.
+    /// TODO: options
+    pub fn create_element_ns(
+        &self,
+        namespace: Option<DOMString>,
+        qualified_name: DOMString,
+        store: impl AsContextMut,
+    ) -> Result<Element> {
+        // 1. Let (namespace, prefix, localName) be the result of validating and extracting namespace
+        // and qualifiedName given "element".
+        let (namespace, prefix, local_name) =
+            validate_and_extract(namespace.as_deref(), &qualified_name)?;
+        // 2. TODO: Let is be null.
+        // 3. TODO: If options is a dictionary and options["is"] exists, then set is to it.
+        // 4. Return the result of creating an element given document, localName, namespace, prefix,
+        // is, and the synchronous custom elements flag set.
+        let tag_name = TagName::exact(local_name, namespace).with_prefix(prefix.map(Into::into));
+        Element::new(self, tag_name, None, store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-document-createtextnode>
+    pub fn create_text_node(&self, data: DOMString, store: impl AsContextMut) -> Result<Text> {
+        // The createTextNode(data) method steps are to return a new Text node whose data is data and
+        // node document is this.
+        Text::new(self, data, store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-document-createcomment>
+    pub fn create_comment(&self, data: DOMString, store: impl AsContextMut) -> Result<Comment> {
+        // The createComment(data) method steps are to return a new Comment node whose data is data
+        // and node document is this.
+        Comment::new(self, data, store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-document-createrange>
+    pub fn create_range(&self, store: impl AsContextMut) -> Result<Range> {
+        // The createRange() method steps are to return a new live range with (this, 0) as its start
+        // and end.
+        Range::new(self, store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-domimplementation-createdocumenttype>
+    ///
+    /// This is a method of `DOMImplementation` in the spec, whose associated document is this.
+    pub fn create_document_type(
+        &self,
+        name: DOMString,
+        public_id: DOMString,
+        system_id: DOMString,
+        store: impl AsContextMut,
+    ) -> Result<DocumentType, DomException> {
+        // 1. If name is not a valid doctype name, then throw an "InvalidCharacterError" DOMException.
+        // A string is a valid doctype name if it does not contain ASCII whitespace, U+0000 NULL, or
+        // U+003E (>).
+        if name
+            .chars()
+            .any(|c| c.is_ascii_whitespace() || c == '\0' || c == '>')
+        {
+            return Err(DomException::InvalidCharacterError(format!(
+                "{name:?} is not a valid doctype name"
+            )));
+        }
+        // 2. Return a new doctype, with name as its name, publicId as its public ID, and systemId as
+        // its system ID, and with its node document set to the associated document of this.
+        Ok(DocumentType::new(self, name, public_id, system_id, store)
+            .expect("Failed to create doctype"))
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-document-doctype>
+    pub fn doctype(&self, store: impl AsContext) -> Option<DocumentType> {
+        // The doctype getter steps are to return the child of this that is a doctype; otherwise
+        // null.
+        Node::from(self.clone())
+            .child_nodes(&store)
+            .into_iter()
+            .find(|child| child.data(&store).is_document_type())
+            .map(|child| DocumentType(child.0))
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#serialising-html-fragments>
+    ///
+    /// Get the HTML serialization of the whole document, including its doctype. Attributes are
+    /// serialized in the order they were added, with their values escaped.
+    pub fn serialize(&self, store: impl AsContext) -> DOMString {
+        Node::from(self.clone()).serialize(store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-document-importnode>
+    ///
+    /// A "NotSupportedError" is returned as a [`DomException`] wrapped in the error.
+    /// TODO: options
+    pub fn import_node(
+        &self,
+        node: &Node,
+        subtree: bool,
+        store: impl AsContextMut,
+    ) -> Result<Node, DomException> {
+        // 1. If node is a document or shadow root, then throw a "NotSupportedError" DOMException.
+        match node.data(&store).data {
+            NodeTypeData::Document(_) => {
+                return Err(DomException::NotSupportedError(
+                    "can not import a document".into(),
+                ));
+            }
+            NodeTypeData::ShadowRoot(_) => {
+                return Err(DomException::NotSupportedError(
+                    "can not import a shadow root".into(),
+                ));
+            }
+            _ => {}
+        }
+        // 2. TODO: Let registry be null.
+        // 3. TODO: If options is a dictionary, then set registry to options["customElementRegistry"].
+        // 4. TODO: If registry is null, then set registry to the result of looking up a custom element
+        // registry given this.
+        // 5. Return the result of cloning a node given node with document set to this, subtree set to
+        // subtree, and fallbackRegistry set to registry.
+        node.clone_with_document(self, subtree, store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-document-adoptnode>
+    pub fn adopt_node(&self, node: Node, store: impl AsContextMut) -> Result<Node, DomException> {
+        // 1. If node is a document, then throw a "NotSupportedError" DOMException.
+        if let NodeTypeData::Document(_) = node.data(&store).data {
+            return Err(DomException::NotSupportedError(
+                "can not adopt a document".into(),
+            ));
+        }
+        // 2. If node is a shadow root, then throw a "HierarchyRequestError" DOMException.
+        if let NodeTypeData::ShadowRoot(_) = node.data(&store).data {
+            return Err(DomException::HierarchyRequestError(
+                "can not adopt a shadow root".into(),
+            ));
+        }
+        // 3. TODO: If node is a DocumentFragment node whose host is non-null, then return node.
+        // 4. Adopt node into this.
+        node.adopt(Some(self.clone()), store);
+        // 5. Return node.
+        Ok(node)
+    }
+
     /// <https://dom.spec.whatwg.org/#dom-document-documentelement>
     pub fn document_element(&self, store: impl AsContext) -> Option<Element> {
         // The documentElement getter steps are to return this’s document element.
-        self.data(&store).as_document().document_element.clone()
+        // The document element of a document is the element whose parent is that document, if it
+        // exists; otherwise null.
+        Node::from(self.clone()).first_element_child(&store)
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#the-html-element-2>
+    fn html_element(&self, store: impl AsContext) -> Option<Element> {
+        // The html element of a document is its document element, if it’s an html element, and
+        // null otherwise.
+        self.document_element(&store)
+            .filter(|element| is_html_element(element, "html", &store))
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-document-head>
+    pub fn head(&self, store: impl AsContext) -> Option<Element> {
+        // The head element of a document is the first head element that is a child of the html
+        // element, if there is one, or null otherwise.
+        self.html_element(&store)?
+            .children(&store)
+            .into_iter()
+            .find(|child| is_html_element(child, "head", &store))
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-document-body>
+    ///
+    /// TODO: The setter.
+    pub fn body(&self, store: impl AsContext) -> Option<Element> {
+        // The body element of a document is the first of the html element’s children that is
+        // either a body element or a frameset element, or null if there is no such element.
+        self.html_element(&store)?
+            .children(&store)
+            .into_iter()
+            .find(|child| {
+                is_html_element(child, "body", &store) || is_html_element(child, "frameset", &store)
+            })
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#the-title-element-2>
+    fn title_element(&self, store: impl AsContext) -> Option<Element> {
+        // The title element of a document is the first title element in the document (in tree
+        // order), if there is one, or null otherwise.
+        Node::from(self.clone())
+            .descendants(&store)
+            .into_iter()
+            .filter(|node| node.data(&store).is_element())
+            .map(|node| Element(node.0))
+            .find(|element| is_html_element(element, "title", &store))
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#document.title>
+    pub fn title(&self, store: impl AsContext) -> DOMString {
+        // 1. TODO: If the document element is an SVG svg element, then let value be the child text
+        // content of the first SVG title element that is a child of the document element.
+        // 2. Otherwise, let value be the child text content of the title element, or the empty
+        // string if the title element is null.
+        let mut value = self
+            .title_element(&store)
+            .map(|title| Node::from(title).child_text_content(&store))
+            .unwrap_or_default();
+        // 3. Strip and collapse ASCII whitespace in value.
+        value.strip_and_collapse_ascii_whitespace();
+        // 4. Return value.
+        value
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#document.title>
+    pub fn set_title(&self, value: DOMString, mut store: impl AsContextMut) -> Result<()> {
+        // 1. TODO: If the document element is an SVG svg element, then set the child text content
+        // of its first SVG title element child.
+        // 2. Otherwise, if the document element is in the HTML namespace:
+        if !self
+            .document_element(&store)
+            .is_some_and(|element| element.is_html_namespace(&store))
+        {
+            // 3. Otherwise, do nothing.
+            return Ok(());
+        }
+        // 2.1 If the title element is null and the head element is null, then return.
+        // 2.2 If the title element is non-null, let element be the title element.
+        let element = match (self.title_element(&store), self.head(&store)) {
+            (Some(title), _) => title,
+            (None, None) => return Ok(()),
+            // 2.3 Otherwise:
+            (None, Some(head)) => {
+                // 2.3.1 Let element be the result of creating an element given the document
+                // element’s node document, "title", and the HTML namespace.
+                let element = Element::new(self, TagName::html("title"), None, &mut store)?;
+                // 2.3.2 Append element to the head element.
+                Node::from(head).pre_insert(element.clone().into(), None, &mut store)?;
+                element
+            }
+        };
+        // 2.4 String replace all with the given value within element.
+        Node::from(element).string_replace_all(value, store)
     }
 
     /// <https://html.spec.whatwg.org/multipage/#populate-with-html/head/body>
     pub fn populate_hhb(&self, mut store: impl AsContextMut) -> Result<()> {
         // 1. Let html be the result of creating an element given document, "html", and the HTML namespace.
-        let html: Node =
-            Element::new(self, ElementLocal::Html, NameSpace::HTML, None, &mut store)?.into();
+        let html: Node = Element::new(self, TagName::html("html"), None, &mut store)?.into();
         // 2. Let head be the result of creating an element given document, "head", and the HTML namespace.
-        let head =
-            Element::new(self, ElementLocal::Head, NameSpace::HTML, None, &mut store)?.into();
+        let head = Element::new(self, TagName::html("head"), None, &mut store)?.into();
         // 3. Let body be the result of creating an element given document, "body", and the HTML namespace.
-        let body =
-            Element::new(self, ElementLocal::Body, NameSpace::HTML, None, &mut store)?.into();
+        let body = Element::new(self, TagName::html("body"), None, &mut store)?.into();
         // 4. Append html to document.
         let document: Node = self.clone().into();
-        document.pre_insert(html.clone(), None, &mut store);
+        document.pre_insert(html.clone(), None, &mut store)?;
         // 5. Append head to html.
-        html.pre_insert(head, None, &mut store);
+        html.pre_insert(head, None, &mut store)?;
         // 6. Append body to html.
-        html.pre_insert(body, None, &mut store);
+        html.pre_insert(body, None, &mut store)?;
         Ok(())
     }
 
+    /// <https://html.spec.whatwg.org/multipage/#concept-relevant-global>
+    pub fn relevant_global_object(
+        &self,
+        user_agent: &UserAgent,
+        store: impl AsContext,
+    ) -> Option<Window> {
+        let id = self.data(&store).as_document().realm;
+        user_agent
+            .realms
+            .get(&id)
+            .and_then(|realm| realm.global_object.clone())
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#relevant-settings-object>
+    pub fn relevant_settings_object<'a>(
+        &self,
+        user_agent: &'a UserAgent,
+        store: impl AsContext,
+    ) -> Option<&'a Environment> {
+        let id = self.data(&store).as_document().realm;
+        user_agent
+            .realms
+            .get(&id)
+            .and_then(|realm| realm.settings_object.as_ref())
+    }
+
+    /// Report the host memory used by the nodes of this document.
+    ///
+    /// `ops` measures heap blocks with the allocator of the embedder. It must be able to tell the
+    /// pointers it has seen, since URLs are shared.
+    pub fn report_memory(&self, ops: &mut MallocSizeOfOps, store: impl AsContext) -> MemoryReport {
+        MemoryReport::measure(&Node::from(self.clone()), ops, store)
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#concept-document-bc>
+    pub fn browsing_context(&self, store: impl AsContext) -> Option<BrowsingContextID> {
+        self.data(&store).as_document().browsing_context
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#discard-a-document>
+    ///
+    /// The relevant realm of the document is removed from `user_agent` as well, since nothing refers
+    /// to it once the document is gone.
+    pub fn discard(&self, user_agent: &mut UserAgent, store: impl AsContext) {
+        // 1. TODO: Set document's salvageable state to false.
+        // 2. TODO: Run any unloading document cleanup steps given document.
+        // 3. TODO: Abort document.
+        // 4. TODO: Remove any tasks whose document is document from any task queue.
+        // 5. TODO: Discard all the child navigables of document.
+        Realm::destroy(user_agent, self.data(&store).as_document().realm);
+    }
+
     /// <https://html.spec.whatwg.org/multipage/#make-active>
-    pub fn active(&self, context: &mut BrowsingContext, visibility: bool, store: impl AsContext) {
+    pub fn active(
+        &self,
+        user_agent: &mut UserAgent,
+        context: &mut BrowsingContext,
+        visibility: bool,
+        store: impl AsContext,
+    ) {
         let id = self.data(&store).as_document().realm;
         let mut window = None;
-        if let Some(realm) = RELEVANT_REALM.lock().unwrap().get_mut(&id) {
+        if let Some(realm) = user_agent.realms.get_mut(&id) {
             // 1. Let window be document's relevant global object.
             window = realm.global_object.clone();
             // 5. Set window's relevant settings object's execution ready flag.
@@ -134,21 +763,31 @@ impl Document {
 }
 
 impl NodeImpl {
+    /// Get `DocumentImpl` shared reference, or `None` if the node is not a `Document`.
+    pub fn try_as_document(&self) -> Option<&DocumentImpl> {
+        match self.data {
+            NodeTypeData::Document(ref doc) => Some(doc),
+            _ => None,
+        }
+    }
+
     /// Get `DocumentImpl` shared reference.
     fn as_document(&self) -> &DocumentImpl {
-        let NodeTypeData::Document(ref doc) = self.data else {
-            unreachable!()
-        };
-        doc
+        self.try_as_document().expect("node is not a Document")
+    }
+
+    /// Get `DocumentImpl` exclusive reference, or `None` if the node is not a `Document`.
+    pub fn try_as_document_mut(&mut self) -> Option<&mut DocumentImpl> {
+        match self.data {
+            NodeTypeData::Document(ref mut doc) => Some(doc),
+            _ => None,
+        }
     }
 
-    // /// Get `DocumentImpl` exclusive reference.
-    // fn as_document_mut(&mut self) -> &mut DocumentImpl {
-    //     let NodeTypeData::Document(ref mut doc) = self.data else {
-    //         unreachable!()
-    //     };
-    //     doc
-    // }
+    /// Get `DocumentImpl` exclusive reference.
+    fn as_document_mut(&mut self) -> &mut DocumentImpl {
+        self.try_as_document_mut().expect("node is not a Document")
+    }
 }
 
 impl Deref for Document {
@@ -163,34 +802,65 @@ impl Deref for Document {
 #[derive(Debug)]
 pub struct DocumentImpl {
     /// <https://dom.spec.whatwg.org/#concept-document-type>
-    _is_html: bool,
+    is_html: bool,
     /// <https://dom.spec.whatwg.org/#concept-document-content-type>
     _content_type: ContentType,
     /// <https://dom.spec.whatwg.org/#concept-document-mode>
-    _mode: DocumentMode,
+    mode: DocumentMode,
     /// <https://dom.spec.whatwg.org/#concept-document-origin>
-    origin: ImmutableOrigin,
+    origin: MutableOrigin,
     /// <https://html.spec.whatwg.org/multipage/#concept-document-bc>
-    _browsing_context: Option<BrowsingContextID>,
+    browsing_context: Option<BrowsingContextID>,
     /// <https://html.spec.whatwg.org/multipage/#concept-document-permissions-policy>
-    _policy: bool,
+    policy: PermissionsPolicy,
     /// <https://html.spec.whatwg.org/multipage/browsers.html#active-sandboxing-flag-set>
-    _flags: SandboxingFlag,
+    flags: SandboxingFlag,
     /// <https://html.spec.whatwg.org/multipage/dom.html#load-timing-info>
-    _time_info: bool,
+    time_info: DocumentLoadTimingInfo,
     /// <https://html.spec.whatwg.org/multipage/dom.html#is-initial-about:blank>
     _is_blank: bool,
     /// <https://html.spec.whatwg.org/multipage/#concept-document-about-base-url>
     about_base_url: Option<DOMUrl>,
     /// <https://dom.spec.whatwg.org/#document-allow-declarative-shadow-roots>
-    _allow_shadow: bool,
+    allow_shadow: bool,
     /// <https://dom.spec.whatwg.org/#document-custom-element-registry>
-    _custom_element: Option<bool>,
+    custom_element_registry: Option<CustomElementRegistry>,
     /// <https://dom.spec.whatwg.org/#concept-document-url>
     url: DOMUrl,
     realm: RealmID,
-    document_element: Option<Element>,
     visibility: AtomicBool,
+    max_tree_depth: usize,
+    /// <https://dom.spec.whatwg.org/#signal-slot-list>
+    ///
+    /// The signal slots belong to the similar-origin window agent in the spec. They are kept by the
+    /// node document of the slots until there is an event loop to notify mutation observers.
+    signal_slots: Vec<HTMLSlotElement>,
+}
+
+/// <https://html.spec.whatwg.org/multipage/#document-load-timing-info>
+///
+/// The times are coarsened moments given by [`unsafe_shared_current_time`], or zero if they
+/// haven't happened yet.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DocumentLoadTimingInfo {
+    /// <https://html.spec.whatwg.org/multipage/#navigation-start-time>
+    pub navigation_start_time: DOMHighResTimeStamp,
+    /// <https://html.spec.whatwg.org/multipage/#dom-content-loaded-event-start-time>
+    pub dom_content_loaded_event_start: DOMHighResTimeStamp,
+    /// <https://html.spec.whatwg.org/multipage/#dom-content-loaded-event-end-time>
+    pub dom_content_loaded_event_end: DOMHighResTimeStamp,
+    /// <https://html.spec.whatwg.org/multipage/#load-event-start-time>
+    pub load_event_start: DOMHighResTimeStamp,
+    /// <https://html.spec.whatwg.org/multipage/#load-event-end-time>
+    pub load_event_end: DOMHighResTimeStamp,
+}
+
+impl MallocSizeOf for DocumentImpl {
+    fn size_of(&self, ops: &mut MallocSizeOfOps) -> usize {
+        // The other fields own no heap memory, or are owned by the user agent or the GC heap.
+        // TODO: The permissions policy.
+        self.origin.size_of(ops) + self.about_base_url.size_of(ops) + self.url.size_of(ops)
+    }
 }
 
 impl DocumentImpl {
@@ -202,70 +872,445 @@ impl DocumentImpl {
         mode: DocumentMode,
         origin: ImmutableOrigin,
         browsing_context: BrowsingContextID,
-        policy: bool,
+        policy: PermissionsPolicy,
         flags: SandboxingFlag,
-        time_info: bool,
+        time_info: DocumentLoadTimingInfo,
         is_blank: bool,
         base_url: Option<DOMUrl>,
         realm: RealmID,
         allow_shadow: bool,
+        custom_element_registry: Option<CustomElementRegistry>,
     ) -> Self {
         DocumentImpl {
-            _is_html: is_html,
+            is_html,
             _content_type: content_type,
-            _mode: mode,
-            origin,
-            _browsing_context: Some(browsing_context),
-            _policy: policy,
-            _flags: flags,
-            _time_info: time_info,
+            mode,
+            origin: MutableOrigin::new(origin),
+            browsing_context: Some(browsing_context),
+            policy,
+            flags,
+            time_info,
             _is_blank: is_blank,
             about_base_url: base_url,
-            _allow_shadow: allow_shadow,
-            _custom_element: None,
+            allow_shadow,
+            custom_element_registry,
             url: DOMUrl::parse("about:blank").unwrap(),
             realm,
-            document_element: None,
             visibility: Default::default(),
+            max_tree_depth: DEFAULT_MAX_TREE_DEPTH,
+            signal_slots: Vec::new(),
         }
     }
 }
 
+impl WindowStates {
+    /// Get the `Document` of `resource`.
+    ///
+    /// This returns an error instead of panicking if the object is not a `Document`.
+    fn get_document(&self, resource: &Resource<Document>) -> Result<Document> {
+        let document = self.table.get(resource)?;
+        document
+            .try_data(&self.store)?
+            .try_as_document()
+            .ok_or_else(|| Error::msg("object is not a Document"))?;
+        Ok(document.clone())
+    }
+}
+
 impl HostDocument for WindowStates {
     fn new(&mut self) -> Result<Resource<Document>> {
-        // FIXME: This is only for demo purpose
-        // let element = Element::new(&mut self.store)?;
-        // let document = Document::new(&mut self.store)?;
-        // document
-        //     .data_mut(&mut self.store)
-        //     .as_document_mut()
-        //     .document_element = Some(element);
-        //
-        // Ok(self.table.push(document)?)
-        todo!()
+        // FIXME: The constructor should create a new Document. Until then it returns the active
+        // document of the window.
+        let document = self
+            .window
+            .document(&self.store)
+            .ok_or_else(|| Error::msg("window has no associated document"))?;
+        Ok(self.push_resource(document)?)
     }
 
     fn drop(&mut self, rep: Resource<Document>) -> Result<()> {
-        self.table.delete(rep)?;
+        self.delete_resource(rep)?;
         Ok(())
     }
 
     fn url(&mut self, self_: Resource<Document>) -> Result<String> {
-        let self_ = self.table.get(&self_)?;
+        let self_ = self.get_document(&self_)?;
         Ok(self_.url(&self.store).to_string())
     }
 
+    fn domain(&mut self, self_: Resource<Document>) -> Result<String> {
+        let self_ = self.get_document(&self_)?;
+        Ok(self_.domain(&self.store).to_string())
+    }
+
+    fn set_domain(
+        &mut self,
+        self_: Resource<Document>,
+        value: String,
+    ) -> Result<Result<(), WitDomException>> {
+        let self_ = self.get_document(&self_)?;
+        Ok(self_
+            .set_domain(&value, &self.window, &self.store)
+            .map_err(Into::into))
+    }
+
+    fn origin(&mut self, self_: Resource<Document>) -> Result<String> {
+        let self_ = self.get_document(&self_)?;
+        Ok(self_.origin(&self.store).ascii_serialization())
+    }
+
+    fn import_node(
+        &mut self,
+        self_: Resource<Document>,
+        node: Resource<Node>,
+        subtree: bool,
+    ) -> Result<Result<Resource<Node>, WitDomException>> {
+        let self_ = self.get_document(&self_)?;
+        let node = self.get_node(&node)?;
+        match self_.import_node(&node, subtree, &mut self.store) {
+            Ok(node) => Ok(Ok(self.push_resource(node)?)),
+            Err(e) => Ok(Err(e.into())),
+        }
+    }
+
+    fn adopt_node(
+        &mut self,
+        self_: Resource<Document>,
+        node: Resource<Node>,
+    ) -> Result<Result<Resource<Node>, WitDomException>> {
+        let self_ = self.get_document(&self_)?;
+        let node = self.get_node(&node)?;
+        match self_.adopt_node(node, &mut self.store) {
+            Ok(node) => Ok(Ok(self.push_resource(node)?)),
+            Err(e) => Ok(Err(e.into())),
+        }
+    }
+
     fn document_element(&mut self, self_: Resource<Document>) -> Result<Option<Resource<Element>>> {
-        let self_ = self.table.get(&self_)?;
+        let self_ = self.get_document(&self_)?;
         match self_.document_element(&self.store) {
-            Some(e) => Ok(Some(self.table.push(e)?)),
+            Some(e) => Ok(Some(self.push_resource(e)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn title(&mut self, self_: Resource<Document>) -> Result<String> {
+        let self_ = self.get_document(&self_)?;
+        Ok(self_.title(&self.store).into())
+    }
+
+    fn set_title(&mut self, self_: Resource<Document>, value: String) -> Result<()> {
+        let self_ = self.get_document(&self_)?;
+        self_.set_title(value.into(), &mut self.store)
+    }
+
+    fn head(&mut self, self_: Resource<Document>) -> Result<Option<Resource<Element>>> {
+        let self_ = self.get_document(&self_)?;
+        match self_.head(&self.store) {
+            Some(e) => Ok(Some(self.push_resource(e)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn body(&mut self, self_: Resource<Document>) -> Result<Option<Resource<Element>>> {
+        let self_ = self.get_document(&self_)?;
+        match self_.body(&self.store) {
+            Some(e) => Ok(Some(self.push_resource(e)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn children(&mut self, self_: Resource<Document>) -> Result<Vec<Resource<Element>>> {
+        let self_ = self.get_document(&self_)?;
+        let children = self_.children(&self.store);
+        children
+            .into_iter()
+            .map(|element| Ok(self.push_resource(element)?))
+            .collect()
+    }
+
+    fn first_element_child(
+        &mut self,
+        self_: Resource<Document>,
+    ) -> Result<Option<Resource<Element>>> {
+        let self_ = self.get_document(&self_)?;
+        match self_.first_element_child(&self.store) {
+            Some(e) => Ok(Some(self.push_resource(e)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn last_element_child(
+        &mut self,
+        self_: Resource<Document>,
+    ) -> Result<Option<Resource<Element>>> {
+        let self_ = self.get_document(&self_)?;
+        match self_.last_element_child(&self.store) {
+            Some(e) => Ok(Some(self.push_resource(e)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn child_element_count(&mut self, self_: Resource<Document>) -> Result<u32> {
+        let self_ = self.get_document(&self_)?;
+        Ok(self_.child_element_count(&self.store) as u32)
+    }
+
+    fn prepend(
+        &mut self,
+        self_: Resource<Document>,
+        nodes: Vec<WitNodeOrString>,
+    ) -> Result<Result<(), WitDomException>> {
+        let nodes = self.get_nodes_or_strings(nodes)?;
+        let self_ = self.get_document(&self_)?;
+        Ok(self_.prepend(nodes, &mut self.store).map_err(Into::into))
+    }
+
+    fn append(
+        &mut self,
+        self_: Resource<Document>,
+        nodes: Vec<WitNodeOrString>,
+    ) -> Result<Result<(), WitDomException>> {
+        let nodes = self.get_nodes_or_strings(nodes)?;
+        let self_ = self.get_document(&self_)?;
+        Ok(self_.append(nodes, &mut self.store).map_err(Into::into))
+    }
+
+    fn query_selector(
+        &mut self,
+        self_: Resource<Document>,
+        selectors: String,
+    ) -> Result<Result<Option<Resource<Element>>, WitDomException>> {
+        let self_ = self.get_document(&self_)?;
+        match self_.query_selector(&selectors, &self.store) {
+            Ok(Some(element)) => Ok(Ok(Some(self.push_resource(element)?))),
+            Ok(None) => Ok(Ok(None)),
+            Err(e) => Ok(Err(e.into())),
+        }
+    }
+
+    fn query_selector_all(
+        &mut self,
+        self_: Resource<Document>,
+        selectors: String,
+    ) -> Result<Result<Vec<Resource<Element>>, WitDomException>> {
+        let self_ = self.get_document(&self_)?;
+        match self_.query_selector_all(&selectors, &self.store) {
+            Ok(elements) => Ok(Ok(elements
+                .into_iter()
+                .map(|element| Ok(self.push_resource(element)?))
+                .collect::<Result<_>>()?)),
+            Err(e) => Ok(Err(e.into())),
+        }
+    }
+
+    fn get_elements_by_tag_name(
+        &mut self,
+        self_: Resource<Document>,
+        qualified_name: String,
+    ) -> Result<Vec<Resource<Element>>> {
+        let self_ = self.get_document(&self_)?;
+        let elements = self_.get_elements_by_tag_name(&qualified_name, &self.store);
+        elements
+            .into_iter()
+            .map(|element| Ok(self.push_resource(element)?))
+            .collect()
+    }
+
+    fn create_element_ns(
+        &mut self,
+        self_: Resource<Document>,
+        namespace: Option<String>,
+        qualified_name: String,
+    ) -> Result<Result<Resource<Element>, WitDomException>> {
+        let self_ = self.get_document(&self_)?;
+        match self_.create_element_ns(
+            namespace.map(Into::into),
+            qualified_name.into(),
+            &mut self.store,
+        ) {
+            Ok(element) => Ok(Ok(self.push_resource(element)?)),
+            Err(e) => Ok(Err(e.downcast::<DomException>()?.into())),
+        }
+    }
+
+    fn create_text_node(
+        &mut self,
+        self_: Resource<Document>,
+        data: String,
+    ) -> Result<Resource<Text>> {
+        let self_ = self.get_document(&self_)?;
+        let text = self_.create_text_node(data.into(), &mut self.store)?;
+        Ok(self.push_resource(text)?)
+    }
+
+    fn create_comment(
+        &mut self,
+        self_: Resource<Document>,
+        data: String,
+    ) -> Result<Resource<Comment>> {
+        let self_ = self.get_document(&self_)?;
+        let comment = self_.create_comment(data.into(), &mut self.store)?;
+        Ok(self.push_resource(comment)?)
+    }
+
+    fn create_document_type(
+        &mut self,
+        self_: Resource<Document>,
+        name: String,
+        public_id: String,
+        system_id: String,
+    ) -> Result<Result<Resource<DocumentType>, WitDomException>> {
+        let self_ = self.get_document(&self_)?;
+        match self_.create_document_type(
+            name.into(),
+            public_id.into(),
+            system_id.into(),
+            &mut self.store,
+        ) {
+            Ok(doctype) => Ok(Ok(self.push_resource(doctype)?)),
+            Err(e) => Ok(Err(e.into())),
+        }
+    }
+
+    fn serialize(&mut self, self_: Resource<Document>) -> Result<String> {
+        let self_ = self.get_document(&self_)?;
+        Ok(self_.serialize(&self.store).to_string())
+    }
+
+    fn doctype(&mut self, self_: Resource<Document>) -> Result<Option<Resource<DocumentType>>> {
+        let self_ = self.get_document(&self_)?;
+        match self_.doctype(&self.store) {
+            Some(doctype) => Ok(Some(self.push_resource(doctype)?)),
             None => Ok(None),
         }
     }
 }
 
+/// Check if `element` is in the HTML namespace and its local name is `local_name`.
+fn is_html_element(element: &Element, local_name: &str, store: impl AsContext) -> bool {
+    element.is_html_namespace(&store) && element.local_name(&store) == local_name
+}
+
+/// <https://dom.spec.whatwg.org/#validate-and-extract>
+///
+/// The context is always "element" since attributes have no namespace yet.
+fn validate_and_extract<'a>(
+    namespace: Option<&str>,
+    qualified_name: &'a str,
+) -> Result<(NameSpace, Option<&'a str>, &'a str)> {
+    // 1. If namespace is the empty string, then set it to null.
+    let namespace = match namespace.filter(|namespace| !namespace.is_empty()) {
+        Some(url) => {
+            NameSpace::from_url(url).ok_or_else(|| not_supported!(format!("namespace {url:?}")))?
+        }
+        None => NameSpace::None,
+    };
+    // 2. Let prefix be null.
+    // 3. Let localName be qualifiedName.
+    // 4. If qualifiedName contains a U+003A (:):
+    // 4.1 Let splitResult be the result of running strictly split given qualifiedName and U+003A
+    // (:).
+    // 4.2 Set prefix to splitResult[0].
+    // 4.3 Set localName to splitResult[1].
+    // 4.4 If prefix is not a valid namespace prefix, then throw an "InvalidCharacterError"
+    // DOMException.
+    let (prefix, local_name) = match qualified_name.split_once(':') {
+        Some((prefix, local_name)) => {
+            if !is_valid_namespace_prefix(prefix) {
+                return Err(DomException::InvalidCharacterError(format!(
+                    "{prefix:?} is not a valid namespace prefix"
+                ))
+                .into());
+            }
+            (Some(prefix), local_name)
+        }
+        None => (None, qualified_name),
+    };
+    // 5. Assert: prefix is either null or a valid namespace prefix.
+    // 6. If context is "attribute" and localName is not a valid attribute local name, then throw an
+    // "InvalidCharacterError" DOMException.
+    // 7. If context is "element" and localName is not a valid element local name, then throw an
+    // "InvalidCharacterError" DOMException.
+    if !is_valid_element_local_name(local_name) {
+        return Err(DomException::InvalidCharacterError(format!(
+            "{local_name:?} is not a valid element local name"
+        ))
+        .into());
+    }
+    // 8. If prefix is non-null and namespace is null, then throw a "NamespaceError" DOMException.
+    if prefix.is_some() && namespace == NameSpace::None {
+        return Err(DomException::NamespaceError("a prefix requires a namespace".into()).into());
+    }
+    // 9. If prefix is "xml" and namespace is not the XML namespace, then throw a "NamespaceError"
+    // DOMException.
+    if prefix == Some("xml") && namespace != NameSpace::XML {
+        return Err(DomException::NamespaceError(
+            "the xml prefix requires the XML namespace".into(),
+        )
+        .into());
+    }
+    // 10. If either qualifiedName or prefix is "xmlns" and namespace is not the XMLNS namespace,
+    // then throw a "NamespaceError" DOMException.
+    let is_xmlns = qualified_name == "xmlns" || prefix == Some("xmlns");
+    if is_xmlns && namespace != NameSpace::XMLNS {
+        return Err(DomException::NamespaceError(
+            "the xmlns name or prefix requires the XMLNS namespace".into(),
+        )
+        .into());
+    }
+    // 11. If namespace is the XMLNS namespace and neither qualifiedName nor prefix is "xmlns", then
+    // throw a "NamespaceError" DOMException.
+    if namespace == NameSpace::XMLNS && !is_xmlns {
+        return Err(DomException::NamespaceError(
+            "the XMLNS namespace requires the xmlns name or prefix".into(),
+        )
+        .into());
+    }
+    // 12. Return (namespace, prefix, localName).
+    Ok((namespace, prefix, local_name))
+}
+
+/// <https://dom.spec.whatwg.org/#valid-namespace-prefix>
+fn is_valid_namespace_prefix(prefix: &str) -> bool {
+    // A string is a valid namespace prefix if its length is at least 1 and it does not contain
+    // ASCII whitespace, U+0000 NULL, U+002F (/), or U+003E (>).
+    !prefix.is_empty()
+        && !prefix
+            .chars()
+            .any(|c| c.is_ascii_whitespace() || matches!(c, '\0' | '/' | '>'))
+}
+
+/// <https://dom.spec.whatwg.org/#valid-element-local-name>
+fn is_valid_element_local_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        // 1. If name’s length is 0, then return false.
+        None => false,
+        // 2. If name’s 0th code point is an ASCII alpha, then:
+        // 2.1 If name contains ASCII whitespace, U+0000 NULL, U+002F (/), or U+003E (>), then return
+        // false.
+        // 2.2 Return true.
+        Some(c) if c.is_ascii_alphabetic() => {
+            !chars.any(|c| c.is_ascii_whitespace() || matches!(c, '\0' | '/' | '>'))
+        }
+        // 3. If name’s 0th code point is not U+003A (:), U+005F (_), or in the range U+0080 to
+        // U+10FFFF, inclusive, then return false.
+        // 4. If name’s subsequent code points, if any, are not ASCII alphas, ASCII digits, U+002D
+        // (-), U+002E (.), U+003A (:), U+005F (_), or in the range U+0080 to U+10FFFF, inclusive,
+        // then return false.
+        // 5. Return true.
+        Some(c) => {
+            matches!(c, ':' | '_' | '\u{80}'..)
+                && chars.all(|c| {
+                    c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | ':' | '_' | '\u{80}'..)
+                })
+        }
+    }
+}
+
 /// <https://dom.spec.whatwg.org/#concept-document-mode>
-#[derive(Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub enum DocumentMode {
     /// "no-quirks"
     #[default]
@@ -275,3 +1320,533 @@ pub enum DocumentMode {
     /// "limited-quirks"
     LimitedQuirks,
 }
+
+impl DocumentMode {
+    /// <https://html.spec.whatwg.org/multipage/#the-initial-insertion-mode>
+    ///
+    /// Get the mode of a document from the DOCTYPE token the parser sees first. `None` stands for a
+    /// missing name, public identifier or system identifier.
+    /// TODO: An iframe srcdoc document is never in quirks mode.
+    pub fn from_doctype(
+        name: Option<&str>,
+        public_id: Option<&str>,
+        system_id: Option<&str>,
+        force_quirks: bool,
+    ) -> Self {
+        // The comparisons of the public and system identifiers are ASCII case-insensitive.
+        let public_id = public_id.map(str::to_ascii_lowercase);
+        let system_id = system_id.map(str::to_ascii_lowercase);
+        let public_id_starts_with = |prefix: &str| {
+            public_id
+                .as_deref()
+                .is_some_and(|id| id.starts_with(prefix))
+        };
+        let html_4_01 = [
+            "-//w3c//dtd html 4.01 frameset//",
+            "-//w3c//dtd html 4.01 transitional//",
+        ];
+        // Then, if the document is not an iframe srcdoc document, and the parser cannot change the
+        // mode flag is false, and the DOCTYPE token matches one of the conditions in the following
+        // list, then set the Document to quirks mode:
+        let is_quirks = force_quirks
+            || name != Some("html")
+            || public_id.as_deref().is_some_and(|id| {
+                QUIRKS_PUBLIC_IDS.contains(&id)
+                    || QUIRKS_PUBLIC_ID_PREFIXES
+                        .iter()
+                        .any(|prefix| id.starts_with(prefix))
+            })
+            || system_id.as_deref()
+                == Some("http://www.ibm.com/data/dtd/v11/ibmxhtml1-transitional.dtd")
+            || (system_id.is_none() && html_4_01.into_iter().any(public_id_starts_with));
+        if is_quirks {
+            return DocumentMode::Quirks;
+        }
+        // Otherwise, if the document is not an iframe srcdoc document, and the parser cannot change
+        // the mode flag is false, and the DOCTYPE token matches one of the conditions in the
+        // following list, then set the Document to limited-quirks mode:
+        let is_limited_quirks = [
+            "-//w3c//dtd xhtml 1.0 frameset//",
+            "-//w3c//dtd xhtml 1.0 transitional//",
+        ]
+        .into_iter()
+        .any(public_id_starts_with)
+            || (system_id.is_some() && html_4_01.into_iter().any(public_id_starts_with));
+        if is_limited_quirks {
+            return DocumentMode::LimitedQuirks;
+        }
+        DocumentMode::NoQuirks
+    }
+}
+
+/// The public identifiers that are set to exactly one of these strings put a document in quirks
+/// mode. They are in lowercase for ASCII case-insensitive comparison.
+const QUIRKS_PUBLIC_IDS: [&str; 3] = [
+    "-//w3o//dtd w3 html strict 3.0//en//",
+    "-/w3c/dtd html 4.0 transitional/en",
+    "html",
+];
+
+/// The public identifiers that start with one of these strings put a document in quirks mode.
+/// They are in lowercase for ASCII case-insensitive comparison.
+const QUIRKS_PUBLIC_ID_PREFIXES: [&str; 55] = [
+    "+//silmaril//dtd html pro v0r11 19970101//",
+    "-//as//dtd html 3.0 aswedit + extensions//",
+    "-//advasoft ltd//dtd html 3.0 aswedit + extensions//",
+    "-//ietf//dtd html 2.0 level 1//",
+    "-//ietf//dtd html 2.0 level 2//",
+    "-//ietf//dtd html 2.0 strict level 1//",
+    "-//ietf//dtd html 2.0 strict level 2//",
+    "-//ietf//dtd html 2.0 strict//",
+    "-//ietf//dtd html 2.0//",
+    "-//ietf//dtd html 2.1e//",
+    "-//ietf//dtd html 3.0//",
+    "-//ietf//dtd html 3.2 final//",
+    "-//ietf//dtd html 3.2//",
+    "-//ietf//dtd html 3//",
+    "-//ietf//dtd html level 0//",
+    "-//ietf//dtd html level 1//",
+    "-//ietf//dtd html level 2//",
+    "-//ietf//dtd html level 3//",
+    "-//ietf//dtd html strict level 0//",
+    "-//ietf//dtd html strict level 1//",
+    "-//ietf//dtd html strict level 2//",
+    "-//ietf//dtd html strict level 3//",
+    "-//ietf//dtd html strict//",
+    "-//ietf//dtd html//",
+    "-//metrius//dtd metrius presentational//",
+    "-//microsoft//dtd internet explorer 2.0 html strict//",
+    "-//microsoft//dtd internet explorer 2.0 html//",
+    "-//microsoft//dtd internet explorer 2.0 tables//",
+    "-//microsoft//dtd internet explorer 3.0 html strict//",
+    "-//microsoft//dtd internet explorer 3.0 html//",
+    "-//microsoft//dtd internet explorer 3.0 tables//",
+    "-//netscape comm. corp.//dtd html//",
+    "-//netscape comm. corp.//dtd strict html//",
+    "-//o'reilly and associates//dtd html 2.0//",
+    "-//o'reilly and associates//dtd html extended 1.0//",
+    "-//o'reilly and associates//dtd html extended relaxed 1.0//",
+    "-//sq//dtd html 2.0 hotmetal + extensions//",
+    "-//softquad software//dtd hotmetal pro 6.0::19990601::extensions to html 4.0//",
+    "-//softquad//dtd hotmetal pro 4.0::19970916::extensions to html 4.0//",
+    "-//spyglass//dtd html 2.0 extended//",
+    "-//sun microsystems corp.//dtd hotjava html//",
+    "-//sun microsystems corp.//dtd hotjava strict html//",
+    "-//w3c//dtd html 3 1995-03-24//",
+    "-//w3c//dtd html 3.2 draft//",
+    "-//w3c//dtd html 3.2 final//",
+    "-//w3c//dtd html 3.2//",
+    "-//w3c//dtd html 3.2s draft//",
+    "-//w3c//dtd html 4.0 frameset//",
+    "-//w3c//dtd html 4.0 transitional//",
+    "-//w3c//dtd html experimental 19960712//",
+    "-//w3c//dtd html experimental 970421//",
+    "-//w3c//dtd w3 html//",
+    "-//w3o//dtd w3 html 3.0//",
+    "-//webtechs//dtd mozilla html 2.0//",
+    "-//webtechs//dtd mozilla html//",
+];
+
+#[cfg(test)]
+mod tests {
+    use wasmtime::{Engine, Store, component::Resource};
+
+    use crate::{
+        Document, DocumentMode, DomException, Element, Node, WindowStates,
+        browsing_context::{BrowsingContext, SandboxingFlag},
+        ohim::dom::node::{HostDocument, HostElement},
+        testing::{self, element, text},
+        url::DOMUrl,
+        user_agent::UserAgent,
+    };
+
+    #[test]
+    fn import_node_clones_into_this_document() {
+        let (mut user_agent, mut store, document) = testing::document();
+        let (_, _, other) = BrowsingContext::new_top_browsing_context(&mut user_agent, &mut store);
+        let div = Node::from(element(&other, "div", &mut store));
+        let child = text(&other, "x", &mut store);
+        div.append_child(child, &mut store);
+
+        let deep = document.import_node(&div, true, &mut store).unwrap();
+        assert!(!deep.is_same_node(&div, &store));
+        let owner = Node::from(deep.owner_document(&store).unwrap());
+        assert!(owner.is_same_node(&Node::from(document.clone()), &store));
+        let children = deep.child_nodes(&store);
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].text_content(&store).as_deref(), Some("x"));
+        // The original stays where it was.
+        assert_eq!(div.child_nodes(&store).len(), 1);
+
+        let shallow = document.import_node(&div, false, &mut store).unwrap();
+        assert!(shallow.child_nodes(&store).is_empty());
+    }
+
+    #[test]
+    fn import_node_rejects_documents() {
+        let (_user_agent, mut store, document) = testing::document();
+        let node = Node::from(document.clone());
+        assert!(matches!(
+            document.import_node(&node, true, &mut store),
+            Err(DomException::NotSupportedError(_))
+        ));
+    }
+
+    #[test]
+    fn create_document_type_validates_the_name() {
+        let (_user_agent, mut store, document) = testing::document();
+        let doctype = document
+            .create_document_type("html".into(), "".into(), "".into(), &mut store)
+            .unwrap();
+        assert_eq!(Node::from(doctype).node_name(&store), "html");
+        for name in ["a b", "a>b", "a\0b"] {
+            assert!(matches!(
+                document.create_document_type(name.into(), "".into(), "".into(), &mut store),
+                Err(DomException::InvalidCharacterError(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn discarding_documents_releases_their_realms() {
+        let (mut user_agent, mut store, document) = testing::document();
+        let realms = user_agent.realms.len();
+        for _ in 0..100 {
+            let (_, _, other) =
+                BrowsingContext::new_top_browsing_context(&mut user_agent, &mut store);
+            assert_eq!(user_agent.realms.len(), realms + 1);
+            other.discard(&mut user_agent, &store);
+        }
+        assert_eq!(user_agent.realms.len(), realms);
+
+        document.discard(&mut user_agent, &store);
+        assert!(user_agent.realms.is_empty());
+    }
+
+    #[test]
+    fn parse_html_document_builds_the_tree() {
+        let (_user_agent, mut store, document) = testing::document();
+        let url = DOMUrl::parse("https://example.com/index.html").unwrap();
+        let html = "\u{FEFF}<!DOCTYPE html><title>Greeting</title><p>Hello, <b>world</b>!";
+        let parsed = document
+            .parse_html_document(html.as_bytes(), url.clone(), &mut store)
+            .unwrap();
+
+        assert_eq!(parsed.mode(&store), DocumentMode::NoQuirks);
+        assert_eq!(parsed.url(&store), url);
+        assert_eq!(parsed.doctype(&store).unwrap().name(&store), "html");
+        assert_eq!(parsed.title(&store), "Greeting");
+        let root = parsed.document_element(&store).unwrap();
+        assert_eq!(root.local_name(&store), "html");
+        let head = Node::from(parsed.head(&store).unwrap());
+        assert_eq!(head.child_nodes(&store).len(), 1);
+        let body = Node::from(parsed.body(&store).unwrap());
+        let [paragraph] = &body.child_nodes(&store)[..] else {
+            panic!("expected the body to hold one paragraph");
+        };
+        assert_eq!(
+            paragraph.text_content(&store).as_deref(),
+            Some("Hello, world!")
+        );
+        // The document it was called on is left untouched.
+        assert_ne!(document.url(&store), url);
+    }
+
+    #[test]
+    fn parse_html_document_sets_the_mode_from_the_doctype() {
+        let (_user_agent, mut store, document) = testing::document();
+        let url = DOMUrl::parse("https://example.com/").unwrap();
+        let cases = [
+            ("<p>No doctype", DocumentMode::Quirks),
+            (
+                "<!DOCTYPE html PUBLIC \"-//W3C//DTD XHTML 1.0 Transitional//EN\"><p>x",
+                DocumentMode::LimitedQuirks,
+            ),
+            ("<!doctype HTML><p>x", DocumentMode::NoQuirks),
+        ];
+        for (html, mode) in cases {
+            let parsed = document
+                .parse_html_document(html.as_bytes(), url.clone(), &mut store)
+                .unwrap();
+            assert_eq!(parsed.mode(&store), mode, "{html}");
+        }
+    }
+
+    #[test]
+    fn parse_html_document_rejects_other_encodings() {
+        let (_user_agent, mut store, document) = testing::document();
+        let url = DOMUrl::parse("https://example.com/").unwrap();
+        // "café" in windows-1252.
+        let error = document
+            .parse_html_document(b"<p>caf\xe9", url, &mut store)
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<DomException>(),
+            Some(DomException::NotSupportedError(_))
+        ));
+    }
+
+    #[test]
+    fn serialize_golden() {
+        let (_user_agent, mut store, document) = testing::document();
+        let root = Node::from(document.clone());
+        let html = root.first_child(&store).unwrap();
+        let doctype = document
+            .create_document_type("html".into(), "".into(), "".into(), &mut store)
+            .unwrap();
+        root.pre_insert(doctype.into(), Some(&html), &mut store)
+            .unwrap();
+        let comment = document
+            .create_comment(" snapshot ".into(), &mut store)
+            .unwrap();
+        root.pre_insert(comment.into(), Some(&html), &mut store)
+            .unwrap();
+
+        let head = Node::from(document.head(&store).unwrap());
+        let title = Node::from(element(&document, "title", &mut store));
+        title.append_child(text(&document, "Q&A", &mut store), &mut store);
+        head.append_child(title, &mut store);
+
+        let body = Node::from(document.body(&store).unwrap());
+        let paragraph = element(&document, "p", &mut store);
+        paragraph
+            .set_attribute("title", "\"1 < 2\" & \u{a0}", &mut store)
+            .unwrap();
+        paragraph.set_attribute("id", "intro", &mut store).unwrap();
+        paragraph
+            .set_attribute("class", "lead", &mut store)
+            .unwrap();
+        let paragraph = Node::from(paragraph);
+        paragraph.append_child(text(&document, "a < b > c\u{a0}", &mut store), &mut store);
+        paragraph.append_child(Node::from(element(&document, "br", &mut store)), &mut store);
+        body.append_child(paragraph, &mut store);
+
+        assert_eq!(
+            document.serialize(&store),
+            "<!DOCTYPE html><!-- snapshot --><html><head><title>Q&amp;A</title></head>\
+             <body><p title=\"&quot;1 &lt; 2&quot; &amp; &nbsp;\" id=\"intro\" class=\"lead\">\
+             a &lt; b &gt; c&nbsp;<br></p></body></html>"
+        );
+    }
+
+    #[test]
+    fn mismatched_handles_are_errors() {
+        let mut states = WindowStates::create(&mut UserAgent::new(Engine::default()));
+        let document = states.window.document(&states.store).unwrap();
+        let div = element(&document, "div", &mut states.store);
+        let div = states.push_resource(div).unwrap();
+        let document = states.push_resource(document).unwrap();
+
+        // A handle of another resource type is rejected by the resource table.
+        let as_document = Resource::<Document>::new_borrow(div.rep());
+        assert!(states.url(as_document).is_err());
+        let as_element = Resource::<Element>::new_borrow(document.rep());
+        assert!(states.has_attributes(as_element).is_err());
+
+        // A handle whose object holds another node type is rejected too.
+        let object = states.table.get(&div).unwrap().0.clone();
+        let wrapped = states.push_resource(Document(object)).unwrap();
+        let error = states.url(Resource::new_borrow(wrapped.rep())).unwrap_err();
+        assert_eq!(error.to_string(), "object is not a Document");
+
+        // The handles still work afterwards.
+        let url = states.url(Resource::new_borrow(document.rep())).unwrap();
+        assert_eq!(url, "about:blank");
+        assert!(
+            !states
+                .has_attributes(Resource::new_borrow(div.rep()))
+                .unwrap()
+        );
+        HostDocument::drop(&mut states, wrapped).unwrap();
+        HostDocument::drop(&mut states, document).unwrap();
+        HostElement::drop(&mut states, div).unwrap();
+        assert_eq!(states.resource_count(), 0);
+    }
+
+    /// Load an empty document from `url` in the browsing context of `document`.
+    fn load(document: &Document, url: &str, store: &mut Store<()>) -> Document {
+        document
+            .parse_html_document(b"", DOMUrl::parse(url).unwrap(), store)
+            .unwrap()
+    }
+
+    #[test]
+    fn set_domain_relaxes_to_a_registrable_domain_suffix() {
+        let (user_agent, mut store, document) = testing::document();
+        let window = document
+            .relevant_global_object(&user_agent, &store)
+            .unwrap();
+        let loaded = load(&document, "https://www.example.co.uk/", &mut store);
+        assert_eq!(loaded.domain(&store), "www.example.co.uk");
+
+        loaded.set_domain("example.co.uk", &window, &store).unwrap();
+        assert_eq!(loaded.domain(&store), "example.co.uk");
+        // The origin itself is kept.
+        assert_eq!(
+            loaded.origin(&store).ascii_serialization(),
+            "https://www.example.co.uk"
+        );
+    }
+
+    #[test]
+    fn set_domain_rejects_public_suffixes() {
+        let (user_agent, mut store, document) = testing::document();
+        let window = document
+            .relevant_global_object(&user_agent, &store)
+            .unwrap();
+        let cases = [
+            ("https://example.com/", "com"),
+            ("https://example.co.uk/", "co.uk"),
+            ("https://www.example.co.uk/", "co.uk"),
+            ("https://example.com/", "other.com"),
+        ];
+        for (url, value) in cases {
+            let loaded = load(&document, url, &mut store);
+            let error = loaded.set_domain(value, &window, &store).unwrap_err();
+            assert!(matches!(error, DomException::SecurityError(_)), "{value:?}");
+            assert!(!loaded.mutable_origin(&store).has_domain());
+        }
+    }
+
+    #[test]
+    fn set_domain_is_rejected_when_sandboxed() {
+        let (user_agent, mut store, document) = testing::document();
+        let window = document
+            .relevant_global_object(&user_agent, &store)
+            .unwrap();
+        let loaded = load(&document, "https://www.example.com/", &mut store);
+        loaded.data_mut(&mut store).as_document_mut().flags =
+            SandboxingFlag::DOCUMENT_DOMAIN_BROWSING_CONTEXT;
+
+        let error = loaded
+            .set_domain("example.com", &window, &store)
+            .unwrap_err();
+        assert!(matches!(error, DomException::SecurityError(_)));
+        assert_eq!(loaded.domain(&store), "www.example.com");
+    }
+
+    #[test]
+    fn set_domain_does_nothing_in_origin_keyed_agent_clusters() {
+        let (user_agent, mut store, document) = testing::document();
+        let window = document
+            .relevant_global_object(&user_agent, &store)
+            .unwrap();
+        assert!(!window.is_origin_keyed(&store));
+        window.set_origin_keyed(true, &mut store);
+        let loaded = load(&document, "https://www.example.com/", &mut store);
+
+        // The value is still checked, but the domain is left unset.
+        let error = loaded.set_domain("com", &window, &store).unwrap_err();
+        assert!(matches!(error, DomException::SecurityError(_)));
+        loaded.set_domain("example.com", &window, &store).unwrap();
+        assert_eq!(loaded.domain(&store), "www.example.com");
+        assert!(!loaded.mutable_origin(&store).has_domain());
+    }
+
+    #[test]
+    fn set_title_creates_the_title_element_in_head() {
+        let (_user_agent, mut store, document) = testing::document();
+        assert_eq!(document.title(&store), "");
+        let head = Node::from(document.head(&store).unwrap());
+        assert!(head.child_nodes(&store).is_empty());
+
+        document.set_title("Hello".into(), &mut store).unwrap();
+        let children = head.child_nodes(&store);
+        assert_eq!(children.len(), 1);
+        let title = Element(children[0].0.clone());
+        assert_eq!(title.local_name(&store), "title");
+        assert_eq!(children[0].text_content(&store).unwrap(), "Hello");
+        assert_eq!(document.title(&store), "Hello");
+    }
+
+    #[test]
+    fn set_title_replaces_the_text_of_the_title_element() {
+        let (_user_agent, mut store, document) = testing::document();
+        document.set_title("Hello".into(), &mut store).unwrap();
+        document.set_title("World".into(), &mut store).unwrap();
+
+        let head = Node::from(document.head(&store).unwrap());
+        let children = head.child_nodes(&store);
+        assert_eq!(children.len(), 1);
+        let texts = children[0].child_nodes(&store);
+        assert_eq!(texts.len(), 1);
+        assert_eq!(texts[0].text_content(&store).unwrap(), "World");
+        assert_eq!(document.title(&store), "World");
+
+        // The empty string leaves the title element empty.
+        document.set_title("".into(), &mut store).unwrap();
+        assert!(children[0].child_nodes(&store).is_empty());
+        assert_eq!(document.title(&store), "");
+    }
+
+    #[test]
+    fn title_collapses_ascii_whitespace() {
+        let (_user_agent, mut store, document) = testing::document();
+        document
+            .set_title(" \t Hello \n\n  World\r\x0C ".into(), &mut store)
+            .unwrap();
+        assert_eq!(document.title(&store), "Hello World");
+        // The text of the element is kept as set.
+        let title = document.head(&store).unwrap().text_content(&store);
+        assert_eq!(title, " \t Hello \n\n  World\r\x0C ");
+    }
+
+    #[test]
+    fn set_title_without_head_does_nothing() {
+        let (_user_agent, mut store, document) = testing::document();
+        Node::from(document.head(&store).unwrap()).remove(false, &mut store);
+        document.set_title("Hello".into(), &mut store).unwrap();
+        assert_eq!(document.title(&store), "");
+        let html = Node::from(document.document_element(&store).unwrap());
+        assert_eq!(html.child_nodes(&store).len(), 1);
+    }
+
+    #[test]
+    fn new_browsing_context_populates_the_document_element() {
+        let (_user_agent, store, document) = testing::document();
+        let html = document.document_element(&store).unwrap();
+        let head = document.head(&store).unwrap();
+        let body = document.body(&store).unwrap();
+        assert_eq!(Node::from(html.clone()).node_name(&store), "HTML");
+        for element in [html, head, body] {
+            assert!(Node::from(element).is_connected(&store));
+        }
+    }
+
+    #[test]
+    fn document_element_follows_removal_and_insertion() {
+        let (_user_agent, mut store, document) = testing::document();
+        let html = Node::from(document.document_element(&store).unwrap());
+        html.remove(false, &mut store);
+        assert!(document.document_element(&store).is_none());
+        assert!(document.head(&store).is_none());
+        assert!(document.body(&store).is_none());
+        assert!(!html.is_connected(&store));
+
+        let root = Node::from(document.clone());
+        root.pre_insert(html.clone(), None, &mut store).unwrap();
+        let element = Node::from(document.document_element(&store).unwrap());
+        assert!(element.is_same_node(&html, &store));
+        assert!(document.body(&store).is_some());
+    }
+
+    #[test]
+    fn document_element_skips_leading_non_element_children() {
+        let (_user_agent, mut store, document) = testing::document();
+        Node::from(document.document_element(&store).unwrap()).remove(false, &mut store);
+        let root = Node::from(document.clone());
+        let doctype = document
+            .create_document_type("html".into(), "".into(), "".into(), &mut store)
+            .unwrap();
+        root.pre_insert(doctype.into(), None, &mut store).unwrap();
+        let comment = document.create_comment("c".into(), &mut store).unwrap();
+        root.pre_insert(comment.into(), None, &mut store).unwrap();
+        assert!(document.document_element(&store).is_none());
+
+        document.populate_hhb(&mut store).unwrap();
+        let html = document.document_element(&store).unwrap();
+        assert_eq!(Node::from(html).node_name(&store), "HTML");
+        assert!(document.head(&store).is_some());
+        assert!(document.body(&store).is_some());
+    }
+}
@@ -0,0 +1,49 @@
+use std::ops::Deref;
+
+use wasmtime::{AsContextMut, ExternRef, Result, Rooted};
+
+use crate::{NodeImpl, NodeTypeData, Object};
+
+use super::{Document, Node};
+
+/// <https://dom.spec.whatwg.org/#documentfragment>
+///
+/// A shadow root is a document fragment too, but it is represented by
+/// [`ShadowRoot`](super::ShadowRoot) instead.
+#[derive(Clone, Debug)]
+pub struct DocumentFragment(pub(crate) Object<NodeImpl>);
+
+impl DocumentFragment {
+    /// Create a new `DocumentFragment` node whose node document is `document`.
+    ///
+    /// <https://dom.spec.whatwg.org/#dom-documentfragment-documentfragment>
+    pub fn new(document: &Document, mut store: impl AsContextMut) -> Result<Self> {
+        let fragment = DocumentFragment(Object::new(
+            &mut store,
+            NodeImpl::new_with_type(NodeTypeData::DocumentFragment),
+        )?);
+        fragment
+            .data_mut(&mut store)
+            .set_node_document(Some(document.clone()));
+        Ok(fragment)
+    }
+
+    /// Get `Rooted<ExternRef>` reference of the `Node`.
+    pub fn as_root(&self) -> &Rooted<ExternRef> {
+        self
+    }
+}
+
+impl Deref for DocumentFragment {
+    type Target = Object<NodeImpl>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<DocumentFragment> for Node {
+    fn from(value: DocumentFragment) -> Self {
+        Self(value.0)
+    }
+}
@@ -0,0 +1,136 @@
+use std::ops::Deref;
+
+use malloc_size_of_derive::MallocSizeOf;
+use wasmtime::{AsContext, AsContextMut, Error, ExternRef, Result, Rooted, component::Resource};
+
+use crate::{
+    NodeImpl, NodeTypeData, Object, WindowStates, ohim::dom::node::HostDocumentType,
+    string::DOMString,
+};
+
+use super::{Document, Node};
+
+/// <https://dom.spec.whatwg.org/#documenttype>
+#[derive(Clone, Debug)]
+pub struct DocumentType(pub(crate) Object<NodeImpl>);
+
+impl DocumentType {
+    /// Create a new `DocumentType` node whose node document is `document`.
+    pub fn new(
+        document: &Document,
+        name: DOMString,
+        public_id: DOMString,
+        system_id: DOMString,
+        mut store: impl AsContextMut,
+    ) -> Result<Self> {
+        let doctype = DocumentType(Object::new(
+            &mut store,
+            NodeImpl::new_with_type(NodeTypeData::DocumentType(DocumentTypeImpl {
+                name,
+                public_id,
+                system_id,
+            })),
+        )?);
+        doctype
+            .data_mut(&mut store)
+            .set_node_document(Some(document.clone()));
+        Ok(doctype)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-documenttype-name>
+    pub fn name(&self, store: impl AsContext) -> DOMString {
+        self.data(&store).as_document_type().name.clone()
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-documenttype-publicid>
+    pub fn public_id(&self, store: impl AsContext) -> DOMString {
+        self.data(&store).as_document_type().public_id.clone()
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-documenttype-systemid>
+    pub fn system_id(&self, store: impl AsContext) -> DOMString {
+        self.data(&store).as_document_type().system_id.clone()
+    }
+
+    /// Get `Rooted<ExternRef>` reference of the `Node`.
+    pub fn as_root(&self) -> &Rooted<ExternRef> {
+        self
+    }
+}
+
+impl NodeImpl {
+    /// Get `DocumentTypeImpl` shared reference, or `None` if the node is not a `DocumentType`.
+    pub fn try_as_document_type(&self) -> Option<&DocumentTypeImpl> {
+        match self.data {
+            NodeTypeData::DocumentType(ref doctype) => Some(doctype),
+            _ => None,
+        }
+    }
+
+    /// Get `DocumentTypeImpl` shared reference.
+    fn as_document_type(&self) -> &DocumentTypeImpl {
+        self.try_as_document_type()
+            .expect("node is not a DocumentType")
+    }
+}
+
+impl Deref for DocumentType {
+    type Target = Object<NodeImpl>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<DocumentType> for Node {
+    fn from(value: DocumentType) -> Self {
+        Self(value.0)
+    }
+}
+
+/// Implementation of acutal `DocumentType` object. This can be accessed from `NodeImpl`.
+#[derive(Clone, Debug, MallocSizeOf)]
+pub struct DocumentTypeImpl {
+    /// <https://dom.spec.whatwg.org/#concept-doctype-name>
+    pub(crate) name: DOMString,
+    /// <https://dom.spec.whatwg.org/#concept-doctype-publicid>
+    pub(crate) public_id: DOMString,
+    /// <https://dom.spec.whatwg.org/#concept-doctype-systemid>
+    pub(crate) system_id: DOMString,
+}
+
+impl WindowStates {
+    /// Get the `DocumentType` of `resource`.
+    ///
+    /// This returns an error instead of panicking if the object is not a `DocumentType`.
+    fn get_document_type(&self, resource: &Resource<DocumentType>) -> Result<DocumentType> {
+        let doctype = self.table.get(resource)?;
+        doctype
+            .try_data(&self.store)?
+            .try_as_document_type()
+            .ok_or_else(|| Error::msg("object is not a DocumentType"))?;
+        Ok(doctype.clone())
+    }
+}
+
+impl HostDocumentType for WindowStates {
+    fn name(&mut self, self_: Resource<DocumentType>) -> Result<String> {
+        let self_ = self.get_document_type(&self_)?;
+        Ok(self_.name(&self.store).into())
+    }
+
+    fn public_id(&mut self, self_: Resource<DocumentType>) -> Result<String> {
+        let self_ = self.get_document_type(&self_)?;
+        Ok(self_.public_id(&self.store).into())
+    }
+
+    fn system_id(&mut self, self_: Resource<DocumentType>) -> Result<String> {
+        let self_ = self.get_document_type(&self_)?;
+        Ok(self_.system_id(&self.store).into())
+    }
+
+    fn drop(&mut self, rep: Resource<DocumentType>) -> Result<()> {
+        self.delete_resource(rep)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,313 @@
+use wasmtime::{AsContext, AsContextMut, Error, Result, component::Resource};
+
+use crate::{
+    DomException, WindowStates,
+    ohim::dom::node::{DomException as WitDomException, HostDomTokenList},
+    string::DOMString,
+};
+
+use super::Element;
+
+/// <https://dom.spec.whatwg.org/#domtokenlist>
+///
+/// This is a live view of the associated attribute. The token set is parsed from the attribute
+/// value whenever it is read, so changes through the attribute and through this list stay
+/// consistent. The attribute value is only rewritten when the list is mutated.
+#[derive(Clone, Debug)]
+pub struct DOMTokenList {
+    element: Element,
+    local_name: DOMString,
+}
+
+impl DOMTokenList {
+    /// Create the `DOMTokenList` of the attribute of `element` whose local name is `local_name`.
+    pub(crate) fn new(element: Element, local_name: DOMString) -> Self {
+        Self {
+            element,
+            local_name,
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-dtl-tokens>
+    fn token_set(&self, store: impl AsContext) -> Vec<DOMString> {
+        // When a DOMTokenList object is created and whenever its associated attribute’s value is
+        // changed, its token set is the result of running the ordered set parser on the value.
+        let value = self.element.get_attribute_value(&self.local_name, store);
+        let mut tokens: Vec<DOMString> = Vec::new();
+        for token in value.split_ascii_whitespace() {
+            if !tokens.iter().any(|t| t == token) {
+                tokens.push(DOMString::from(token));
+            }
+        }
+        tokens
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-dtl-update>
+    fn update(&self, tokens: &[DOMString], store: impl AsContextMut) {
+        // 1. If the associated element does not have an associated attribute and token set is
+        // empty, then return.
+        if tokens.is_empty() && !self.element.has_attribute_value(&self.local_name, &store) {
+            return;
+        }
+        // 2. Set an attribute value for the associated element using associated attribute’s local
+        // name and the result of running the ordered set serializer for token set.
+        self.element
+            .set_attribute_value(&self.local_name, &tokens.join(" "), store);
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-domtokenlist-length>
+    pub fn length(&self, store: impl AsContext) -> usize {
+        self.token_set(store).len()
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-domtokenlist-item>
+    pub fn item(&self, index: usize, store: impl AsContext) -> Option<DOMString> {
+        // 1. If index is equal to or greater than this’s token set’s size, then return null.
+        // 2. Return this’s token set[index].
+        self.token_set(store).into_iter().nth(index)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-domtokenlist-contains>
+    pub fn contains(&self, token: &str, store: impl AsContext) -> bool {
+        // The contains(token) method steps are to return true if this’s token set[token] exists;
+        // otherwise false.
+        self.token_set(store).iter().any(|t| t == token)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-domtokenlist-add>
+    pub fn add(&self, tokens: &[&str], mut store: impl AsContextMut) -> Result<(), DomException> {
+        // 1. For each token of tokens:
+        for token in tokens {
+            validate_token(token)?;
+        }
+        // 2. For each token of tokens, append token to this’s token set.
+        let mut token_set = self.token_set(&store);
+        for token in tokens {
+            if !token_set.iter().any(|t| t == *token) {
+                token_set.push(DOMString::from(*token));
+            }
+        }
+        // 3. Run the update steps.
+        self.update(&token_set, &mut store);
+        Ok(())
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-domtokenlist-remove>
+    pub fn remove(
+        &self,
+        tokens: &[&str],
+        mut store: impl AsContextMut,
+    ) -> Result<(), DomException> {
+        // 1. For each token of tokens:
+        for token in tokens {
+            validate_token(token)?;
+        }
+        // 2. For each token in tokens, remove token from this’s token set.
+        let mut token_set = self.token_set(&store);
+        token_set.retain(|t| tokens.iter().all(|token| t != *token));
+        // 3. Run the update steps.
+        self.update(&token_set, &mut store);
+        Ok(())
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-domtokenlist-toggle>
+    pub fn toggle(
+        &self,
+        token: &str,
+        force: Option<bool>,
+        mut store: impl AsContextMut,
+    ) -> Result<bool, DomException> {
+        // 1. If token is the empty string, then throw a "SyntaxError" DOMException.
+        // 2. If token contains any ASCII whitespace, then throw an "InvalidCharacterError"
+        // DOMException.
+        validate_token(token)?;
+        let mut token_set = self.token_set(&store);
+        // 3. If this’s token set[token] exists, then:
+        if let Some(index) = token_set.iter().position(|t| t == token) {
+            // 3.1 If force is either not given or is false, then remove token from this’s token set,
+            // run the update steps and return false.
+            if force != Some(true) {
+                token_set.remove(index);
+                self.update(&token_set, &mut store);
+                return Ok(false);
+            }
+            // 3.2 Return true.
+            return Ok(true);
+        }
+        // 4. Otherwise, if force not given or is true, append token to this’s token set, run the
+        // update steps, and return true.
+        if force != Some(false) {
+            token_set.push(DOMString::from(token));
+            self.update(&token_set, &mut store);
+            return Ok(true);
+        }
+        // 5. Return false.
+        Ok(false)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-domtokenlist-replace>
+    pub fn replace(
+        &self,
+        token: &str,
+        new_token: &str,
+        mut store: impl AsContextMut,
+    ) -> Result<bool, DomException> {
+        // 1. If either token or newToken is the empty string, then throw a "SyntaxError"
+        // DOMException.
+        // 2. If either token or newToken contains any ASCII whitespace, then throw an
+        // "InvalidCharacterError" DOMException.
+        if token.is_empty() || new_token.is_empty() {
+            return Err(DomException::SyntaxError("a token can not be empty".into()));
+        }
+        validate_token(token)?;
+        validate_token(new_token)?;
+        // 3. If this’s token set does not contain token, then return false.
+        let mut token_set = self.token_set(&store);
+        if !token_set.iter().any(|t| t == token) {
+            return Ok(false);
+        }
+        // 4. Replace token in this’s token set with newToken.
+        // To replace within an ordered set set, given item and replacement: if set contains item or
+        // replacement, then replace the first instance of either with replacement and remove all
+        // other instances.
+        let mut replaced = false;
+        token_set.retain_mut(|t| {
+            if t != token && t != new_token {
+                return true;
+            }
+            if replaced {
+                return false;
+            }
+            *t = DOMString::from(new_token);
+            replaced = true;
+            true
+        });
+        // 5. Run the update steps.
+        self.update(&token_set, &mut store);
+        // 6. Return true.
+        Ok(true)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-domtokenlist-value>
+    pub fn value(&self, store: impl AsContext) -> DOMString {
+        // The value attribute must return the result of running this’s serialize steps, which is
+        // the result of getting an attribute value given the associated element and the associated
+        // attribute’s local name.
+        self.element.get_attribute_value(&self.local_name, store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-domtokenlist-value>
+    pub fn set_value(&self, value: &str, store: impl AsContextMut) {
+        // Setting the value attribute must set an attribute value for the associated element using
+        // associated attribute’s local name and the given value.
+        self.element
+            .set_attribute_value(&self.local_name, value, store)
+    }
+}
+
+/// Check the token given to `add`, `remove` and `toggle`.
+fn validate_token(token: &str) -> Result<(), DomException> {
+    // 1. If token is the empty string, then throw a "SyntaxError" DOMException.
+    if token.is_empty() {
+        return Err(DomException::SyntaxError("a token can not be empty".into()));
+    }
+    // 2. If token contains any ASCII whitespace, then throw an "InvalidCharacterError"
+    // DOMException.
+    if token.chars().any(|c| c.is_ascii_whitespace()) {
+        return Err(DomException::InvalidCharacterError(format!(
+            "\"{token}\" contains ASCII whitespace"
+        )));
+    }
+    Ok(())
+}
+
+impl WindowStates {
+    /// Get the `DOMTokenList` of `resource`.
+    ///
+    /// This returns an error instead of panicking if its element is not an `Element`.
+    fn get_dom_token_list(&self, resource: &Resource<DOMTokenList>) -> Result<DOMTokenList> {
+        let list = self.table.get(resource)?;
+        list.element
+            .try_data(&self.store)?
+            .try_as_element()
+            .ok_or_else(|| Error::msg("object is not an Element"))?;
+        Ok(list.clone())
+    }
+}
+
+impl HostDomTokenList for WindowStates {
+    fn length(&mut self, self_: Resource<DOMTokenList>) -> Result<u32> {
+        let self_ = self.get_dom_token_list(&self_)?;
+        Ok(self_.length(&self.store) as u32)
+    }
+
+    fn item(&mut self, self_: Resource<DOMTokenList>, index: u32) -> Result<Option<String>> {
+        let self_ = self.get_dom_token_list(&self_)?;
+        Ok(self_.item(index as usize, &self.store).map(Into::into))
+    }
+
+    fn contains(&mut self, self_: Resource<DOMTokenList>, token: String) -> Result<bool> {
+        let self_ = self.get_dom_token_list(&self_)?;
+        Ok(self_.contains(&token, &self.store))
+    }
+
+    fn add(
+        &mut self,
+        self_: Resource<DOMTokenList>,
+        tokens: Vec<String>,
+    ) -> Result<Result<(), WitDomException>> {
+        let self_ = self.get_dom_token_list(&self_)?;
+        let tokens: Vec<&str> = tokens.iter().map(String::as_str).collect();
+        Ok(self_.add(&tokens, &mut self.store).map_err(Into::into))
+    }
+
+    fn remove(
+        &mut self,
+        self_: Resource<DOMTokenList>,
+        tokens: Vec<String>,
+    ) -> Result<Result<(), WitDomException>> {
+        let self_ = self.get_dom_token_list(&self_)?;
+        let tokens: Vec<&str> = tokens.iter().map(String::as_str).collect();
+        Ok(self_.remove(&tokens, &mut self.store).map_err(Into::into))
+    }
+
+    fn toggle(
+        &mut self,
+        self_: Resource<DOMTokenList>,
+        token: String,
+        force: Option<bool>,
+    ) -> Result<Result<bool, WitDomException>> {
+        let self_ = self.get_dom_token_list(&self_)?;
+        Ok(self_
+            .toggle(&token, force, &mut self.store)
+            .map_err(Into::into))
+    }
+
+    fn replace(
+        &mut self,
+        self_: Resource<DOMTokenList>,
+        token: String,
+        new_token: String,
+    ) -> Result<Result<bool, WitDomException>> {
+        let self_ = self.get_dom_token_list(&self_)?;
+        Ok(self_
+            .replace(&token, &new_token, &mut self.store)
+            .map_err(Into::into))
+    }
+
+    fn value(&mut self, self_: Resource<DOMTokenList>) -> Result<String> {
+        let self_ = self.get_dom_token_list(&self_)?;
+        Ok(self_.value(&self.store).into())
+    }
+
+    fn set_value(&mut self, self_: Resource<DOMTokenList>, value: String) -> Result<()> {
+        let self_ = self.get_dom_token_list(&self_)?;
+        self_.set_value(&value, &mut self.store);
+        Ok(())
+    }
+
+    fn drop(&mut self, rep: Resource<DOMTokenList>) -> Result<()> {
+        self.delete_resource(rep)?;
+        Ok(())
+    }
+}
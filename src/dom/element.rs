@@ -1,13 +1,26 @@
 use std::ops::Deref;
 
-use wasmtime::{AsContext, AsContextMut, ExternRef, Result, Rooted, component::Resource};
+use malloc_size_of_derive::MallocSizeOf;
+use wasmtime::{AsContext, AsContextMut, Error, ExternRef, Result, Rooted, component::Resource};
 
 use crate::{
-    NodeImpl, NodeTypeData, Object, WindowStates, agent::NameSpace, ohim::dom::node::HostElement,
+    DomException, NodeImpl, NodeTypeData, Object, WindowStates,
+    agent::NameSpace,
+    ohim::dom::node::{
+        DomException as WitDomException, HostElement, NodeOrString as WitNodeOrString,
+        SanitizerConfig as WitSanitizerConfig, ShadowRootInit as WitShadowRootInit,
+    },
     string::DOMString,
 };
 
-use super::{Document, HTMLElementImpl, HTMLElementType};
+use super::{
+    Attr, CustomElementDefinition, DOMTokenList, Document, HTMLElementImpl, HTMLElementType,
+    HTMLSlotElement, HTMLSlotElementImpl, NamedNodeMap, Node, NodeOrString, Sanitizer,
+    SelectorList, ShadowRoot, ShadowRootInit, ShadowRootMode, SlotAssignmentMode,
+    html_slot_element::{assign_slot, assign_slottables_for_tree, find_slot, is_slottable},
+    is_valid_custom_element_name,
+    parser::parse_fragment,
+};
 
 /// <https://dom.spec.whatwg.org/#element>
 #[derive(Clone, Debug)]
@@ -16,29 +29,100 @@ pub struct Element(pub(crate) Object<NodeImpl>);
 // TODO: This should be NodeMethods traits. Same for a EventTarget traits
 impl Element {
     /// <https://dom.spec.whatwg.org/#concept-create-element>
-    /// TODO: synchronousCustomElements, registry
+    /// TODO: synchronousCustomElements, registry, customized built-in elements
     pub fn new(
         document: &Document,
-        local: ElementLocal,
-        name_space: NameSpace,
+        tag_name: TagName,
         is: Option<DOMString>,
-        store: impl AsContextMut,
+        mut store: impl AsContextMut,
     ) -> Result<Self> {
-        // TODO: Step 1 ~ 5 implement CustomElementRegistry
+        // 1. Let result be null.
+        // 2. If registry is "default", then set registry to the result of looking up a custom
+        // element registry given document.
+        let registry = document.custom_element_registry(&store);
+        // 3. Let definition be the result of looking up a custom element definition given
+        // registry, namespace, localName, and is.
+        let definition = registry.and_then(|registry| {
+            registry.look_up(
+                tag_name.name_space(),
+                tag_name.local_name(),
+                is.as_deref(),
+                &store,
+            )
+        });
+        // 4. TODO: If definition is non-null, and definition’s name is not equal to its local name
+        // (i.e., definition represents a customized built-in element), then:
+        let state = if definition.is_some()
+            || (tag_name.name_space() == NameSpace::HTML
+                && (is_valid_custom_element_name(tag_name.local_name()) || is.is_some()))
+        {
+            // 5. Otherwise, if definition is non-null:
+            // 5.1 TODO: If synchronousCustomElements is true, then run the constructor.
+            // 5.2 Otherwise:
+            // 5.2.1 Set result to the result of creating an element internal given document,
+            // HTMLElement, localName, the HTML namespace, prefix, "undefined", null, and registry.
+            // 6.3 If namespace is the HTML namespace, and either localName is a valid custom element
+            // name or is is non-null, then set result’s custom element state to "undefined".
+            CustomElementState::Undefined
+        } else {
+            CustomElementState::Uncustomized
+        };
+        // 6. Otherwise:
         // 6.1 Let interface be the element interface for localName and namespace.
         // This is done by new_internal.
         // 6.2 Set result to the result of creating an element internal given document, interface, localName,
         // namespace, prefix, "uncustomized", is, and registry.
-        Ok(Element(Object::new(
-            store,
+        let element = Element(Object::new(
+            &mut store,
             NodeImpl::new_with_type(NodeTypeData::Element(ElementImpl::new(
-                document,
-                local,
-                name_space,
-                CustomElementState::Uncustomized,
-                is,
+                document, tag_name, state, is,
             ))),
-        )?))
+        )?);
+        element
+            .data_mut(&mut store)
+            .set_node_document(Some(document.clone()));
+        // 5.2.2 Enqueue a custom element upgrade reaction given result and definition.
+        // TODO: There is no custom element reaction queue yet, so the element is upgraded
+        // immediately.
+        if let Some(definition) = definition {
+            element.upgrade(&definition, &mut store);
+        }
+        // 7. Return result.
+        Ok(element)
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-element-custom-element-state>
+    pub fn custom_element_state(&self, store: impl AsContext) -> CustomElementState {
+        self.data(&store).as_element().state
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#concept-upgrade-an-element>
+    pub(crate) fn upgrade(
+        &self,
+        _definition: &CustomElementDefinition,
+        mut store: impl AsContextMut,
+    ) {
+        let element = self.data_mut(&mut store).as_element_mut();
+        // 1. If element’s custom element state is not "undefined" or "uncustomized", then return.
+        if !matches!(
+            element.state,
+            CustomElementState::Undefined | CustomElementState::Uncustomized
+        ) {
+            return;
+        }
+        // 2. TODO: Set element’s custom element definition to definition.
+        // 3. Set element’s custom element state to "failed".
+        element.state = CustomElementState::Failed;
+        // 4 ~ 7. TODO: Enqueue attributeChangedCallback and connectedCallback reactions.
+        // 8. Run the following steps while catching any exceptions:
+        // 8.1 TODO: If definition’s disable shadow is true and element’s shadow root is non-null,
+        // then throw a "NotSupportedError" DOMException.
+        // 8.2 Set element’s custom element state to "precustomized".
+        element.state = CustomElementState::Precustomized;
+        // 8.3 ~ 9. TODO: Run the constructor of definition. Guest callbacks are not supported yet,
+        // so the constructor always succeeds.
+        // 10. Set element’s custom element state to "custom".
+        element.state = CustomElementState::Custom;
     }
 
     /// <https://dom.spec.whatwg.org/#dom-element-hasattributes>
@@ -46,6 +130,655 @@ impl Element {
         !self.data(&store).as_element().attribute_list.is_empty()
     }
 
+    /// <https://dom.spec.whatwg.org/#dom-element-localname>
+    pub fn local_name(&self, store: impl AsContext) -> DOMString {
+        DOMString::from(self.data(&store).as_element().tag_name.local_name())
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-element-prefix>
+    pub fn prefix(&self, store: impl AsContext) -> Option<DOMString> {
+        self.data(&store)
+            .as_element()
+            .tag_name
+            .prefix()
+            .map(DOMString::from)
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-element-qualified-name>
+    pub fn qualified_name(&self, store: impl AsContext) -> DOMString {
+        self.data(&store).as_element().tag_name.qualified_name()
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-element-tagname>
+    pub fn tag_name(&self, store: impl AsContext) -> DOMString {
+        // 1. Let qualifiedName be this’s qualified name.
+        let mut qualified_name = self.qualified_name(&store);
+        // 2. If this is in the HTML namespace and its node document is an HTML document, then set
+        // qualifiedName to qualifiedName in ASCII uppercase.
+        if self.is_html_element_in_html_document(&store) {
+            qualified_name.make_ascii_uppercase();
+        }
+        // 3. Return qualifiedName.
+        qualified_name
+    }
+
+    /// Get the `TagName` identifying this element.
+    pub fn tag(&self, store: impl AsContext) -> TagName {
+        self.data(&store).as_element().tag_name.clone()
+    }
+
+    /// Check if the element is in the HTML namespace.
+    pub fn is_html_namespace(&self, store: impl AsContext) -> bool {
+        self.data(&store).as_element().tag_name.name_space() == NameSpace::HTML
+    }
+
+    /// Check if the element is in the HTML namespace and its node document is an HTML document.
+    pub(crate) fn is_html_element_in_html_document(&self, store: impl AsContext) -> bool {
+        self.is_html_namespace(&store)
+            && self
+                .data(&store)
+                .node_document()
+                .is_some_and(|document| document.is_html(&store))
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-element-getattribute>
+    pub fn get_attribute(&self, qualified_name: &str, store: impl AsContext) -> Option<DOMString> {
+        // 1. Let attr be the result of getting an attribute given qualifiedName and this.
+        // 2. If attr is null, return null.
+        // 3. Return attr’s value.
+        let index = self.attribute_position(qualified_name, &store)?;
+        Some(
+            self.data(&store).as_element().attribute_list[index]
+                .value
+                .clone(),
+        )
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-element-attributes>
+    pub fn attributes(&self) -> NamedNodeMap {
+        NamedNodeMap::new(self.clone())
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-element-getattributenames>
+    pub fn get_attribute_names(&self, store: impl AsContext) -> Vec<DOMString> {
+        // The getAttributeNames() method steps are to return the qualified names of the attributes
+        // in this’s attribute list, in order; otherwise a new list.
+        self.data(&store)
+            .as_element()
+            .attribute_list
+            .iter()
+            .map(Attr::name)
+            .collect()
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-element-removeattribute>
+    pub fn remove_attribute(&self, qualified_name: &str, mut store: impl AsContextMut) {
+        // The removeAttribute(qualifiedName) method steps are to remove an attribute given
+        // qualifiedName and this, and then return undefined.
+        // 1. Let attr be the result of getting an attribute given qualifiedName and element.
+        // 2. If attr is non-null, then remove attr.
+        if let Some(index) = self.attribute_position(qualified_name, &store) {
+            let attr = self
+                .data_mut(&mut store)
+                .as_element_mut()
+                .attribute_list
+                .remove(index);
+            if attr.name_space == NameSpace::None {
+                self.run_attribute_change_steps(&attr.local_name, Some(&*attr.value), None, store);
+            }
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-element-setattribute>
+    pub fn set_attribute(
+        &self,
+        qualified_name: &str,
+        value: &str,
+        mut store: impl AsContextMut,
+    ) -> Result<(), DomException> {
+        // 1. If qualifiedName is not a valid attribute local name, then throw an
+        // "InvalidCharacterError" DOMException.
+        if !is_valid_attribute_local_name(qualified_name) {
+            return Err(DomException::InvalidCharacterError(format!(
+                "\"{qualified_name}\" is not a valid attribute name"
+            )));
+        }
+        // 2. If this is in the HTML namespace and its node document is an HTML document, then set
+        // qualifiedName to qualifiedName in ASCII lowercase.
+        let qualified_name = if self.is_html_element_in_html_document(&store) {
+            qualified_name.to_ascii_lowercase()
+        } else {
+            qualified_name.to_owned()
+        };
+        // 3. Let attribute be the first attribute in this’s attribute list whose qualified name is
+        // qualifiedName, and null otherwise.
+        let index = self.attribute_position(&qualified_name, &store);
+        let attribute_list = &mut self.data_mut(&mut store).as_element_mut().attribute_list;
+        let (local_name, old_value) = match index {
+            // 5. Change attribute to value.
+            Some(index) => {
+                let attr = &mut attribute_list[index];
+                let old_value = std::mem::replace(&mut attr.value, DOMString::from(value));
+                let local_name = attr.local_name.clone();
+                // Only attributes in the null namespace have change steps.
+                if attr.name_space != NameSpace::None {
+                    return Ok(());
+                }
+                (local_name, Some(old_value))
+            }
+            // 4. If attribute is null, create an attribute whose local name is qualifiedName, value is
+            // value, and node document is this’s node document, then append this attribute to this,
+            // and then return.
+            None => {
+                let local_name = DOMString::from(qualified_name);
+                attribute_list.push(Attr::new(local_name.clone(), DOMString::from(value)));
+                (local_name, None)
+            }
+        };
+        self.run_attribute_change_steps(&local_name, old_value.as_deref(), Some(value), store);
+        Ok(())
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-element-attributes-get-by-name>
+    ///
+    /// Get the index of the attribute in the attribute list instead of the attribute itself.
+    pub(crate) fn attribute_position(
+        &self,
+        qualified_name: &str,
+        store: impl AsContext,
+    ) -> Option<usize> {
+        // 1. If element is in the HTML namespace and its node document is an HTML document, then set
+        // qualifiedName to qualifiedName in ASCII lowercase.
+        let qualified_name = if self.is_html_element_in_html_document(&store) {
+            qualified_name.to_ascii_lowercase()
+        } else {
+            qualified_name.to_owned()
+        };
+        // 2. Return the first attribute in element’s attribute list whose qualified name is
+        // qualifiedName; otherwise null.
+        self.data(&store)
+            .as_element()
+            .attribute_list
+            .iter()
+            .position(|attribute| attribute.name() == qualified_name.as_str())
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-element-attributes-get-value>
+    ///
+    /// The attribute is in the null namespace.
+    pub(crate) fn get_attribute_value(&self, local_name: &str, store: impl AsContext) -> DOMString {
+        // 1. Let attr be the result of getting an attribute given namespace, localName, and element.
+        // 2. If attr is null, then return the empty string.
+        // 3. Return attr’s value.
+        self.data(&store)
+            .as_element()
+            .attribute_list
+            .iter()
+            .find(|attr| attr.name_space == NameSpace::None && attr.local_name == local_name)
+            .map(|attr| attr.value.clone())
+            .unwrap_or_default()
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-element-attributes-set-value>
+    ///
+    /// The attribute is in the null namespace and has no prefix.
+    pub(crate) fn set_attribute_value(
+        &self,
+        local_name: &str,
+        value: &str,
+        mut store: impl AsContextMut,
+    ) {
+        // 1. Let attribute be the result of getting an attribute given namespace, localName, and
+        // element.
+        let attribute_list = &mut self.data_mut(&mut store).as_element_mut().attribute_list;
+        let old_value = match attribute_list
+            .iter_mut()
+            .find(|attr| attr.name_space == NameSpace::None && attr.local_name == local_name)
+        {
+            // 3. Change attribute to value.
+            Some(attr) => Some(std::mem::replace(&mut attr.value, DOMString::from(value))),
+            // 2. If attribute is null, create an attribute whose namespace is namespace, namespace
+            // prefix is prefix, local name is localName, value is value, and node document is
+            // element’s node document, then append this attribute to element, and then return.
+            None => {
+                attribute_list.push(Attr::new(
+                    DOMString::from(local_name),
+                    DOMString::from(value),
+                ));
+                None
+            }
+        };
+        self.run_attribute_change_steps(local_name, old_value.as_deref(), Some(value), store);
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-element-attributes-change-ext>
+    ///
+    /// Run the attribute change steps after the attribute in the null namespace whose local name
+    /// is `local_name` changed from `old_value` to `value`. `None` stands for an absent attribute.
+    pub(crate) fn run_attribute_change_steps(
+        &self,
+        local_name: &str,
+        old_value: Option<&str>,
+        value: Option<&str>,
+        mut store: impl AsContextMut,
+    ) {
+        // The slot name and slottable name steps return early if value is oldValue, or if one of
+        // them is null and the other is the empty string.
+        if old_value.unwrap_or_default() == value.unwrap_or_default() {
+            return;
+        }
+        let node = Node::from(self.clone());
+        match local_name {
+            // <https://dom.spec.whatwg.org/#slot-name>
+            "name" => {
+                if node.as_slot(&store).is_some() {
+                    // 4 ~ 5. Set element’s name to value, or the empty string if value is null.
+                    // 6. Run assign slottables for a tree with element’s root.
+                    assign_slottables_for_tree(&node.root(&store), store);
+                }
+            }
+            // <https://dom.spec.whatwg.org/#slotable-name>
+            "slot" => {
+                // 4. If element is assigned, then run assign slottables for element’s assigned slot.
+                let assigned_slot = self.data(&store).assigned_slot.clone();
+                if let Some(slot) = assigned_slot {
+                    slot.assign_slottables(&mut store);
+                }
+                // 5. Set element’s name to value, or the empty string if value is null.
+                // 6. Assign a slot for element.
+                assign_slot(&node, store);
+            }
+            _ => {}
+        }
+    }
+
+    /// Check if the element has an attribute in the null namespace whose local name is
+    /// `local_name`.
+    pub(crate) fn has_attribute_value(&self, local_name: &str, store: impl AsContext) -> bool {
+        self.data(&store)
+            .as_element()
+            .attribute_list
+            .iter()
+            .any(|attr| attr.name_space == NameSpace::None && attr.local_name == local_name)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-element-id>
+    pub fn id(&self, store: impl AsContext) -> DOMString {
+        // The id attribute must reflect the "id" content attribute.
+        self.get_attribute_value("id", store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-element-id>
+    pub fn set_id(&self, id: &str, store: impl AsContextMut) {
+        self.set_attribute_value("id", id, store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-element-classname>
+    pub fn class_name(&self, store: impl AsContext) -> DOMString {
+        // The className attribute must reflect the "class" content attribute.
+        self.get_attribute_value("class", store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-element-classname>
+    pub fn set_class_name(&self, class_name: &str, store: impl AsContextMut) {
+        self.set_attribute_value("class", class_name, store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-element-classlist>
+    pub fn class_list(&self) -> DOMTokenList {
+        // The classList getter steps are to return a DOMTokenList object whose associated element
+        // is this and whose associated attribute’s local name is class.
+        DOMTokenList::new(self.clone(), DOMString::from("class"))
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-element-attachshadow>
+    pub fn attach_shadow(
+        &self,
+        init: ShadowRootInit,
+        mut store: impl AsContextMut,
+    ) -> Result<ShadowRoot, DomException> {
+        // 1. TODO: Let registry be this’s node document’s custom element registry.
+        // 2. Run attach a shadow root with this, init["mode"], init["clonable"],
+        // init["serializable"], init["delegatesFocus"], init["slotAssignment"], and registry.
+        // 3. Return this’s shadow root.
+        self.attach_shadow_root(
+            init.mode,
+            init.delegates_focus,
+            init.slot_assignment,
+            &mut store,
+        )?;
+        Ok(self
+            .data(&store)
+            .as_element()
+            .shadow_root
+            .clone()
+            .expect("shadow root is just attached"))
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-attach-a-shadow-root>
+    ///
+    /// TODO: clonable, serializable and registry.
+    fn attach_shadow_root(
+        &self,
+        mode: ShadowRootMode,
+        delegates_focus: bool,
+        slot_assignment: SlotAssignmentMode,
+        mut store: impl AsContextMut,
+    ) -> Result<(), DomException> {
+        // 1. If element’s namespace is not the HTML namespace, then throw a "NotSupportedError"
+        // DOMException.
+        if !self.is_html_namespace(&store) {
+            return Err(DomException::NotSupportedError(
+                "only HTML elements can be a shadow host".into(),
+            ));
+        }
+        // 2. If element’s local name is not a valid shadow host name, then throw a
+        // "NotSupportedError" DOMException.
+        let local_name = self.local_name(&store);
+        if !is_valid_shadow_host_name(&local_name) {
+            return Err(DomException::NotSupportedError(format!(
+                "\"{local_name}\" can not be a shadow host"
+            )));
+        }
+        // 3. TODO: If element’s local name is a valid custom element name, or element’s is value is
+        // non-null, then: if definition is not null and definition’s disable shadow is true, then
+        // throw a "NotSupportedError" DOMException.
+        // 4. If element is a shadow host, then:
+        // TODO: There are no declarative shadow roots yet, so an existing shadow root is never
+        // declarative.
+        if self.data(&store).as_element().shadow_root.is_some() {
+            return Err(DomException::NotSupportedError(
+                "the element is already a shadow host".into(),
+            ));
+        }
+        // 5. Let shadow be a new shadow root whose node document is element’s node document, host
+        // is element, and mode is mode.
+        // 6. Set shadow’s delegates focus to delegatesFocus.
+        // 7 ~ 8. TODO: available to element internals and declarative.
+        // 9. Set shadow’s slot assignment to slotAssignment.
+        // 10 ~ 12. TODO: clonable, serializable and custom element registry.
+        let shadow = ShadowRoot::new(self, mode, delegates_focus, slot_assignment, &mut store)
+            .expect("Failed to create shadow root");
+        // 13. Set element’s shadow root to shadow.
+        self.data_mut(&mut store).as_element_mut().shadow_root = Some(shadow);
+        Ok(())
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-element-shadowroot>
+    pub fn shadow_root(&self, store: impl AsContext) -> Option<ShadowRoot> {
+        // 1. Let shadow be this’s shadow root.
+        // 2. If shadow is null or its mode is "closed", then return null.
+        // 3. Return shadow.
+        self.data(&store)
+            .as_element()
+            .shadow_root
+            .clone()
+            .filter(|shadow| shadow.mode(&store) == ShadowRootMode::Open)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-element-slot>
+    pub fn slot(&self, store: impl AsContext) -> DOMString {
+        // The slot attribute must reflect the "slot" content attribute.
+        self.get_attribute_value("slot", store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-element-slot>
+    pub fn set_slot(&self, slot: &str, store: impl AsContextMut) {
+        self.set_attribute_value("slot", slot, store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-slotable-assignedslot>
+    pub fn assigned_slot(&self, store: impl AsContext) -> Option<HTMLSlotElement> {
+        // The assignedSlot getter steps are to return the result of find a slot given this and
+        // true.
+        find_slot(&self.clone().into(), true, store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-node-textcontent>
+    pub fn text_content(&self, store: impl AsContext) -> DOMString {
+        Node::from(self.clone()).descendant_text_content(store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-node-textcontent>
+    pub fn set_text_content(
+        &self,
+        value: Option<DOMString>,
+        store: impl AsContextMut,
+    ) -> Result<()> {
+        Node::from(self.clone()).set_text_content(value, store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-parentnode-children>
+    ///
+    /// This returns a snapshot instead of a live `HTMLCollection`.
+    pub fn children(&self, store: impl AsContext) -> Vec<Element> {
+        Node::from(self.clone()).children(store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-parentnode-firstelementchild>
+    pub fn first_element_child(&self, store: impl AsContext) -> Option<Element> {
+        Node::from(self.clone()).first_element_child(store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-parentnode-lastelementchild>
+    pub fn last_element_child(&self, store: impl AsContext) -> Option<Element> {
+        Node::from(self.clone()).last_element_child(store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-parentnode-childelementcount>
+    pub fn child_element_count(&self, store: impl AsContext) -> usize {
+        Node::from(self.clone()).child_element_count(store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-parentnode-prepend>
+    pub fn prepend(
+        &self,
+        nodes: Vec<NodeOrString>,
+        store: impl AsContextMut,
+    ) -> Result<(), DomException> {
+        Node::from(self.clone()).prepend(nodes, store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-parentnode-append>
+    pub fn append(
+        &self,
+        nodes: Vec<NodeOrString>,
+        store: impl AsContextMut,
+    ) -> Result<(), DomException> {
+        Node::from(self.clone()).append(nodes, store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-element-insertadjacentelement>
+    pub fn insert_adjacent_element(
+        &self,
+        position: &str,
+        element: Element,
+        store: impl AsContextMut,
+    ) -> Result<Option<Element>, DomException> {
+        // The insertAdjacentElement(where, element) method steps are to return the result of running
+        // insert adjacent, given this, where, and element.
+        Ok(self
+            .insert_adjacent(position, element.into(), store)?
+            .map(|node| Element(node.0)))
+    }
+
+    /// <https://dom.spec.whatwg.org/#insert-adjacent>
+    fn insert_adjacent(
+        &self,
+        position: &str,
+        node: Node,
+        mut store: impl AsContextMut,
+    ) -> Result<Option<Node>, DomException> {
+        let element = Node::from(self.clone());
+        // To insert adjacent, given an element element, string where, and a node node, run the steps
+        // associated with the first ASCII case-insensitive match for where:
+        match position.to_ascii_lowercase().as_str() {
+            "beforebegin" => {
+                // If element’s parent is null, return null.
+                let Some(parent) = element.parent_node(&store) else {
+                    return Ok(None);
+                };
+                // Return the result of pre-inserting node into element’s parent before element.
+                parent
+                    .insert_before(node, Some(&element), &mut store)
+                    .map(Some)
+            }
+            "afterbegin" => {
+                // Return the result of pre-inserting node into element before element’s first child.
+                let first_child = element.child_nodes(&store).into_iter().next();
+                element
+                    .insert_before(node, first_child.as_ref(), &mut store)
+                    .map(Some)
+            }
+            "beforeend" => {
+                // Return the result of pre-inserting node into element before null.
+                element.insert_before(node, None, &mut store).map(Some)
+            }
+            "afterend" => {
+                // If element’s parent is null, return null.
+                let Some(parent) = element.parent_node(&store) else {
+                    return Ok(None);
+                };
+                // Return the result of pre-inserting node into element’s parent before element’s next
+                // sibling.
+                let next_sibling = element.next_sibling(&store);
+                parent
+                    .insert_before(node, next_sibling.as_ref(), &mut store)
+                    .map(Some)
+            }
+            // Otherwise: Throw a "SyntaxError" DOMException.
+            _ => Err(DomException::SyntaxError(format!(
+                "\"{position}\" is not a valid position"
+            ))),
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-element-getelementsbytagname>
+    ///
+    /// This returns a snapshot instead of a live `HTMLCollection`.
+    pub fn get_elements_by_tag_name(
+        &self,
+        qualified_name: &str,
+        store: impl AsContext,
+    ) -> Vec<Element> {
+        Node::from(self.clone()).get_elements_by_tag_name(qualified_name, store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-parentnode-queryselector>
+    pub fn query_selector(
+        &self,
+        selectors: &str,
+        store: impl AsContext,
+    ) -> Result<Option<Element>, DomException> {
+        Node::from(self.clone()).query_selector(selectors, store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-parentnode-queryselectorall>
+    pub fn query_selector_all(
+        &self,
+        selectors: &str,
+        store: impl AsContext,
+    ) -> Result<Vec<Element>, DomException> {
+        Node::from(self.clone()).query_selector_all(selectors, store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-element-matches>
+    pub fn matches(&self, selectors: &str, store: impl AsContext) -> Result<bool, DomException> {
+        // 1. Let s be the result of parse a selector from selectors.
+        // 2. If s is failure, then throw a "SyntaxError" DOMException.
+        let selectors: SelectorList = selectors.parse()?;
+        // 3. If the result of match a selector against an element, using s, this, and scoping root
+        // this, returns success, then return true; otherwise, return false.
+        Ok(selectors.matches(self, store))
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-element-outerhtml>
+    ///
+    /// TODO: Use the XML serialization algorithm when the node document is an XML document.
+    pub fn outer_html(&self, store: impl AsContext) -> DOMString {
+        // The outerHTML getter steps are to return the result of running fragment serializing
+        // algorithm steps with a fictional node whose only child is this and true.
+        Node::from(self.clone()).serialize(store)
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-element-innerhtml>
+    ///
+    /// TODO: Use the XML serialization algorithm when the node document is an XML document.
+    pub fn inner_html(&self, store: impl AsContext) -> DOMString {
+        // The innerHTML getter steps are to return the result of running fragment serializing
+        // algorithm steps with this and true.
+        serialize_html_fragment(&Node::from(self.clone()), store)
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-element-innerhtml>
+    ///
+    /// Scripts in `html` are parsed as inert elements, they are never executed.
+    pub fn set_inner_html(&self, html: &str, mut store: impl AsContextMut) -> Result<()> {
+        // 1. TODO: Let compliantString be the result of invoking the Get Trusted Type compliant string
+        // algorithm with TrustedHTML, this's relevant global object, the given value,
+        // "Element innerHTML", and "script".
+        // 2. Let context be this.
+        // 3. Let fragment be the result of invoking the fragment parsing algorithm steps with context
+        // and compliantString.
+        let fragment = parse_fragment(self, html, &mut store)?;
+        // 4. TODO: If context is a template element, then set context to the template element's
+        // template contents (a DocumentFragment).
+        // 5. Replace all with fragment within context.
+        Node::from(self.clone()).replace_all(fragment, store);
+        Ok(())
+    }
+
+    /// <https://wicg.github.io/sanitizer-api/#dom-element-sethtml>
+    ///
+    /// Parse `html` as the children of this element and remove the nodes and attributes
+    /// `sanitizer` does not allow before inserting them. Like [`Element::set_inner_html`], scripts
+    /// are never executed and no resource is fetched while parsing.
+    pub fn set_html(
+        &self,
+        html: &str,
+        sanitizer: &Sanitizer,
+        mut store: impl AsContextMut,
+    ) -> Result<()> {
+        let fragment = parse_fragment(self, html, &mut store)?;
+        let fragment = sanitizer.sanitize(fragment, &mut store);
+        Node::from(self.clone()).replace_all(fragment, store);
+        Ok(())
+    }
+
+    /// Append an attribute to the attribute list without validating its name.
+    ///
+    /// This is used by the parser, which creates attributes from tokens.
+    pub(crate) fn append_attribute(&self, attr: Attr, mut store: impl AsContextMut) {
+        self.data_mut(&mut store)
+            .as_element_mut()
+            .attribute_list
+            .push(attr);
+    }
+
+    /// <https://dom.spec.whatwg.org/#clone-a-single-node>
+    ///
+    /// These are the steps when node is an element.
+    pub(crate) fn clone_single_node(
+        &self,
+        document: &Document,
+        mut store: impl AsContextMut,
+    ) -> Element {
+        let element = self.data(&store).as_element();
+        let tag_name = element.tag_name.clone();
+        let is = element.is.clone();
+        let attributes = element.attribute_list.clone();
+        // 1. Let copy be the result of creating an element, given document, node’s local name, node’s
+        // namespace, node’s namespace prefix, and node’s is value, with the synchronous custom
+        // elements flag unset.
+        let copy =
+            Element::new(document, tag_name, is, &mut store).expect("Failed to create element");
+        // 2. For each attribute of node’s attribute list:
+        // 2.1 Let copyAttribute be the result of cloning a single node given attribute and document.
+        // 2.2 Append copyAttribute to copy.
+        copy.data_mut(&mut store).as_element_mut().attribute_list = attributes;
+        copy
+    }
+
     /// Get `Rooted<ExternRef>` reference of the `Node`.
     pub fn as_root(&self) -> &Rooted<ExternRef> {
         self
@@ -53,21 +786,31 @@ impl Element {
 }
 
 impl NodeImpl {
+    /// Get `ElementImpl` shared reference, or `None` if the node is not an `Element`.
+    pub fn try_as_element(&self) -> Option<&ElementImpl> {
+        match self.data {
+            NodeTypeData::Element(ref element) => Some(element),
+            _ => None,
+        }
+    }
+
     /// Get `ElementImpl` shared reference.
-    fn as_element(&self) -> &ElementImpl {
-        let NodeTypeData::Element(ref element) = self.data else {
-            unreachable!()
-        };
-        element
+    pub(crate) fn as_element(&self) -> &ElementImpl {
+        self.try_as_element().expect("node is not an Element")
     }
 
-    // /// Get `ElementImpl` exclusive reference.
-    // fn as_element_mut(&mut self) -> &mut ElementImpl {
-    //     let NodeTypeData::Element(ref mut element) = self.data else {
-    //         unreachable!()
-    //     };
-    //     element
-    // }
+    /// Get `ElementImpl` exclusive reference, or `None` if the node is not an `Element`.
+    pub fn try_as_element_mut(&mut self) -> Option<&mut ElementImpl> {
+        match self.data {
+            NodeTypeData::Element(ref mut element) => Some(element),
+            _ => None,
+        }
+    }
+
+    /// Get `ElementImpl` exclusive reference.
+    pub(crate) fn as_element_mut(&mut self) -> &mut ElementImpl {
+        self.try_as_element_mut().expect("node is not an Element")
+    }
 }
 
 impl Deref for Element {
@@ -79,15 +822,25 @@ impl Deref for Element {
 }
 
 /// Implementation of acutal `Element` object. This can be accessed from `NodeImpl`.
-#[derive(Debug)]
+#[derive(Debug, MallocSizeOf)]
 pub struct ElementImpl {
-    _name_space: NameSpace,
-    _local_name: ElementLocal,
-    _state: CustomElementState,
-    _is: Option<DOMString>,
+    tag_name: TagName,
+    state: CustomElementState,
+    is: Option<DOMString>,
+    #[ignore_malloc_size_of = "owned by the GC heap"]
     _node_document: Document,
-    attribute_list: Vec<u8>,
-    _element_type: ElementType,
+    /// <https://dom.spec.whatwg.org/#concept-element-attribute>
+    ///
+    /// Attributes are kept in the order they are first appended, and everything that iterates over
+    /// them (`attributes`, `get_attribute_names` and the serializer) follows this order:
+    /// - Changing the value of an existing attribute updates it in place without moving it.
+    /// - Removing an attribute and setting it again appends it to the end.
+    /// - The parser appends attributes in source order.
+    pub(crate) attribute_list: Vec<Attr>,
+    /// <https://dom.spec.whatwg.org/#concept-element-shadow-root>
+    #[ignore_malloc_size_of = "owned by the GC heap"]
+    pub(crate) shadow_root: Option<ShadowRoot>,
+    element_type: ElementType,
 }
 
 impl ElementImpl {
@@ -95,38 +848,580 @@ impl ElementImpl {
     /// TODO: registry, custom element definition
     fn new(
         document: &Document,
-        local: ElementLocal,
-        name_space: NameSpace,
+        tag_name: TagName,
         state: CustomElementState,
         is: Option<DOMString>,
     ) -> Self {
-        let element_type = ElementType::get(&local, &name_space);
+        let element_type = ElementType::get(&tag_name);
         Self {
             _node_document: document.clone(),
             attribute_list: Vec::new(),
-            _name_space: name_space,
-            _local_name: local,
-            _state: state,
-            _is: is,
-            _element_type: element_type,
+            shadow_root: None,
+            tag_name,
+            state,
+            is,
+            element_type,
+        }
+    }
+
+    /// Get `HTMLSlotElementImpl` shared reference, or `None` if the element is not a slot.
+    pub(crate) fn try_as_slot(&self) -> Option<&HTMLSlotElementImpl> {
+        match self.element_type {
+            ElementType::HTMLElement(ref html) => html.slot.as_ref(),
+            ElementType::None => None,
+        }
+    }
+
+    /// Get `HTMLSlotElementImpl` exclusive reference, or `None` if the element is not a slot.
+    pub(crate) fn try_as_slot_mut(&mut self) -> Option<&mut HTMLSlotElementImpl> {
+        match self.element_type {
+            ElementType::HTMLElement(ref mut html) => html.slot.as_mut(),
+            ElementType::None => None,
         }
     }
 }
 
+impl WindowStates {
+    /// Get the `Element` of `resource`.
+    ///
+    /// This returns an error instead of panicking if the object is not an `Element`.
+    fn get_element(&self, resource: &Resource<Element>) -> Result<Element> {
+        let element = self.table.get(resource)?;
+        element
+            .try_data(&self.store)?
+            .try_as_element()
+            .ok_or_else(|| Error::msg("object is not an Element"))?;
+        Ok(element.clone())
+    }
+}
+
 impl HostElement for WindowStates {
     fn has_attributes(&mut self, self_: Resource<Element>) -> Result<bool> {
-        let self_ = self.table.get(&self_)?;
+        let self_ = self.get_element(&self_)?;
         Ok(self_.has_attributes(&self.store))
     }
 
+    fn attributes(&mut self, self_: Resource<Element>) -> Result<Vec<Resource<Attr>>> {
+        let self_ = self.get_element(&self_)?;
+        let attributes = self_.attributes();
+        let attributes: Vec<Attr> = (0..attributes.length(&self.store))
+            .filter_map(|index| attributes.item(index, &self.store))
+            .collect();
+        attributes
+            .into_iter()
+            .map(|attr| Ok(self.push_resource(attr)?))
+            .collect()
+    }
+
+    fn text_content(&mut self, self_: Resource<Element>) -> Result<String> {
+        let self_ = self.get_element(&self_)?;
+        Ok(self_.text_content(&self.store).into())
+    }
+
+    fn set_text_content(&mut self, self_: Resource<Element>, value: Option<String>) -> Result<()> {
+        let self_ = self.get_element(&self_)?;
+        self_.set_text_content(value.map(DOMString::from), &mut self.store)
+    }
+
+    fn children(&mut self, self_: Resource<Element>) -> Result<Vec<Resource<Element>>> {
+        let self_ = self.get_element(&self_)?;
+        let children = self_.children(&self.store);
+        children
+            .into_iter()
+            .map(|element| Ok(self.push_resource(element)?))
+            .collect()
+    }
+
+    fn first_element_child(
+        &mut self,
+        self_: Resource<Element>,
+    ) -> Result<Option<Resource<Element>>> {
+        let self_ = self.get_element(&self_)?;
+        match self_.first_element_child(&self.store) {
+            Some(e) => Ok(Some(self.push_resource(e)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn last_element_child(
+        &mut self,
+        self_: Resource<Element>,
+    ) -> Result<Option<Resource<Element>>> {
+        let self_ = self.get_element(&self_)?;
+        match self_.last_element_child(&self.store) {
+            Some(e) => Ok(Some(self.push_resource(e)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn child_element_count(&mut self, self_: Resource<Element>) -> Result<u32> {
+        let self_ = self.get_element(&self_)?;
+        Ok(self_.child_element_count(&self.store) as u32)
+    }
+
+    fn prepend(
+        &mut self,
+        self_: Resource<Element>,
+        nodes: Vec<WitNodeOrString>,
+    ) -> Result<Result<(), WitDomException>> {
+        let nodes = self.get_nodes_or_strings(nodes)?;
+        let self_ = self.get_element(&self_)?;
+        Ok(self_.prepend(nodes, &mut self.store).map_err(Into::into))
+    }
+
+    fn append(
+        &mut self,
+        self_: Resource<Element>,
+        nodes: Vec<WitNodeOrString>,
+    ) -> Result<Result<(), WitDomException>> {
+        let nodes = self.get_nodes_or_strings(nodes)?;
+        let self_ = self.get_element(&self_)?;
+        Ok(self_.append(nodes, &mut self.store).map_err(Into::into))
+    }
+
+    fn insert_adjacent_element(
+        &mut self,
+        self_: Resource<Element>,
+        position: String,
+        element: Resource<Element>,
+    ) -> Result<Result<Option<Resource<Element>>, WitDomException>> {
+        let self_ = self.get_element(&self_)?;
+        let element = self.get_element(&element)?;
+        match self_.insert_adjacent_element(&position, element, &mut self.store) {
+            Ok(Some(element)) => Ok(Ok(Some(self.push_resource(element)?))),
+            Ok(None) => Ok(Ok(None)),
+            Err(e) => Ok(Err(e.into())),
+        }
+    }
+
+    fn outer_html(&mut self, self_: Resource<Element>) -> Result<String> {
+        let self_ = self.get_element(&self_)?;
+        Ok(self_.outer_html(&self.store).into())
+    }
+
+    fn inner_html(&mut self, self_: Resource<Element>) -> Result<String> {
+        let self_ = self.get_element(&self_)?;
+        Ok(self_.inner_html(&self.store).into())
+    }
+
+    fn set_inner_html(&mut self, self_: Resource<Element>, html: String) -> Result<()> {
+        let self_ = self.get_element(&self_)?;
+        self_.set_inner_html(&html, &mut self.store)
+    }
+
+    fn set_html(
+        &mut self,
+        self_: Resource<Element>,
+        html: String,
+        config: WitSanitizerConfig,
+    ) -> Result<()> {
+        let self_ = self.get_element(&self_)?;
+        self_.set_html(&html, &config.into(), &mut self.store)
+    }
+
+    fn query_selector(
+        &mut self,
+        self_: Resource<Element>,
+        selectors: String,
+    ) -> Result<Result<Option<Resource<Element>>, WitDomException>> {
+        let self_ = self.get_element(&self_)?;
+        match self_.query_selector(&selectors, &self.store) {
+            Ok(Some(element)) => Ok(Ok(Some(self.push_resource(element)?))),
+            Ok(None) => Ok(Ok(None)),
+            Err(e) => Ok(Err(e.into())),
+        }
+    }
+
+    fn query_selector_all(
+        &mut self,
+        self_: Resource<Element>,
+        selectors: String,
+    ) -> Result<Result<Vec<Resource<Element>>, WitDomException>> {
+        let self_ = self.get_element(&self_)?;
+        match self_.query_selector_all(&selectors, &self.store) {
+            Ok(elements) => Ok(Ok(elements
+                .into_iter()
+                .map(|element| Ok(self.push_resource(element)?))
+                .collect::<Result<_>>()?)),
+            Err(e) => Ok(Err(e.into())),
+        }
+    }
+
+    fn matches(
+        &mut self,
+        self_: Resource<Element>,
+        selectors: String,
+    ) -> Result<Result<bool, WitDomException>> {
+        let self_ = self.get_element(&self_)?;
+        Ok(self_.matches(&selectors, &self.store).map_err(Into::into))
+    }
+
+    fn get_elements_by_tag_name(
+        &mut self,
+        self_: Resource<Element>,
+        qualified_name: String,
+    ) -> Result<Vec<Resource<Element>>> {
+        let self_ = self.get_element(&self_)?;
+        let elements = self_.get_elements_by_tag_name(&qualified_name, &self.store);
+        elements
+            .into_iter()
+            .map(|element| Ok(self.push_resource(element)?))
+            .collect()
+    }
+
+    fn id(&mut self, self_: Resource<Element>) -> Result<String> {
+        let self_ = self.get_element(&self_)?;
+        Ok(self_.id(&self.store).into())
+    }
+
+    fn set_id(&mut self, self_: Resource<Element>, id: String) -> Result<()> {
+        let self_ = self.get_element(&self_)?;
+        self_.set_id(&id, &mut self.store);
+        Ok(())
+    }
+
+    fn class_name(&mut self, self_: Resource<Element>) -> Result<String> {
+        let self_ = self.get_element(&self_)?;
+        Ok(self_.class_name(&self.store).into())
+    }
+
+    fn set_class_name(&mut self, self_: Resource<Element>, class_name: String) -> Result<()> {
+        let self_ = self.get_element(&self_)?;
+        self_.set_class_name(&class_name, &mut self.store);
+        Ok(())
+    }
+
+    fn class_list(&mut self, self_: Resource<Element>) -> Result<Resource<DOMTokenList>> {
+        let self_ = self.get_element(&self_)?;
+        Ok(self.push_resource(self_.class_list())?)
+    }
+
+    fn attach_shadow(
+        &mut self,
+        self_: Resource<Element>,
+        init: WitShadowRootInit,
+    ) -> Result<Result<Resource<ShadowRoot>, WitDomException>> {
+        let self_ = self.get_element(&self_)?;
+        match self_.attach_shadow(init.into(), &mut self.store) {
+            Ok(shadow) => Ok(Ok(self.push_resource(shadow)?)),
+            Err(e) => Ok(Err(e.into())),
+        }
+    }
+
+    fn shadow_root(&mut self, self_: Resource<Element>) -> Result<Option<Resource<ShadowRoot>>> {
+        let self_ = self.get_element(&self_)?;
+        match self_.shadow_root(&self.store) {
+            Some(shadow) => Ok(Some(self.push_resource(shadow)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn slot(&mut self, self_: Resource<Element>) -> Result<String> {
+        let self_ = self.get_element(&self_)?;
+        Ok(self_.slot(&self.store).into())
+    }
+
+    fn set_slot(&mut self, self_: Resource<Element>, slot: String) -> Result<()> {
+        let self_ = self.get_element(&self_)?;
+        self_.set_slot(&slot, &mut self.store);
+        Ok(())
+    }
+
+    fn assigned_slot(&mut self, self_: Resource<Element>) -> Result<Option<Resource<Element>>> {
+        let self_ = self.get_element(&self_)?;
+        match self_.assigned_slot(&self.store) {
+            Some(slot) => Ok(Some(self.push_resource(Element::from(slot))?)),
+            None => Ok(None),
+        }
+    }
+
+    fn assigned_nodes(
+        &mut self,
+        self_: Resource<Element>,
+        flatten: bool,
+    ) -> Result<Vec<Resource<Node>>> {
+        let self_ = self.get_element(&self_)?;
+        let Some(slot) = Node::from(self_).as_slot(&self.store) else {
+            return Ok(Vec::new());
+        };
+        let nodes = slot.assigned_nodes(flatten, &self.store);
+        self.push_nodes(nodes)
+    }
+
+    fn assign(&mut self, self_: Resource<Element>, nodes: Vec<Resource<Node>>) -> Result<()> {
+        let self_ = self.get_element(&self_)?;
+        let Some(slot) = Node::from(self_).as_slot(&self.store) else {
+            return Err(Error::msg(
+                "TypeError: assign() is only defined on slot elements",
+            ));
+        };
+        let nodes = nodes
+            .iter()
+            .map(|node| {
+                let node = self.get_node(node)?;
+                if !is_slottable(&node, &self.store) {
+                    return Err(Error::msg(
+                        "TypeError: only Element and Text nodes can be assigned to a slot",
+                    ));
+                }
+                Ok(node)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        slot.assign(nodes, &mut self.store);
+        Ok(())
+    }
+
     fn drop(&mut self, rep: Resource<Element>) -> Result<()> {
-        self.table.delete(rep)?;
+        self.delete_resource(rep)?;
         Ok(())
     }
 }
 
+/// <https://dom.spec.whatwg.org/#valid-shadow-host-name>
+fn is_valid_shadow_host_name(name: &str) -> bool {
+    // A valid shadow host name is a valid custom element name, "article", "aside", "blockquote",
+    // "body", "div", "footer", "h1", "h2", "h3", "h4", "h5", "h6", "header", "main", "nav", "p",
+    // "section", or "span".
+    is_valid_custom_element_name(name)
+        || matches!(
+            name,
+            "article"
+                | "aside"
+                | "blockquote"
+                | "body"
+                | "div"
+                | "footer"
+                | "h1"
+                | "h2"
+                | "h3"
+                | "h4"
+                | "h5"
+                | "h6"
+                | "header"
+                | "main"
+                | "nav"
+                | "p"
+                | "section"
+                | "span"
+        )
+}
+
+/// <https://dom.spec.whatwg.org/#valid-attribute-local-name>
+fn is_valid_attribute_local_name(name: &str) -> bool {
+    // A string is a valid attribute local name if its length is at least 1 and it does not contain
+    // ASCII whitespace, U+0000 NULL, U+002F (/), U+003D (=), or U+003E (>).
+    !name.is_empty()
+        && !name
+            .chars()
+            .any(|c| c.is_ascii_whitespace() || matches!(c, '\0' | '/' | '=' | '>'))
+}
+
+/// <https://html.spec.whatwg.org/multipage/#serialising-html-fragments>
+fn serialize_html_fragment(node: &Node, store: impl AsContext) -> DOMString {
+    // 5. For each child node of the node, in tree order, run the following steps:
+    let child_nodes = node.child_nodes(&store);
+    serialize_html_nodes(child_nodes, store)
+}
+
+/// Serialize `nodes` and their descendants in order, as the steps of the HTML fragment
+/// serialization algorithm do for each child node.
+///
+/// The subtree is walked with a stack instead of recursion, so deep trees can not overflow the
+/// call stack.
+/// TODO: Serialize template contents, shadow roots and processing instructions.
+pub(crate) fn serialize_html_nodes(nodes: Vec<Node>, store: impl AsContext) -> DOMString {
+    enum Step {
+        Node(Node),
+        EndTag(DOMString),
+    }
+    // 3. Let s be a string, and initialize it to the empty string.
+    let mut s = DOMString::new();
+    let mut stack: Vec<Step> = nodes.into_iter().rev().map(Step::Node).collect();
+    while let Some(step) = stack.pop() {
+        let current = match step {
+            Step::Node(node) => node,
+            // The end tag of an element whose children are all serialized.
+            Step::EndTag(tag_name) => {
+                s.push_str("</");
+                s.push_str(&tag_name);
+                s.push('>');
+                continue;
+            }
+        };
+        // 5.1 Let current node be the child node being processed.
+        // 5.2 Append the appropriate string from the following list to s:
+        match &current.data(&store).data {
+            // If current node is an Element
+            NodeTypeData::Element(element) => {
+                // If current node is an element in the HTML namespace, the MathML namespace, or the
+                // SVG namespace, then let tagname be current node’s local name. Otherwise, let
+                // tagname be current node’s qualified name.
+                let tag_name = match element.tag_name.name_space() {
+                    NameSpace::HTML | NameSpace::MathML | NameSpace::SVG => {
+                        DOMString::from(element.tag_name.local_name())
+                    }
+                    _ => element.tag_name.qualified_name(),
+                };
+                // Append a U+003C LESS-THAN SIGN character (<), followed by tagname.
+                s.push('<');
+                s.push_str(&tag_name);
+                // If current node’s is value is not null, and the element does not have an is
+                // content attribute in its attribute list, then append the string " is="",
+                // followed by current node’s is value escaped as described below in attribute
+                // mode, followed by a U+0022 QUOTATION MARK character (").
+                let has_is_attribute = element.attribute_list.iter().any(|attribute| {
+                    attribute.name_space == NameSpace::None && attribute.local_name == "is"
+                });
+                if let Some(is) = element.is.as_ref().filter(|_| !has_is_attribute) {
+                    s.push_str(" is=\"");
+                    escape_string(&mut s, is, true);
+                    s.push('"');
+                }
+                // For each attribute that the element has, append a U+0020 SPACE character, the
+                // attribute’s serialized name as described below, a U+003D EQUALS SIGN character
+                // (=), a U+0022 QUOTATION MARK character ("), the attribute’s value, escaped as
+                // described below in attribute mode, and a second U+0022 QUOTATION MARK character
+                // (").
+                for attribute in &element.attribute_list {
+                    s.push(' ');
+                    s.push_str(&attribute.serialized_name());
+                    s.push_str("=\"");
+                    escape_string(&mut s, &attribute.value, true);
+                    s.push('"');
+                }
+                // Append a U+003E GREATER-THAN SIGN character (>).
+                s.push('>');
+                // If current node serializes as void, then continue on to the next child node at
+                // this point.
+                if serializes_as_void(&element.tag_name) {
+                    continue;
+                }
+                // Append the value of running the HTML fragment serialization algorithm with current
+                // node, serializableShadowRoots, and shadowRoots (thus recursing into this
+                // algorithm for that element), followed by a U+003C LESS-THAN SIGN character (<),
+                // a U+002F SOLIDUS character (/), tagname again, and finally a U+003E GREATER-THAN
+                // SIGN character (>).
+                stack.push(Step::EndTag(tag_name));
+                stack.extend(
+                    current
+                        .child_nodes(&store)
+                        .into_iter()
+                        .rev()
+                        .map(Step::Node),
+                );
+            }
+            // If current node is a Text node
+            NodeTypeData::Text(text) => {
+                // If the parent of current node is a style, script, xmp, iframe, noembed, noframes,
+                // or plaintext element, or if the parent of current node is a noscript element and
+                // scripting is enabled for the node, then append the value of current node’s data
+                // IDL attribute literally.
+                // Scripting is always enabled since guests run in this engine.
+                let is_raw_text = current.parent_node(&store).is_some_and(|parent| {
+                    let NodeTypeData::Element(ref parent) = parent.data(&store).data else {
+                        return false;
+                    };
+                    parent.tag_name.name_space() == NameSpace::HTML
+                        && matches!(
+                            parent.tag_name.local_name(),
+                            "style"
+                                | "script"
+                                | "xmp"
+                                | "iframe"
+                                | "noembed"
+                                | "noframes"
+                                | "plaintext"
+                                | "noscript"
+                        )
+                });
+                if is_raw_text {
+                    s.push_str(&text.data);
+                } else {
+                    // Otherwise, append the value of current node’s data IDL attribute, escaped as
+                    // described below.
+                    escape_string(&mut s, &text.data, false);
+                }
+            }
+            // If current node is a Comment
+            NodeTypeData::Comment(comment) => {
+                // Append the literal string "<!--" (U+003C LESS-THAN SIGN, U+0021 EXCLAMATION MARK,
+                // U+002D HYPHEN-MINUS, U+002D HYPHEN-MINUS), followed by the value of current node’s
+                // data IDL attribute, followed by the literal string "-->" (U+002D HYPHEN-MINUS,
+                // U+002D HYPHEN-MINUS, U+003E GREATER-THAN SIGN).
+                s.push_str("<!--");
+                s.push_str(&comment.data);
+                s.push_str("-->");
+            }
+            // If current node is a DocumentType
+            NodeTypeData::DocumentType(doctype) => {
+                // Append the literal string "<!DOCTYPE" (U+003C LESS-THAN SIGN, U+0021 EXCLAMATION
+                // MARK, U+0044 LATIN CAPITAL LETTER D, U+004F LATIN CAPITAL LETTER O, U+0043 LATIN
+                // CAPITAL LETTER C, U+0054 LATIN CAPITAL LETTER T, U+0059 LATIN CAPITAL LETTER Y,
+                // U+0050 LATIN CAPITAL LETTER P, U+0045 LATIN CAPITAL LETTER E), followed by a space
+                // (U+0020 SPACE), followed by the value of current node’s name IDL attribute,
+                // followed by the literal string ">" (U+003E GREATER-THAN SIGN).
+                s.push_str("<!DOCTYPE ");
+                s.push_str(&doctype.name);
+                s.push('>');
+            }
+            _ => {}
+        }
+    }
+    // 6. Return s.
+    s
+}
+
+/// <https://html.spec.whatwg.org/multipage/#serializes-as-void>
+fn serializes_as_void(tag_name: &TagName) -> bool {
+    // An element serializes as void if its element type is one of the void elements, or is
+    // basefont, bgsound, frame, keygen, or param.
+    tag_name.name_space() == NameSpace::HTML
+        && matches!(
+            tag_name.local_name(),
+            "area"
+                | "base"
+                | "br"
+                | "col"
+                | "embed"
+                | "hr"
+                | "img"
+                | "input"
+                | "link"
+                | "meta"
+                | "source"
+                | "track"
+                | "wbr"
+                | "basefont"
+                | "bgsound"
+                | "frame"
+                | "keygen"
+                | "param"
+        )
+}
+
+/// <https://html.spec.whatwg.org/multipage/#escapingString>
+fn escape_string(s: &mut DOMString, string: &str, attribute_mode: bool) {
+    for c in string.chars() {
+        match c {
+            // 1. Replace any occurrence of the "&" character by the string "&amp;".
+            '&' => s.push_str("&amp;"),
+            // 2. Replace any occurrences of the U+00A0 NO-BREAK SPACE character by the string
+            // "&nbsp;".
+            '\u{a0}' => s.push_str("&nbsp;"),
+            // 3. Replace any occurrences of the "<" character by the string "&lt;".
+            '<' => s.push_str("&lt;"),
+            // 4. Replace any occurrences of the ">" character by the string "&gt;".
+            '>' => s.push_str("&gt;"),
+            // 5. If the algorithm was invoked in the attribute mode, then replace any occurrences of
+            // the U+0022 QUOTATION MARK character (") by the string "&quot;".
+            '"' if attribute_mode => s.push_str("&quot;"),
+            c => s.push(c),
+        }
+    }
+}
+
 /// <https://dom.spec.whatwg.org/#concept-element-custom-element-state>
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, MallocSizeOf, PartialEq, Eq)]
 pub enum CustomElementState {
     /// "undefined"
     Undefined,
@@ -141,7 +1436,7 @@ pub enum CustomElementState {
 }
 
 /// The actual implementation of each element type
-#[derive(Debug, Default)]
+#[derive(Debug, Default, MallocSizeOf)]
 pub enum ElementType {
     /// HTMLElement
     HTMLElement(HTMLElementImpl),
@@ -151,34 +1446,285 @@ pub enum ElementType {
 }
 
 impl ElementType {
-    fn get(local: &ElementLocal, name_space: &NameSpace) -> Self {
-        match name_space {
-            NameSpace::HTML => match local {
-                ElementLocal::Html => {
-                    ElementType::HTMLElement(HTMLElementImpl::new(HTMLElementType::Html))
-                }
-                ElementLocal::Head => {
-                    ElementType::HTMLElement(HTMLElementImpl::new(HTMLElementType::Head))
-                }
-                ElementLocal::Body => {
-                    ElementType::HTMLElement(HTMLElementImpl::new(HTMLElementType::Body))
-                }
-                _ => ElementType::None,
-            },
-            NameSpace::None => ElementType::None,
+    fn get(tag_name: &TagName) -> Self {
+        match tag_name.name_space() {
+            NameSpace::HTML => HTML_ELEMENTS
+                .iter()
+                .find(|(local, _)| *local == tag_name.local_name())
+                .map(|(_, ty)| ElementType::HTMLElement(HTMLElementImpl::new(*ty)))
+                .unwrap_or_default(),
+            // TODO: Create SVGElement and MathMLElement for the SVG and MathML namespaces.
+            _ => ElementType::None,
         }
     }
 }
 
-/// Collection of local name to create an element.
-#[derive(Debug, Clone)]
-pub enum ElementLocal {
-    /// "html"
-    Html,
-    /// "head"
-    Head,
-    /// "body"
-    Body,
-    /// "custom"
-    Custom(DOMString),
+/// Known elements in the HTML namespace and the interface they are created with.
+///
+/// Adding support for a new HTML element only needs a new entry here.
+const HTML_ELEMENTS: &[(&str, HTMLElementType)] = &[
+    ("html", HTMLElementType::Html),
+    ("head", HTMLElementType::Head),
+    ("body", HTMLElementType::Body),
+    ("title", HTMLElementType::Title),
+    ("slot", HTMLElementType::Slot),
+];
+
+/// The name identifying an element: its local name, namespace and namespace prefix.
+///
+/// Every path that creates or compares element names should go through this type so the HTML
+/// case rules are applied in one place. Use [`TagName::from_author_input`] for names coming from
+/// scripts (e.g. `createElement`), and [`TagName::exact`] for names that must be kept as-is like
+/// those from the parser or XML documents.
+#[derive(Clone, Debug, MallocSizeOf, PartialEq, Eq, Hash)]
+pub struct TagName {
+    local_name: DOMString,
+    name_space: NameSpace,
+    prefix: Option<DOMString>,
+}
+
+impl TagName {
+    /// Create a `TagName` from a name provided by an author. The name is converted to ASCII
+    /// lowercase when it is in the HTML namespace of an HTML document.
+    pub fn from_author_input(name: &str, name_space: NameSpace, is_html_document: bool) -> Self {
+        let local_name = if is_html_document && name_space == NameSpace::HTML {
+            name.to_ascii_lowercase()
+        } else {
+            name.to_owned()
+        };
+        Self {
+            local_name: DOMString::from(local_name),
+            name_space,
+            prefix: None,
+        }
+    }
+
+    /// Create a `TagName` keeping the name exactly as provided.
+    pub fn exact(name: &str, name_space: NameSpace) -> Self {
+        Self {
+            local_name: DOMString::from(name),
+            name_space,
+            prefix: None,
+        }
+    }
+
+    /// Create a `TagName` in the HTML namespace keeping the name exactly as provided.
+    pub fn html(name: &str) -> Self {
+        Self::exact(name, NameSpace::HTML)
+    }
+
+    /// Get the local name.
+    pub fn local_name(&self) -> &str {
+        &self.local_name
+    }
+
+    /// Get the namespace.
+    pub fn name_space(&self) -> NameSpace {
+        self.name_space
+    }
+
+    /// Set the namespace prefix.
+    pub fn with_prefix(mut self, prefix: Option<DOMString>) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    /// Get the namespace prefix.
+    pub fn prefix(&self) -> Option<&str> {
+        self.prefix.as_deref()
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-element-qualified-name>
+    pub fn qualified_name(&self) -> DOMString {
+        // An element’s qualified name is its local name if its namespace prefix is null; otherwise
+        // its namespace prefix, followed by ":", followed by its local name.
+        match &self.prefix {
+            Some(prefix) => DOMString::from(format!("{prefix}:{}", self.local_name)),
+            None => self.local_name.clone(),
+        }
+    }
+
+    /// Check if this name matches `qualified_name` with the rules of
+    /// <https://dom.spec.whatwg.org/#concept-getelementsbytagname>: HTML elements in an HTML document
+    /// compare against the ASCII lowercase of `qualified_name`, everything else compares exactly.
+    pub fn matches_qualified_name(&self, qualified_name: &str, is_html_document: bool) -> bool {
+        let name = self.qualified_name();
+        if is_html_document && self.name_space == NameSpace::HTML {
+            name == qualified_name.to_ascii_lowercase().as_str()
+        } else {
+            name == qualified_name
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        CustomElementConstructor,
+        testing::{self, element},
+    };
+
+    fn init(mode: ShadowRootMode) -> ShadowRootInit {
+        ShadowRootInit {
+            mode,
+            delegates_focus: false,
+            slot_assignment: SlotAssignmentMode::Named,
+        }
+    }
+
+    #[test]
+    fn insert_adjacent_element_positions() {
+        let (_user_agent, mut store, document) = testing::document();
+        let parent = Node::from(element(&document, "div", &mut store));
+        let target = element(&document, "p", &mut store);
+        parent.append_child(target.clone().into(), &mut store);
+
+        for (position, name) in [
+            ("beforeBegin", "h1"),
+            ("afterbegin", "b"),
+            ("BEFOREEND", "i"),
+            ("afterend", "hr"),
+        ] {
+            let new = element(&document, name, &mut store);
+            let inserted = target
+                .insert_adjacent_element(position, new.clone(), &mut store)
+                .unwrap()
+                .unwrap();
+            assert!(Node::from(inserted).is_same_node(&new.into(), &store));
+        }
+        let names = |node: &Node| -> Vec<DOMString> {
+            node.child_nodes(&store)
+                .iter()
+                .map(|child| child.node_name(&store))
+                .collect()
+        };
+        assert_eq!(names(&parent), ["H1", "P", "HR"]);
+        assert_eq!(names(&target.clone().into()), ["B", "I"]);
+
+        let detached = element(&document, "section", &mut store);
+        let new = element(&document, "span", &mut store);
+        assert!(
+            detached
+                .insert_adjacent_element("beforebegin", new.clone(), &mut store)
+                .unwrap()
+                .is_none()
+        );
+        assert!(matches!(
+            target.insert_adjacent_element("middle", new, &mut store),
+            Err(DomException::SyntaxError(_))
+        ));
+    }
+
+    #[test]
+    fn attach_shadow_to_a_valid_host() {
+        let (_user_agent, mut store, document) = testing::document();
+        let div = element(&document, "div", &mut store);
+        let shadow = div
+            .attach_shadow(init(ShadowRootMode::Open), &mut store)
+            .unwrap();
+        assert_eq!(shadow.mode(&store), ShadowRootMode::Open);
+        assert!(Node::from(shadow.host(&store)).is_same_node(&Node::from(div.clone()), &store));
+        let shadow_root = div.shadow_root(&store).unwrap();
+        assert!(Node::from(shadow_root).is_same_node(&Node::from(shadow), &store));
+
+        // A closed shadow root is attached but not exposed.
+        let span = element(&document, "span", &mut store);
+        span.attach_shadow(init(ShadowRootMode::Closed), &mut store)
+            .unwrap();
+        assert!(span.shadow_root(&store).is_none());
+    }
+
+    #[test]
+    fn attach_shadow_rejects_invalid_hosts() {
+        let (_user_agent, mut store, document) = testing::document();
+        let img = element(&document, "img", &mut store);
+        assert!(matches!(
+            img.attach_shadow(init(ShadowRootMode::Open), &mut store),
+            Err(DomException::NotSupportedError(_))
+        ));
+        let svg = document
+            .create_element_ns(
+                Some("http://www.w3.org/2000/svg".into()),
+                "svg".into(),
+                &mut store,
+            )
+            .unwrap();
+        assert!(matches!(
+            svg.attach_shadow(init(ShadowRootMode::Open), &mut store),
+            Err(DomException::NotSupportedError(_))
+        ));
+        let div = element(&document, "div", &mut store);
+        div.attach_shadow(init(ShadowRootMode::Open), &mut store)
+            .unwrap();
+        assert!(matches!(
+            div.attach_shadow(init(ShadowRootMode::Open), &mut store),
+            Err(DomException::NotSupportedError(_))
+        ));
+    }
+
+    #[test]
+    fn set_inner_html_keeps_the_depth_limit() {
+        let (_user_agent, mut store, document) = testing::document();
+        document.set_max_tree_depth(4, &mut store);
+        // The body is at depth 2, so two more levels fit.
+        let body = document.body(&store).unwrap();
+        body.set_inner_html("<div><div>x</div></div>", &mut store)
+            .unwrap_err();
+        body.set_inner_html("<div><div></div></div>", &mut store)
+            .unwrap();
+        assert_eq!(body.inner_html(&store), "<div><div></div></div>");
+
+        let error = body
+            .set_inner_html("<p><b><i></i></b></p>", &mut store)
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<DomException>(),
+            Some(DomException::HierarchyRequestError(_))
+        ));
+        // Nothing is replaced when parsing fails.
+        assert_eq!(body.inner_html(&store), "<div><div></div></div>");
+    }
+
+    #[test]
+    fn define_after_creation_upgrades_undefined_elements() {
+        let (_user_agent, mut store, document) = testing::document();
+        let div = element(&document, "div", &mut store);
+        assert_eq!(
+            div.custom_element_state(&store),
+            CustomElementState::Uncustomized
+        );
+        let thing = element(&document, "my-thing", &mut store);
+        assert_eq!(
+            thing.custom_element_state(&store),
+            CustomElementState::Undefined
+        );
+        let body = Node::from(document.body(&store).unwrap());
+        body.pre_insert(thing.clone().into(), None, &mut store)
+            .unwrap();
+
+        let registry = document.custom_element_registry(&store).unwrap();
+        registry
+            .define("my-thing", CustomElementConstructor(1), &mut store)
+            .unwrap();
+        assert_eq!(
+            thing.custom_element_state(&store),
+            CustomElementState::Custom
+        );
+        // Elements created after the definition are custom right away.
+        let created = element(&document, "my-thing", &mut store);
+        assert_eq!(
+            created.custom_element_state(&store),
+            CustomElementState::Custom
+        );
+        // Upgrading a custom element again keeps it as is.
+        let definition = registry
+            .look_up(NameSpace::HTML, "my-thing", None, &store)
+            .unwrap();
+        thing.upgrade(&definition, &mut store);
+        assert_eq!(
+            thing.custom_element_state(&store),
+            CustomElementState::Custom
+        );
+    }
 }
@@ -1,4 +1,6 @@
-use crate::IsEventTarget;
+use crate::{
+    IsEventTarget, browsing_context::BrowsingContextID, structured_clone::SerializedValue,
+};
 
 /// <https://dom.spec.whatwg.org/#event>
 #[derive(Clone, Debug)]
@@ -6,3 +8,51 @@ pub struct Event {
     _type_: String,
     _target: Option<IsEventTarget>,
 }
+
+/// <https://html.spec.whatwg.org/multipage/#messageevent>
+///
+/// Events can't be dispatched to guests yet, so a `MessageEvent` is handed to the embedder by
+/// [`Window::take_messages`](super::Window::take_messages), and its type is always "message".
+#[derive(Clone, Debug)]
+pub struct MessageEvent {
+    data: SerializedValue,
+    origin: String,
+    source: Option<BrowsingContextID>,
+}
+
+impl MessageEvent {
+    /// Create a "message" `MessageEvent`.
+    pub(crate) fn new(
+        data: SerializedValue,
+        origin: String,
+        source: Option<BrowsingContextID>,
+    ) -> Self {
+        Self {
+            data,
+            origin,
+            source,
+        }
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-messageevent-data>
+    ///
+    /// The data is still serialized, use
+    /// [`structured_deserialize`](crate::structured_clone::structured_deserialize) to get a value
+    /// graph in the realm it is delivered to.
+    pub fn data(&self) -> &SerializedValue {
+        &self.data
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-messageevent-origin>
+    pub fn origin(&self) -> &str {
+        &self.origin
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-messageevent-source>
+    ///
+    /// The browsing context of the window that posted the message, which stands for its
+    /// `WindowProxy`.
+    pub fn source(&self) -> Option<BrowsingContextID> {
+        self.source
+    }
+}
@@ -1,11 +1,19 @@
-use std::{collections::HashMap, fmt::Debug, sync::Arc};
+use malloc_size_of_derive::MallocSizeOf;
+use wasmtime::{Result, component::Resource};
 
-use crate::{Event, Node};
+use crate::{
+    Node, WindowStates,
+    ohim::dom::event_target::{
+        AddEventListenerOptions as WitAddEventListenerOptions,
+        EventListenerOptions as WitEventListenerOptions, Host, HostEventTarget,
+    },
+};
 
 /// <https://dom.spec.whatwg.org/#eventtarget>
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, MallocSizeOf)]
 pub struct EventTarget {
-    _callbacks: Option<HashMap<String, EventListener>>,
+    /// <https://dom.spec.whatwg.org/#eventtarget-event-listener-list>
+    event_listener_list: Vec<EventListenerEntry>,
 }
 
 impl EventTarget {
@@ -13,36 +21,165 @@ impl EventTarget {
     pub fn new() -> Self {
         Self::default()
     }
-}
 
-// impl EventTarget {
-//     fn add_event_listener(
-//         &mut self,
-//         ty: String,
-//         callback: EventListener,
-//         store: impl AsContextMut,
-//     ) {
-//     }
-//     fn remove_event_listener(
-//         &mut self,
-//         ty: String,
-//         callback: EventListener,
-//         store: impl AsContextMut,
-//     ) {
-//     }
-//     fn dispatch_event(&self, event: Event) {}
-// }
+    /// <https://dom.spec.whatwg.org/#dom-eventtarget-addeventlistener>
+    pub fn add_event_listener(
+        &mut self,
+        ty: &str,
+        callback: Option<EventListener>,
+        options: AddEventListenerOptions,
+    ) {
+        // 1. Let capture, passive, once, and signal be the result of flattening more options.
+        // 2. Add an event listener with this and an event listener whose type is type, callback is
+        // callback, capture is capture, passive is passive, once is once, and signal is signal.
+        self.add_an_event_listener(EventListenerEntry {
+            ty: ty.into(),
+            callback,
+            capture: options.capture,
+            passive: options.passive,
+            once: options.once,
+        });
+    }
+
+    /// <https://dom.spec.whatwg.org/#add-an-event-listener>
+    fn add_an_event_listener(&mut self, mut listener: EventListenerEntry) {
+        // 1. TODO: If eventTarget is a ServiceWorkerGlobalScope object, its service worker’s script
+        // resource’s has ever been evaluated flag is set, and listener’s type matches the type
+        // attribute value of any of the service worker events, then report a warning to the console
+        // that this might not give the expected results.
+        // 2. TODO: If listener’s signal is not null and is aborted, then return.
+        // 3. If listener’s callback is null, then return.
+        if listener.callback.is_none() {
+            return;
+        }
+        // 4. If listener’s passive is null, then set it to the default passive value given
+        // listener’s type and eventTarget.
+        // TODO: The default passive value is true for touch and wheel events on a Window, its
+        // Document, or the document element and body of its Document.
+        listener.passive.get_or_insert(false);
+        // 5. If eventTarget’s event listener list does not contain an event listener whose type is
+        // listener’s type, callback is listener’s callback, and capture is listener’s capture, then
+        // append listener to eventTarget’s event listener list.
+        // Otherwise, the listener is ignored and the flags of the listener already added are kept.
+        if !self
+            .event_listener_list
+            .iter()
+            .any(|entry| entry.matches(&listener.ty, listener.callback, listener.capture))
+        {
+            self.event_listener_list.push(listener);
+        }
+        // 6. TODO: If listener’s signal is not null, then add the following abort steps to it:
+        // Remove an event listener with eventTarget and listener.
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-eventtarget-removeeventlistener>
+    pub fn remove_event_listener(
+        &mut self,
+        ty: &str,
+        callback: Option<EventListener>,
+        options: EventListenerOptions,
+    ) {
+        // 1. Let capture be the result of flattening options.
+        // 2. If this’s event listener list contains an event listener whose type is type, callback
+        // is callback, and capture is capture, then remove an event listener with this and that
+        // event listener.
+        if let Some(index) = self
+            .event_listener_list
+            .iter()
+            .position(|entry| entry.matches(ty, callback, options.capture))
+        {
+            self.remove_an_event_listener(index);
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#remove-an-event-listener>
+    fn remove_an_event_listener(&mut self, index: usize) {
+        // 1. TODO: If eventTarget is a ServiceWorkerGlobalScope object and its service worker’s set of
+        // event types to handle contains listener’s type, then report a warning to the console that
+        // this might not give the expected results.
+        // 2. Set listener’s removed to true and remove listener from eventTarget’s event listener
+        // list.
+        // TODO: Set the removed flag once event dispatch keeps a clone of the list.
+        self.event_listener_list.remove(index);
+    }
+
+    /// Get the event listener list of this `EventTarget`, in the order listeners were added.
+    pub fn event_listeners(&self) -> &[EventListenerEntry] {
+        &self.event_listener_list
+    }
+}
 
 /// <https://dom.spec.whatwg.org/#callbackdef-eventlistener>
-#[derive(Clone)]
-pub struct EventListener(Arc<dyn FnMut(Event) + Send + Sync>);
+///
+/// Guests can not pass functions to the host yet, so a callback is identified by an id chosen by
+/// the guest. Two listeners with the same id are the same callback, and different ids are different
+/// callbacks even if the guest runs the same function for them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, MallocSizeOf)]
+pub struct EventListener(pub u32);
+
+/// <https://dom.spec.whatwg.org/#concept-event-listener>
+#[derive(Clone, Debug, MallocSizeOf)]
+pub struct EventListenerEntry {
+    ty: String,
+    callback: Option<EventListener>,
+    capture: bool,
+    passive: Option<bool>,
+    once: bool,
+    // TODO: signal
+}
+
+impl EventListenerEntry {
+    /// Check if this is the event listener identified by `ty`, `callback` and `capture`. Other
+    /// options are not part of the identity of an event listener.
+    fn matches(&self, ty: &str, callback: Option<EventListener>, capture: bool) -> bool {
+        self.ty == ty && self.callback == callback && self.capture == capture
+    }
 
-impl Debug for EventListener {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str("...")
+    /// Get the type of the event listener.
+    pub fn ty(&self) -> &str {
+        &self.ty
+    }
+
+    /// Get the callback of the event listener.
+    pub fn callback(&self) -> Option<EventListener> {
+        self.callback
+    }
+
+    /// Get the capture flag of the event listener.
+    pub fn capture(&self) -> bool {
+        self.capture
+    }
+
+    /// Get the passive flag of the event listener.
+    pub fn passive(&self) -> bool {
+        self.passive.unwrap_or_default()
+    }
+
+    /// Get the once flag of the event listener.
+    pub fn once(&self) -> bool {
+        self.once
     }
 }
 
+/// <https://dom.spec.whatwg.org/#dictdef-eventlisteneroptions>
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EventListenerOptions {
+    /// `capture`
+    pub capture: bool,
+}
+
+/// <https://dom.spec.whatwg.org/#dictdef-addeventlisteneroptions>
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AddEventListenerOptions {
+    /// `capture`
+    pub capture: bool,
+    /// `passive`
+    pub passive: Option<bool>,
+    /// `once`
+    pub once: bool,
+    // TODO: signal
+}
+
 /// Types that inherent `EventTarget` and can be added to `Event`'s target fields.
 ///
 /// See <https://dontcallmedom.github.io/webidlpedia/names/EventTarget.html> for full list.
@@ -54,35 +191,171 @@ pub enum IsEventTarget {
     Node(Node),
 }
 
-// impl EventListener {
-//     fn call(&mut self, event: Event) {
-//         self.0(event)
-//     }
-// }
-
-// impl EventTargetMethods
-//     fn add_event_listener(&mut self, ty: String, callback: String) {
-//         let callbacks = self.callbacks.get_or_insert_default();
-//         callbacks
-//             .entry(ty)
-//             .and_modify(|v| v.push(callback.clone()))
-//             .or_insert(vec![callback]);
-//     }
-// }
-//
-// impl HostEventTarget for WindowStates {
-//     fn new(&mut self) -> Resource<EventTarget> {
-//         let target = EventTarget::new();
-//         self.table.push(target).unwrap()
-//     }
-//
-//     fn add_event_listener(&mut self, self_: Resource<EventTarget>, ty: String, callback: String) {
-//         let target = self.table.get_mut(&self_).unwrap();
-//         target.add_event_listener(ty, callback);
-//     }
-//
-//     fn drop(&mut self, rep: Resource<EventTarget>) -> Result<()> {
-//         self.table.delete(rep)?;
-//         Ok(())
-//     }
-// }
+impl From<WitEventListenerOptions> for EventListenerOptions {
+    fn from(value: WitEventListenerOptions) -> Self {
+        EventListenerOptions {
+            capture: value.capture,
+        }
+    }
+}
+
+impl From<WitAddEventListenerOptions> for AddEventListenerOptions {
+    fn from(value: WitAddEventListenerOptions) -> Self {
+        AddEventListenerOptions {
+            capture: value.capture,
+            passive: value.passive,
+            once: value.once,
+        }
+    }
+}
+
+impl Host for WindowStates {}
+
+impl HostEventTarget for WindowStates {
+    fn new(&mut self) -> Result<Resource<EventTarget>> {
+        Ok(self.push_resource(EventTarget::new())?)
+    }
+
+    fn add_event_listener(
+        &mut self,
+        self_: Resource<EventTarget>,
+        ty: String,
+        callback: u32,
+        options: WitAddEventListenerOptions,
+    ) -> Result<()> {
+        let target = self.table.get_mut(&self_)?;
+        target.add_event_listener(&ty, Some(EventListener(callback)), options.into());
+        Ok(())
+    }
+
+    fn remove_event_listener(
+        &mut self,
+        self_: Resource<EventTarget>,
+        ty: String,
+        callback: u32,
+        options: WitEventListenerOptions,
+    ) -> Result<()> {
+        let target = self.table.get_mut(&self_)?;
+        target.remove_event_listener(&ty, Some(EventListener(callback)), options.into());
+        Ok(())
+    }
+
+    fn drop(&mut self, rep: Resource<EventTarget>) -> Result<()> {
+        self.delete_resource(rep)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wasmtime::Engine;
+
+    use super::*;
+    use crate::user_agent::UserAgent;
+
+    fn add(target: &mut EventTarget, ty: &str, callback: u32, capture: bool) {
+        let options = AddEventListenerOptions {
+            capture,
+            ..Default::default()
+        };
+        target.add_event_listener(ty, Some(EventListener(callback)), options);
+    }
+
+    fn remove(target: &mut EventTarget, ty: &str, callback: u32, capture: bool) {
+        let options = EventListenerOptions { capture };
+        target.remove_event_listener(ty, Some(EventListener(callback)), options);
+    }
+
+    #[test]
+    fn duplicate_listeners_are_ignored() {
+        let mut target = EventTarget::new();
+        add(&mut target, "click", 1, false);
+        add(&mut target, "click", 1, false);
+        assert_eq!(target.event_listeners().len(), 1);
+
+        // The capture flag, the type and the callback id are each part of the identity.
+        add(&mut target, "click", 1, true);
+        add(&mut target, "keydown", 1, false);
+        add(&mut target, "click", 2, false);
+        assert_eq!(target.event_listeners().len(), 4);
+
+        // A null callback is never added.
+        target.add_event_listener("click", None, AddEventListenerOptions::default());
+        assert_eq!(target.event_listeners().len(), 4);
+    }
+
+    #[test]
+    fn first_registration_options_win() {
+        let mut target = EventTarget::new();
+        add(&mut target, "click", 1, false);
+        let options = AddEventListenerOptions {
+            capture: false,
+            passive: Some(true),
+            once: true,
+        };
+        target.add_event_listener("click", Some(EventListener(1)), options);
+        let [listener] = target.event_listeners() else {
+            panic!("expected one listener");
+        };
+        assert!(!listener.once());
+        assert!(!listener.passive());
+
+        let mut target = EventTarget::new();
+        target.add_event_listener("click", Some(EventListener(1)), options);
+        add(&mut target, "click", 1, false);
+        let [listener] = target.event_listeners() else {
+            panic!("expected one listener");
+        };
+        assert!(listener.once());
+        assert!(listener.passive());
+    }
+
+    #[test]
+    fn removal_matches_type_callback_and_capture() {
+        let mut target = EventTarget::new();
+        add(&mut target, "click", 1, true);
+        remove(&mut target, "click", 1, false);
+        remove(&mut target, "click", 2, true);
+        remove(&mut target, "keydown", 1, true);
+        assert_eq!(target.event_listeners().len(), 1);
+        remove(&mut target, "click", 1, true);
+        assert!(target.event_listeners().is_empty());
+    }
+
+    #[test]
+    fn guests_share_the_same_rules() {
+        let mut states = WindowStates::create(&mut UserAgent::new(Engine::default()));
+        let target = HostEventTarget::new(&mut states).unwrap();
+        let options = |capture| WitAddEventListenerOptions {
+            capture,
+            passive: None,
+            once: false,
+        };
+        for capture in [false, false, true] {
+            let target = Resource::new_borrow(target.rep());
+            states
+                .add_event_listener(target, "click".into(), 1, options(capture))
+                .unwrap();
+        }
+        let listeners = |states: &WindowStates| {
+            states
+                .table
+                .get(&Resource::<EventTarget>::new_borrow(target.rep()))
+                .unwrap()
+                .event_listeners()
+                .len()
+        };
+        assert_eq!(listeners(&states), 2);
+        states
+            .remove_event_listener(
+                Resource::new_borrow(target.rep()),
+                "click".into(),
+                1,
+                WitEventListenerOptions { capture: true },
+            )
+            .unwrap();
+        assert_eq!(listeners(&states), 1);
+        HostEventTarget::drop(&mut states, target).unwrap();
+        assert_eq!(states.resource_count(), 0);
+    }
+}
@@ -0,0 +1,115 @@
+use std::{error::Error, fmt};
+
+use crate::ohim::dom::node::{DomException as WitDomException, DomExceptionName};
+
+/// <https://webidl.spec.whatwg.org/#idl-DOMException>
+///
+/// Each variant is one of the [error names] and carries a message describing what went wrong.
+///
+/// [error names]: https://webidl.spec.whatwg.org/#idl-DOMException-error-names
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DomException {
+    /// The index is not in the allowed range.
+    IndexSizeError(String),
+    /// The operation would yield an incorrect node tree.
+    HierarchyRequestError(String),
+    /// The object is in the wrong document.
+    WrongDocumentError(String),
+    /// The string contains invalid characters.
+    InvalidCharacterError(String),
+    /// The object can not be found here.
+    NotFoundError(String),
+    /// The operation is not supported.
+    NotSupportedError(String),
+    /// The attribute is in use by another element.
+    InUseAttributeError(String),
+    /// The object is in an invalid state.
+    InvalidStateError(String),
+    /// The string did not match the expected pattern.
+    SyntaxError(String),
+    /// The operation is not allowed by namespaces in XML.
+    NamespaceError(String),
+    /// The operation is insecure.
+    SecurityError(String),
+    /// A network error occurred.
+    NetworkError(String),
+    /// The supplied node is incorrect or has an incorrect ancestor for this operation.
+    InvalidNodeTypeError(String),
+    /// The object can not be cloned.
+    DataCloneError(String),
+}
+
+impl DomException {
+    /// Get the error name of the exception.
+    pub fn name(&self) -> &'static str {
+        match self {
+            DomException::IndexSizeError(_) => "IndexSizeError",
+            DomException::HierarchyRequestError(_) => "HierarchyRequestError",
+            DomException::WrongDocumentError(_) => "WrongDocumentError",
+            DomException::InvalidCharacterError(_) => "InvalidCharacterError",
+            DomException::NotFoundError(_) => "NotFoundError",
+            DomException::NotSupportedError(_) => "NotSupportedError",
+            DomException::InUseAttributeError(_) => "InUseAttributeError",
+            DomException::InvalidStateError(_) => "InvalidStateError",
+            DomException::SyntaxError(_) => "SyntaxError",
+            DomException::NamespaceError(_) => "NamespaceError",
+            DomException::SecurityError(_) => "SecurityError",
+            DomException::NetworkError(_) => "NetworkError",
+            DomException::InvalidNodeTypeError(_) => "InvalidNodeTypeError",
+            DomException::DataCloneError(_) => "DataCloneError",
+        }
+    }
+
+    /// Get the message of the exception.
+    pub fn message(&self) -> &str {
+        match self {
+            DomException::IndexSizeError(message)
+            | DomException::HierarchyRequestError(message)
+            | DomException::WrongDocumentError(message)
+            | DomException::InvalidCharacterError(message)
+            | DomException::NotFoundError(message)
+            | DomException::NotSupportedError(message)
+            | DomException::InUseAttributeError(message)
+            | DomException::InvalidStateError(message)
+            | DomException::SyntaxError(message)
+            | DomException::NamespaceError(message)
+            | DomException::SecurityError(message)
+            | DomException::NetworkError(message)
+            | DomException::InvalidNodeTypeError(message)
+            | DomException::DataCloneError(message) => message,
+        }
+    }
+}
+
+impl fmt::Display for DomException {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.name(), self.message())
+    }
+}
+
+impl Error for DomException {}
+
+impl From<DomException> for WitDomException {
+    fn from(value: DomException) -> Self {
+        let name = match value {
+            DomException::IndexSizeError(_) => DomExceptionName::IndexSizeError,
+            DomException::HierarchyRequestError(_) => DomExceptionName::HierarchyRequestError,
+            DomException::WrongDocumentError(_) => DomExceptionName::WrongDocumentError,
+            DomException::InvalidCharacterError(_) => DomExceptionName::InvalidCharacterError,
+            DomException::NotFoundError(_) => DomExceptionName::NotFoundError,
+            DomException::NotSupportedError(_) => DomExceptionName::NotSupportedError,
+            DomException::InUseAttributeError(_) => DomExceptionName::InUseAttributeError,
+            DomException::InvalidStateError(_) => DomExceptionName::InvalidStateError,
+            DomException::SyntaxError(_) => DomExceptionName::SyntaxError,
+            DomException::NamespaceError(_) => DomExceptionName::NamespaceError,
+            DomException::SecurityError(_) => DomExceptionName::SecurityError,
+            DomException::NetworkError(_) => DomExceptionName::NetworkError,
+            DomException::InvalidNodeTypeError(_) => DomExceptionName::InvalidNodeTypeError,
+            DomException::DataCloneError(_) => DomExceptionName::DataCloneError,
+        };
+        WitDomException {
+            name,
+            message: value.message().to_owned(),
+        }
+    }
+}
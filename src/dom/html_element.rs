@@ -1,8 +1,9 @@
 use std::ops::Deref;
 
+use malloc_size_of_derive::MallocSizeOf;
 use wasmtime::{ExternRef, Rooted};
 
-use super::{NodeImpl, Object};
+use super::{HTMLSlotElementImpl, NodeImpl, Object};
 
 /// <https://html.spec.whatwg.org/multipage/#htmlelement>
 #[derive(Clone, Debug)]
@@ -42,20 +43,25 @@ impl Deref for HTMLElement {
 }
 
 /// Implementation of acutal `HTMLElement` object. This can be accessed from `NodeImpl`.
-#[derive(Debug)]
+#[derive(Debug, MallocSizeOf)]
 pub struct HTMLElementImpl {
     _html_type: HTMLElementType,
+    /// The state of an `HTMLSlotElement`, or `None` for other elements.
+    pub(crate) slot: Option<HTMLSlotElementImpl>,
 }
 
 impl HTMLElementImpl {
     /// Create an `HTMLElementImpl` with provided node type data.
     pub fn new(data: HTMLElementType) -> Self {
-        Self { _html_type: data }
+        Self {
+            _html_type: data,
+            slot: matches!(data, HTMLElementType::Slot).then(HTMLSlotElementImpl::default),
+        }
     }
 }
 
 /// The actual implementation of each HTMLElement type
-#[derive(Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, MallocSizeOf)]
 pub enum HTMLElementType {
     /// HTMLHtmlElement
     Html,
@@ -63,6 +69,10 @@ pub enum HTMLElementType {
     Head,
     /// HTMLBodyElement
     Body,
+    /// HTMLTitleElement
+    Title,
+    /// HTMLSlotElement
+    Slot,
     /// Similer to `Option::None`.
     #[default]
     None,
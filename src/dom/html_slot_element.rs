@@ -0,0 +1,652 @@
+use std::ops::Deref;
+
+use malloc_size_of_derive::MallocSizeOf;
+use wasmtime::{AsContext, AsContextMut, ExternRef, Rooted};
+
+use crate::{NodeImpl, NodeTypeData, Object, string::DOMString};
+
+use super::{Element, Node, ShadowRootMode, SlotAssignmentMode};
+
+/// <https://html.spec.whatwg.org/multipage/#htmlslotelement>
+#[derive(Clone, Debug)]
+pub struct HTMLSlotElement(pub(crate) Object<NodeImpl>);
+
+impl HTMLSlotElement {
+    /// <https://html.spec.whatwg.org/multipage/#dom-slot-name>
+    ///
+    /// This is also the [slot's name](https://dom.spec.whatwg.org/#slot-name), which is the empty
+    /// string if the attribute is absent.
+    pub fn name(&self, store: impl AsContext) -> DOMString {
+        // The name IDL attribute must reflect the content attribute of the same name.
+        Element::from(self.clone()).get_attribute_value("name", store)
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-slot-name>
+    pub fn set_name(&self, name: &str, store: impl AsContextMut) {
+        Element::from(self.clone()).set_attribute_value("name", name, store)
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-slot-assignednodes>
+    pub fn assigned_nodes(&self, flatten: bool, store: impl AsContext) -> Vec<Node> {
+        // 1. If options["flatten"] is false, then return this's assigned nodes.
+        if !flatten {
+            return self.data(&store).as_slot().assigned_nodes.clone();
+        }
+        // 2. Return the result of finding flattened slottables with this.
+        self.find_flattened_slottables(store)
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-slot-assignedelements>
+    pub fn assigned_elements(&self, flatten: bool, store: impl AsContext) -> Vec<Element> {
+        // 1. If options["flatten"] is false, then return this's assigned nodes, filtered to contain
+        // only Element nodes.
+        // 2. Return the result of finding flattened slottables with this, filtered to contain only
+        // Element nodes.
+        self.assigned_nodes(flatten, &store)
+            .into_iter()
+            .filter_map(|node| node.as_element(&store))
+            .collect()
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-slot-assign>
+    ///
+    /// Each of `nodes` must be an `Element` or a `Text` node. The nodes only get assigned to this
+    /// slot if it is in a shadow root whose slot assignment is "manual".
+    pub fn assign(&self, nodes: Vec<Node>, mut store: impl AsContextMut) {
+        // 1. For each node of this's manually assigned nodes, set node's manual slot assignment to
+        // null.
+        let old_nodes = std::mem::take(
+            &mut self
+                .data_mut(&mut store)
+                .as_slot_mut()
+                .manually_assigned_nodes,
+        );
+        for node in old_nodes {
+            node.data_mut(&mut store).manual_slot_assignment = None;
+        }
+        // 2. Let nodesSet be a new ordered set.
+        let mut nodes_set: Vec<Node> = Vec::with_capacity(nodes.len());
+        // 3. For each node of nodes:
+        for node in nodes {
+            debug_assert!(is_slottable(&node, &store));
+            if nodes_set.iter().any(|n| n.is_same_node(&node, &store)) {
+                continue;
+            }
+            // 3.1 If node's manual slot assignment refers to a slot, then remove node from that
+            // slot's manually assigned nodes.
+            let previous = node.data(&store).manual_slot_assignment.clone();
+            if let Some(previous) = previous {
+                let index = previous
+                    .data(&store)
+                    .as_slot()
+                    .manually_assigned_nodes
+                    .iter()
+                    .position(|n| n.is_same_node(&node, &store));
+                if let Some(index) = index {
+                    previous
+                        .data_mut(&mut store)
+                        .as_slot_mut()
+                        .manually_assigned_nodes
+                        .remove(index);
+                }
+            }
+            // 3.2 Set node's manual slot assignment to this.
+            node.data_mut(&mut store).manual_slot_assignment = Some(self.clone());
+            // 3.3 Append node to nodesSet.
+            nodes_set.push(node);
+        }
+        // 4. Set this's manually assigned nodes to nodesSet.
+        self.data_mut(&mut store)
+            .as_slot_mut()
+            .manually_assigned_nodes = nodes_set;
+        // 5. Run assign slottables for a tree for this's root.
+        let root = Node::from(self.clone()).root(&store);
+        assign_slottables_for_tree(&root, store);
+    }
+
+    /// Check if this slot is the same slot as `other`.
+    fn is_same_slot(&self, other: &HTMLSlotElement, store: impl AsContext) -> bool {
+        Node::from(self.clone()).is_same_node(&other.clone().into(), store)
+    }
+
+    /// Get the host of the shadow root that is the root of this slot, or `None` if the root is
+    /// not a shadow root.
+    fn shadow_root_host(&self, store: impl AsContext) -> Option<(Element, SlotAssignmentMode)> {
+        let root = Node::from(self.clone()).root(&store);
+        match &root.data(&store).data {
+            NodeTypeData::ShadowRoot(shadow) => Some((shadow.host.clone(), shadow.slot_assignment)),
+            _ => None,
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#find-slotables>
+    pub(crate) fn find_slottables(&self, store: impl AsContext) -> Vec<Node> {
+        // 1. Let result be « ».
+        // 2. Let root be slot's root.
+        // 3. If root is not a shadow root, then return result.
+        // 4. Let host be root's host.
+        let Some((host, slot_assignment)) = self.shadow_root_host(&store) else {
+            return Vec::new();
+        };
+        let host = Node::from(host);
+        // 5. If root's slot assignment is "manual":
+        if slot_assignment == SlotAssignmentMode::Manual {
+            // 5.1 For each slottable slottable of slot's manually assigned nodes, if slottable's
+            // parent is host, append slottable to result.
+            return self
+                .data(&store)
+                .as_slot()
+                .manually_assigned_nodes
+                .iter()
+                .filter(|slottable| {
+                    slottable
+                        .parent_node(&store)
+                        .is_some_and(|parent| parent.is_same_node(&host, &store))
+                })
+                .cloned()
+                .collect();
+        }
+        // 6. Otherwise, for each slottable child slottable of host, in tree order:
+        // 6.1 Let foundSlot be the result of finding a slot given slottable.
+        // 6.2 If foundSlot is slot, then append slottable to result.
+        // 7. Return result.
+        host.child_nodes(&store)
+            .into_iter()
+            .filter(|slottable| {
+                is_slottable(slottable, &store)
+                    && find_slot(slottable, false, &store)
+                        .is_some_and(|slot| slot.is_same_slot(self, &store))
+            })
+            .collect()
+    }
+
+    /// <https://dom.spec.whatwg.org/#find-flattened-slotables>
+    pub(crate) fn find_flattened_slottables(&self, store: impl AsContext) -> Vec<Node> {
+        // 1. Let result be « ».
+        let mut result = Vec::new();
+        // 2. If slot's root is not a shadow root, then return result.
+        if self.shadow_root_host(&store).is_none() {
+            return result;
+        }
+        // 3. Let slottables be the result of finding slottables given slot.
+        let mut slottables = self.find_slottables(&store);
+        // 4. If slottables is the empty list, then append each slottable child of slot, in tree
+        // order, to slottables.
+        if slottables.is_empty() {
+            slottables = Node::from(self.clone())
+                .child_nodes(&store)
+                .into_iter()
+                .filter(|child| is_slottable(child, &store))
+                .collect();
+        }
+        // 5. For each node of slottables:
+        for node in slottables {
+            match node
+                .as_slot(&store)
+                .filter(|slot| slot.shadow_root_host(&store).is_some())
+            {
+                // 5.1 If node is a slot whose root is a shadow root:
+                // 5.1.1 Let temporaryResult be the result of finding flattened slottables given
+                // node.
+                // 5.1.2 Append each slottable in temporaryResult, in order, to result.
+                Some(slot) => result.extend(slot.find_flattened_slottables(&store)),
+                // 5.2 Otherwise, append node to result.
+                None => result.push(node),
+            }
+        }
+        // 6. Return result.
+        result
+    }
+
+    /// <https://dom.spec.whatwg.org/#assign-slotables>
+    pub(crate) fn assign_slottables(&self, mut store: impl AsContextMut) {
+        // 1. Let slottables be the result of finding slottables for slot.
+        let slottables = self.find_slottables(&store);
+        // 2. If slottables and slot's assigned nodes are not identical, then run signal a slot
+        // change for slot.
+        let assigned_nodes = &self.data(&store).as_slot().assigned_nodes;
+        let identical = slottables.len() == assigned_nodes.len()
+            && slottables
+                .iter()
+                .zip(assigned_nodes)
+                .all(|(a, b)| a.is_same_node(b, &store));
+        if !identical {
+            self.signal_slot_change(&mut store);
+        }
+        // 3. Set slot's assigned nodes to slottables.
+        self.data_mut(&mut store).as_slot_mut().assigned_nodes = slottables.clone();
+        // 4. For each slottable of slottables, set slottable's assigned slot to slot.
+        for slottable in slottables {
+            slottable.data_mut(&mut store).assigned_slot = Some(self.clone());
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#signal-a-slot-change>
+    pub(crate) fn signal_slot_change(&self, mut store: impl AsContextMut) {
+        // 1. Append slot to slot's relevant agent's signal slots.
+        // The signal slots are kept by the node document of the slot. See
+        // [`Document::take_signal_slots`](super::Document::take_signal_slots).
+        if let Some(document) = self.data(&store).node_document().cloned() {
+            document.append_signal_slot(self, &mut store);
+        }
+        // 2. TODO: Queue a mutation observer microtask.
+    }
+
+    /// Get `Rooted<ExternRef>` reference of the `Node`.
+    pub fn as_root(&self) -> &Rooted<ExternRef> {
+        self
+    }
+}
+
+/// <https://dom.spec.whatwg.org/#concept-slotable>
+///
+/// `Element` and `Text` nodes are slottables.
+pub(crate) fn is_slottable(node: &Node, store: impl AsContext) -> bool {
+    let data = node.data(&store);
+    data.is_element() || data.is_text()
+}
+
+/// <https://dom.spec.whatwg.org/#slotable-name>
+///
+/// The name of an element is its slot attribute, and the name of a `Text` node is always the empty
+/// string.
+fn slottable_name(slottable: &Node, store: impl AsContext) -> DOMString {
+    slottable
+        .as_element(&store)
+        .map(|element| element.get_attribute_value("slot", &store))
+        .unwrap_or_default()
+}
+
+/// <https://dom.spec.whatwg.org/#find-a-slot>
+pub(crate) fn find_slot(
+    slottable: &Node,
+    open: bool,
+    store: impl AsContext,
+) -> Option<HTMLSlotElement> {
+    // 1. If slottable's parent is null, then return null.
+    let parent = slottable.parent_node(&store)?;
+    // 2. Let shadow be slottable's parent's shadow root.
+    // 3. If shadow is null, then return null.
+    let shadow = parent.data(&store).try_as_element()?.shadow_root.clone()?;
+    // 4. If the open flag is set and shadow's mode is not "open", then return null.
+    if open && shadow.mode(&store) != ShadowRootMode::Open {
+        return None;
+    }
+    let mut slots = Node::from(shadow.clone())
+        .descendants(&store)
+        .into_iter()
+        .filter_map(|node| node.as_slot(&store));
+    // 5. If shadow's slot assignment is "manual", then return the slot in shadow's descendants
+    // whose manually assigned nodes contains slottable, if any; otherwise null.
+    if shadow.slot_assignment(&store) == SlotAssignmentMode::Manual {
+        return slots.find(|slot| {
+            slot.data(&store)
+                .as_slot()
+                .manually_assigned_nodes
+                .iter()
+                .any(|node| node.is_same_node(slottable, &store))
+        });
+    }
+    // 6. Return the first slot in tree order in shadow's descendants whose name is slottable's
+    // name, if any; otherwise null.
+    let name = slottable_name(slottable, &store);
+    slots.find(|slot| slot.name(&store) == name)
+}
+
+/// <https://dom.spec.whatwg.org/#assign-slotables-for-a-tree>
+pub(crate) fn assign_slottables_for_tree(root: &Node, mut store: impl AsContextMut) {
+    // To assign slottables for a tree, given a node root, run assign slottables for each slot of
+    // root's inclusive descendants, in tree order.
+    let mut nodes = vec![root.clone()];
+    nodes.extend(root.descendants(&store));
+    for node in nodes {
+        if let Some(slot) = node.as_slot(&store) {
+            slot.assign_slottables(&mut store);
+        }
+    }
+}
+
+/// <https://dom.spec.whatwg.org/#assign-a-slot>
+pub(crate) fn assign_slot(slottable: &Node, mut store: impl AsContextMut) {
+    // 1. Let slot be the result of finding a slot with slottable.
+    // 2. If slot is non-null, then run assign slottables for slot.
+    if let Some(slot) = find_slot(slottable, false, &store) {
+        slot.assign_slottables(&mut store);
+    }
+}
+
+impl NodeImpl {
+    /// Get `HTMLSlotElementImpl` shared reference.
+    fn as_slot(&self) -> &HTMLSlotElementImpl {
+        self.try_as_element()
+            .and_then(|element| element.try_as_slot())
+            .expect("node is not an HTMLSlotElement")
+    }
+
+    /// Get `HTMLSlotElementImpl` exclusive reference.
+    fn as_slot_mut(&mut self) -> &mut HTMLSlotElementImpl {
+        self.try_as_element_mut()
+            .and_then(|element| element.try_as_slot_mut())
+            .expect("node is not an HTMLSlotElement")
+    }
+}
+
+impl Deref for HTMLSlotElement {
+    type Target = Object<NodeImpl>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<HTMLSlotElement> for Node {
+    fn from(value: HTMLSlotElement) -> Self {
+        Self(value.0)
+    }
+}
+
+impl From<HTMLSlotElement> for Element {
+    fn from(value: HTMLSlotElement) -> Self {
+        Self(value.0)
+    }
+}
+
+/// Implementation of acutal `HTMLSlotElement` object. This can be accessed from `NodeImpl`.
+#[derive(Debug, Default, MallocSizeOf)]
+pub struct HTMLSlotElementImpl {
+    /// <https://dom.spec.whatwg.org/#slot-assigned-nodes>
+    #[ignore_malloc_size_of = "owned by the GC heap"]
+    assigned_nodes: Vec<Node>,
+    /// <https://html.spec.whatwg.org/multipage/#manually-assigned-nodes>
+    #[ignore_malloc_size_of = "owned by the GC heap"]
+    manually_assigned_nodes: Vec<Node>,
+}
+
+#[cfg(test)]
+mod tests {
+    use wasmtime::Store;
+
+    use super::*;
+    use crate::{
+        Document, ShadowRoot, ShadowRootInit,
+        testing::{self, element, text},
+    };
+
+    /// Append `child` to `parent` the way `appendChild` does.
+    fn append(parent: impl Into<Node>, child: impl Into<Node>, store: &mut Store<()>) {
+        let parent: Node = parent.into();
+        parent.pre_insert(child.into(), None, store).unwrap();
+    }
+
+    /// Create a slot element named `name`.
+    fn slot(document: &Document, name: &str, store: &mut Store<()>) -> HTMLSlotElement {
+        let slot = Node::from(element(document, "slot", store))
+            .as_slot(&*store)
+            .unwrap();
+        slot.set_name(name, &mut *store);
+        slot
+    }
+
+    /// Create a div in the body with an open shadow root.
+    fn host(
+        document: &Document,
+        slot_assignment: SlotAssignmentMode,
+        store: &mut Store<()>,
+    ) -> (Element, ShadowRoot) {
+        let host = element(document, "div", store);
+        append(document.body(&*store).unwrap(), host.clone(), store);
+        let init = ShadowRootInit {
+            mode: ShadowRootMode::Open,
+            delegates_focus: false,
+            slot_assignment,
+        };
+        let shadow = host.attach_shadow(init, &mut *store).unwrap();
+        (host, shadow)
+    }
+
+    fn assert_nodes(actual: Vec<Node>, expected: &[Node], store: &Store<()>) {
+        assert_eq!(actual.len(), expected.len());
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert!(actual.is_same_node(expected, store));
+        }
+    }
+
+    fn assert_slot(actual: Option<HTMLSlotElement>, expected: &HTMLSlotElement, store: &Store<()>) {
+        assert!(actual.unwrap().is_same_slot(expected, store));
+    }
+
+    #[test]
+    fn named_and_default_slots() {
+        let (_user_agent, mut store, document) = testing::document();
+        let (host, shadow) = host(&document, SlotAssignmentMode::Named, &mut store);
+        let span = element(&document, "span", &mut store);
+        span.set_slot("a", &mut store);
+        let data = document.create_text_node("t".into(), &mut store).unwrap();
+        let unknown = element(&document, "i", &mut store);
+        unknown.set_slot("missing", &mut store);
+        append(host.clone(), span.clone(), &mut store);
+        append(host.clone(), data.clone(), &mut store);
+        append(host.clone(), unknown.clone(), &mut store);
+
+        // The slots are inserted after the host children.
+        let named = slot(&document, "a", &mut store);
+        let default = slot(&document, "", &mut store);
+        append(shadow.clone(), default.clone(), &mut store);
+        append(shadow.clone(), named.clone(), &mut store);
+        // This child is inserted after the slots.
+        let b = element(&document, "b", &mut store);
+        append(host.clone(), b.clone(), &mut store);
+
+        assert_nodes(
+            named.assigned_nodes(false, &store),
+            &[span.clone().into()],
+            &store,
+        );
+        assert_nodes(
+            default.assigned_nodes(false, &store),
+            &[data.clone().into(), b.clone().into()],
+            &store,
+        );
+        assert_slot(span.assigned_slot(&store), &named, &store);
+        assert_slot(b.assigned_slot(&store), &default, &store);
+        assert_slot(data.assigned_slot(&store), &default, &store);
+        assert!(unknown.assigned_slot(&store).is_none());
+
+        // Removing a child takes it out of its slot.
+        Node::from(b.clone()).remove(false, &mut store);
+        assert_nodes(
+            default.assigned_nodes(false, &store),
+            &[data.into()],
+            &store,
+        );
+        assert!(b.assigned_slot(&store).is_none());
+    }
+
+    #[test]
+    fn closed_shadow_roots_hide_the_assigned_slot() {
+        let (_user_agent, mut store, document) = testing::document();
+        let host = element(&document, "div", &mut store);
+        let init = ShadowRootInit {
+            mode: ShadowRootMode::Closed,
+            delegates_focus: false,
+            slot_assignment: SlotAssignmentMode::Named,
+        };
+        let shadow = host.attach_shadow(init, &mut store).unwrap();
+        let default = slot(&document, "", &mut store);
+        append(shadow, default.clone(), &mut store);
+        let span = element(&document, "span", &mut store);
+        append(host, span.clone(), &mut store);
+
+        assert_nodes(
+            default.assigned_nodes(false, &store),
+            &[span.clone().into()],
+            &store,
+        );
+        assert!(span.assigned_slot(&store).is_none());
+    }
+
+    #[test]
+    fn changing_the_slot_attribute_signals_each_affected_slot_once() {
+        let (_user_agent, mut store, document) = testing::document();
+        let (host, shadow) = host(&document, SlotAssignmentMode::Named, &mut store);
+        let named = slot(&document, "a", &mut store);
+        let other = slot(&document, "b", &mut store);
+        let default = slot(&document, "", &mut store);
+        append(shadow.clone(), named.clone(), &mut store);
+        append(shadow.clone(), other.clone(), &mut store);
+        append(shadow, default.clone(), &mut store);
+        let span = element(&document, "span", &mut store);
+        span.set_slot("a", &mut store);
+        let em = element(&document, "em", &mut store);
+        append(host.clone(), span.clone(), &mut store);
+        append(host, em.clone(), &mut store);
+        let signaled = document.take_signal_slots(&mut store);
+        assert_nodes(
+            signaled.into_iter().map(Node::from).collect(),
+            &[named.clone().into(), default.clone().into()],
+            &store,
+        );
+
+        // Moving span to the default slot changes both slots, even though the default slot is
+        // reassigned twice.
+        span.remove_attribute("slot", &mut store);
+        em.set_attribute("slot", "b", &mut store).unwrap();
+        let signaled = document.take_signal_slots(&mut store);
+        assert_nodes(
+            signaled.into_iter().map(Node::from).collect(),
+            &[
+                named.clone().into(),
+                default.clone().into(),
+                other.clone().into(),
+            ],
+            &store,
+        );
+        assert!(named.assigned_nodes(false, &store).is_empty());
+        assert_nodes(
+            default.assigned_nodes(false, &store),
+            &[span.clone().into()],
+            &store,
+        );
+        assert_nodes(
+            other.assigned_nodes(false, &store),
+            &[em.clone().into()],
+            &store,
+        );
+
+        // An absent slot attribute and an empty one are the same name.
+        span.set_slot("", &mut store);
+        assert!(document.take_signal_slots(&mut store).is_empty());
+
+        // Renaming a slot reassigns the slottables of its tree.
+        other.set_name("a", &mut store);
+        let signaled = document.take_signal_slots(&mut store);
+        assert_nodes(
+            signaled.into_iter().map(Node::from).collect(),
+            &[other.clone().into()],
+            &store,
+        );
+        assert!(other.assigned_nodes(false, &store).is_empty());
+    }
+
+    #[test]
+    fn flatten_resolves_nested_shadow_trees() {
+        let (_user_agent, mut store, document) = testing::document();
+        let (outer, outer_shadow) = host(&document, SlotAssignmentMode::Named, &mut store);
+        let span = element(&document, "span", &mut store);
+        append(outer, span.clone(), &mut store);
+
+        // The outer shadow tree forwards its children to the inner one through `forward`.
+        let inner = element(&document, "section", &mut store);
+        append(outer_shadow, inner.clone(), &mut store);
+        let init = ShadowRootInit {
+            mode: ShadowRootMode::Open,
+            delegates_focus: false,
+            slot_assignment: SlotAssignmentMode::Named,
+        };
+        let inner_shadow = inner.attach_shadow(init, &mut store).unwrap();
+        let innermost = slot(&document, "", &mut store);
+        let fallback = slot(&document, "x", &mut store);
+        let fallback_text = text(&document, "fallback", &mut store);
+        append(fallback.clone(), fallback_text.clone(), &mut store);
+        append(inner_shadow.clone(), innermost.clone(), &mut store);
+        append(inner_shadow, fallback.clone(), &mut store);
+        let forward = slot(&document, "", &mut store);
+        append(inner, forward.clone(), &mut store);
+
+        assert_nodes(
+            forward.assigned_nodes(false, &store),
+            &[span.clone().into()],
+            &store,
+        );
+        assert_nodes(
+            innermost.assigned_nodes(false, &store),
+            &[forward.into()],
+            &store,
+        );
+        assert_nodes(
+            innermost.assigned_nodes(true, &store),
+            &[span.clone().into()],
+            &store,
+        );
+        assert_eq!(innermost.assigned_elements(true, &store).len(), 1);
+        // A slot without assigned nodes flattens to its children.
+        assert!(fallback.assigned_nodes(false, &store).is_empty());
+        assert_nodes(
+            fallback.assigned_nodes(true, &store),
+            &[fallback_text],
+            &store,
+        );
+    }
+
+    #[test]
+    fn manual_assignment_ignores_names() {
+        let (_user_agent, mut store, document) = testing::document();
+        let (host, shadow) = host(&document, SlotAssignmentMode::Manual, &mut store);
+        let named = slot(&document, "a", &mut store);
+        let other = slot(&document, "", &mut store);
+        append(shadow.clone(), named.clone(), &mut store);
+        append(shadow, other.clone(), &mut store);
+        let span = element(&document, "span", &mut store);
+        span.set_slot("a", &mut store);
+        let b = element(&document, "b", &mut store);
+        append(host.clone(), span.clone(), &mut store);
+        append(host.clone(), b.clone(), &mut store);
+
+        // Nothing is assigned by name.
+        assert!(named.assigned_nodes(false, &store).is_empty());
+        assert!(other.assigned_nodes(false, &store).is_empty());
+
+        other.assign(vec![span.clone().into(), b.clone().into()], &mut store);
+        assert_nodes(
+            other.assigned_nodes(false, &store),
+            &[span.clone().into(), b.clone().into()],
+            &store,
+        );
+        assert_slot(span.assigned_slot(&store), &other, &store);
+
+        // Assigning a node to another slot takes it from the previous one.
+        named.assign(vec![b.clone().into()], &mut store);
+        assert_nodes(
+            other.assigned_nodes(false, &store),
+            &[span.clone().into()],
+            &store,
+        );
+        assert_nodes(
+            named.assigned_nodes(false, &store),
+            &[b.clone().into()],
+            &store,
+        );
+
+        // Only children of the host are assigned.
+        let detached = element(&document, "i", &mut store);
+        other.assign(vec![detached.into(), span.clone().into()], &mut store);
+        assert_nodes(
+            other.assigned_nodes(false, &store),
+            &[span.clone().into()],
+            &store,
+        );
+
+        Node::from(span).remove(false, &mut store);
+        assert!(other.assigned_nodes(false, &store).is_empty());
+    }
+}
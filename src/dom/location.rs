@@ -0,0 +1,156 @@
+use wasmtime::{AsContext, AsContextMut};
+
+use crate::{
+    navigible::{Navigable, NavigationHistoryBehavior},
+    string::DOMString,
+    url::DOMUrl,
+};
+
+use super::Document;
+
+/// <https://html.spec.whatwg.org/multipage/#the-location-interface>
+#[derive(Clone, Debug)]
+pub struct Location {
+    /// <https://html.spec.whatwg.org/multipage/#relevant-document>
+    document: Document,
+}
+
+impl Location {
+    /// Create a `Location` object whose relevant `Document` is `document`.
+    pub fn new(document: Document) -> Self {
+        Self { document }
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#concept-location-url>
+    fn url(&self, store: impl AsContext) -> DOMUrl {
+        self.document.url(store)
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-location-href>
+    pub fn href(&self, store: impl AsContext) -> DOMString {
+        DOMString::from(self.url(store).as_str())
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-location-href>
+    pub fn set_href(&self, value: &str, store: impl AsContextMut) -> Result<(), url::ParseError> {
+        self.assign(value, store)
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-location-protocol>
+    pub fn protocol(&self, store: impl AsContext) -> DOMString {
+        // Return this's url's scheme, followed by ":".
+        DOMString::from(format!("{}:", self.url(store).scheme()))
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-location-host>
+    pub fn host(&self, store: impl AsContext) -> DOMString {
+        let url = self.url(store);
+        match (url.host_str(), url.port()) {
+            // 3. If url's host is null, return the empty string.
+            (None, _) => DOMString::new(),
+            // 4. If url's port is null, return url's host, serialized.
+            (Some(host), None) => DOMString::from(host),
+            // 5. Return url's host, serialized, followed by ":" and url's port, serialized.
+            (Some(host), Some(port)) => DOMString::from(format!("{host}:{port}")),
+        }
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-location-hostname>
+    pub fn hostname(&self, store: impl AsContext) -> DOMString {
+        DOMString::from(self.url(store).host_str().unwrap_or_default())
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-location-port>
+    pub fn port(&self, store: impl AsContext) -> DOMString {
+        self.url(store)
+            .port()
+            .map(|port| DOMString::from(port.to_string()))
+            .unwrap_or_default()
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-location-pathname>
+    pub fn pathname(&self, store: impl AsContext) -> DOMString {
+        DOMString::from(self.url(store).path())
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-location-search>
+    pub fn search(&self, store: impl AsContext) -> DOMString {
+        match self.url(store).query() {
+            // 2. If this's url's query is either null or the empty string, return the empty string.
+            None | Some("") => DOMString::new(),
+            // 3. Return "?", followed by this's url's query.
+            Some(query) => DOMString::from(format!("?{query}")),
+        }
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-location-hash>
+    pub fn hash(&self, store: impl AsContext) -> DOMString {
+        match self.url(store).fragment() {
+            // 2. If this's url's fragment is either null or the empty string, return the empty string.
+            None | Some("") => DOMString::new(),
+            // 3. Return "#", followed by this's url's fragment.
+            Some(fragment) => DOMString::from(format!("#{fragment}")),
+        }
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-location-hash>
+    pub fn set_hash(&self, value: &str, store: impl AsContextMut) {
+        // 2. Let copyURL be a copy of this's url.
+        let mut copy_url = self.url(&store);
+        // 3. Let thisURLFragment be copyURL's fragment if it is non-null; otherwise the empty string.
+        let this_url_fragment = copy_url.fragment().unwrap_or_default().to_owned();
+        // 4. Let input be the given value with a single leading "#" removed, if any.
+        let input = value.strip_prefix('#').unwrap_or(value);
+        // 5. Set copyURL's fragment to the empty string.
+        // 6. Basic URL parse input, with copyURL as url and fragment state as state override.
+        copy_url.set_fragment(Some(input));
+        // 7. If copyURL's fragment is thisURLFragment, then return.
+        if copy_url.fragment() == Some(this_url_fragment.as_str()) {
+            return;
+        }
+        // 8. Location-object navigate this to copyURL.
+        self.navigate(copy_url, NavigationHistoryBehavior::Auto, store);
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-location-assign>
+    pub fn assign(&self, url: &str, store: impl AsContextMut) -> Result<(), url::ParseError> {
+        // 3. Parse url relative to the relevant settings object. If that failed, throw a
+        // "SyntaxError" DOMException.
+        let url = self.url(&store).join(url)?;
+        // 4. Location-object navigate this to the resulting URL record.
+        self.navigate(url, NavigationHistoryBehavior::Auto, store);
+        Ok(())
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-location-replace>
+    pub fn replace(&self, url: &str, store: impl AsContextMut) -> Result<(), url::ParseError> {
+        // 2. Parse url relative to the relevant settings object. If that failed, throw a
+        // "SyntaxError" DOMException.
+        let url = self.url(&store).join(url)?;
+        // 3. Location-object navigate this to the resulting URL record given "replace".
+        self.navigate(url, NavigationHistoryBehavior::Replace, store);
+        Ok(())
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-location-reload>
+    pub fn reload(&self, store: impl AsContextMut) {
+        // TODO: Reload this's relevant global object's navigable once documents can be fetched.
+        // Until then reloading is navigating to the current URL in place.
+        let url = self.url(&store);
+        self.navigate(url, NavigationHistoryBehavior::Replace, store);
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#location-object-navigate>
+    fn navigate(
+        &self,
+        url: DOMUrl,
+        history_handling: NavigationHistoryBehavior,
+        store: impl AsContextMut,
+    ) {
+        // TODO: 3. If this's relevant Document is not yet completely loaded, then set historyHandling
+        // to "replace".
+        // 4. Navigate navigable to url using sourceDocument, with exceptionsEnabled set to true and
+        // historyHandling set to historyHandling.
+        Navigable::navigate(&self.document, url, history_handling, store);
+    }
+}
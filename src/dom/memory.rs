@@ -0,0 +1,160 @@
+//! Measuring the host memory used by the nodes of a document.
+
+use std::mem::size_of;
+
+use malloc_size_of::{MallocSizeOf, MallocSizeOfOps};
+use wasmtime::AsContext;
+
+use super::{Attr, Node, NodeImpl, NodeTypeData};
+
+/// The number of objects of a kind and the bytes they use.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// The number of objects.
+    pub count: usize,
+    /// The bytes used by the objects.
+    pub bytes: usize,
+}
+
+impl MemoryUsage {
+    fn add(&mut self, bytes: usize) {
+        self.count += 1;
+        self.bytes += bytes;
+    }
+}
+
+/// The host memory used by the nodes of a tree, see [`Document::report_memory`].
+///
+/// The bytes of a node are the size of its `NodeImpl` and the heap memory it owns. Nodes it refers
+/// to are owned by the GC heap and are counted as nodes of their own. Attributes are counted apart
+/// from their elements.
+///
+/// [`Document::report_memory`]: super::Document::report_memory
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryReport {
+    /// Element nodes.
+    pub elements: MemoryUsage,
+    /// Text nodes.
+    pub text: MemoryUsage,
+    /// Attributes of the elements.
+    pub attributes: MemoryUsage,
+    /// Other nodes, which are documents, doctypes, comments and shadow roots.
+    pub other: MemoryUsage,
+}
+
+impl MemoryReport {
+    /// Measure `root` and its descendants in tree order.
+    ///
+    /// TODO: Shadow trees.
+    pub(crate) fn measure(root: &Node, ops: &mut MallocSizeOfOps, store: impl AsContext) -> Self {
+        let mut report = MemoryReport::default();
+        for node in std::iter::once(root.clone()).chain(root.descendants(&store)) {
+            let data = node.data(&store);
+            let bytes = size_of::<NodeImpl>() + data.size_of(ops);
+            match &data.data {
+                NodeTypeData::Element(element) => {
+                    let mut attributes = 0;
+                    for attr in &element.attribute_list {
+                        let attr_bytes = size_of::<Attr>() + attr.size_of(ops);
+                        report.attributes.add(attr_bytes);
+                        attributes += attr_bytes;
+                    }
+                    report.elements.add(bytes.saturating_sub(attributes));
+                }
+                NodeTypeData::Text(_) => report.text.add(bytes),
+                _ => report.other.add(bytes),
+            }
+        }
+        report
+    }
+
+    /// The bytes used by all nodes and attributes.
+    pub fn total_bytes(&self) -> usize {
+        self.elements.bytes + self.text.bytes + self.attributes.bytes + self.other.bytes
+    }
+}
+
+// Measuring needs the allocator to tell the size of a heap block, which glibc does.
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use std::{collections::HashSet, ffi::c_void};
+
+    use super::*;
+    use crate::testing::{self, element, text};
+
+    unsafe extern "C" {
+        fn malloc_usable_size(ptr: *const c_void) -> usize;
+    }
+
+    fn ops() -> MallocSizeOfOps {
+        let mut seen = HashSet::new();
+        MallocSizeOfOps::new(
+            malloc_usable_size,
+            None,
+            Some(Box::new(move |ptr: *const c_void| {
+                !seen.insert(ptr as usize)
+            })),
+        )
+    }
+
+    #[test]
+    fn nodes_are_counted_by_kind() {
+        let (_user_agent, mut store, document) = testing::document();
+        // The document has <html>, <head> and <body>.
+        let report = document.report_memory(&mut ops(), &store);
+        assert_eq!(report.elements.count, 3);
+        assert_eq!(report.text.count, 0);
+        assert_eq!(report.attributes.count, 0);
+        assert_eq!(report.other.count, 1);
+
+        let body = Node::from(document.body(&store).unwrap());
+        let div = element(&document, "div", &mut store);
+        div.set_attribute("id", "main", &mut store).unwrap();
+        div.set_attribute("class", "a b", &mut store).unwrap();
+        let div = Node::from(div);
+        body.pre_insert(div.clone(), None, &mut store).unwrap();
+        for data in ["one", "two"] {
+            let child = text(&document, data, &mut store);
+            div.pre_insert(child, None, &mut store).unwrap();
+        }
+        let comment = document.create_comment("c".into(), &mut store).unwrap();
+        body.pre_insert(comment.into(), None, &mut store).unwrap();
+
+        let report = document.report_memory(&mut ops(), &store);
+        assert_eq!(report.elements.count, 4);
+        assert_eq!(report.text.count, 2);
+        assert_eq!(report.attributes.count, 2);
+        assert_eq!(report.other.count, 2);
+        assert_eq!(
+            report.total_bytes(),
+            report.elements.bytes
+                + report.text.bytes
+                + report.attributes.bytes
+                + report.other.bytes
+        );
+        // A subtree is measured on its own.
+        let report = MemoryReport::measure(&div, &mut ops(), &store);
+        assert_eq!(report.elements.count, 1);
+        assert_eq!(report.text.count, 2);
+        assert_eq!(report.other.count, 0);
+    }
+
+    #[test]
+    fn bytes_grow_with_attributes() {
+        let (_user_agent, mut store, document) = testing::document();
+        let div = element(&document, "div", &mut store);
+        let mut last = MemoryReport::measure(&Node::from(div.clone()), &mut ops(), &store);
+        assert!(last.elements.bytes >= size_of::<NodeImpl>());
+        for index in 0..10 {
+            let name = format!("data-attribute-{index}");
+            div.set_attribute(&name, "some value", &mut store).unwrap();
+            let report = MemoryReport::measure(&Node::from(div.clone()), &mut ops(), &store);
+            assert_eq!(report.attributes.count, index + 1);
+            assert!(report.attributes.bytes > last.attributes.bytes);
+            assert!(report.total_bytes() > last.total_bytes());
+            last = report;
+        }
+        // Each attribute is at least its `Attr`.
+        assert!(last.attributes.bytes >= 10 * size_of::<Attr>());
+    }
+}
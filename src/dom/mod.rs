@@ -1,19 +1,58 @@
 //! DOM standard implementation
 
+mod attr;
+mod character_data;
+mod comment;
+mod custom_element_registry;
 mod document;
+mod document_fragment;
+mod document_type;
+mod dom_token_list;
 mod element;
 mod event;
 mod event_target;
+mod exception;
 mod html_element;
+mod html_slot_element;
+mod location;
+mod memory;
+mod mutation_observer;
+mod named_node_map;
 mod node;
 mod object;
+mod parser;
+mod range;
+mod sanitizer;
+mod selectors;
+mod shadow_root;
+mod text;
+mod timers;
 mod window;
 
+pub use attr::*;
+pub use character_data::*;
+pub use comment::*;
+pub use custom_element_registry::*;
 pub use document::*;
+pub use document_fragment::*;
+pub use document_type::*;
+pub use dom_token_list::*;
 pub use element::*;
 pub use event::*;
 pub use event_target::*;
+pub use exception::*;
 pub use html_element::*;
+pub use html_slot_element::*;
+pub use location::*;
+pub use memory::*;
+pub use mutation_observer::*;
+pub use named_node_map::*;
 pub use node::*;
 pub use object::*;
+pub use range::*;
+pub use sanitizer::*;
+pub use selectors::*;
+pub use shadow_root::*;
+pub use text::*;
+pub use timers::*;
 pub use window::*;
@@ -0,0 +1,684 @@
+use std::collections::VecDeque;
+
+use wasmtime::{AsContext, AsContextMut, Error, Result, Rooted, component::Resource};
+
+use crate::{
+    WindowStates,
+    ohim::dom::node::{
+        HostMutationObserver, MutationObserverInit as WitMutationObserverInit,
+        MutationRecord as WitMutationRecord,
+    },
+    string::DOMString,
+};
+
+use super::{Node, Object};
+
+/// <https://dom.spec.whatwg.org/#mutationobserver>
+///
+/// Only "childList" and "characterData" mutations are observed for now.
+/// TODO: Attributes and transient registered observers.
+#[derive(Clone, Debug)]
+pub struct MutationObserver(Object<MutationObserverImpl>);
+
+impl MutationObserver {
+    /// <https://dom.spec.whatwg.org/#dom-mutationobserver-mutationobserver>
+    pub fn new(callback: MutationCallback, store: impl AsContextMut) -> Result<Self> {
+        // The new MutationObserver(callback) constructor steps are to set this’s callback to
+        // callback.
+        Ok(MutationObserver(Object::new(
+            store,
+            MutationObserverImpl {
+                callback,
+                node_list: Vec::new(),
+                record_queue: VecDeque::new(),
+            },
+        )?))
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-mo-callback>
+    pub fn callback(&self, store: impl AsContext) -> MutationCallback {
+        self.0.data(&store).callback
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-mutationobserver-observe>
+    pub fn observe(
+        &self,
+        target: &Node,
+        options: MutationObserverInit,
+        mut store: impl AsContextMut,
+    ) -> Result<()> {
+        let mut options = options;
+        // 1. TODO: If either options["attributeOldValue"] or options["attributeFilter"] exists, and
+        // options["attributes"] does not exist, then set options["attributes"] to true.
+        // 2. If options["characterDataOldValue"] exists and options["characterData"] does not
+        // exist, then set options["characterData"] to true.
+        if options.character_data_old_value.is_some() && options.character_data.is_none() {
+            options.character_data = Some(true);
+        }
+        // 3. If none of options["childList"], options["attributes"], and options["characterData"]
+        // is true, then throw a TypeError.
+        // TODO: attributes
+        if !options.child_list && options.character_data != Some(true) {
+            return Err(Error::msg(
+                "TypeError: childList or characterData must be true to observe mutations",
+            ));
+        }
+        // 4 ~ 5. TODO: Validate attributeOldValue and attributeFilter.
+        // 6. If options["characterDataOldValue"] is true and options["characterData"] is false,
+        // then throw a TypeError.
+        if options.character_data_old_value == Some(true) && options.character_data == Some(false) {
+            return Err(Error::msg(
+                "TypeError: characterDataOldValue requires characterData to be true",
+            ));
+        }
+        // 7. For each registered of target’s registered observer list, if registered’s observer is
+        // this:
+        let index = target
+            .data(&store)
+            .registered_observers
+            .iter()
+            .position(|registered| registered.observer.is_same(self, &store));
+        match index {
+            Some(index) => {
+                // 7.1 TODO: For each node of this’s node list, remove all transient registered
+                // observers whose source is registered from node’s registered observer list.
+                // 7.2 Set registered’s options to options.
+                target.data_mut(&mut store).registered_observers[index].options = options;
+            }
+            // 8. Otherwise:
+            None => {
+                // 8.1 Append a new registered observer whose observer is this and options is
+                // options to target’s registered observer list.
+                target
+                    .data_mut(&mut store)
+                    .registered_observers
+                    .push(RegisteredObserver {
+                        observer: self.clone(),
+                        options,
+                    });
+                // 8.2 Append a weak reference to target to this’s node list.
+                self.0.data_mut(&mut store).node_list.push(target.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-mutationobserver-disconnect>
+    pub fn disconnect(&self, mut store: impl AsContextMut) {
+        // 1. For each node of this’s node list, remove any registered observer from node’s
+        // registered observer list for which this is the observer.
+        // The node list is emptied as well, since none of its nodes is observed anymore.
+        let node_list = std::mem::take(&mut self.0.data_mut(&mut store).node_list);
+        for node in node_list {
+            let registered_observers = node
+                .data(&store)
+                .registered_observers
+                .iter()
+                .filter(|registered| !registered.observer.is_same(self, &store))
+                .cloned()
+                .collect();
+            node.data_mut(&mut store).registered_observers = registered_observers;
+        }
+        // 2. Empty this’s record queue.
+        self.0.data_mut(&mut store).record_queue.clear();
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-mutationobserver-takerecords>
+    pub fn take_records(&self, mut store: impl AsContextMut) -> Vec<MutationRecord> {
+        // 1. Let records be a clone of this’s record queue.
+        // 2. Empty this’s record queue.
+        let records = std::mem::take(&mut self.0.data_mut(&mut store).record_queue);
+        // 3. Return records.
+        records.into()
+    }
+
+    /// Check if this is the same `MutationObserver` as `other`.
+    fn is_same(&self, other: &MutationObserver, store: impl AsContext) -> bool {
+        Rooted::ref_eq(&store, &*self.0, &*other.0).unwrap_or_default()
+    }
+}
+
+/// Implementation of acutal `MutationObserver` object.
+#[derive(Debug)]
+pub struct MutationObserverImpl {
+    /// <https://dom.spec.whatwg.org/#concept-mo-callback>
+    callback: MutationCallback,
+    /// <https://dom.spec.whatwg.org/#mutationobserver-node-list>
+    node_list: Vec<Node>,
+    /// <https://dom.spec.whatwg.org/#concept-mo-queue>
+    record_queue: VecDeque<MutationRecord>,
+}
+
+/// <https://dom.spec.whatwg.org/#callbackdef-mutationcallback>
+///
+/// Guests can not pass functions to the host yet, so a callback is identified by an id chosen by
+/// the guest, similar to [`EventListener`](super::EventListener).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MutationCallback(pub u32);
+
+/// <https://dom.spec.whatwg.org/#dictdef-mutationobserverinit>
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MutationObserverInit {
+    /// `childList`
+    pub child_list: bool,
+    /// `subtree`
+    pub subtree: bool,
+    /// `characterData`
+    pub character_data: Option<bool>,
+    /// `characterDataOldValue`
+    pub character_data_old_value: Option<bool>,
+    // TODO: attributes, attributeOldValue, attributeFilter
+}
+
+/// <https://dom.spec.whatwg.org/#registered-observer>
+#[derive(Clone, Debug)]
+pub(crate) struct RegisteredObserver {
+    observer: MutationObserver,
+    options: MutationObserverInit,
+}
+
+/// <https://dom.spec.whatwg.org/#dom-mutationrecord-type>
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MutationRecordType {
+    /// "characterData"
+    CharacterData,
+    /// "childList"
+    ChildList,
+    // TODO: "attributes"
+}
+
+impl MutationRecordType {
+    /// Get the string of the type.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MutationRecordType::CharacterData => "characterData",
+            MutationRecordType::ChildList => "childList",
+        }
+    }
+}
+
+/// <https://dom.spec.whatwg.org/#mutationrecord>
+#[derive(Clone, Debug)]
+pub struct MutationRecord {
+    ty: MutationRecordType,
+    target: Node,
+    added_nodes: Vec<Node>,
+    removed_nodes: Vec<Node>,
+    previous_sibling: Option<Node>,
+    next_sibling: Option<Node>,
+    old_value: Option<DOMString>,
+}
+
+impl MutationRecord {
+    /// <https://dom.spec.whatwg.org/#dom-mutationrecord-type>
+    pub fn ty(&self) -> MutationRecordType {
+        self.ty
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-mutationrecord-target>
+    pub fn target(&self) -> &Node {
+        &self.target
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-mutationrecord-addednodes>
+    pub fn added_nodes(&self) -> &[Node] {
+        &self.added_nodes
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-mutationrecord-removednodes>
+    pub fn removed_nodes(&self) -> &[Node] {
+        &self.removed_nodes
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-mutationrecord-previoussibling>
+    pub fn previous_sibling(&self) -> Option<&Node> {
+        self.previous_sibling.as_ref()
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-mutationrecord-nextsibling>
+    pub fn next_sibling(&self) -> Option<&Node> {
+        self.next_sibling.as_ref()
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-mutationrecord-oldvalue>
+    pub fn old_value(&self) -> Option<&DOMString> {
+        self.old_value.as_ref()
+    }
+}
+
+/// <https://dom.spec.whatwg.org/#queue-a-tree-mutation-record>
+pub(crate) fn queue_tree_mutation_record(
+    target: &Node,
+    added_nodes: Vec<Node>,
+    removed_nodes: Vec<Node>,
+    previous_sibling: Option<Node>,
+    next_sibling: Option<Node>,
+    store: impl AsContextMut,
+) {
+    // 1. Assert: either addedNodes or removedNodes is not empty.
+    debug_assert!(!added_nodes.is_empty() || !removed_nodes.is_empty());
+    // 2. Queue a mutation record of "childList" for target with null, null, null, addedNodes,
+    // removedNodes, previousSibling, and nextSibling.
+    queue_mutation_record(
+        MutationRecord {
+            ty: MutationRecordType::ChildList,
+            target: target.clone(),
+            added_nodes,
+            removed_nodes,
+            previous_sibling,
+            next_sibling,
+            old_value: None,
+        },
+        None,
+        store,
+    );
+}
+
+/// <https://dom.spec.whatwg.org/#concept-cd-replace>
+///
+/// Queue a mutation record of "characterData" for `target` with null, null, `old_value`, « », « »,
+/// null, and null.
+pub(crate) fn queue_character_data_mutation_record(
+    target: &Node,
+    old_value: DOMString,
+    store: impl AsContextMut,
+) {
+    queue_mutation_record(
+        MutationRecord {
+            ty: MutationRecordType::CharacterData,
+            target: target.clone(),
+            added_nodes: Vec::new(),
+            removed_nodes: Vec::new(),
+            previous_sibling: None,
+            next_sibling: None,
+            old_value: None,
+        },
+        Some(old_value),
+        store,
+    );
+}
+
+/// <https://dom.spec.whatwg.org/#queue-a-mutation-record>
+///
+/// `record` holds the type, target and the other arguments of the algorithm except `old_value`,
+/// which is only set on the records of observers that asked for it.
+fn queue_mutation_record(
+    record: MutationRecord,
+    old_value: Option<DOMString>,
+    mut store: impl AsContextMut,
+) {
+    // 1. Let interestedObservers be an empty map.
+    let mut interested_observers: Vec<(MutationObserver, Option<DOMString>)> = Vec::new();
+    // 2. Let nodes be the inclusive ancestors of target.
+    let mut node = Some(record.target.clone());
+    // 3. For each node in nodes, and then for each registered of node’s registered observer list:
+    while let Some(current) = node {
+        for registered in &current.data(&store).registered_observers {
+            // 3.1 Let options be registered’s options.
+            let options = registered.options;
+            // 3.2 If none of the following are true
+            // - node is not target and options["subtree"] is false
+            // - type is "characterData" and options["characterData"] is either not present or
+            //   false
+            // - type is "childList" and options["childList"] is false
+            // then:
+            // TODO: "attributes" records.
+            if (!current.is_same_node(&record.target, &store) && !options.subtree)
+                || (record.ty == MutationRecordType::CharacterData
+                    && options.character_data != Some(true))
+                || (record.ty == MutationRecordType::ChildList && !options.child_list)
+            {
+                continue;
+            }
+            // 3.2.1 Let mo be registered’s observer.
+            // 3.2.2 If interestedObservers[mo] does not exist, then set interestedObservers[mo] to
+            // null.
+            let index = match interested_observers
+                .iter()
+                .position(|(observer, _)| observer.is_same(&registered.observer, &store))
+            {
+                Some(index) => index,
+                None => {
+                    interested_observers.push((registered.observer.clone(), None));
+                    interested_observers.len() - 1
+                }
+            };
+            // 3.2.3 If either type is "attributes" and options["attributeOldValue"] is true, or type
+            // is "characterData" and options["characterDataOldValue"] is true, then set
+            // interestedObservers[mo] to oldValue.
+            // TODO: "attributes" records.
+            if record.ty == MutationRecordType::CharacterData
+                && options.character_data_old_value == Some(true)
+            {
+                interested_observers[index].1 = old_value.clone();
+            }
+        }
+        node = current.parent_node(&store);
+    }
+    // 4. For each observer → mappedOldValue of interestedObservers:
+    for (observer, mapped_old_value) in interested_observers {
+        // 4.1 Let record be a new MutationRecord object with its type set to type, target set to
+        // target, attributeName set to name, attributeNamespace set to namespace, oldValue set to
+        // mappedOldValue, addedNodes set to addedNodes, removedNodes set to removedNodes,
+        // previousSibling set to previousSibling, and nextSibling set to nextSibling.
+        // 4.2 Enqueue record to observer’s record queue.
+        observer
+            .0
+            .data_mut(&mut store)
+            .record_queue
+            .push_back(MutationRecord {
+                old_value: mapped_old_value,
+                ..record.clone()
+            });
+        // 4.3 TODO: Append observer to the surrounding agent’s pending mutation observers.
+    }
+    // 5. TODO: Queue a mutation observer microtask. Until there is an event loop to notify
+    // mutation observers, records are only delivered by `MutationObserver::take_records`.
+}
+
+impl From<WitMutationObserverInit> for MutationObserverInit {
+    fn from(value: WitMutationObserverInit) -> Self {
+        MutationObserverInit {
+            child_list: value.child_list,
+            subtree: value.subtree,
+            character_data: value.character_data,
+            character_data_old_value: value.character_data_old_value,
+        }
+    }
+}
+
+impl WindowStates {
+    /// Get the `MutationObserver` of `resource`.
+    ///
+    /// This returns an error instead of panicking if the object is not a `MutationObserver`.
+    fn get_mutation_observer(
+        &self,
+        resource: &Resource<MutationObserver>,
+    ) -> Result<MutationObserver> {
+        let observer = self.table.get(resource)?;
+        observer.0.try_data(&self.store)?;
+        Ok(observer.clone())
+    }
+
+    /// Push each of `nodes` to the resource table.
+    pub(crate) fn push_nodes(&mut self, nodes: Vec<Node>) -> Result<Vec<Resource<Node>>> {
+        nodes
+            .into_iter()
+            .map(|node| self.push_resource(node))
+            .collect()
+    }
+}
+
+impl HostMutationObserver for WindowStates {
+    fn new(&mut self, callback: u32) -> Result<Resource<MutationObserver>> {
+        let observer = MutationObserver::new(MutationCallback(callback), &mut self.store)?;
+        Ok(self.push_resource(observer)?)
+    }
+
+    fn observe(
+        &mut self,
+        self_: Resource<MutationObserver>,
+        target: Resource<Node>,
+        options: WitMutationObserverInit,
+    ) -> Result<()> {
+        let self_ = self.get_mutation_observer(&self_)?;
+        let target = self.get_node(&target)?;
+        self_.observe(&target, options.into(), &mut self.store)
+    }
+
+    fn disconnect(&mut self, self_: Resource<MutationObserver>) -> Result<()> {
+        let self_ = self.get_mutation_observer(&self_)?;
+        self_.disconnect(&mut self.store);
+        Ok(())
+    }
+
+    fn take_records(
+        &mut self,
+        self_: Resource<MutationObserver>,
+    ) -> Result<Vec<WitMutationRecord>> {
+        let self_ = self.get_mutation_observer(&self_)?;
+        let mut records = Vec::new();
+        for record in self_.take_records(&mut self.store) {
+            records.push(WitMutationRecord {
+                ty: record.ty.as_str().to_string(),
+                target: self.push_resource(record.target)?,
+                added_nodes: self.push_nodes(record.added_nodes)?,
+                removed_nodes: self.push_nodes(record.removed_nodes)?,
+                previous_sibling: record
+                    .previous_sibling
+                    .map(|node| self.push_resource(node))
+                    .transpose()?,
+                next_sibling: record
+                    .next_sibling
+                    .map(|node| self.push_resource(node))
+                    .transpose()?,
+                old_value: record.old_value.map(|value| value.to_string()),
+            });
+        }
+        Ok(records)
+    }
+
+    fn drop(&mut self, rep: Resource<MutationObserver>) -> Result<()> {
+        self.delete_resource(rep)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        CharacterData,
+        testing::{self, element, text},
+    };
+
+    fn observer(store: &mut wasmtime::Store<()>) -> MutationObserver {
+        MutationObserver::new(MutationCallback(0), store).unwrap()
+    }
+
+    #[test]
+    fn observe_validates_options() {
+        let (_user_agent, mut store, document) = testing::document();
+        let target = Node::from(document);
+        let observer = observer(&mut store);
+        assert!(
+            observer
+                .observe(&target, MutationObserverInit::default(), &mut store)
+                .is_err()
+        );
+        let options = MutationObserverInit {
+            character_data: Some(false),
+            character_data_old_value: Some(true),
+            ..Default::default()
+        };
+        assert!(observer.observe(&target, options, &mut store).is_err());
+        // characterDataOldValue implies characterData.
+        let options = MutationObserverInit {
+            character_data_old_value: Some(false),
+            ..Default::default()
+        };
+        assert!(observer.observe(&target, options, &mut store).is_ok());
+    }
+
+    #[test]
+    fn records_child_list_mutations() {
+        let (_user_agent, mut store, document) = testing::document();
+        let parent = Node::from(element(&document, "div", &mut store));
+        let observer = observer(&mut store);
+        let options = MutationObserverInit {
+            child_list: true,
+            ..Default::default()
+        };
+        observer.observe(&parent, options, &mut store).unwrap();
+
+        let child = text(&document, "x", &mut store);
+        parent.pre_insert(child.clone(), None, &mut store).unwrap();
+        let records = observer.take_records(&mut store);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].ty(), MutationRecordType::ChildList);
+        assert!(records[0].target().is_same_node(&parent, &store));
+        assert_eq!(records[0].added_nodes().len(), 1);
+        assert!(records[0].added_nodes()[0].is_same_node(&child, &store));
+        assert!(records[0].old_value().is_none());
+        assert!(observer.take_records(&mut store).is_empty());
+    }
+
+    #[test]
+    fn records_character_data_mutations() {
+        let (_user_agent, mut store, document) = testing::document();
+        let parent = Node::from(element(&document, "div", &mut store));
+        let child = text(&document, "old", &mut store);
+        parent.append_child(child.clone(), &mut store);
+
+        let with_old_value = observer(&mut store);
+        let options = MutationObserverInit {
+            subtree: true,
+            character_data_old_value: Some(true),
+            ..Default::default()
+        };
+        with_old_value
+            .observe(&parent, options, &mut store)
+            .unwrap();
+        let without_old_value = observer(&mut store);
+        let options = MutationObserverInit {
+            character_data: Some(true),
+            ..Default::default()
+        };
+        without_old_value
+            .observe(&child, options, &mut store)
+            .unwrap();
+        let child_list_only = observer(&mut store);
+        let options = MutationObserverInit {
+            child_list: true,
+            subtree: true,
+            ..Default::default()
+        };
+        child_list_only
+            .observe(&parent, options, &mut store)
+            .unwrap();
+
+        CharacterData(child.0.clone()).set_data("new", &mut store);
+
+        let records = with_old_value.take_records(&mut store);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].ty(), MutationRecordType::CharacterData);
+        assert!(records[0].target().is_same_node(&child, &store));
+        assert_eq!(records[0].old_value().map(|value| &**value), Some("old"));
+        let records = without_old_value.take_records(&mut store);
+        assert_eq!(records.len(), 1);
+        assert!(records[0].old_value().is_none());
+        assert!(child_list_only.take_records(&mut store).is_empty());
+    }
+
+    #[test]
+    fn disconnect_stops_observing() {
+        let (_user_agent, mut store, document) = testing::document();
+        let parent = Node::from(element(&document, "div", &mut store));
+        let observer = observer(&mut store);
+        let options = MutationObserverInit {
+            child_list: true,
+            ..Default::default()
+        };
+        observer.observe(&parent, options, &mut store).unwrap();
+        let child = text(&document, "x", &mut store);
+        parent.pre_insert(child, None, &mut store).unwrap();
+        observer.disconnect(&mut store);
+        assert!(observer.take_records(&mut store).is_empty());
+        let child = text(&document, "y", &mut store);
+        parent.pre_insert(child, None, &mut store).unwrap();
+        assert!(observer.take_records(&mut store).is_empty());
+    }
+
+    fn same_nodes(actual: &[Node], expected: &[&Node], store: &wasmtime::Store<()>) -> bool {
+        actual.len() == expected.len()
+            && actual
+                .iter()
+                .zip(expected)
+                .all(|(actual, expected)| actual.is_same_node(expected, store))
+    }
+
+    #[test]
+    fn subtree_observers_record_mutations_of_descendants() {
+        let (_user_agent, mut store, document) = testing::document();
+        let parent = Node::from(element(&document, "div", &mut store));
+        let child = Node::from(element(&document, "p", &mut store));
+        parent.pre_insert(child.clone(), None, &mut store).unwrap();
+        let subtree = observer(&mut store);
+        let options = MutationObserverInit {
+            child_list: true,
+            subtree: true,
+            ..Default::default()
+        };
+        subtree.observe(&parent, options, &mut store).unwrap();
+        let children_only = observer(&mut store);
+        let options = MutationObserverInit {
+            child_list: true,
+            ..Default::default()
+        };
+        children_only.observe(&parent, options, &mut store).unwrap();
+
+        let grandchild = text(&document, "x", &mut store);
+        child
+            .pre_insert(grandchild.clone(), None, &mut store)
+            .unwrap();
+
+        let records = subtree.take_records(&mut store);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].ty(), MutationRecordType::ChildList);
+        assert!(records[0].target().is_same_node(&child, &store));
+        assert!(same_nodes(records[0].added_nodes(), &[&grandchild], &store));
+        assert!(records[0].removed_nodes().is_empty());
+        assert!(records[0].previous_sibling().is_none());
+        assert!(records[0].next_sibling().is_none());
+        assert!(children_only.take_records(&mut store).is_empty());
+    }
+
+    #[test]
+    fn removals_record_the_removed_node_and_its_siblings() {
+        let (_user_agent, mut store, document) = testing::document();
+        let parent = Node::from(element(&document, "div", &mut store));
+        let [a, b, c] = ["a", "b", "c"].map(|data| text(&document, data, &mut store));
+        for child in [&a, &b, &c] {
+            parent.pre_insert(child.clone(), None, &mut store).unwrap();
+        }
+        let observer = observer(&mut store);
+        let options = MutationObserverInit {
+            child_list: true,
+            ..Default::default()
+        };
+        observer.observe(&parent, options, &mut store).unwrap();
+
+        b.remove(false, &mut store);
+        let records = observer.take_records(&mut store);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].ty(), MutationRecordType::ChildList);
+        assert!(records[0].target().is_same_node(&parent, &store));
+        assert!(records[0].added_nodes().is_empty());
+        assert!(same_nodes(records[0].removed_nodes(), &[&b], &store));
+        assert!(
+            records[0]
+                .previous_sibling()
+                .unwrap()
+                .is_same_node(&a, &store)
+        );
+        assert!(records[0].next_sibling().unwrap().is_same_node(&c, &store));
+
+        // Removing the first child has no previous sibling.
+        a.remove(false, &mut store);
+        let records = observer.take_records(&mut store);
+        assert_eq!(records.len(), 1);
+        assert!(same_nodes(records[0].removed_nodes(), &[&a], &store));
+        assert!(records[0].previous_sibling().is_none());
+        assert!(records[0].next_sibling().unwrap().is_same_node(&c, &store));
+
+        // Replacing a child is a single record with both lists.
+        let d = text(&document, "d", &mut store);
+        parent.replace_child(d.clone(), &c, &mut store).unwrap();
+        let records = observer.take_records(&mut store);
+        assert_eq!(records.len(), 1);
+        assert!(same_nodes(records[0].added_nodes(), &[&d], &store));
+        assert!(same_nodes(records[0].removed_nodes(), &[&c], &store));
+        assert!(records[0].previous_sibling().is_none());
+        assert!(records[0].next_sibling().is_none());
+    }
+}
@@ -0,0 +1,262 @@
+use wasmtime::{AsContext, AsContextMut};
+
+use crate::{DomException, agent::NameSpace};
+
+use super::{Attr, Element, Node};
+
+/// <https://dom.spec.whatwg.org/#namednodemap>
+///
+/// This is a live view of the attribute list of its element. Attributes are kept in the order they
+/// are appended, and the attributes returned from it have the element as their owner element.
+#[derive(Clone, Debug)]
+pub struct NamedNodeMap {
+    element: Element,
+}
+
+impl NamedNodeMap {
+    /// Create the `NamedNodeMap` of `element`.
+    pub(crate) fn new(element: Element) -> Self {
+        Self { element }
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-namednodemap-length>
+    pub fn length(&self, store: impl AsContext) -> usize {
+        // The length getter steps are to return the attribute list’s size.
+        self.element.data(&store).as_element().attribute_list.len()
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-namednodemap-item>
+    pub fn item(&self, index: usize, store: impl AsContext) -> Option<Attr> {
+        // 1. If index is equal to or greater than this’s attribute list’s size, then return null.
+        // 2. Otherwise, return this’s attribute list[index].
+        self.element
+            .data(&store)
+            .as_element()
+            .attribute_list
+            .get(index)
+            .cloned()
+            .map(|attr| attr.with_owner_element(Some(self.element.clone())))
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-namednodemap-getnameditem>
+    pub fn get_named_item(&self, qualified_name: &str, store: impl AsContext) -> Option<Attr> {
+        // The getNamedItem(qualifiedName) method steps are to return the result of getting an
+        // attribute given qualifiedName and element.
+        self.element
+            .attribute_position(qualified_name, &store)
+            .and_then(|index| self.item(index, &store))
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-namednodemap-setnameditem>
+    ///
+    /// An attribute with the same namespace and local name is replaced in place.
+    pub fn set_named_item(
+        &self,
+        attr: Attr,
+        mut store: impl AsContextMut,
+    ) -> Result<Option<Attr>, DomException> {
+        // The setNamedItem(attr) method steps are to return the result of setting an attribute
+        // given attr and element.
+        // 1. If attr’s element is neither null nor element, throw an "InUseAttributeError"
+        // DOMException.
+        let element = Node::from(self.element.clone());
+        if attr.owner_element.as_ref().is_some_and(|owner_element| {
+            !Node::from(owner_element.clone()).is_same_node(&element, &store)
+        }) {
+            return Err(DomException::InUseAttributeError(format!(
+                "\"{}\" is an attribute of another element",
+                attr.name()
+            )));
+        }
+        let attr = attr.with_owner_element(None);
+        // 2. Let oldAttr be the result of getting an attribute given attr’s namespace, attr’s local
+        // name, and element.
+        let attribute_list = &mut self
+            .element
+            .data_mut(&mut store)
+            .as_element_mut()
+            .attribute_list;
+        let old_attr = attribute_list.iter_mut().find(|old_attr| {
+            old_attr.name_space == attr.name_space && old_attr.local_name == attr.local_name
+        });
+        // 3. If oldAttr is attr, return attr.
+        // Attributes are stored by value, so replacing oldAttr with attr below is the same.
+        let (local_name, name_space, value) =
+            (attr.local_name.clone(), attr.name_space, attr.value.clone());
+        let old_attr = match old_attr {
+            // 4. If oldAttr is non-null, then replace oldAttr with attr.
+            Some(old_attr) => Some(std::mem::replace(old_attr, attr)),
+            // 5. Otherwise, append attr to element.
+            None => {
+                attribute_list.push(attr);
+                None
+            }
+        };
+        // Only attributes in the null namespace have change steps.
+        if name_space == NameSpace::None {
+            self.element.run_attribute_change_steps(
+                &local_name,
+                old_attr.as_ref().map(|old_attr| old_attr.value()),
+                Some(&*value),
+                store,
+            );
+        }
+        // 6. Return oldAttr.
+        Ok(old_attr)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-namednodemap-removenameditem>
+    pub fn remove_named_item(
+        &self,
+        qualified_name: &str,
+        mut store: impl AsContextMut,
+    ) -> Result<Attr, DomException> {
+        // 1. Let attr be the result of removing an attribute given qualifiedName and element.
+        // 2. If attr is null, then throw a "NotFoundError" DOMException.
+        let Some(index) = self.element.attribute_position(qualified_name, &store) else {
+            return Err(DomException::NotFoundError(format!(
+                "\"{qualified_name}\" is not an attribute"
+            )));
+        };
+        let attr = self
+            .element
+            .data_mut(&mut store)
+            .as_element_mut()
+            .attribute_list
+            .remove(index);
+        if attr.name_space == NameSpace::None {
+            self.element.run_attribute_change_steps(
+                &attr.local_name,
+                Some(attr.value()),
+                None,
+                store,
+            );
+        }
+        // 3. Return attr.
+        Ok(attr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wasmtime::Store;
+
+    use super::*;
+    use crate::{
+        ShadowRootInit, ShadowRootMode, SlotAssignmentMode,
+        string::DOMString,
+        testing::{self, element},
+    };
+
+    fn names(attributes: &NamedNodeMap, store: &Store<()>) -> Vec<DOMString> {
+        (0..attributes.length(store))
+            .map(|index| attributes.item(index, store).unwrap().name())
+            .collect()
+    }
+
+    fn attr(name: &str, value: &str) -> Attr {
+        Attr::new(name.into(), value.into())
+    }
+
+    #[test]
+    fn attributes_are_kept_in_insertion_order() {
+        let (_user_agent, mut store, document) = testing::document();
+        let div = element(&document, "div", &mut store);
+        let attributes = div.attributes();
+        for name in ["b", "a", "c"] {
+            assert!(
+                attributes
+                    .set_named_item(attr(name, "1"), &mut store)
+                    .unwrap()
+                    .is_none()
+            );
+        }
+        assert_eq!(names(&attributes, &store), ["b", "a", "c"]);
+
+        // Replacing an attribute keeps its position.
+        let old = attributes
+            .set_named_item(attr("a", "2"), &mut store)
+            .unwrap()
+            .unwrap();
+        assert_eq!(old.value(), "1");
+        assert_eq!(names(&attributes, &store), ["b", "a", "c"]);
+        assert_eq!(div.get_attribute("a", &store).unwrap(), "2");
+
+        // Removing and adding it again appends it.
+        let removed = attributes.remove_named_item("b", &mut store).unwrap();
+        assert_eq!(removed.value(), "1");
+        attributes.set_named_item(removed, &mut store).unwrap();
+        assert_eq!(names(&attributes, &store), ["a", "c", "b"]);
+
+        let item = attributes.item(0, &store).unwrap();
+        assert!(item.owner_element().is_some());
+        assert!(attributes.item(3, &store).is_none());
+        let error = attributes
+            .remove_named_item("missing", &mut store)
+            .unwrap_err();
+        assert!(matches!(error, DomException::NotFoundError(_)));
+    }
+
+    #[test]
+    fn attributes_of_other_elements_are_in_use() {
+        let (_user_agent, mut store, document) = testing::document();
+        let div = element(&document, "div", &mut store);
+        let span = element(&document, "span", &mut store);
+        div.set_attribute("title", "div", &mut store).unwrap();
+        let title = div.attributes().get_named_item("title", &store).unwrap();
+
+        let error = span
+            .attributes()
+            .set_named_item(title.clone(), &mut store)
+            .unwrap_err();
+        assert!(matches!(error, DomException::InUseAttributeError(_)));
+        assert!(span.get_attribute("title", &store).is_none());
+        // An attribute of the element itself is not in use.
+        let old = div
+            .attributes()
+            .set_named_item(title, &mut store)
+            .unwrap()
+            .unwrap();
+        assert_eq!(old.value(), "div");
+    }
+
+    #[test]
+    fn named_items_run_the_attribute_change_steps() {
+        let (_user_agent, mut store, document) = testing::document();
+        let host = element(&document, "div", &mut store);
+        let init = ShadowRootInit {
+            mode: ShadowRootMode::Open,
+            delegates_focus: false,
+            slot_assignment: SlotAssignmentMode::Named,
+        };
+        let shadow = host.attach_shadow(init, &mut store).unwrap();
+        let slot = element(&document, "slot", &mut store);
+        slot.set_attribute("name", "a", &mut store).unwrap();
+        Node::from(shadow)
+            .pre_insert(slot.into(), None, &mut store)
+            .unwrap();
+        let span = element(&document, "span", &mut store);
+        Node::from(host)
+            .pre_insert(span.clone().into(), None, &mut store)
+            .unwrap();
+        assert!(span.assigned_slot(&store).is_none());
+
+        // The slot attribute assigns span to the slot named "a".
+        span.attributes()
+            .set_named_item(attr("slot", "a"), &mut store)
+            .unwrap();
+        assert!(span.assigned_slot(&store).is_some());
+        span.attributes()
+            .set_named_item(attr("slot", "b"), &mut store)
+            .unwrap();
+        assert!(span.assigned_slot(&store).is_none());
+        span.attributes()
+            .set_named_item(attr("slot", "a"), &mut store)
+            .unwrap();
+        assert!(span.assigned_slot(&store).is_some());
+        span.attributes()
+            .remove_named_item("slot", &mut store)
+            .unwrap();
+        assert!(span.assigned_slot(&store).is_none());
+    }
+}
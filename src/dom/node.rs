@@ -1,12 +1,28 @@
-use std::{collections::VecDeque, ops::Deref};
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    ops::Deref,
+};
 
-use wasmtime::{AsContextMut, ExternRef, Result, Rooted, component::Resource};
+use malloc_size_of_derive::MallocSizeOf;
+use wasmtime::{AsContext, AsContextMut, ExternRef, Result, Rooted, component::Resource};
 
 use crate::{
-    DocumentImpl, ElementImpl, EventTarget, Object, WindowStates, ohim::dom::node::HostNode,
+    AddEventListenerOptions, CommentImpl, DocumentImpl, DocumentTypeImpl, DomException,
+    ElementImpl, EventListener, EventListenerOptions, EventTarget, Object, ShadowRootImpl,
+    TextImpl, WindowStates,
+    ohim::dom::node::{DomException as WitDomException, HostNode, NodeOrString as WitNodeOrString},
+    string::DOMString,
 };
 
-use super::{Document, Element};
+use super::{
+    CharacterData, Document, DocumentFragment, Element, HTMLSlotElement, Range, RegisteredObserver,
+    SelectorList, SlotAssignmentMode, Text,
+    element::serialize_html_nodes,
+    html_slot_element::{assign_slot, assign_slottables_for_tree, is_slottable},
+    mutation_observer::queue_tree_mutation_record,
+};
 
 /// <https://dom.spec.whatwg.org/#node>
 #[derive(Clone, Debug)]
@@ -14,107 +30,1558 @@ pub struct Node(pub(crate) Object<NodeImpl>);
 
 // TODO: This should be NodeMethods traits. Same for a EventTarget traits
 impl Node {
+    /// <https://dom.spec.whatwg.org/#dom-node-element_node>
+    pub const ELEMENT_NODE: u16 = 1;
+    /// <https://dom.spec.whatwg.org/#dom-node-attribute_node>
+    pub const ATTRIBUTE_NODE: u16 = 2;
+    /// <https://dom.spec.whatwg.org/#dom-node-text_node>
+    pub const TEXT_NODE: u16 = 3;
+    /// <https://dom.spec.whatwg.org/#dom-node-cdata_section_node>
+    pub const CDATA_SECTION_NODE: u16 = 4;
+    /// <https://dom.spec.whatwg.org/#dom-node-processing_instruction_node>
+    pub const PROCESSING_INSTRUCTION_NODE: u16 = 7;
+    /// <https://dom.spec.whatwg.org/#dom-node-comment_node>
+    pub const COMMENT_NODE: u16 = 8;
+    /// <https://dom.spec.whatwg.org/#dom-node-document_node>
+    pub const DOCUMENT_NODE: u16 = 9;
+    /// <https://dom.spec.whatwg.org/#dom-node-document_type_node>
+    pub const DOCUMENT_TYPE_NODE: u16 = 10;
+    /// <https://dom.spec.whatwg.org/#dom-node-document_fragment_node>
+    pub const DOCUMENT_FRAGMENT_NODE: u16 = 11;
+    /// <https://dom.spec.whatwg.org/#dom-node-document_position_disconnected>
+    pub const DOCUMENT_POSITION_DISCONNECTED: u16 = 0x01;
+    /// <https://dom.spec.whatwg.org/#dom-node-document_position_preceding>
+    pub const DOCUMENT_POSITION_PRECEDING: u16 = 0x02;
+    /// <https://dom.spec.whatwg.org/#dom-node-document_position_following>
+    pub const DOCUMENT_POSITION_FOLLOWING: u16 = 0x04;
+    /// <https://dom.spec.whatwg.org/#dom-node-document_position_contains>
+    pub const DOCUMENT_POSITION_CONTAINS: u16 = 0x08;
+    /// <https://dom.spec.whatwg.org/#dom-node-document_position_contained_by>
+    pub const DOCUMENT_POSITION_CONTAINED_BY: u16 = 0x10;
+    /// <https://dom.spec.whatwg.org/#dom-node-document_position_implementation_specific>
+    pub const DOCUMENT_POSITION_IMPLEMENTATION_SPECIFIC: u16 = 0x20;
+
+    /// <https://dom.spec.whatwg.org/#dom-node-nodetype>
+    ///
+    /// The placeholder nodes with `NodeTypeData::None` return 0, which is not a node type.
+    pub fn node_type(&self, store: impl AsContext) -> u16 {
+        // The nodeType getter steps are to return the first matching statement, switching on the
+        // interface this implements:
+        match self.data(&store).data {
+            NodeTypeData::Element(_) => Self::ELEMENT_NODE,
+            NodeTypeData::Text(_) => Self::TEXT_NODE,
+            NodeTypeData::Comment(_) => Self::COMMENT_NODE,
+            NodeTypeData::Document(_) => Self::DOCUMENT_NODE,
+            NodeTypeData::DocumentType(_) => Self::DOCUMENT_TYPE_NODE,
+            NodeTypeData::DocumentFragment | NodeTypeData::ShadowRoot(_) => {
+                Self::DOCUMENT_FRAGMENT_NODE
+            }
+            NodeTypeData::None => 0,
+        }
+    }
+
+    /// Downcast the node to an `Element`, or `None` if it is not an element.
+    pub fn as_element(&self, store: impl AsContext) -> Option<Element> {
+        match self.data(&store).data {
+            NodeTypeData::Element(_) => Some(Element(self.0.clone())),
+            _ => None,
+        }
+    }
+
+    /// Downcast the node to an `HTMLSlotElement`, or `None` if it is not a slot element.
+    pub fn as_slot(&self, store: impl AsContext) -> Option<HTMLSlotElement> {
+        self.data(&store)
+            .try_as_element()?
+            .try_as_slot()
+            .map(|_| HTMLSlotElement(self.0.clone()))
+    }
+
+    /// Downcast the node to a `Document`, or `None` if it is not a document.
+    pub fn as_document(&self, store: impl AsContext) -> Option<Document> {
+        match self.data(&store).data {
+            NodeTypeData::Document(_) => Some(Document(self.0.clone())),
+            _ => None,
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-node-nodename>
+    pub fn node_name(&self, store: impl AsContext) -> DOMString {
+        // The nodeName getter steps are to return the first matching statement, switching on the
+        // interface this implements:
+        match &self.data(&store).data {
+            // Element: Its HTML-uppercased qualified name.
+            NodeTypeData::Element(_) => Element(self.0.clone()).tag_name(&store),
+            // Text: "#text".
+            NodeTypeData::Text(_) => DOMString::from("#text"),
+            // Comment: "#comment".
+            NodeTypeData::Comment(_) => DOMString::from("#comment"),
+            // Document: "#document".
+            NodeTypeData::Document(_) => DOMString::from("#document"),
+            // DocumentType: Its name.
+            NodeTypeData::DocumentType(doctype) => doctype.name.clone(),
+            // DocumentFragment: "#document-fragment".
+            NodeTypeData::DocumentFragment | NodeTypeData::ShadowRoot(_) => {
+                DOMString::from("#document-fragment")
+            }
+            NodeTypeData::None => DOMString::new(),
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-node-ownerdocument>
+    pub fn owner_document(&self, store: impl AsContext) -> Option<Document> {
+        // The ownerDocument getter steps are to return null, if this is a document; otherwise
+        // this’s node document.
+        let node = self.data(&store);
+        if let NodeTypeData::Document(_) = node.data {
+            return None;
+        }
+        node.node_document().cloned()
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-node-isconnected>
+    pub fn is_connected(&self, store: impl AsContext) -> bool {
+        // The isConnected getter steps are to return true, if this is connected; otherwise false.
+        // A node is connected if its shadow-including root is a document.
+        let root = self.shadow_including_root(&store);
+        matches!(root.data(&store).data, NodeTypeData::Document(_))
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-node-getrootnode>
+    pub fn get_root_node(&self, composed: bool, store: impl AsContext) -> Node {
+        // The getRootNode(options) method steps are to return this’s shadow-including root if
+        // options["composed"] is true; otherwise this’s root.
+        if composed {
+            self.shadow_including_root(store)
+        } else {
+            self.root(store)
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-tree-root>
+    pub(crate) fn root(&self, store: impl AsContext) -> Node {
+        // The root of an object is itself, if its parent is null, or else it is the root of its
+        // parent.
+        let mut root = self.clone();
+        while let Some(parent) = root.parent_node(&store) {
+            root = parent;
+        }
+        root
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-shadow-including-root>
+    pub(crate) fn shadow_including_root(&self, store: impl AsContext) -> Node {
+        // The shadow-including root of an object is its root’s host’s shadow-including root, if the
+        // object’s root is a shadow root; otherwise its root.
+        let mut root = self.root(&store);
+        while let NodeTypeData::ShadowRoot(shadow) = &root.data(&store).data {
+            root = Node::from(shadow.host.clone()).root(&store);
+        }
+        root
+    }
+
+    /// Check if the root of this node is a shadow root.
+    fn is_in_shadow_tree(&self, store: impl AsContext) -> bool {
+        matches!(
+            self.root(&store).data(&store).data,
+            NodeTypeData::ShadowRoot(_)
+        )
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-node-ensure-pre-insertion-validity>
+    pub fn ensure_pre_insert_validity(
+        &self,
+        node: &Node,
+        child: Option<&Node>,
+        store: impl AsContext,
+    ) -> Result<(), DomException> {
+        let parent = self.data(&store);
+        // 1. If parent is not a Document, DocumentFragment, or Element node, then throw a
+        // "HierarchyRequestError" DOMException.
+        if !matches!(
+            parent.data,
+            NodeTypeData::Document(_)
+                | NodeTypeData::DocumentFragment
+                | NodeTypeData::ShadowRoot(_)
+                | NodeTypeData::Element(_)
+        ) {
+            return Err(DomException::HierarchyRequestError(
+                "parent is not a Document, a DocumentFragment or an Element".into(),
+            ));
+        }
+        // 2. If node is a host-including inclusive ancestor of parent, then throw a
+        // "HierarchyRequestError" DOMException.
+        if node.is_host_including_inclusive_ancestor_of(self, &store) {
+            return Err(DomException::HierarchyRequestError(
+                "node is a host-including inclusive ancestor of parent".into(),
+            ));
+        }
+        // 3. If child is non-null and its parent is not parent, then throw a "NotFoundError" DOMException.
+        if let Some(child) = child {
+            let is_child = child
+                .data(&store)
+                .parent_node
+                .as_ref()
+                .is_some_and(|p| p.is_same_node(self, &store));
+            if !is_child {
+                return Err(DomException::NotFoundError(
+                    "child is not a child of parent".into(),
+                ));
+            }
+        }
+        // 4. If node is not a DocumentFragment, DocumentType, Element, or CharacterData node, then throw a
+        // "HierarchyRequestError" DOMException.
+        let node_data = node.data(&store);
+        if !node_data.is_document_fragment()
+            && !node_data.is_element()
+            && !node_data.is_character_data()
+            && !node_data.is_document_type()
+        {
+            return Err(DomException::HierarchyRequestError(
+                "node can not be inserted".into(),
+            ));
+        }
+        // 5. If either node is a Text node and parent is a document, or node is a doctype and parent
+        // is not a document, then throw a "HierarchyRequestError" DOMException.
+        let parent_is_document = matches!(parent.data, NodeTypeData::Document(_));
+        if node_data.is_text() && parent_is_document {
+            return Err(DomException::HierarchyRequestError(
+                "a Text node can not be a child of a document".into(),
+            ));
+        }
+        if node_data.is_document_type() && !parent_is_document {
+            return Err(DomException::HierarchyRequestError(
+                "a doctype can only be a child of a document".into(),
+            ));
+        }
+        // 6. If parent is a document, and any of the statements below, switched on the interface node
+        // implements, are true, then throw a "HierarchyRequestError" DOMException.
+        if parent_is_document {
+            let children = self.child_nodes(&store);
+            let index =
+                child.and_then(|child| children.iter().position(|c| c.is_same_node(child, &store)));
+            let has_element_child = children.iter().any(|c| c.data(&store).is_element());
+            // child is a doctype, or child is non-null and a doctype is following child.
+            let doctype_is_following = index.is_some_and(|i| {
+                children[i..]
+                    .iter()
+                    .any(|c| c.data(&store).is_document_type())
+            });
+            // DocumentFragment: If node has more than one element child or has a Text node child.
+            // Otherwise, if node has one element child and either parent has an element child,
+            // child is a doctype, or child is non-null and a doctype is following child.
+            // Element: parent has an element child, child is a doctype, or child is non-null and a
+            // doctype is following child.
+            let element_count = if node_data.is_document_fragment() {
+                let node_children = node.child_nodes(&store);
+                if node_children.iter().any(|c| c.data(&store).is_text()) {
+                    return Err(DomException::HierarchyRequestError(
+                        "a Text node can not be a child of a document".into(),
+                    ));
+                }
+                node_children
+                    .iter()
+                    .filter(|c| c.data(&store).is_element())
+                    .count()
+            } else {
+                usize::from(node_data.is_element())
+            };
+            if element_count > 1 {
+                return Err(DomException::HierarchyRequestError(
+                    "a document can only have one element child".into(),
+                ));
+            }
+            if element_count == 1 {
+                if has_element_child {
+                    return Err(DomException::HierarchyRequestError(
+                        "document already has an element child".into(),
+                    ));
+                }
+                if doctype_is_following {
+                    return Err(DomException::HierarchyRequestError(
+                        "an element can not be inserted before a doctype".into(),
+                    ));
+                }
+            } else if node_data.is_document_type() {
+                // DocumentType: parent has a doctype child, child is non-null and an element is
+                // preceding child, or child is null and parent has an element child.
+                if children.iter().any(|c| c.data(&store).is_document_type()) {
+                    return Err(DomException::HierarchyRequestError(
+                        "document already has a doctype child".into(),
+                    ));
+                }
+                let end = index.unwrap_or(children.len());
+                if children[..end].iter().any(|c| c.data(&store).is_element()) {
+                    return Err(DomException::HierarchyRequestError(
+                        "a doctype can not be inserted after an element".into(),
+                    ));
+                }
+            }
+        }
+        // Not in the spec: node must not make the tree deeper than the maximum tree depth.
+        self.ensure_tree_depth(node, &store)
+    }
+
+    /// Check that inserting `node` into this node does not make the tree deeper than the maximum
+    /// tree depth of this node's node document.
+    fn ensure_tree_depth(&self, node: &Node, store: impl AsContext) -> Result<(), DomException> {
+        let Some(document) = self.data(&store).node_document() else {
+            return Ok(());
+        };
+        let max_depth = document.max_tree_depth(&store);
+        let depth = self.shadow_including_depth(&store);
+        self.ensure_depth_within(node, depth, max_depth, store)
+    }
+
+    /// Get the number of shadow-including ancestors of this node. The host of a shadow root counts
+    /// as its parent.
+    pub(crate) fn shadow_including_depth(&self, store: impl AsContext) -> usize {
+        let mut depth = 0;
+        let mut current = self.clone();
+        loop {
+            current = match current.parent_node(&store) {
+                Some(parent) => parent,
+                None => match &current.data(&store).data {
+                    NodeTypeData::ShadowRoot(shadow) => shadow.host.clone().into(),
+                    _ => return depth,
+                },
+            };
+            depth += 1;
+        }
+    }
+
+    /// Check that inserting `node` into this node, which is `depth` levels deep, puts no inserted
+    /// node deeper than `max_depth`.
+    ///
+    /// The walk stops at the first node beyond `max_depth`, so an overly deep subtree is rejected
+    /// without walking all of it.
+    pub(crate) fn ensure_depth_within(
+        &self,
+        node: &Node,
+        depth: usize,
+        max_depth: usize,
+        store: impl AsContext,
+    ) -> Result<(), DomException> {
+        // A document fragment is not inserted itself, only its children are.
+        let mut stack: Vec<(Node, usize)> = if node.data(&store).is_document_fragment() {
+            node.child_nodes(&store)
+                .into_iter()
+                .map(|child| (child, depth + 1))
+                .collect()
+        } else {
+            vec![(node.clone(), depth + 1)]
+        };
+        while let Some((node, level)) = stack.pop() {
+            if level > max_depth {
+                return Err(DomException::HierarchyRequestError(format!(
+                    "the tree would be deeper than the maximum depth of {max_depth}"
+                )));
+            }
+            stack.extend(
+                node.child_nodes(&store)
+                    .into_iter()
+                    .map(|child| (child, level + 1)),
+            );
+            if let Some(shadow) = node
+                .data(&store)
+                .try_as_element()
+                .and_then(|element| element.shadow_root.clone())
+            {
+                stack.push((shadow.into(), level + 1));
+            }
+        }
+        Ok(())
+    }
+
     /// <https://dom.spec.whatwg.org/#concept-node-pre-insert>
-    pub fn pre_insert(&self, node: Node, child: Option<&Node>, mut store: impl AsContextMut) {
-        // TODO:
+    pub fn pre_insert(
+        &self,
+        node: Node,
+        child: Option<&Node>,
+        mut store: impl AsContextMut,
+    ) -> Result<Node, DomException> {
         // 1. Ensure pre-insert validity of node into parent before child.
+        self.ensure_pre_insert_validity(&node, child, &store)?;
 
         // 2. Let referenceChild be child.
+        let mut reference_child = child.cloned();
         // 3. If referenceChild is node, then set referenceChild to node’s next sibling.
-        if let Some(reference) = child {
-            if let Ok(true) = Rooted::ref_eq(&store, reference.as_root(), node.as_root()) {
-                let node = node.data_mut(&mut store);
-                node.next_sibling = Some(reference.clone());
+        if reference_child
+            .as_ref()
+            .is_some_and(|r| r.is_same_node(&node, &store))
+        {
+            reference_child = node.data(&store).next_sibling.clone();
+        }
+        // 4. Insert node into parent before referenceChild.
+        self.insert(node.clone(), reference_child.as_ref(), false, store)?;
+        // 5. Return node.
+        Ok(node)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-node-insertbefore>
+    pub fn insert_before(
+        &self,
+        node: Node,
+        child: Option<&Node>,
+        store: impl AsContextMut,
+    ) -> Result<Node, DomException> {
+        // The insertBefore(node, child) method steps are to return the result of pre-inserting node
+        // into this before child.
+        self.pre_insert(node, child, store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-node-replace>
+    pub fn replace_child(
+        &self,
+        node: Node,
+        child: &Node,
+        mut store: impl AsContextMut,
+    ) -> Result<Node, DomException> {
+        let parent = self.data(&store);
+        // 1. If parent is not a Document, DocumentFragment, or Element node, then throw a
+        // "HierarchyRequestError" DOMException.
+        if !matches!(
+            parent.data,
+            NodeTypeData::Document(_)
+                | NodeTypeData::DocumentFragment
+                | NodeTypeData::ShadowRoot(_)
+                | NodeTypeData::Element(_)
+        ) {
+            return Err(DomException::HierarchyRequestError(
+                "parent is not a Document, a DocumentFragment or an Element".into(),
+            ));
+        }
+        // 2. If node is a host-including inclusive ancestor of parent, then throw a
+        // "HierarchyRequestError" DOMException.
+        if node.is_host_including_inclusive_ancestor_of(self, &store) {
+            return Err(DomException::HierarchyRequestError(
+                "node is a host-including inclusive ancestor of parent".into(),
+            ));
+        }
+        // 3. If child’s parent is not parent, then throw a "NotFoundError" DOMException.
+        let is_child = child
+            .data(&store)
+            .parent_node
+            .as_ref()
+            .is_some_and(|p| p.is_same_node(self, &store));
+        if !is_child {
+            return Err(DomException::NotFoundError(
+                "child is not a child of parent".into(),
+            ));
+        }
+        // 4. If node is not a DocumentFragment, DocumentType, Element, or CharacterData node, then throw a
+        // "HierarchyRequestError" DOMException.
+        let node_data = node.data(&store);
+        if !node_data.is_document_fragment()
+            && !node_data.is_element()
+            && !node_data.is_character_data()
+            && !node_data.is_document_type()
+        {
+            return Err(DomException::HierarchyRequestError(
+                "node can not be inserted".into(),
+            ));
+        }
+        // 5. If either node is a Text node and parent is a document, or node is a doctype and parent
+        // is not a document, then throw a "HierarchyRequestError" DOMException.
+        let parent_is_document = matches!(parent.data, NodeTypeData::Document(_));
+        if node_data.is_text() && parent_is_document {
+            return Err(DomException::HierarchyRequestError(
+                "a Text node can not be a child of a document".into(),
+            ));
+        }
+        if node_data.is_document_type() && !parent_is_document {
+            return Err(DomException::HierarchyRequestError(
+                "a doctype can only be a child of a document".into(),
+            ));
+        }
+        // 6. If parent is a document, and any of the statements below, switched on the interface node
+        // implements, are true, then throw a "HierarchyRequestError" DOMException.
+        if parent_is_document {
+            let children = self.child_nodes(&store);
+            let index = children
+                .iter()
+                .position(|c| c.is_same_node(child, &store))
+                .unwrap_or(children.len());
+            // DocumentFragment: If node has more than one element child or has a Text node child.
+            // Otherwise, if node has one element child and either parent has an element child that
+            // is not child or a doctype is following child.
+            // Element: parent has an element child that is not child or a doctype is following
+            // child.
+            let element_count = if node_data.is_document_fragment() {
+                let node_children = node.child_nodes(&store);
+                if node_children.iter().any(|c| c.data(&store).is_text()) {
+                    return Err(DomException::HierarchyRequestError(
+                        "a Text node can not be a child of a document".into(),
+                    ));
+                }
+                node_children
+                    .iter()
+                    .filter(|c| c.data(&store).is_element())
+                    .count()
+            } else {
+                usize::from(node_data.is_element())
+            };
+            if element_count > 1 {
+                return Err(DomException::HierarchyRequestError(
+                    "a document can only have one element child".into(),
+                ));
+            }
+            if element_count == 1 {
+                if children
+                    .iter()
+                    .any(|c| c.data(&store).is_element() && !c.is_same_node(child, &store))
+                {
+                    return Err(DomException::HierarchyRequestError(
+                        "document already has an element child".into(),
+                    ));
+                }
+                if children[index..]
+                    .iter()
+                    .skip(1)
+                    .any(|c| c.data(&store).is_document_type())
+                {
+                    return Err(DomException::HierarchyRequestError(
+                        "an element can not be inserted before a doctype".into(),
+                    ));
+                }
+            } else if node_data.is_document_type() {
+                // DocumentType: parent has a doctype child that is not child, or an element is
+                // preceding child.
+                if children
+                    .iter()
+                    .any(|c| c.data(&store).is_document_type() && !c.is_same_node(child, &store))
+                {
+                    return Err(DomException::HierarchyRequestError(
+                        "document already has a doctype child".into(),
+                    ));
+                }
+                if children[..index]
+                    .iter()
+                    .any(|c| c.data(&store).is_element())
+                {
+                    return Err(DomException::HierarchyRequestError(
+                        "a doctype can not be inserted after an element".into(),
+                    ));
+                }
             }
         }
+        // Not in the spec: node must not make the tree deeper than the maximum tree depth.
+        self.ensure_tree_depth(&node, &store)?;
+        // 7. Let referenceChild be child’s next sibling.
+        let mut reference_child = child.data(&store).next_sibling.clone();
+        // 8. If referenceChild is node, then set referenceChild to node’s next sibling.
+        if reference_child
+            .as_ref()
+            .is_some_and(|r| r.is_same_node(&node, &store))
+        {
+            reference_child = node.data(&store).next_sibling.clone();
+        }
+        // 9. Let previousSibling be child’s previous sibling.
+        let previous_sibling = child.data(&store).previous_sibling.clone();
+        // 10. Let removedNodes be the empty set.
+        let mut removed_nodes = Vec::new();
+        // 11. If child’s parent is non-null, then:
+        if child.parent_node(&store).is_some() {
+            // 11.1 Set removedNodes to « child ».
+            removed_nodes.push(child.clone());
+            // 11.2 Remove child with the suppress observers flag set.
+            child.remove(true, &mut store);
+        }
+        // 12. Let nodes be node’s children if node is a DocumentFragment node; otherwise « node ».
+        let nodes = if node.data(&store).is_document_fragment() {
+            node.child_nodes(&store)
+        } else {
+            vec![node.clone()]
+        };
+        // 13. Insert node into parent before referenceChild with the suppress observers flag set.
+        self.insert(node, reference_child.as_ref(), true, &mut store)?;
+        // 14. Queue a tree mutation record for parent with nodes, removedNodes, previousSibling,
+        // and referenceChild.
+        queue_tree_mutation_record(
+            self,
+            nodes,
+            removed_nodes,
+            previous_sibling,
+            reference_child,
+            &mut store,
+        );
+        // 15. Return child.
+        Ok(child.clone())
+    }
 
-        // TODO:
-        // 4. Insert node into parent before referenceChild.
-        self.insert(node, child, false, store);
+    /// <https://dom.spec.whatwg.org/#concept-node-remove>
+    pub fn remove(&self, suppress_observers: bool, mut store: impl AsContextMut) {
+        // 1. Let parent be node’s parent.
+        // 2. Assert: parent is non-null.
+        let Some(parent) = self.parent_node(&store) else {
+            return;
+        };
+        // 3 ~ 8. TODO: Update live ranges and NodeIterator objects with node’s index.
+        // 9. Let oldPreviousSibling be node’s previous sibling.
+        let old_previous_sibling = self.data(&store).previous_sibling.clone();
+        // 10. Let oldNextSibling be node’s next sibling.
+        let old_next_sibling = self.data(&store).next_sibling.clone();
+        // 11. Remove node from its parent’s children.
+        match &old_previous_sibling {
+            Some(previous) => previous.data_mut(&mut store).next_sibling = old_next_sibling.clone(),
+            None => parent.data_mut(&mut store).first_child = old_next_sibling.clone(),
+        }
+        match &old_next_sibling {
+            Some(next) => next.data_mut(&mut store).previous_sibling = old_previous_sibling.clone(),
+            None => parent.data_mut(&mut store).last_child = old_previous_sibling.clone(),
+        }
+        let node = self.data_mut(&mut store);
+        node.parent_node = None;
+        node.previous_sibling = None;
+        node.next_sibling = None;
+        // 12. If node is assigned, then run assign slottables for node’s assigned slot.
+        let assigned_slot = self.data(&store).assigned_slot.clone();
+        if let Some(slot) = assigned_slot {
+            slot.assign_slottables(&mut store);
+        }
+        // Slots only get slottables in shadow trees, so nothing changes for other trees.
+        if parent.is_in_shadow_tree(&store) {
+            // 13. If parent’s root is a shadow root, and parent is a slot whose assigned nodes is the
+            // empty list, then run signal a slot change for parent.
+            if let Some(slot) = parent
+                .as_slot(&store)
+                .filter(|slot| slot.assigned_nodes(false, &store).is_empty())
+            {
+                slot.signal_slot_change(&mut store);
+            }
+            // 14. If node has an inclusive descendant that is a slot, then:
+            let mut inclusive_descendants = vec![self.clone()];
+            inclusive_descendants.extend(self.descendants(&store));
+            if inclusive_descendants
+                .iter()
+                .any(|node| node.as_slot(&store).is_some())
+            {
+                // 14.1 Run assign slottables for a tree with parent’s root.
+                assign_slottables_for_tree(&parent.root(&store), &mut store);
+                // 14.2 Run assign slottables for a tree with node.
+                assign_slottables_for_tree(self, &mut store);
+            }
+        }
+        // 15 ~ 18. TODO: Removing steps and custom element reactions.
+        // 19. TODO: For each inclusive ancestor inclusiveAncestor of parent, and then for each
+        // registered of inclusiveAncestor’s registered observer list, if registered’s
+        // options["subtree"] is true, then append a new transient registered observer whose observer
+        // is registered’s observer, options is registered’s options, and source is registered to
+        // node’s registered observer list.
+        // 20. If suppress observers flag is unset, then queue a tree mutation record for parent
+        // with « », « node », oldPreviousSibling, and oldNextSibling.
+        if !suppress_observers {
+            queue_tree_mutation_record(
+                &parent,
+                Vec::new(),
+                vec![self.clone()],
+                old_previous_sibling,
+                old_next_sibling,
+                &mut store,
+            );
+        }
+        // 21. TODO: Run the children changed steps for parent.
     }
 
     /// <https://dom.spec.whatwg.org/#concept-node-insert>
+    ///
+    /// Callers are expected to have validated `child` already. A "NotFoundError" is returned if it
+    /// is not a child of this node, before `node` is adopted or moved.
     pub fn insert(
         &self,
         node: Node,
         child: Option<&Node>,
-        _suppress: bool,
+        suppress_observers: bool,
         mut store: impl AsContextMut,
-    ) {
-        // 1. TODO: Let nodes be node’s children, if node is a DocumentFragment node; otherwise « node ».
-        // This should implement a node iterator in tree order.
-        let nodes = [node];
-        // 4. TODO: If node is a DocumentFragment node:
+    ) -> Result<(), DomException> {
+        // 1. Let nodes be node’s children, if node is a DocumentFragment node; otherwise « node ».
+        let is_fragment = node.data(&store).is_document_fragment();
+        let nodes = if is_fragment {
+            node.child_nodes(&store)
+        } else {
+            vec![node.clone()]
+        };
+        // 2. Let count be nodes’s size.
+        // 3. If count is 0, then return.
+        if nodes.is_empty() {
+            return Ok(());
+        }
+        if child.is_some_and(|c| {
+            !c.parent_node(&store)
+                .is_some_and(|p| p.is_same_node(self, &store))
+        }) {
+            return Err(DomException::NotFoundError(
+                "child is not a child of parent".into(),
+            ));
+        }
+        // 4. If node is a DocumentFragment node:
+        if is_fragment {
+            // 4.1 Remove its children with the suppress observers flag set.
+            for node in &nodes {
+                node.remove(true, &mut store);
+            }
+            // 4.2 Queue a tree mutation record for node with « », nodes, null, and null.
+            queue_tree_mutation_record(&node, Vec::new(), nodes.clone(), None, None, &mut store);
+        }
         // 5. TODO: If child is non-null:
         // 6. Let previousSibling be child’s previous sibling or parent’s last child if child is null.
-        let _previous_sibling = match child {
-            Some(c) => c.data(&store).previous_sibling.as_ref(),
-            None => self.data(&store).last_child(),
+        let previous_sibling = match child {
+            Some(c) => c.data(&store).previous_sibling.clone(),
+            None => self.data(&store).last_child().cloned(),
         };
         // 7. For each node in nodes, in tree order:
-        for node in nodes {
+        for node in nodes.iter().cloned() {
             // 7.1 Adopt node into parent’s node document.
             node.adopt(self.data(&store).node_document.clone(), &mut store);
-            match child {
-                // 7.2 If child is null, then append node to parent’s children.
-                None => self.append_child(node, &mut store),
-                // 7.3 Otherwise, insert node into parent’s children before child’s index.
-                Some(c) => {
-                    if let Some(index) = self.data(&store).child_nodes.iter().position(|n| {
-                        Rooted::ref_eq(&store, n.as_root(), c.as_root()).unwrap_or_default()
-                    }) {
-                        self.insert_child(index, node, &mut store);
-                    } else {
-                        // TODO: log warning!
-                    }
+            // 7.2 If child is null, then append node to parent’s children.
+            // 7.3 Otherwise, insert node into parent’s children before child’s index.
+            self.insert_child_before(node.clone(), child, &mut store);
+            // 7.4 If parent is a shadow host whose shadow root’s slot assignment is "named" and node
+            // is a slottable, then assign a slot for node.
+            let named_host = self
+                .data(&store)
+                .try_as_element()
+                .and_then(|element| element.shadow_root.clone())
+                .is_some_and(|shadow| shadow.slot_assignment(&store) == SlotAssignmentMode::Named);
+            if named_host && is_slottable(&node, &store) {
+                assign_slot(&node, &mut store);
+            }
+            // 7.5 If parent’s root is a shadow root, and parent is a slot whose assigned nodes is the
+            // empty list, then run signal a slot change for parent.
+            // 7.6 Run assign slottables for a tree with node’s root.
+            // Slots only get slottables in shadow trees, so the walk is skipped for other trees.
+            if self.is_in_shadow_tree(&store) {
+                if let Some(slot) = self
+                    .as_slot(&store)
+                    .filter(|slot| slot.assigned_nodes(false, &store).is_empty())
+                {
+                    slot.signal_slot_change(&mut store);
                 }
+                assign_slottables_for_tree(&node.root(&store), &mut store);
             }
-            // TODO: Step 7.4 ~ 7.7
+            // 7.7 TODO: Run the insertion steps and custom element reactions.
         }
-        // TODO: Step 8 ~ 12
+        // 8. If suppress observers flag is unset, then queue a tree mutation record for parent with
+        // nodes, « », previousSibling, and child.
+        if !suppress_observers {
+            queue_tree_mutation_record(
+                self,
+                nodes,
+                Vec::new(),
+                previous_sibling,
+                child.cloned(),
+                &mut store,
+            );
+        }
+        // TODO: Step 9 ~ 12
+        Ok(())
     }
 
     /// <https://dom.spec.whatwg.org/#concept-node-adopt>
     pub fn adopt(&self, document: Option<Document>, mut store: impl AsContextMut) {
         // 1. Let oldDocument be node’s node document.
-        let old_document = self.data(&store).node_document.as_ref();
-        // 2. TODO: If node’s parent is non-null, then remove node.
+        let old_document = self.data(&store).node_document.clone();
+        // 2. If node’s parent is non-null, then remove node.
+        if self.parent_node(&store).is_some() {
+            self.remove(false, &mut store);
+        }
         // 3. If document is not oldDocument:
-        let not_same = match (&document, old_document) {
+        let not_same = match (&document, &old_document) {
             (Some(d), Some(od)) => {
-                Rooted::ref_eq(&store, d.as_root(), od.as_root()).unwrap_or_default()
+                !Rooted::ref_eq(&store, d.as_root(), od.as_root()).unwrap_or_default()
             }
-            _ => false,
+            (None, None) => false,
+            _ => true,
         };
         if not_same {
-            // TODO: Step 3.1, 3.2, 3.3
-            self.data_mut(&mut store).set_node_document(document);
+            // 3.1 For each inclusiveDescendant in node’s shadow-including inclusive descendants:
+            for inclusive_descendant in &self.shadow_including_inclusive_descendants(&store) {
+                // 3.1.1 Set inclusiveDescendant’s node document to document.
+                // 3.1.2 TODO: If inclusiveDescendant is a shadow root and its custom element registry is
+                // a global custom element registry, then set it to document’s effective global custom
+                // element registry.
+                // 3.1.3 TODO: If inclusiveDescendant is an element, then set the node document of each
+                // attribute in inclusiveDescendant’s attribute list to document.
+                inclusive_descendant
+                    .data_mut(&mut store)
+                    .set_node_document(document.clone());
+            }
+            // 3.2 TODO: For each inclusiveDescendant in node’s shadow-including inclusive descendants
+            // that is custom, enqueue a custom element callback reaction with inclusiveDescendant,
+            // callback name "adoptedCallback", and « oldDocument, document ».
+            // 3.3 TODO: For each inclusiveDescendant in node’s shadow-including inclusive descendants,
+            // in shadow-including tree order, run the adopting steps with inclusiveDescendant and
+            // oldDocument.
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-node-clonenode>
+    pub fn clone_node(
+        &self,
+        subtree: bool,
+        mut store: impl AsContextMut,
+    ) -> Result<Node, DomException> {
+        // 1. TODO: If this is a shadow root, then throw a "NotSupportedError" DOMException.
+        // 2. Return the result of cloning a node given this with subtree set to subtree.
+        let Some(document) = self.data(&store).node_document.clone() else {
+            return Err(DomException::NotSupportedError(
+                "node has no node document".into(),
+            ));
+        };
+        self.clone_with_document(&document, subtree, &mut store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-node-clone>
+    ///
+    /// The clone and its descendants have `document` as their node document.
+    /// TODO: fallbackRegistry
+    pub(crate) fn clone_with_document(
+        &self,
+        document: &Document,
+        subtree: bool,
+        mut store: impl AsContextMut,
+    ) -> Result<Node, DomException> {
+        // 2. Let copy be the result of cloning a single node given node and document.
+        let copy = self.clone_single_node(document, &mut store)?;
+        // 3. TODO: Run any cloning steps defined for node in other applicable specifications and pass
+        // node, copy, and subtree as parameters.
+        // 5. If subtree is true, then for each child of node’s children, in tree order: clone a node
+        // given child with document set to document, subtree set to subtree, and parent set to copy.
+        // This walks the subtree with a stack instead of recursion.
+        if subtree {
+            let mut stack: Vec<(Node, Node)> = self
+                .child_nodes(&store)
+                .into_iter()
+                .rev()
+                .map(|child| (child, copy.clone()))
+                .collect();
+            while let Some((node, parent)) = stack.pop() {
+                let node_copy = node.clone_single_node(document, &mut store)?;
+                // 4. If parent is non-null, then append copy to parent.
+                parent.append_child(node_copy.clone(), &mut store);
+                stack.extend(
+                    node.child_nodes(&store)
+                        .into_iter()
+                        .rev()
+                        .map(|child| (child, node_copy.clone())),
+                );
+            }
         }
+        // 6. TODO: If node is an element, node is a shadow host, and node’s shadow root’s clonable is
+        // true, then clone the shadow root.
+        // 7. Return copy.
+        Ok(copy)
+    }
+
+    /// <https://dom.spec.whatwg.org/#clone-a-single-node>
+    fn clone_single_node(
+        &self,
+        document: &Document,
+        mut store: impl AsContextMut,
+    ) -> Result<Node, DomException> {
+        // 2. If node is an element:
+        if self.data(&store).is_element() {
+            return Ok(Element(self.0.clone())
+                .clone_single_node(document, store)
+                .into());
+        }
+        // 3. Otherwise, set copy to a node that implements the same interfaces as node, and fulfills
+        // these additional requirements, switched on the interface node implements:
+        // TODO: Copy the data of Document, Attr and ProcessingInstruction.
+        let data = match &self.data(&store).data {
+            NodeTypeData::Document(_) => {
+                return Err(not_supported!("cloning a Document"));
+            }
+            // ShadowRoot: cloneNode() throws a "NotSupportedError" DOMException for a shadow root.
+            // TODO: Clone a clonable shadow root along with its host.
+            NodeTypeData::ShadowRoot(_) => {
+                return Err(DomException::NotSupportedError(
+                    "cloning a ShadowRoot is not supported".into(),
+                ));
+            }
+            // Text: Set copy’s data to that of node.
+            NodeTypeData::Text(text) => NodeTypeData::Text(TextImpl {
+                data: text.data.clone(),
+            }),
+            NodeTypeData::DocumentFragment => NodeTypeData::DocumentFragment,
+            // DocumentType: Set copy’s name, public ID, and system ID to those of node.
+            NodeTypeData::DocumentType(doctype) => NodeTypeData::DocumentType(doctype.clone()),
+            // Comment: Set copy’s data to that of node.
+            NodeTypeData::Comment(comment) => NodeTypeData::Comment(CommentImpl {
+                data: comment.data.clone(),
+            }),
+            _ => NodeTypeData::None,
+        };
+        let copy = Node(
+            Object::new(&mut store, NodeImpl::new_with_type(data)).expect("Failed to create node"),
+        );
+        // 4. If node is a document, then set document to copy.
+        // 5. Set copy’s node document to document.
+        copy.data_mut(&mut store)
+            .set_node_document(Some(document.clone()));
+        // 6. Return copy.
+        Ok(copy)
     }
 
     /// Append a child node to this node.
-    pub fn append_child(&self, node: Node, mut store: impl AsContextMut) {
-        if let Some(child) = self.data(&store).child_nodes.back() {
-            let child = child.clone();
-            child.clone().data_mut(&mut store).next_sibling = Some(node.clone());
-            node.clone().data_mut(&mut store).previous_sibling = Some(child);
+    pub fn append_child(&self, node: Node, store: impl AsContextMut) {
+        self.insert_child_before(node, None, store);
+    }
+
+    /// Insert a child node to this node before `child`, or at the end if `child` is `None`.
+    ///
+    /// `child` must be a child of this node and `node` must not have a parent. Children are a
+    /// doubly linked list of siblings, so this takes constant time.
+    pub fn insert_child_before(
+        &self,
+        node: Node,
+        child: Option<&Node>,
+        mut store: impl AsContextMut,
+    ) {
+        let previous = match child {
+            Some(child) => child.data(&store).previous_sibling.clone(),
+            None => self.data(&store).last_child.clone(),
+        };
+        match &previous {
+            Some(previous) => previous.data_mut(&mut store).next_sibling = Some(node.clone()),
+            None => self.data_mut(&mut store).first_child = Some(node.clone()),
+        }
+        match child {
+            Some(child) => child.data_mut(&mut store).previous_sibling = Some(node.clone()),
+            None => self.data_mut(&mut store).last_child = Some(node.clone()),
         }
-        self.data_mut(&mut store).child_nodes.push_back(node);
+        let data = node.data_mut(&mut store);
+        data.parent_node = Some(self.clone());
+        data.previous_sibling = previous;
+        data.next_sibling = child.cloned();
     }
 
-    /// Insert a child node to this node.
-    pub fn insert_child(&self, index: usize, node: Node, mut store: impl AsContextMut) {
-        if let Some(prev) = self.data(&store).child_nodes.get(index - 1) {
-            let prev = prev.clone();
-            prev.clone().data_mut(&mut store).next_sibling = Some(node.clone());
-            node.clone().data_mut(&mut store).previous_sibling = Some(prev);
+    /// <https://dom.spec.whatwg.org/#dom-node-childnodes>
+    ///
+    /// This returns a snapshot instead of a live `NodeList`.
+    pub fn child_nodes(&self, store: impl AsContext) -> Vec<Node> {
+        let mut children = Vec::new();
+        let mut current = self.data(&store).first_child.clone();
+        while let Some(child) = current {
+            current = child.data(&store).next_sibling.clone();
+            children.push(child);
         }
-        if let Some(next) = self.data(&store).child_nodes.get(index) {
-            let next = next.clone();
-            node.clone().data_mut(&mut store).next_sibling = Some(next.clone());
-            next.data_mut(&mut store).previous_sibling = Some(node.clone());
+        children
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-node-firstchild>
+    pub fn first_child(&self, store: impl AsContext) -> Option<Node> {
+        self.data(&store).first_child.clone()
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-node-lastchild>
+    pub fn last_child(&self, store: impl AsContext) -> Option<Node> {
+        self.data(&store).last_child.clone()
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-node-parentnode>
+    pub fn parent_node(&self, store: impl AsContext) -> Option<Node> {
+        self.data(&store).parent_node.clone()
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-node-previoussibling>
+    pub fn previous_sibling(&self, store: impl AsContext) -> Option<Node> {
+        self.data(&store).previous_sibling.clone()
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-node-nextsibling>
+    pub fn next_sibling(&self, store: impl AsContext) -> Option<Node> {
+        self.data(&store).next_sibling.clone()
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#serialising-html-fragments>
+    ///
+    /// Get the HTML serialization of this node and its descendants. A document or a document
+    /// fragment is serialized as its children, since it has no markup of its own.
+    pub fn serialize(&self, store: impl AsContext) -> DOMString {
+        let nodes = match self.data(&store).data {
+            NodeTypeData::Document(_)
+            | NodeTypeData::DocumentFragment
+            | NodeTypeData::ShadowRoot(_) => self.child_nodes(&store),
+            _ => vec![self.clone()],
+        };
+        serialize_html_nodes(nodes, store)
+    }
+
+    /// Get a hash of the HTML serialization of this node and its descendants.
+    ///
+    /// A document is hashed by the serialization of its children. Trees with the same
+    /// serialization, including the order of attributes, have the same hash, so this is a cheap
+    /// equality check between snapshots of a tree. The hash is only stable within the same build.
+    pub fn content_hash(&self, store: impl AsContext) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.serialize(store).hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-node-issamenode>
+    pub fn is_same_node(&self, other: &Node, store: impl AsContext) -> bool {
+        Rooted::ref_eq(&store, self.as_root(), other.as_root()).unwrap_or_default()
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-tree-index>
+    pub fn index(&self, store: impl AsContext) -> usize {
+        // The index of an object is its number of preceding siblings, or 0 if it has none.
+        let mut index = 0;
+        let mut current = self.data(&store).previous_sibling.clone();
+        while let Some(sibling) = current {
+            index += 1;
+            current = sibling.data(&store).previous_sibling.clone();
+        }
+        index
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-node-length>
+    pub fn length(&self, store: impl AsContext) -> usize {
+        match &self.data(&store).data {
+            // 1. If node is a DocumentType or Attr node, then return 0.
+            NodeTypeData::DocumentType(_) => 0,
+            // 2. If node is a CharacterData node, then return node’s data’s length.
+            NodeTypeData::Text(_) | NodeTypeData::Comment(_) => {
+                self.data(&store).as_character_data().len_utf16()
+            }
+            // 3. Return the number of node’s children.
+            _ => self.child_nodes(&store).len(),
         }
-        self.data_mut(&mut store).child_nodes.insert(index, node);
+    }
+
+    /// Get the inclusive ancestors of this node, starting from its root.
+    fn inclusive_ancestors_from_root(&self, store: impl AsContext) -> Vec<Node> {
+        let mut ancestors = vec![self.clone()];
+        while let Some(parent) = ancestors[ancestors.len() - 1].parent_node(&store) {
+            ancestors.push(parent);
+        }
+        ancestors.reverse();
+        ancestors
+    }
+
+    /// Check if this node precedes its sibling `other`. The siblings are walked in both directions
+    /// at once, so this takes time proportional to the distance between them.
+    fn precedes_sibling(&self, other: &Node, store: impl AsContext) -> bool {
+        let mut next = self.data(&store).next_sibling.clone();
+        let mut previous = self.data(&store).previous_sibling.clone();
+        while next.is_some() || previous.is_some() {
+            if let Some(node) = next {
+                if node.is_same_node(other, &store) {
+                    return true;
+                }
+                next = node.data(&store).next_sibling.clone();
+            }
+            if let Some(node) = previous {
+                if node.is_same_node(other, &store) {
+                    return false;
+                }
+                previous = node.data(&store).previous_sibling.clone();
+            }
+        }
+        false
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-node-comparedocumentposition>
+    ///
+    /// TODO: Steps 2 ~ 6 handle attributes, which are not nodes yet.
+    pub fn compare_document_position(&self, other: &Node, store: impl AsContext) -> u16 {
+        // 1. If this is other, then return zero.
+        if self.is_same_node(other, &store) {
+            return 0;
+        }
+        // 7. If node1 or node2 is null, or node1’s root is not node2’s root, then return the result
+        // of adding DOCUMENT_POSITION_DISCONNECTED, DOCUMENT_POSITION_IMPLEMENTATION_SPECIFIC, and
+        // either DOCUMENT_POSITION_PRECEDING or DOCUMENT_POSITION_FOLLOWING, with the constraint
+        // that this is to be consistent, together.
+        let Some(order) = tree_order_cmp(other, self, &store) else {
+            // The address of the node data does not change while the node is alive.
+            let position = if node_key(other, &store) < node_key(self, &store) {
+                Self::DOCUMENT_POSITION_PRECEDING
+            } else {
+                Self::DOCUMENT_POSITION_FOLLOWING
+            };
+            return Self::DOCUMENT_POSITION_DISCONNECTED
+                | Self::DOCUMENT_POSITION_IMPLEMENTATION_SPECIFIC
+                | position;
+        };
+        // 8. If node1 is an ancestor of node2 and attr1 is null, or node1 is node2 and attr2 is
+        // non-null, then return the result of adding DOCUMENT_POSITION_CONTAINS to
+        // DOCUMENT_POSITION_PRECEDING.
+        if other.is_inclusive_ancestor_of(self, &store) {
+            return Self::DOCUMENT_POSITION_CONTAINS | Self::DOCUMENT_POSITION_PRECEDING;
+        }
+        // 9. If node1 is a descendant of node2 and attr2 is null, or node1 is node2 and attr1 is
+        // non-null, then return the result of adding DOCUMENT_POSITION_CONTAINED_BY to
+        // DOCUMENT_POSITION_FOLLOWING.
+        if self.is_inclusive_ancestor_of(other, &store) {
+            return Self::DOCUMENT_POSITION_CONTAINED_BY | Self::DOCUMENT_POSITION_FOLLOWING;
+        }
+        // 10. If node1 is preceding node2, then return DOCUMENT_POSITION_PRECEDING.
+        if order == Ordering::Less {
+            return Self::DOCUMENT_POSITION_PRECEDING;
+        }
+        // 11. Return DOCUMENT_POSITION_FOLLOWING.
+        Self::DOCUMENT_POSITION_FOLLOWING
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-tree-inclusive-ancestor>
+    pub fn is_inclusive_ancestor_of(&self, other: &Node, store: impl AsContext) -> bool {
+        let mut current = Some(other.clone());
+        while let Some(node) = current {
+            if self.is_same_node(&node, &store) {
+                return true;
+            }
+            current = node.parent_node(&store);
+        }
+        false
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-tree-host-including-inclusive-ancestor>
+    pub fn is_host_including_inclusive_ancestor_of(
+        &self,
+        other: &Node,
+        store: impl AsContext,
+    ) -> bool {
+        // An object A is a host-including inclusive ancestor of an object B, if either A is an
+        // inclusive ancestor of B, or if B’s root has a non-null host and A is a host-including
+        // inclusive ancestor of B’s root’s host.
+        let mut current = other.clone();
+        loop {
+            if self.is_inclusive_ancestor_of(&current, &store) {
+                return true;
+            }
+            let mut root = current;
+            while let Some(parent) = root.parent_node(&store) {
+                root = parent;
+            }
+            match &root.data(&store).data {
+                NodeTypeData::ShadowRoot(shadow) => current = shadow.host.clone().into(),
+                _ => return false,
+            }
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-parentnode-children>
+    ///
+    /// This returns a snapshot instead of a live `HTMLCollection`.
+    pub fn children(&self, store: impl AsContext) -> Vec<Element> {
+        self.child_nodes(&store)
+            .into_iter()
+            .filter(|node| node.data(&store).is_element())
+            .map(|node| Element(node.0))
+            .collect()
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-parentnode-firstelementchild>
+    pub fn first_element_child(&self, store: impl AsContext) -> Option<Element> {
+        let mut current = self.data(&store).first_child.clone();
+        while let Some(node) = current {
+            if node.data(&store).is_element() {
+                return Some(Element(node.0));
+            }
+            current = node.data(&store).next_sibling.clone();
+        }
+        None
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-parentnode-lastelementchild>
+    pub fn last_element_child(&self, store: impl AsContext) -> Option<Element> {
+        let mut current = self.data(&store).last_child.clone();
+        while let Some(node) = current {
+            if node.data(&store).is_element() {
+                return Some(Element(node.0));
+            }
+            current = node.data(&store).previous_sibling.clone();
+        }
+        None
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-parentnode-childelementcount>
+    pub fn child_element_count(&self, store: impl AsContext) -> usize {
+        self.child_nodes(&store)
+            .iter()
+            .filter(|node| node.data(&store).is_element())
+            .count()
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-parentnode-prepend>
+    pub fn prepend(
+        &self,
+        nodes: Vec<NodeOrString>,
+        mut store: impl AsContextMut,
+    ) -> Result<(), DomException> {
+        // 1. Let node be the result of converting nodes into a node given nodes and this’s node document.
+        let node = self.convert_nodes_into_node(nodes, &mut store)?;
+        // 2. Pre-insert node into this before this’s first child.
+        let first_child = self.data(&store).first_child.clone();
+        self.pre_insert(node, first_child.as_ref(), &mut store)?;
+        Ok(())
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-parentnode-append>
+    pub fn append(
+        &self,
+        nodes: Vec<NodeOrString>,
+        mut store: impl AsContextMut,
+    ) -> Result<(), DomException> {
+        // 1. Let node be the result of converting nodes into a node given nodes and this’s node document.
+        let node = self.convert_nodes_into_node(nodes, &mut store)?;
+        // 2. Append node to this.
+        self.pre_insert(node, None, &mut store)?;
+        Ok(())
+    }
+
+    /// <https://dom.spec.whatwg.org/#converting-nodes-into-a-node>
+    ///
+    /// The document is this node's node document.
+    fn convert_nodes_into_node(
+        &self,
+        nodes: Vec<NodeOrString>,
+        mut store: impl AsContextMut,
+    ) -> Result<Node, DomException> {
+        let document = self
+            .data(&store)
+            .node_document
+            .clone()
+            .expect("parent node has a node document");
+        // 1. Replace each string of nodes with a new Text node whose data is the string and node
+        // document is document.
+        let mut nodes: Vec<Node> = nodes
+            .into_iter()
+            .map(|node| match node {
+                NodeOrString::Node(node) => node,
+                NodeOrString::String(data) => Text::new(&document, data, &mut store)
+                    .expect("Failed to create text")
+                    .into(),
+            })
+            .collect();
+        // 2. If nodes contains one node, then return nodes[0].
+        if nodes.len() == 1 {
+            return Ok(nodes.remove(0));
+        }
+        // 3. Let fragment be a new DocumentFragment node whose node document is document.
+        let fragment = Node::from(
+            DocumentFragment::new(&document, &mut store)
+                .expect("Failed to create document fragment"),
+        );
+        // 4. For each node of nodes: append node to fragment.
+        for node in nodes {
+            fragment.pre_insert(node, None, &mut store)?;
+        }
+        // 5. Return fragment.
+        Ok(fragment)
+    }
+
+    /// Get the descendants of this node in tree order.
+    ///
+    /// <https://dom.spec.whatwg.org/#concept-tree-order>
+    pub fn descendants(&self, store: impl AsContext) -> Vec<Node> {
+        let mut descendants = Vec::new();
+        let mut stack: Vec<Node> = self.child_nodes(&store).into_iter().rev().collect();
+        while let Some(node) = stack.pop() {
+            stack.extend(node.child_nodes(&store).into_iter().rev());
+            descendants.push(node);
+        }
+        descendants
+    }
+
+    /// Get the shadow-including inclusive descendants of this node in shadow-including tree order.
+    ///
+    /// <https://dom.spec.whatwg.org/#concept-shadow-including-inclusive-descendant>
+    pub fn shadow_including_inclusive_descendants(&self, store: impl AsContext) -> Vec<Node> {
+        let mut descendants = Vec::new();
+        let mut stack = vec![self.clone()];
+        while let Some(node) = stack.pop() {
+            stack.extend(node.child_nodes(&store).into_iter().rev());
+            // A shadow root is traversed right after its host, before the host’s children.
+            if let Some(shadow) = node
+                .data(&store)
+                .try_as_element()
+                .and_then(|element| element.shadow_root.clone())
+            {
+                stack.push(shadow.into());
+            }
+            descendants.push(node);
+        }
+        descendants
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-parentnode-queryselector>
+    pub fn query_selector(
+        &self,
+        selectors: &str,
+        store: impl AsContext,
+    ) -> Result<Option<Element>, DomException> {
+        // The querySelector(selectors) method steps are to return the first result of running
+        // scope-match a selectors string selectors against this, if the result is not an empty
+        // list; otherwise null.
+        Ok(self.scope_match(selectors, store)?.into_iter().next())
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-parentnode-queryselectorall>
+    ///
+    /// This returns a snapshot instead of a static `NodeList`.
+    pub fn query_selector_all(
+        &self,
+        selectors: &str,
+        store: impl AsContext,
+    ) -> Result<Vec<Element>, DomException> {
+        // The querySelectorAll(selectors) method steps are to return the static result of running
+        // scope-match a selectors string selectors against this.
+        self.scope_match(selectors, store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#scope-match-a-selectors-string>
+    fn scope_match(
+        &self,
+        selectors: &str,
+        store: impl AsContext,
+    ) -> Result<Vec<Element>, DomException> {
+        // 1. Let s be the result of parse a selector selectors.
+        // 2. If s is failure, then throw a "SyntaxError" DOMException.
+        let selectors: SelectorList = selectors.parse()?;
+        // 3. Return the result of match a selector against a tree with s and node’s root, using
+        // scoping root node.
+        // Only the descendants of node can match when node is the scoping root.
+        Ok(self
+            .descendants(&store)
+            .into_iter()
+            .filter(|node| node.data(&store).is_element())
+            .map(|node| Element(node.0))
+            .filter(|element| selectors.matches(element, &store))
+            .collect())
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-getelementsbytagname>
+    ///
+    /// The spec returns a live `HTMLCollection`. This returns a snapshot of the matching elements in
+    /// tree order instead, so later tree mutations are not reflected.
+    pub fn get_elements_by_tag_name(
+        &self,
+        qualified_name: &str,
+        store: impl AsContext,
+    ) -> Vec<Element> {
+        let elements = self
+            .descendants(&store)
+            .into_iter()
+            .filter(|node| node.data(&store).is_element())
+            .map(|node| Element(node.0));
+        // 1. If qualifiedName is U+002A (*), return a HTMLCollection rooted at root, whose filter
+        // matches only descendant elements.
+        if qualified_name == "*" {
+            return elements.collect();
+        }
+        // 2. Otherwise, if root’s node document is an HTML document, return a HTMLCollection rooted
+        // at root, whose filter matches the following descendant elements:
+        // - Whose namespace is the HTML namespace and whose qualified name is qualifiedName, in ASCII lowercase.
+        // - Whose namespace is not the HTML namespace and whose qualified name is qualifiedName.
+        // 3. Otherwise, return a HTMLCollection rooted at root, whose filter matches descendant
+        // elements whose qualified name is qualifiedName.
+        let is_html = self
+            .data(&store)
+            .node_document()
+            .is_some_and(|document| document.is_html(&store));
+        elements
+            .filter(|element| {
+                element
+                    .tag(&store)
+                    .matches_qualified_name(qualified_name, is_html)
+            })
+            .collect()
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-child-text-content>
+    pub fn child_text_content(&self, store: impl AsContext) -> DOMString {
+        // The child text content of a node node is the concatenation of the data of all the Text
+        // node children of node, in tree order.
+        let mut content = DOMString::new();
+        let mut current = self.data(&store).first_child.clone();
+        while let Some(child) = current {
+            if let NodeTypeData::Text(text) = &child.data(&store).data {
+                content.push_str(&text.data);
+            }
+            current = child.data(&store).next_sibling.clone();
+        }
+        content
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-node-textcontent>
+    pub fn text_content(&self, store: impl AsContext) -> Option<DOMString> {
+        // The textContent getter steps are to return the following, switching on the interface this
+        // implements:
+        match &self.data(&store).data {
+            // Element: the descendant text content of this.
+            NodeTypeData::Element(_) => Some(self.descendant_text_content(&store)),
+            // CharacterData: this’s data.
+            NodeTypeData::Text(text) => Some(text.data.clone()),
+            NodeTypeData::Comment(comment) => Some(comment.data.clone()),
+            // Otherwise: null.
+            _ => None,
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-node-textcontent>
+    pub fn set_text_content(
+        &self,
+        value: Option<DOMString>,
+        mut store: impl AsContextMut,
+    ) -> Result<()> {
+        // If the given value is null, act as if it was the empty string instead, and then do as
+        // described below, switching on the interface this implements:
+        let value = value.unwrap_or_default();
+        let node = self.data(&store);
+        if node.is_element() {
+            // Element: String replace all with the given value within this.
+            self.string_replace_all(value, store)?;
+        } else if node.is_character_data() {
+            // CharacterData: Replace data with node this, offset 0, count this’s length, and data the
+            // given value.
+            CharacterData(self.0.clone()).set_data(&value, store);
+        }
+        // Otherwise: Do nothing.
+        Ok(())
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-node-normalize>
+    pub fn normalize(&self, mut store: impl AsContextMut) {
+        // The normalize() method steps are to run these steps for each descendant exclusive Text
+        // node node of this:
+        let text_nodes: Vec<Node> = self
+            .descendants(&store)
+            .into_iter()
+            .filter(|node| node.data(&store).is_text())
+            .collect();
+        for node in text_nodes {
+            // A node merged into a previous Text node has been removed already.
+            if node.parent_node(&store).is_none() {
+                continue;
+            }
+            let character_data = CharacterData(node.0.clone());
+            // 1. Let length be node’s length.
+            // 2. If length is zero, then remove node and continue with the next exclusive Text
+            // node, if any.
+            if character_data.length(&store) == 0 {
+                node.remove(false, &mut store);
+                continue;
+            }
+            // 3. Let data be the concatenation of the data of node’s contiguous exclusive Text
+            // nodes (excluding itself), in tree order.
+            // The preceding ones are merged into an earlier node already, so only the following
+            // siblings are left.
+            let mut contiguous = Vec::new();
+            let mut current = node.next_sibling(&store);
+            while let Some(sibling) = current {
+                if !sibling.data(&store).is_text() {
+                    break;
+                }
+                current = sibling.next_sibling(&store);
+                contiguous.push(sibling);
+            }
+            let mut data = DOMString::new();
+            for text in &contiguous {
+                data.push_str(text.data(&store).as_character_data());
+            }
+            // 4. Replace data with node node, offset length, count 0, and data data.
+            character_data.append_data(&data, &mut store);
+            // 5 ~ 6. TODO: Update live ranges.
+            // 7. Remove node’s contiguous exclusive Text nodes (excluding itself), in tree order.
+            for text in contiguous {
+                text.remove(false, &mut store);
+            }
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-descendant-text-content>
+    pub fn descendant_text_content(&self, store: impl AsContext) -> DOMString {
+        // The descendant text content of a node node is the concatenation of the data of all the Text
+        // node descendants of node, in tree order.
+        let mut content = DOMString::new();
+        for node in self.descendants(&store) {
+            if let NodeTypeData::Text(text) = &node.data(&store).data {
+                content.push_str(&text.data);
+            }
+        }
+        content
+    }
+
+    /// <https://dom.spec.whatwg.org/#string-replace-all>
+    pub(crate) fn string_replace_all(
+        &self,
+        string: DOMString,
+        mut store: impl AsContextMut,
+    ) -> Result<()> {
+        // 1. Let node be null.
+        // 2. If string is not the empty string, then set node to a new Text node whose data is string
+        // and node document is parent’s node document.
+        let node = match self.data(&store).node_document.clone() {
+            Some(document) if !string.is_empty() => {
+                Some(Node::from(Text::new(&document, string, &mut store)?))
+            }
+            _ => None,
+        };
+        // 3. Replace all with node within parent.
+        self.replace_all(node.into_iter().collect(), store);
+        Ok(())
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-node-replace-all>
+    ///
+    /// `nodes` stands for node, or its children if node is a DocumentFragment node.
+    pub(crate) fn replace_all(&self, nodes: Vec<Node>, mut store: impl AsContextMut) {
+        // 1. Let removedNodes be parent’s children.
+        let removed_nodes = self.child_nodes(&store);
+        // 2. Let addedNodes be the empty set.
+        // 3. TODO: If node is a DocumentFragment node, then set addedNodes to node’s children.
+        // 4. Otherwise, if node is non-null, set addedNodes to « node ».
+        let added_nodes = nodes.clone();
+        // 5. Remove all parent’s children, in tree order, with the suppress observers flag set.
+        for child in &removed_nodes {
+            child.remove(true, &mut store);
+        }
+        // 6. If node is non-null, then insert node into parent before null with the suppress observers
+        // flag set.
+        for node in nodes {
+            self.insert(node, None, true, &mut store)
+                .expect("inserting before null can't fail");
+        }
+        // 7. If either addedNodes or removedNodes is not empty, then queue a tree mutation record
+        // for parent with addedNodes, removedNodes, null, and null.
+        if !added_nodes.is_empty() || !removed_nodes.is_empty() {
+            queue_tree_mutation_record(self, added_nodes, removed_nodes, None, None, &mut store);
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-eventtarget-addeventlistener>
+    pub fn add_event_listener(
+        &self,
+        ty: &str,
+        callback: Option<EventListener>,
+        options: AddEventListenerOptions,
+        mut store: impl AsContextMut,
+    ) {
+        self.data_mut(&mut store)
+            .event_target
+            .add_event_listener(ty, callback, options);
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-eventtarget-removeeventlistener>
+    pub fn remove_event_listener(
+        &self,
+        ty: &str,
+        callback: Option<EventListener>,
+        options: EventListenerOptions,
+        mut store: impl AsContextMut,
+    ) {
+        self.data_mut(&mut store)
+            .event_target
+            .remove_event_listener(ty, callback, options);
+    }
+
+    /// Get the canonical path of this node from its root. See [`path`](crate::path) for the syntax.
+    ///
+    /// Returns `None` if the root of this node is not a document.
+    pub fn path_from_root(&self, store: impl AsContext) -> Option<String> {
+        crate::path::path_from_root(self, store)
     }
 
     /// Get `Rooted<ExternRef>` reference of the `Node`.
@@ -123,6 +1590,104 @@ impl Node {
     }
 }
 
+/// Compare `a` and `b` in tree order, or return `None` if they are not in the same tree.
+///
+/// Only the siblings where the ancestors of `a` and `b` diverge are compared, so this takes time
+/// proportional to the depth of the nodes and the distance between those siblings.
+///
+/// <https://dom.spec.whatwg.org/#concept-tree-order>
+pub fn tree_order_cmp(a: &Node, b: &Node, store: impl AsContext) -> Option<Ordering> {
+    let a_ancestors = a.inclusive_ancestors_from_root(&store);
+    let b_ancestors = b.inclusive_ancestors_from_root(&store);
+    if !a_ancestors[0].is_same_node(&b_ancestors[0], &store) {
+        return None;
+    }
+    let common = a_ancestors
+        .iter()
+        .zip(&b_ancestors)
+        .take_while(|(a, b)| a.is_same_node(b, &store))
+        .count();
+    let order = match (a_ancestors.get(common), b_ancestors.get(common)) {
+        (None, None) => Ordering::Equal,
+        // An ancestor precedes its descendants.
+        (None, Some(_)) => Ordering::Less,
+        (Some(_), None) => Ordering::Greater,
+        (Some(a), Some(b)) if a.precedes_sibling(b, &store) => Ordering::Less,
+        (Some(_), Some(_)) => Ordering::Greater,
+    };
+    Some(order)
+}
+
+/// Sort `nodes` in tree order and remove duplicates of the same node.
+///
+/// The position of each node is computed once before sorting, so this does not walk the tree for
+/// every comparison like [`tree_order_cmp`] would. Indices are cached while sorting, so the
+/// siblings of each parent are walked at most once in total. Nodes of different trees are grouped
+/// by tree, in the order each tree first appears in `nodes`.
+pub fn sort_in_tree_order(nodes: &mut Vec<Node>, store: impl AsContext) {
+    let mut indices = HashMap::new();
+    let mut roots: Vec<Node> = Vec::new();
+    let mut positions: Vec<((usize, Vec<usize>), Node)> = nodes
+        .drain(..)
+        .map(|node| {
+            // The indices of the inclusive ancestors of node starting from the root's child.
+            let mut path = Vec::new();
+            let mut current = node.clone();
+            while let Some(parent) = current.parent_node(&store) {
+                path.push(cached_index(&current, &mut indices, &store));
+                current = parent;
+            }
+            path.reverse();
+            let tree = match roots.iter().position(|r| r.is_same_node(&current, &store)) {
+                Some(tree) => tree,
+                None => {
+                    roots.push(current);
+                    roots.len() - 1
+                }
+            };
+            ((tree, path), node)
+        })
+        .collect();
+    positions.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+    // Nodes at the same position of the same tree are the same node.
+    positions.dedup_by(|a, b| a.0 == b.0);
+    nodes.extend(positions.into_iter().map(|(_, node)| node));
+}
+
+/// Get the address of the data of `node`, which identifies it while it is alive.
+fn node_key(node: &Node, store: impl AsContext) -> *const NodeImpl {
+    std::ptr::from_ref(node.data(&store))
+}
+
+/// Get the index of `node`, walking its previous siblings only until one with a cached index.
+/// The indices of all walked siblings are cached.
+fn cached_index(
+    node: &Node,
+    indices: &mut HashMap<*const NodeImpl, usize>,
+    store: impl AsContext,
+) -> usize {
+    let mut uncached = vec![node_key(node, &store)];
+    if let Some(&index) = indices.get(&uncached[0]) {
+        return index;
+    }
+    let mut first = 0;
+    let mut current = node.data(&store).previous_sibling.clone();
+    while let Some(sibling) = current {
+        let key = node_key(&sibling, &store);
+        if let Some(&index) = indices.get(&key) {
+            first = index + 1;
+            break;
+        }
+        uncached.push(key);
+        current = sibling.data(&store).previous_sibling.clone();
+    }
+    // uncached holds node and its uncached previous siblings, nearest first.
+    for (offset, key) in uncached.iter().rev().enumerate() {
+        indices.insert(*key, first + offset);
+    }
+    first + uncached.len() - 1
+}
+
 impl Deref for Node {
     type Target = Object<NodeImpl>;
 
@@ -145,14 +1710,38 @@ impl From<Element> for Node {
 
 /// Implementation of acutal `Node` object. It also contains data of types that inherent `Node`
 /// like `Document`, `Element`, `Attr`... etc. So it can also present as these types.
-#[derive(Debug)]
+#[derive(Debug, MallocSizeOf)]
 pub struct NodeImpl {
-    _event_target: EventTarget,
-    _parent_node: Option<Node>,
-    child_nodes: VecDeque<Node>,
+    event_target: EventTarget,
+    #[ignore_malloc_size_of = "owned by the GC heap"]
+    parent_node: Option<Node>,
+    /// The children are a doubly linked list of siblings from `first_child` to `last_child`.
+    #[ignore_malloc_size_of = "owned by the GC heap"]
+    first_child: Option<Node>,
+    #[ignore_malloc_size_of = "owned by the GC heap"]
+    last_child: Option<Node>,
+    #[ignore_malloc_size_of = "owned by the GC heap"]
     previous_sibling: Option<Node>,
+    #[ignore_malloc_size_of = "owned by the GC heap"]
     next_sibling: Option<Node>,
+    #[ignore_malloc_size_of = "owned by the GC heap"]
     node_document: Option<Document>,
+    /// <https://dom.spec.whatwg.org/#registered-observer-list>
+    #[ignore_malloc_size_of = "owned by the GC heap"]
+    pub(crate) registered_observers: Vec<RegisteredObserver>,
+    /// The live ranges whose start node or end node is this node.
+    #[ignore_malloc_size_of = "owned by the GC heap"]
+    pub(crate) live_ranges: Vec<Range>,
+    /// <https://dom.spec.whatwg.org/#slotable-assigned-slot>
+    ///
+    /// This is only set for slottables.
+    #[ignore_malloc_size_of = "owned by the GC heap"]
+    pub(crate) assigned_slot: Option<HTMLSlotElement>,
+    /// <https://dom.spec.whatwg.org/#slottable-manual-slot-assignment>
+    ///
+    /// This is only set for slottables.
+    #[ignore_malloc_size_of = "owned by the GC heap"]
+    pub(crate) manual_slot_assignment: Option<HTMLSlotElement>,
     pub(crate) data: NodeTypeData,
 }
 
@@ -160,12 +1749,17 @@ impl NodeImpl {
     /// Create an `NodeImpl` with provided node type data.
     pub fn new_with_type(data: NodeTypeData) -> Self {
         NodeImpl {
-            _event_target: EventTarget::new(),
-            _parent_node: None,
-            child_nodes: VecDeque::new(),
+            event_target: EventTarget::new(),
+            parent_node: None,
+            first_child: None,
+            last_child: None,
             previous_sibling: None,
             next_sibling: None,
             node_document: None,
+            registered_observers: Vec::new(),
+            live_ranges: Vec::new(),
+            assigned_slot: None,
+            manual_slot_assignment: None,
             data,
         }
     }
@@ -175,39 +1769,808 @@ impl NodeImpl {
         self.node_document = document;
     }
 
+    /// Get Node's node document.
+    pub fn node_document(&self) -> Option<&Document> {
+        self.node_document.as_ref()
+    }
+
+    /// Get the `EventTarget` of this node.
+    pub fn event_target(&self) -> &EventTarget {
+        &self.event_target
+    }
+
+    /// Get first child of node's child nodes.
+    pub fn first_child(&self) -> Option<&Node> {
+        self.first_child.as_ref()
+    }
+
     /// Get last child of node's child nodes.
     pub fn last_child(&self) -> Option<&Node> {
-        self.child_nodes.back()
+        self.last_child.as_ref()
+    }
+
+    /// Check if the node is an `Element`.
+    pub fn is_element(&self) -> bool {
+        matches!(self.data, NodeTypeData::Element(_))
+    }
+
+    /// Check if the node is a `Text`.
+    pub fn is_text(&self) -> bool {
+        matches!(self.data, NodeTypeData::Text(_))
+    }
+
+    /// Check if the node is a `Comment`.
+    pub fn is_comment(&self) -> bool {
+        matches!(self.data, NodeTypeData::Comment(_))
+    }
+
+    /// Check if the node is a `DocumentType`.
+    pub fn is_document_type(&self) -> bool {
+        matches!(self.data, NodeTypeData::DocumentType(_))
+    }
+
+    /// Check if the node is a `CharacterData`.
+    pub fn is_character_data(&self) -> bool {
+        self.is_text() || self.is_comment()
+    }
+
+    /// Check if the node is a `DocumentFragment` other than a shadow root.
+    pub fn is_document_fragment(&self) -> bool {
+        matches!(self.data, NodeTypeData::DocumentFragment)
+    }
+}
+
+/// A node, or a string that stands for a Text node, as accepted by methods like
+/// [`Node::append`].
+#[derive(Clone, Debug)]
+pub enum NodeOrString {
+    /// A node.
+    Node(Node),
+    /// The data of a new Text node.
+    String(DOMString),
+}
+
+impl From<Node> for NodeOrString {
+    fn from(value: Node) -> Self {
+        Self::Node(value)
+    }
+}
+
+impl From<DOMString> for NodeOrString {
+    fn from(value: DOMString) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<&str> for NodeOrString {
+    fn from(value: &str) -> Self {
+        Self::String(value.into())
     }
 }
 
 /// The actual implementation of each node type
-#[derive(Debug, Default)]
+#[derive(Debug, Default, MallocSizeOf)]
 pub enum NodeTypeData {
     /// `ELEMENT_NODE`
     Element(ElementImpl),
+    /// `TEXT_NODE`
+    Text(TextImpl),
+    /// `COMMENT_NODE`
+    Comment(CommentImpl),
     /// `DOCUMENT_NODE`
     Document(DocumentImpl),
+    /// `DOCUMENT_TYPE_NODE`
+    DocumentType(DocumentTypeImpl),
+    /// `DOCUMENT_FRAGMENT_NODE`
+    DocumentFragment,
+    /// `DOCUMENT_FRAGMENT_NODE` of a shadow root
+    ShadowRoot(ShadowRootImpl),
     /// Similer to `Option::None`.
     #[default]
     None,
 }
 
+impl WindowStates {
+    /// Get the `Node` of `resource`.
+    ///
+    /// This returns an error instead of panicking if the object is not a `Node`.
+    pub(crate) fn get_node(&self, resource: &Resource<Node>) -> Result<Node> {
+        let node = self.table.get(resource)?;
+        node.try_data(&self.store)?;
+        Ok(node.clone())
+    }
+
+    /// Get the `Node` of each node handle in `nodes`, keeping strings as they are.
+    pub(crate) fn get_nodes_or_strings(
+        &self,
+        nodes: Vec<WitNodeOrString>,
+    ) -> Result<Vec<NodeOrString>> {
+        nodes
+            .into_iter()
+            .map(|node| match node {
+                WitNodeOrString::Node(node) => Ok(NodeOrString::Node(self.get_node(&node)?)),
+                WitNodeOrString::String(data) => Ok(NodeOrString::String(data.into())),
+            })
+            .collect()
+    }
+}
+
 impl HostNode for WindowStates {
+    fn node_type(&mut self, self_: Resource<Node>) -> Result<u16> {
+        let self_ = self.get_node(&self_)?;
+        Ok(self_.node_type(&self.store))
+    }
+
+    fn node_name(&mut self, self_: Resource<Node>) -> Result<String> {
+        let self_ = self.get_node(&self_)?;
+        Ok(self_.node_name(&self.store).into())
+    }
+
+    fn owner_document(&mut self, self_: Resource<Node>) -> Result<Option<Resource<Document>>> {
+        let self_ = self.get_node(&self_)?;
+        match self_.owner_document(&self.store) {
+            Some(document) => Ok(Some(self.push_resource(document)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn is_connected(&mut self, self_: Resource<Node>) -> Result<bool> {
+        let self_ = self.get_node(&self_)?;
+        Ok(self_.is_connected(&self.store))
+    }
+
+    fn get_root_node(&mut self, self_: Resource<Node>, composed: bool) -> Result<Resource<Node>> {
+        let self_ = self.get_node(&self_)?;
+        let root = self_.get_root_node(composed, &self.store);
+        Ok(self.push_resource(root)?)
+    }
+
+    fn compare_document_position(
+        &mut self,
+        self_: Resource<Node>,
+        other: Resource<Node>,
+    ) -> Result<u16> {
+        let self_ = self.get_node(&self_)?;
+        let other = self.get_node(&other)?;
+        Ok(self_.compare_document_position(&other, &self.store))
+    }
+
     fn append_child(
         &mut self,
         self_: Resource<Node>,
         child: Resource<Node>,
-    ) -> Result<Resource<Node>> {
-        // TODO: properly handle error for all host traits
-        let self_ = self.table.get(&self_)?;
-        let child_ = self.table.get(&child)?.clone();
-        self_.pre_insert(child_, None, &mut self.store);
-        Ok(child)
+    ) -> Result<Result<Resource<Node>, WitDomException>> {
+        let self_ = self.get_node(&self_)?;
+        let child = self.get_node(&child)?;
+        match self_.pre_insert(child, None, &mut self.store) {
+            Ok(child) => Ok(Ok(self.push_resource(child)?)),
+            Err(e) => Ok(Err(e.into())),
+        }
+    }
+
+    fn insert_before(
+        &mut self,
+        self_: Resource<Node>,
+        node: Resource<Node>,
+        child: Option<Resource<Node>>,
+    ) -> Result<Result<Resource<Node>, WitDomException>> {
+        let self_ = self.get_node(&self_)?;
+        let node = self.get_node(&node)?;
+        let child = child.map(|child| self.get_node(&child)).transpose()?;
+        match self_.insert_before(node, child.as_ref(), &mut self.store) {
+            Ok(node) => Ok(Ok(self.push_resource(node)?)),
+            Err(e) => Ok(Err(e.into())),
+        }
+    }
+
+    fn replace_child(
+        &mut self,
+        self_: Resource<Node>,
+        node: Resource<Node>,
+        child: Resource<Node>,
+    ) -> Result<Result<Resource<Node>, WitDomException>> {
+        let self_ = self.get_node(&self_)?;
+        let node = self.get_node(&node)?;
+        let child = self.get_node(&child)?;
+        match self_.replace_child(node, &child, &mut self.store) {
+            Ok(child) => Ok(Ok(self.push_resource(child)?)),
+            Err(e) => Ok(Err(e.into())),
+        }
+    }
+
+    fn text_content(&mut self, self_: Resource<Node>) -> Result<Option<String>> {
+        let self_ = self.get_node(&self_)?;
+        Ok(self_.text_content(&self.store).map(Into::into))
+    }
+
+    fn set_text_content(&mut self, self_: Resource<Node>, value: Option<String>) -> Result<()> {
+        let self_ = self.get_node(&self_)?;
+        self_.set_text_content(value.map(DOMString::from), &mut self.store)
+    }
+
+    fn normalize(&mut self, self_: Resource<Node>) -> Result<()> {
+        let self_ = self.get_node(&self_)?;
+        self_.normalize(&mut self.store);
+        Ok(())
     }
 
     fn drop(&mut self, rep: Resource<Node>) -> Result<()> {
-        self.table.delete(rep)?;
+        self.delete_resource(rep)?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ShadowRootInit, ShadowRootMode,
+        browsing_context::BrowsingContext,
+        testing::{self, element, text},
+        user_agent::UserAgent,
+    };
+
+    fn is_in(node: &Node, document: &Document, store: &wasmtime::Store<()>) -> bool {
+        node.data(store)
+            .node_document()
+            .is_some_and(|d| Node::from(d.clone()).is_same_node(&document.clone().into(), store))
+    }
+
+    #[test]
+    fn insert_checks_child_before_adopting() {
+        let (mut user_agent, mut store, document) = testing::document();
+        let (_, _, other) = BrowsingContext::new_top_browsing_context(&mut user_agent, &mut store);
+        let old_parent = Node::from(element(&other, "div", &mut store));
+        let node = text(&other, "x", &mut store);
+        old_parent.append_child(node.clone(), &mut store);
+
+        let parent = Node::from(element(&document, "div", &mut store));
+        let stranger = text(&document, "y", &mut store);
+        assert!(matches!(
+            parent.insert(node.clone(), Some(&stranger), false, &mut store),
+            Err(DomException::NotFoundError(_))
+        ));
+        // node is neither removed from its parent nor adopted.
+        assert!(
+            node.parent_node(&store)
+                .is_some_and(|p| p.is_same_node(&old_parent, &store))
+        );
+        assert!(is_in(&node, &other, &store));
+        assert!(parent.child_nodes(&store).is_empty());
+    }
+
+    fn names(parent: &Node, store: &wasmtime::Store<()>) -> Vec<String> {
+        parent
+            .child_nodes(store)
+            .iter()
+            .map(|child| match child.text_content(store) {
+                Some(data) if child.data(store).is_text() => data.to_string(),
+                _ => child.node_name(store).to_ascii_lowercase(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn insert_before_and_replace_child() {
+        let (_user_agent, mut store, document) = testing::document();
+        let parent = Node::from(element(&document, "div", &mut store));
+        let a = text(&document, "a", &mut store);
+        let b = text(&document, "b", &mut store);
+        let c = text(&document, "c", &mut store);
+        parent.insert_before(b.clone(), None, &mut store).unwrap();
+        parent
+            .insert_before(a.clone(), Some(&b), &mut store)
+            .unwrap();
+        parent.insert_before(c.clone(), None, &mut store).unwrap();
+        assert_eq!(names(&parent, &store), ["a", "b", "c"]);
+
+        // Moving a node before its own next sibling keeps the order valid.
+        parent
+            .insert_before(a.clone(), Some(&c), &mut store)
+            .unwrap();
+        assert_eq!(names(&parent, &store), ["b", "a", "c"]);
+
+        let span = Node::from(element(&document, "span", &mut store));
+        let replaced = parent.replace_child(span.clone(), &a, &mut store).unwrap();
+        assert!(replaced.is_same_node(&a, &store));
+        assert!(a.parent_node(&store).is_none());
+        assert_eq!(names(&parent, &store), ["b", "span", "c"]);
+
+        // Replacing a child with its previous sibling.
+        parent.replace_child(b.clone(), &span, &mut store).unwrap();
+        assert_eq!(names(&parent, &store), ["b", "c"]);
+
+        let stranger = text(&document, "x", &mut store);
+        assert!(matches!(
+            parent.replace_child(a.clone(), &stranger, &mut store),
+            Err(DomException::NotFoundError(_))
+        ));
+        assert!(matches!(
+            parent.insert_before(a.clone(), Some(&stranger), &mut store),
+            Err(DomException::NotFoundError(_))
+        ));
+        assert!(matches!(
+            parent.insert_before(parent.clone(), None, &mut store),
+            Err(DomException::HierarchyRequestError(_))
+        ));
+        let root = Node::from(document.clone());
+        let html = root.first_child(&store).unwrap();
+        assert!(matches!(
+            root.replace_child(a, &html, &mut store),
+            Err(DomException::HierarchyRequestError(_))
+        ));
+    }
+
+    #[test]
+    fn adopt_walks_shadow_trees() {
+        let (mut user_agent, mut store, document) = testing::document();
+        let (_, _, other) = BrowsingContext::new_top_browsing_context(&mut user_agent, &mut store);
+        let host = element(&other, "div", &mut store);
+        let init = ShadowRootInit {
+            mode: ShadowRootMode::Closed,
+            delegates_focus: false,
+            slot_assignment: SlotAssignmentMode::Named,
+        };
+        let shadow = Node::from(host.attach_shadow(init, &mut store).unwrap());
+        let inner = Node::from(element(&other, "span", &mut store));
+        shadow.append_child(inner.clone(), &mut store);
+        let child = text(&other, "x", &mut store);
+        Node::from(host.clone()).append_child(child.clone(), &mut store);
+
+        let host = Node::from(host);
+        let order = host.shadow_including_inclusive_descendants(&store);
+        assert_eq!(order.len(), 4);
+        for (node, expected) in order.iter().zip([&host, &shadow, &inner, &child]) {
+            assert!(node.is_same_node(expected, &store));
+        }
+
+        document.adopt_node(host.clone(), &mut store).unwrap();
+        for node in [&host, &shadow, &inner, &child] {
+            assert!(is_in(node, &document, &store));
+        }
+    }
+
+    #[test]
+    fn prepend_and_append_convert_strings() {
+        let (_user_agent, mut store, document) = testing::document();
+        let parent = Node::from(element(&document, "div", &mut store));
+        let b = Node::from(element(&document, "b", &mut store));
+        parent.append(vec![b.into()], &mut store).unwrap();
+        parent
+            .append(vec!["c".into(), "d".into()], &mut store)
+            .unwrap();
+        parent.prepend(vec!["a".into()], &mut store).unwrap();
+        assert_eq!(names(&parent, &store), ["a", "b", "c", "d"]);
+        assert!(parent.first_child(&store).unwrap().data(&store).is_text());
+    }
+
+    #[test]
+    fn append_is_atomic() {
+        let (_user_agent, mut store, document) = testing::document();
+        let root = Node::from(document.clone());
+        let before = root.child_nodes(&store).len();
+        let a = Node::from(element(&document, "a", &mut store));
+        let b = Node::from(element(&document, "b", &mut store));
+        // A document can not have a second element child, so nothing is inserted.
+        assert!(matches!(
+            root.append(vec![a.clone().into(), b.into()], &mut store),
+            Err(DomException::HierarchyRequestError(_))
+        ));
+        assert_eq!(root.child_nodes(&store).len(), before);
+        assert!(
+            a.parent_node(&store)
+                .is_none_or(|parent| !parent.is_same_node(&root, &store))
+        );
+    }
+
+    #[test]
+    fn inserting_a_fragment_moves_its_children() {
+        let (_user_agent, mut store, document) = testing::document();
+        let fragment = Node::from(DocumentFragment::new(&document, &mut store).unwrap());
+        fragment.append_child(text(&document, "x", &mut store), &mut store);
+        fragment.append_child(text(&document, "y", &mut store), &mut store);
+        let parent = Node::from(element(&document, "div", &mut store));
+        parent
+            .pre_insert(fragment.clone(), None, &mut store)
+            .unwrap();
+        assert_eq!(names(&parent, &store), ["x", "y"]);
+        assert!(fragment.child_nodes(&store).is_empty());
+        assert!(fragment.parent_node(&store).is_none());
+    }
+
+    /// Build a chain of `n` nested divs and return its outermost div.
+    fn chain(document: &Document, n: usize, store: &mut wasmtime::Store<()>) -> Node {
+        let root = Node::from(element(document, "div", store));
+        let mut current = root.clone();
+        for _ in 1..n {
+            let child = Node::from(element(document, "div", store));
+            current.append_child(child.clone(), &mut *store);
+            current = child;
+        }
+        root
+    }
+
+    #[test]
+    fn deep_insert_is_rejected() {
+        let (_user_agent, mut store, document) = testing::document();
+        document.set_max_tree_depth(5, &mut store);
+        // The body is at depth 2, so three more levels fit.
+        let body = Node::from(document.body(&store).unwrap());
+        let fits = chain(&document, 3, &mut store);
+        body.append_child(fits.clone(), &mut store);
+        let too_deep = chain(&document, 4, &mut store);
+        assert!(matches!(
+            body.pre_insert(too_deep.clone(), None, &mut store),
+            Err(DomException::HierarchyRequestError(_))
+        ));
+        assert!(too_deep.parent_node(&store).is_none());
+        assert!(matches!(
+            body.replace_child(too_deep, &fits, &mut store),
+            Err(DomException::HierarchyRequestError(_))
+        ));
+
+        let deepest = fits
+            .first_child(&store)
+            .unwrap()
+            .first_child(&store)
+            .unwrap();
+        assert_eq!(deepest.shadow_including_depth(&store), 5);
+        let leaf = text(&document, "x", &mut store);
+        assert!(matches!(
+            deepest.pre_insert(leaf, None, &mut store),
+            Err(DomException::HierarchyRequestError(_))
+        ));
+    }
+
+    #[test]
+    fn depth_counts_shadow_trees() {
+        let (_user_agent, mut store, document) = testing::document();
+        document.set_max_tree_depth(4, &mut store);
+        let init = || ShadowRootInit {
+            mode: ShadowRootMode::Open,
+            delegates_focus: false,
+            slot_assignment: SlotAssignmentMode::Named,
+        };
+        // The shadow root of the body is at depth 3.
+        let body = document.body(&store).unwrap();
+        let shadow = Node::from(body.attach_shadow(init(), &mut store).unwrap());
+        assert_eq!(shadow.shadow_including_depth(&store), 3);
+        shadow
+            .pre_insert(chain(&document, 1, &mut store), None, &mut store)
+            .unwrap();
+        assert!(matches!(
+            shadow.pre_insert(chain(&document, 2, &mut store), None, &mut store),
+            Err(DomException::HierarchyRequestError(_))
+        ));
+
+        // A host brings its shadow tree along.
+        let host = element(&document, "div", &mut store);
+        let inner = Node::from(host.attach_shadow(init(), &mut store).unwrap());
+        inner.append_child(text(&document, "x", &mut store), &mut store);
+        assert!(matches!(
+            Node::from(body).pre_insert(host.into(), None, &mut store),
+            Err(DomException::HierarchyRequestError(_))
+        ));
+    }
+
+    /// A xorshift generator, so the randomized tests are reproducible.
+    struct Random(u64);
+
+    impl Random {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+    }
+
+    /// Build a random tree of `size` elements and text nodes under a detached div and return the
+    /// div with its descendants in tree order.
+    fn random_tree(
+        document: &Document,
+        size: usize,
+        random: &mut Random,
+        store: &mut wasmtime::Store<()>,
+    ) -> Vec<Node> {
+        let root = Node::from(element(document, "div", store));
+        let mut parents = vec![root.clone()];
+        for _ in 0..size {
+            let parent = parents[random.below(parents.len())].clone();
+            // Insert before a random child, or at the end.
+            let children = parent.child_nodes(&*store);
+            let child = children.get(random.below(children.len() + 1)).cloned();
+            let node = if random.below(4) == 0 {
+                text(document, "x", store)
+            } else {
+                let node = Node::from(element(document, "span", store));
+                parents.push(node.clone());
+                node
+            };
+            parent
+                .pre_insert(node, child.as_ref(), &mut *store)
+                .unwrap();
+        }
+        let mut nodes = vec![root.clone()];
+        nodes.extend(root.descendants(&*store));
+        nodes
+    }
+
+    #[test]
+    fn sort_in_tree_order_matches_a_traversal() {
+        let (_user_agent, mut store, document) = testing::document();
+        let mut random = Random(0x2545_f491_4f6c_dd1d);
+        let nodes = random_tree(&document, 300, &mut random, &mut store);
+        for _ in 0..50 {
+            // A random subset with duplicates, in random order.
+            let picked: Vec<usize> = (0..random.below(40) + 1)
+                .map(|_| random.below(nodes.len()))
+                .collect();
+            let mut sorted: Vec<Node> = picked.iter().map(|&i| nodes[i].clone()).collect();
+            sort_in_tree_order(&mut sorted, &store);
+            let mut expected = picked.clone();
+            expected.sort_unstable();
+            expected.dedup();
+            assert_eq!(sorted.len(), expected.len());
+            for (node, &i) in sorted.iter().zip(&expected) {
+                assert!(node.is_same_node(&nodes[i], &store));
+            }
+
+            let (a, b) = (random.below(nodes.len()), random.below(nodes.len()));
+            assert_eq!(
+                tree_order_cmp(&nodes[a], &nodes[b], &store),
+                Some(a.cmp(&b))
+            );
+        }
+    }
+
+    #[test]
+    fn sort_in_tree_order_groups_trees() {
+        let (_user_agent, mut store, document) = testing::document();
+        let body = Node::from(document.body(&store).unwrap());
+        let detached = Node::from(element(&document, "div", &mut store));
+        let child = text(&document, "x", &mut store);
+        detached.append_child(child.clone(), &mut store);
+        assert_eq!(tree_order_cmp(&body, &child, &store), None);
+
+        let root = Node::from(document.clone());
+        let mut nodes = vec![
+            child.clone(),
+            body.clone(),
+            detached.clone(),
+            root.clone(),
+            child.clone(),
+        ];
+        sort_in_tree_order(&mut nodes, &store);
+        let expected = [&detached, &child, &root, &body];
+        assert_eq!(nodes.len(), expected.len());
+        for (node, expected) in nodes.iter().zip(expected) {
+            assert!(node.is_same_node(expected, &store));
+        }
+    }
+
+    #[test]
+    fn compare_document_position() {
+        let (_user_agent, mut store, document) = testing::document();
+        let body = Node::from(document.body(&store).unwrap());
+        let head = body.previous_sibling(&store).unwrap();
+        let html = body.parent_node(&store).unwrap();
+        assert_eq!(body.compare_document_position(&body, &store), 0);
+        assert_eq!(
+            body.compare_document_position(&head, &store),
+            Node::DOCUMENT_POSITION_PRECEDING
+        );
+        assert_eq!(
+            head.compare_document_position(&body, &store),
+            Node::DOCUMENT_POSITION_FOLLOWING
+        );
+        assert_eq!(
+            body.compare_document_position(&html, &store),
+            Node::DOCUMENT_POSITION_CONTAINS | Node::DOCUMENT_POSITION_PRECEDING
+        );
+        assert_eq!(
+            html.compare_document_position(&body, &store),
+            Node::DOCUMENT_POSITION_CONTAINED_BY | Node::DOCUMENT_POSITION_FOLLOWING
+        );
+
+        let detached = Node::from(element(&document, "div", &mut store));
+        let position = body.compare_document_position(&detached, &store);
+        assert_ne!(position & Node::DOCUMENT_POSITION_DISCONNECTED, 0);
+        assert_ne!(
+            position & Node::DOCUMENT_POSITION_IMPLEMENTATION_SPECIFIC,
+            0
+        );
+        // The order of disconnected nodes is consistent.
+        let reverse = detached.compare_document_position(&body, &store);
+        assert_eq!(
+            position ^ reverse,
+            Node::DOCUMENT_POSITION_PRECEDING | Node::DOCUMENT_POSITION_FOLLOWING
+        );
+    }
+
+    #[test]
+    fn node_name_of_each_node_type() {
+        let (_user_agent, mut store, document) = testing::document();
+        let html_element = Node::from(element(&document, "div", &mut store));
+        assert_eq!(html_element.node_name(&store), "DIV");
+        // Only HTML elements in an HTML document are uppercased.
+        let svg = document
+            .create_element_ns(
+                Some("http://www.w3.org/2000/svg".into()),
+                "svg:foreignObject".into(),
+                &mut store,
+            )
+            .unwrap();
+        assert_eq!(Node::from(svg).node_name(&store), "svg:foreignObject");
+        assert_eq!(text(&document, "x", &mut store).node_name(&store), "#text");
+        let comment = document.create_comment("x".into(), &mut store).unwrap();
+        assert_eq!(Node::from(comment).node_name(&store), "#comment");
+        assert_eq!(Node::from(document.clone()).node_name(&store), "#document");
+        let doctype = document
+            .create_document_type("html".into(), "".into(), "".into(), &mut store)
+            .unwrap();
+        assert_eq!(Node::from(doctype).node_name(&store), "html");
+        let init = ShadowRootInit {
+            mode: ShadowRootMode::Open,
+            delegates_focus: false,
+            slot_assignment: SlotAssignmentMode::Named,
+        };
+        let host = document.body(&store).unwrap();
+        let shadow = Node::from(host.attach_shadow(init, &mut store).unwrap());
+        assert_eq!(shadow.node_name(&store), "#document-fragment");
+    }
+
+    #[test]
+    fn guests_read_the_node_name() {
+        let mut states = WindowStates::create(&mut UserAgent::new(wasmtime::Engine::default()));
+        let document = states.window.document(&states.store).unwrap();
+        let span = Node::from(element(&document, "span", &mut states.store));
+        let span = states.push_resource(span).unwrap();
+        let name = HostNode::node_name(&mut states, Resource::new_borrow(span.rep())).unwrap();
+        assert_eq!(name, "SPAN");
+        HostNode::drop(&mut states, span).unwrap();
+        assert_eq!(states.resource_count(), 0);
+    }
+
+    /// Create a div with `children` appended in order.
+    fn div_with(document: &Document, children: &[Node], store: &mut wasmtime::Store<()>) -> Node {
+        let div = Node::from(element(document, "div", store));
+        for child in children {
+            div.pre_insert(child.clone(), None, &mut *store).unwrap();
+        }
+        div
+    }
+
+    fn data(node: &Node, store: &wasmtime::Store<()>) -> Vec<String> {
+        node.child_nodes(store)
+            .iter()
+            .map(|child| match child.data(store).try_as_character_data() {
+                Some(data) => data.to_string(),
+                None => format!("<{}>", Element(child.0.clone()).local_name(store)),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn normalize_merges_adjacent_text_nodes() {
+        let (_user_agent, mut store, document) = testing::document();
+        let first = text(&document, "a", &mut store);
+        let second = text(&document, "b", &mut store);
+        let div = div_with(&document, &[first.clone(), second.clone()], &mut store);
+
+        div.normalize(&mut store);
+        assert_eq!(data(&div, &store), ["ab"]);
+        // The first node is kept and the others are removed.
+        assert!(div.child_nodes(&store)[0].is_same_node(&first, &store));
+        assert!(second.parent_node(&store).is_none());
+        assert_eq!(CharacterData(second.0.clone()).data(&store), "b");
+    }
+
+    #[test]
+    fn normalize_removes_empty_text_nodes() {
+        let (_user_agent, mut store, document) = testing::document();
+        let children = [
+            text(&document, "", &mut store),
+            element(&document, "span", &mut store).into(),
+            text(&document, "", &mut store),
+        ];
+        let div = div_with(&document, &children, &mut store);
+
+        div.normalize(&mut store);
+        assert_eq!(data(&div, &store), ["<span>"]);
+        // An empty node in a run is merged away as well.
+        let children = [
+            text(&document, "a", &mut store),
+            text(&document, "", &mut store),
+            text(&document, "b", &mut store),
+        ];
+        let div = div_with(&document, &children, &mut store);
+        div.normalize(&mut store);
+        assert_eq!(data(&div, &store), ["ab"]);
+    }
+
+    #[test]
+    fn normalize_merges_runs_of_text_nodes() {
+        let (_user_agent, mut store, document) = testing::document();
+        let comment = document.create_comment("c".into(), &mut store).unwrap();
+        let children = [
+            text(&document, "a", &mut store),
+            text(&document, "b", &mut store),
+            text(&document, "c", &mut store),
+            element(&document, "span", &mut store).into(),
+            text(&document, "d", &mut store),
+            text(&document, "e", &mut store),
+            text(&document, "f", &mut store),
+            text(&document, "g", &mut store),
+            // Comments are not Text nodes, so they end a run.
+            comment.into(),
+            text(&document, "h", &mut store),
+        ];
+        let div = div_with(&document, &children, &mut store);
+
+        div.normalize(&mut store);
+        assert_eq!(data(&div, &store), ["abc", "<span>", "defg", "c", "h"]);
+        // Descendants are normalized too.
+        let inner = [
+            text(&document, "x", &mut store),
+            text(&document, "y", &mut store),
+            text(&document, "z", &mut store),
+        ];
+        let p = div_with(&document, &inner, &mut store);
+        let outer = div_with(&document, &[p.clone()], &mut store);
+        outer.normalize(&mut store);
+        assert_eq!(data(&p, &store), ["xyz"]);
+    }
+
+    fn local_names(elements: &[Element], store: &wasmtime::Store<()>) -> Vec<String> {
+        elements
+            .iter()
+            .map(|element| element.local_name(store).to_string())
+            .collect()
+    }
+
+    #[test]
+    fn get_elements_by_tag_name_matches_wildcards_and_custom_names() {
+        let (_user_agent, mut store, document) = testing::document();
+        let bold = Node::from(element(&document, "b", &mut store));
+        let custom_element = Node::from(element(&document, "my-element", &mut store));
+        custom_element.pre_insert(bold, None, &mut store).unwrap();
+        let svg = document
+            .create_element_ns(
+                Some("http://www.w3.org/2000/svg".into()),
+                "svg:foreignObject".into(),
+                &mut store,
+            )
+            .unwrap();
+        let span = Node::from(element(&document, "span", &mut store));
+        let root = div_with(
+            &document,
+            &[
+                span,
+                custom_element,
+                text(&document, "x", &mut store),
+                svg.into(),
+            ],
+            &mut store,
+        );
+
+        // The root itself and non-element descendants are never included.
+        let all = root.get_elements_by_tag_name("*", &store);
+        assert_eq!(
+            local_names(&all, &store),
+            ["span", "my-element", "b", "foreignObject"]
+        );
+        for name in ["my-element", "MY-ELEMENT"] {
+            let custom = root.get_elements_by_tag_name(name, &store);
+            assert_eq!(local_names(&custom, &store), ["my-element"]);
+        }
+        // Elements outside the HTML namespace are matched case-sensitively.
+        let svg = root.get_elements_by_tag_name("svg:foreignObject", &store);
+        assert_eq!(svg.len(), 1);
+        assert!(
+            root.get_elements_by_tag_name("svg:foreignobject", &store)
+                .is_empty()
+        );
+        assert!(root.get_elements_by_tag_name("div", &store).is_empty());
+    }
+}
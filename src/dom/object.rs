@@ -1,8 +1,8 @@
 use std::{any::Any, marker::PhantomData, ops::Deref};
 
 use wasmtime::{
-    AsContextMut, Error, ExternRef, GcHeapOutOfMemory, Result, Rooted, StoreContext,
-    StoreContextMut,
+    AsContextMut, Error, ExternRef, GcHeapOutOfMemory, ManuallyRooted, Result, Rooted,
+    StoreContext, StoreContextMut,
 };
 
 /// This is a GC traced object represented as a DOM object.
@@ -46,6 +46,18 @@ impl<T: 'static + Any + Send + Sync> Object<T> {
         })
     }
 
+    /// Convert this `Object` into a [`ManuallyRootedObject`].
+    ///
+    /// `Rooted` references stay alive until the `RootScope` they were created in exits, which is the
+    /// whole lifetime of the `Store` when there is none. Long-lived structures should keep a
+    /// `ManuallyRootedObject` instead, so the object can be collected once it is unrooted.
+    pub fn to_manually_rooted(&self, store: impl AsContextMut) -> Result<ManuallyRootedObject<T>> {
+        Ok(ManuallyRootedObject {
+            object: self.object.to_manually_rooted(store)?,
+            _phantom: PhantomData,
+        })
+    }
+
     /// Get a shared borrow of the underlying data for this `Object`.
     pub fn data<'a, U>(&self, store: impl Into<StoreContext<'a, U>>) -> &'a T
     where
@@ -55,7 +67,9 @@ impl<T: 'static + Any + Send + Sync> Object<T> {
             .expect("externref was not requested type")
     }
 
-    fn try_data<'a, U>(&self, store: impl Into<StoreContext<'a, U>>) -> Result<&'a T>
+    /// Get a shared borrow of the underlying data for this `Object`, or an error if the data is
+    /// not of type `T`.
+    pub fn try_data<'a, U>(&self, store: impl Into<StoreContext<'a, U>>) -> Result<&'a T>
     where
         U: 'static,
     {
@@ -75,7 +89,9 @@ impl<T: 'static + Any + Send + Sync> Object<T> {
             .expect("externref was not requested type")
     }
 
-    fn try_data_mut<'a, U>(&self, store: impl Into<StoreContextMut<'a, U>>) -> Result<&'a mut T>
+    /// Get an exclusive borrow of the underlying data for this `Object`, or an error if the data
+    /// is not of type `T`.
+    pub fn try_data_mut<'a, U>(&self, store: impl Into<StoreContextMut<'a, U>>) -> Result<&'a mut T>
     where
         U: 'static,
     {
@@ -94,3 +110,74 @@ impl<T: 'static + Any + Send + Sync> Deref for Object<T> {
         &self.object
     }
 }
+
+/// An [`Object`] whose root is managed manually.
+///
+/// Unlike `Object`, it is not released when a `RootScope` exits. It must be released with
+/// [`ManuallyRootedObject::unroot`], otherwise the object is never collected.
+#[derive(Debug)]
+pub struct ManuallyRootedObject<T: 'static + Any + Send + Sync> {
+    object: ManuallyRooted<ExternRef>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: 'static + Any + Send + Sync> ManuallyRootedObject<T> {
+    /// Get an `Object` rooted in the current `RootScope` of the store.
+    pub fn to_object(&self, store: impl AsContextMut) -> Object<T> {
+        Object {
+            object: self.object.to_rooted(store),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Release the root of this object, so the GC can collect it once nothing else roots it.
+    pub fn unroot(self, store: impl AsContextMut) {
+        self.object.unroot(store);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use wasmtime::{RootScope, Store};
+
+    use super::*;
+
+    /// The number of `Tracked` values dropped so far.
+    static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+    /// Host data counting how many times it is dropped, which happens when the GC collects it.
+    struct Tracked;
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            DROPPED.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn manually_rooted_objects_survive_until_unrooted() {
+        let mut store = Store::<()>::default();
+        let kept = {
+            let mut scope = RootScope::new(&mut store);
+            let kept = Object::new(&mut scope, Tracked).unwrap();
+            for _ in 0..100 {
+                Object::new(&mut scope, Tracked).unwrap();
+            }
+            kept.to_manually_rooted(&mut scope).unwrap()
+        };
+        // The objects only rooted by the scope are collected once it exits.
+        store.gc(None);
+        assert_eq!(DROPPED.load(Ordering::SeqCst), 100);
+
+        {
+            let mut scope = RootScope::new(&mut store);
+            let object = kept.to_object(&mut scope);
+            assert!(object.try_data(&scope).is_ok());
+        }
+        kept.unroot(&mut store);
+        store.gc(None);
+        assert_eq!(DROPPED.load(Ordering::SeqCst), 101);
+    }
+}
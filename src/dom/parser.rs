@@ -0,0 +1,375 @@
+//! HTML parsing backed by html5ever.
+
+use std::{borrow::Cow, cell::RefCell};
+
+use html5ever::{
+    Attribute, LocalName, Namespace, ParseOpts, QualName,
+    interface::{ElemName, ElementFlags, NodeOrText, QuirksMode, TreeSink},
+    local_name, ns,
+    tendril::{StrTendril, TendrilSink},
+};
+use wasmtime::{AsContext, AsContextMut, Error, Result, StoreContextMut};
+
+use crate::{NodeImpl, NodeTypeData, Object, agent::NameSpace, string::DOMString};
+
+use super::{
+    Attr, CharacterData, Comment, Document, DocumentMode, DocumentType, Element, Node, TagName,
+    Text,
+};
+
+/// <https://html.spec.whatwg.org/multipage/#parsing>
+///
+/// Parse `input` as a whole HTML document and append the parsed nodes to `document`. The mode of
+/// `document` is set from its doctype.
+///
+/// Scripts are parsed as inert elements, they are never executed. Nodes nested deeper than the
+/// maximum tree depth of `document` are dropped and a "HierarchyRequestError" is returned.
+pub(crate) fn parse_document(
+    document: &Document,
+    input: &str,
+    mut store: impl AsContextMut,
+) -> Result<()> {
+    let sink = Sink {
+        document: document.clone(),
+        parser_document: document.clone().into(),
+        max_depth: document.max_tree_depth(&store),
+        store: RefCell::new(store.as_context_mut()),
+        error: RefCell::new(None),
+    };
+    html5ever::parse_document(sink, ParseOpts::default()).one(input)?;
+    Ok(())
+}
+
+/// <https://html.spec.whatwg.org/multipage/#html-fragment-parsing-algorithm>
+///
+/// Parse `markup` with `context` as the context element and return the parsed nodes in tree order.
+/// They have no parent and their node document is the node document of `context`.
+///
+/// Scripts are parsed as inert elements, they are never executed. Nodes that would be nested deeper
+/// than the maximum tree depth once inserted into `context` are dropped and a
+/// "HierarchyRequestError" is returned.
+pub(crate) fn parse_fragment(
+    context: &Element,
+    markup: &str,
+    mut store: impl AsContextMut,
+) -> Result<Vec<Node>> {
+    let Some(document) = context.data(&store).node_document().cloned() else {
+        return Err(Error::msg("context element has no node document"));
+    };
+    // 1. Let document be a Document node whose type is "html".
+    // The parsed nodes would be adopted into the node document of context afterwards, so they are
+    // created with it directly. The parser only needs a node to hold the root element.
+    // TODO: 2. If context's node document is in quirks mode, then let document's mode be "quirks".
+    let parser_document = Node(Object::new(
+        &mut store,
+        NodeImpl::new_with_type(NodeTypeData::None),
+    )?);
+    let context_name = QualName::new(
+        None,
+        Namespace::from(context.tag(&store).name_space()),
+        LocalName::from(context.local_name(&store)),
+    );
+    // The root html element stands for context, so a node at depth d under the parser document is
+    // at depth d - 1 below context once inserted.
+    let max_depth = (document.max_tree_depth(&store) + 1)
+        .saturating_sub(Node::from(context.clone()).shadow_including_depth(&store))
+        .max(1);
+    let sink = Sink {
+        document,
+        parser_document,
+        max_depth,
+        store: RefCell::new(store.as_context_mut()),
+        error: RefCell::new(None),
+    };
+    // 4. Create a new HTML parser, and associate it with document.
+    // 5 ~ 15. Set the state of the tokenizer, create the root html element and start parsing. These
+    // are done by html5ever.
+    // Scripting is always enabled since guests run in this engine.
+    let parser_document =
+        html5ever::parse_fragment(sink, ParseOpts::default(), context_name, Vec::new(), true)
+            .one(markup)?;
+    // 16. Return root's children, in tree order.
+    let Some(root) = parser_document.child_nodes(&store).into_iter().next() else {
+        return Ok(Vec::new());
+    };
+    let children = root.child_nodes(&store);
+    for child in &children {
+        child.remove(false, &mut store);
+    }
+    Ok(children)
+}
+
+/// The `TreeSink` building nodes of a `Store`.
+struct Sink<'a, T: 'static> {
+    /// The node document of the created nodes.
+    document: Document,
+    /// The document the parser appends the root html element to.
+    parser_document: Node,
+    /// The maximum depth of nodes under `parser_document`.
+    max_depth: usize,
+    store: RefCell<StoreContextMut<'a, T>>,
+    /// The first error of the store while creating nodes. Parsing goes on with a placeholder node
+    /// and the error is returned when it finishes.
+    error: RefCell<Option<Error>>,
+}
+
+impl<T: 'static> Sink<'_, T> {
+    /// Get the created node, or record the error and return a placeholder.
+    fn node(&self, result: Result<Node>) -> Node {
+        result.unwrap_or_else(|e| {
+            self.record_error(e);
+            self.parser_document.clone()
+        })
+    }
+
+    /// Keep the first error of the store.
+    fn record_error(&self, e: Error) {
+        let mut error = self.error.borrow_mut();
+        if error.is_none() {
+            *error = Some(e);
+        }
+    }
+
+    /// Check that `node` can be inserted into `parent` within the maximum depth, or record the
+    /// error.
+    fn fits(&self, parent: &Node, node: &Node, store: impl AsContext) -> bool {
+        let depth = parent.shadow_including_depth(&store);
+        match parent.ensure_depth_within(node, depth, self.max_depth, &store) {
+            Ok(()) => true,
+            Err(e) => {
+                self.record_error(e.into());
+                false
+            }
+        }
+    }
+
+    /// Insert `child` into `parent` before `before`, or at the end if `before` is `None`. Text is
+    /// merged into the previous sibling if it is a Text node.
+    fn insert(&self, parent: &Node, before: Option<&Node>, child: NodeOrText<Node>) {
+        let mut store = self.store.borrow_mut();
+        let node = match child {
+            NodeOrText::AppendNode(node) => node,
+            NodeOrText::AppendText(text) => {
+                let previous = match before {
+                    Some(before) => before.previous_sibling(&*store),
+                    None => parent.last_child(&*store),
+                }
+                .filter(|previous| previous.data(&*store).is_text());
+                if let Some(previous) = previous {
+                    CharacterData(previous.0).append_data(&text, &mut *store);
+                    return;
+                }
+                match Text::new(&self.document, DOMString::from(&*text), &mut *store) {
+                    Ok(text) => text.into(),
+                    Err(e) => {
+                        self.record_error(e);
+                        return;
+                    }
+                }
+            }
+        };
+        if self.fits(parent, &node, &*store) {
+            parent.insert_child_before(node, before, &mut *store);
+        }
+    }
+}
+
+impl<T: 'static> TreeSink for Sink<'_, T> {
+    type Handle = Node;
+    type Output = Result<Node>;
+    type ElemName<'a>
+        = ParsedName
+    where
+        Self: 'a;
+
+    fn finish(self) -> Self::Output {
+        match self.error.into_inner() {
+            Some(error) => Err(error),
+            None => Ok(self.parser_document),
+        }
+    }
+
+    fn parse_error(&self, _msg: Cow<'static, str>) {}
+
+    fn get_document(&self) -> Node {
+        self.parser_document.clone()
+    }
+
+    fn elem_name<'a>(&'a self, target: &'a Node) -> ParsedName {
+        let store = self.store.borrow();
+        if !target.data(&*store).is_element() {
+            return ParsedName {
+                ns: ns!(),
+                local: local_name!(""),
+            };
+        }
+        let tag_name = Element(target.0.clone()).tag(&*store);
+        ParsedName {
+            ns: Namespace::from(tag_name.name_space()),
+            local: LocalName::from(tag_name.local_name()),
+        }
+    }
+
+    fn create_element(&self, name: QualName, attrs: Vec<Attribute>, _flags: ElementFlags) -> Node {
+        let mut store = self.store.borrow_mut();
+        let tag_name = TagName::exact(&name.local, NameSpace::from(&name.ns));
+        let is = attrs
+            .iter()
+            .find(|attr| attr.name.local == local_name!("is"))
+            .map(|attr| DOMString::from(&*attr.value));
+        let element = match Element::new(&self.document, tag_name, is, &mut *store) {
+            Ok(element) => element,
+            Err(e) => return self.node(Err(e)),
+        };
+        for attr in attrs {
+            element.append_attribute(to_attr(attr), &mut *store);
+        }
+        element.into()
+    }
+
+    fn create_comment(&self, text: StrTendril) -> Node {
+        let comment = Comment::new(
+            &self.document,
+            DOMString::from(&*text),
+            &mut *self.store.borrow_mut(),
+        );
+        self.node(comment.map(Into::into))
+    }
+
+    fn create_pi(&self, _target: StrTendril, _data: StrTendril) -> Node {
+        // TODO: Create a ProcessingInstruction node. The HTML parser never creates one.
+        let node = Object::new(
+            &mut *self.store.borrow_mut(),
+            NodeImpl::new_with_type(NodeTypeData::None),
+        );
+        self.node(node.map(Node))
+    }
+
+    fn append(&self, parent: &Node, child: NodeOrText<Node>) {
+        self.insert(parent, None, child);
+    }
+
+    fn append_based_on_parent_node(
+        &self,
+        element: &Node,
+        prev_element: &Node,
+        child: NodeOrText<Node>,
+    ) {
+        let has_parent = element.parent_node(&*self.store.borrow()).is_some();
+        if has_parent {
+            self.append_before_sibling(element, child);
+        } else {
+            self.append(prev_element, child);
+        }
+    }
+
+    fn append_doctype_to_document(
+        &self,
+        name: StrTendril,
+        public_id: StrTendril,
+        system_id: StrTendril,
+    ) {
+        let doctype = DocumentType::new(
+            &self.document,
+            DOMString::from(&*name),
+            DOMString::from(&*public_id),
+            DOMString::from(&*system_id),
+            &mut *self.store.borrow_mut(),
+        );
+        match doctype {
+            Ok(doctype) => self.append(
+                &self.parser_document,
+                NodeOrText::AppendNode(doctype.into()),
+            ),
+            Err(e) => self.record_error(e),
+        }
+    }
+
+    fn get_template_contents(&self, target: &Node) -> Node {
+        // TODO: Return the template contents once DocumentFragment exists.
+        target.clone()
+    }
+
+    fn same_node(&self, x: &Node, y: &Node) -> bool {
+        x.is_same_node(y, &*self.store.borrow())
+    }
+
+    fn set_quirks_mode(&self, mode: QuirksMode) {
+        let mut store = self.store.borrow_mut();
+        // The fragment parser has no document to set the mode of.
+        if self
+            .parser_document
+            .data(&*store)
+            .try_as_document()
+            .is_none()
+        {
+            return;
+        }
+        let mode = match mode {
+            QuirksMode::Quirks => DocumentMode::Quirks,
+            QuirksMode::LimitedQuirks => DocumentMode::LimitedQuirks,
+            QuirksMode::NoQuirks => DocumentMode::NoQuirks,
+        };
+        self.document.set_mode(mode, &mut *store);
+    }
+
+    fn append_before_sibling(&self, sibling: &Node, new_node: NodeOrText<Node>) {
+        let parent = sibling.parent_node(&*self.store.borrow());
+        if let Some(parent) = parent {
+            self.insert(&parent, Some(sibling), new_node);
+        }
+    }
+
+    fn add_attrs_if_missing(&self, target: &Node, attrs: Vec<Attribute>) {
+        let mut store = self.store.borrow_mut();
+        let element = Element(target.0.clone());
+        for attr in attrs.into_iter().map(to_attr) {
+            if element.get_attribute(&attr.name(), &*store).is_none() {
+                element.append_attribute(attr, &mut *store);
+            }
+        }
+    }
+
+    fn remove_from_parent(&self, target: &Node) {
+        target.remove(false, &mut *self.store.borrow_mut());
+    }
+
+    fn reparent_children(&self, node: &Node, new_parent: &Node) {
+        let mut store = self.store.borrow_mut();
+        for child in node.child_nodes(&*store) {
+            child.remove(false, &mut *store);
+            if self.fits(new_parent, &child, &*store) {
+                new_parent.append_child(child, &mut *store);
+            }
+        }
+    }
+}
+
+/// Convert an attribute of html5ever into an `Attr`.
+fn to_attr(attr: Attribute) -> Attr {
+    Attr::new(
+        DOMString::from(&*attr.name.local),
+        DOMString::from(&*attr.value),
+    )
+    .with_namespace(
+        NameSpace::from(&attr.name.ns),
+        attr.name.prefix.map(|prefix| DOMString::from(&*prefix)),
+    )
+}
+
+/// The name of an element given to html5ever.
+#[derive(Debug)]
+struct ParsedName {
+    ns: Namespace,
+    local: LocalName,
+}
+
+impl ElemName for ParsedName {
+    fn ns(&self) -> &Namespace {
+        &self.ns
+    }
+
+    fn local_name(&self) -> &LocalName {
+        &self.local
+    }
+}
@@ -0,0 +1,336 @@
+use std::cmp::Ordering;
+
+use wasmtime::{AsContext, AsContextMut, Result, Rooted};
+
+use crate::DomException;
+
+use super::{Document, Node, Object, node::tree_order_cmp};
+
+/// <https://dom.spec.whatwg.org/#concept-live-range>
+///
+/// The boundary points are kept up to date when character data is replaced.
+/// TODO: Updates on insertion, removal, splitting and normalizing, and the rest of the `Range`
+/// interface.
+#[derive(Clone, Debug)]
+pub struct Range(Object<RangeImpl>);
+
+impl Range {
+    /// <https://dom.spec.whatwg.org/#dom-range-range>
+    pub fn new(document: &Document, mut store: impl AsContextMut) -> Result<Self> {
+        // The new Range() constructor steps are to set this’s start and end to (current global
+        // object’s associated Document, 0).
+        let point = BoundaryPoint {
+            node: document.clone().into(),
+            offset: 0,
+        };
+        let range = Range(Object::new(
+            &mut store,
+            RangeImpl {
+                start: point.clone(),
+                end: point.clone(),
+            },
+        )?);
+        point
+            .node
+            .data_mut(&mut store)
+            .live_ranges
+            .push(range.clone());
+        Ok(range)
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-range-start>
+    pub fn start(&self, store: impl AsContext) -> BoundaryPoint {
+        self.0.data(&store).start.clone()
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-range-end>
+    pub fn end(&self, store: impl AsContext) -> BoundaryPoint {
+        self.0.data(&store).end.clone()
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-range-collapsed>
+    pub fn collapsed(&self, store: impl AsContext) -> bool {
+        // A range is collapsed if its start node is its end node and its start offset is its end
+        // offset.
+        let RangeImpl { start, end } = self.0.data(&store);
+        start.node.is_same_node(&end.node, &store) && start.offset == end.offset
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-range-setstart>
+    pub fn set_start(
+        &self,
+        node: &Node,
+        offset: usize,
+        store: impl AsContextMut,
+    ) -> Result<(), DomException> {
+        // The setStart(node, offset) method steps are to set the start of this to boundary point
+        // (node, offset).
+        self.set_start_or_end(node, offset, true, store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-range-setend>
+    pub fn set_end(
+        &self,
+        node: &Node,
+        offset: usize,
+        store: impl AsContextMut,
+    ) -> Result<(), DomException> {
+        // The setEnd(node, offset) method steps are to set the end of this to boundary point
+        // (node, offset).
+        self.set_start_or_end(node, offset, false, store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-range-bp-set>
+    fn set_start_or_end(
+        &self,
+        node: &Node,
+        offset: usize,
+        is_start: bool,
+        mut store: impl AsContextMut,
+    ) -> Result<(), DomException> {
+        // 1. If node is a doctype, then throw an "InvalidNodeTypeError" DOMException.
+        if node.data(&store).is_document_type() {
+            return Err(DomException::InvalidNodeTypeError(
+                "a boundary point can not be in a doctype".into(),
+            ));
+        }
+        // 2. If offset is greater than node’s length, then throw an "IndexSizeError" DOMException.
+        let length = node.length(&store);
+        if offset > length {
+            return Err(DomException::IndexSizeError(format!(
+                "offset {offset} is greater than the length {length}"
+            )));
+        }
+        // 3. Let bp be the boundary point (node, offset).
+        let bp = BoundaryPoint {
+            node: node.clone(),
+            offset,
+        };
+        let RangeImpl { start, end } = self.0.data(&store).clone();
+        let is_other_root = |other: &BoundaryPoint| {
+            !node
+                .get_root_node(false, &store)
+                .is_same_node(&other.node.get_root_node(false, &store), &store)
+        };
+        if is_start {
+            // 4. If these steps were invoked as "set the start",
+            // 4.1 If range’s root is not equal to node’s root, or if bp is after the range’s end,
+            // set range’s end to bp.
+            if is_other_root(&end) || bp.position(&end, &store) == Ordering::Greater {
+                self.set_boundary_point(false, bp.clone(), &mut store);
+            }
+            // 4.2 Set range’s start to bp.
+            self.set_boundary_point(true, bp, &mut store);
+        } else {
+            // 5. If these steps were invoked as "set the end",
+            // 5.1 If range’s root is not equal to node’s root, or if bp is before the range’s
+            // start, set range’s start to bp.
+            if is_other_root(&start) || bp.position(&start, &store) == Ordering::Less {
+                self.set_boundary_point(true, bp.clone(), &mut store);
+            }
+            // 5.2 Set range’s end to bp.
+            self.set_boundary_point(false, bp, &mut store);
+        }
+        Ok(())
+    }
+
+    /// Set the start or end of this range to `bp`, and keep the live ranges of the old and new
+    /// nodes in sync.
+    fn set_boundary_point(&self, is_start: bool, bp: BoundaryPoint, mut store: impl AsContextMut) {
+        let range = self.0.data_mut(&mut store);
+        let (point, other) = if is_start {
+            (&mut range.start, range.end.node.clone())
+        } else {
+            (&mut range.end, range.start.node.clone())
+        };
+        let old = std::mem::replace(point, bp.clone()).node;
+        if old.is_same_node(&bp.node, &store) {
+            return;
+        }
+        if !old.is_same_node(&other, &store) {
+            let index = old
+                .data(&store)
+                .live_ranges
+                .iter()
+                .position(|range| range.is_same(self, &store));
+            if let Some(index) = index {
+                old.data_mut(&mut store).live_ranges.remove(index);
+            }
+        }
+        if !bp.node.is_same_node(&other, &store) {
+            bp.node.data_mut(&mut store).live_ranges.push(self.clone());
+        }
+    }
+
+    /// Check if this is the same `Range` as `other`.
+    fn is_same(&self, other: &Range, store: impl AsContext) -> bool {
+        Rooted::ref_eq(&store, &*self.0, &*other.0).unwrap_or_default()
+    }
+
+    /// Set the start offset of this range without moving it to another node.
+    pub(crate) fn set_start_offset(&self, offset: usize, store: impl AsContextMut) {
+        self.0.data_mut(store).start.offset = offset;
+    }
+
+    /// Set the end offset of this range without moving it to another node.
+    pub(crate) fn set_end_offset(&self, offset: usize, store: impl AsContextMut) {
+        self.0.data_mut(store).end.offset = offset;
+    }
+}
+
+/// Implementation of acutal `Range` object.
+#[derive(Clone, Debug)]
+pub struct RangeImpl {
+    /// <https://dom.spec.whatwg.org/#concept-range-start>
+    start: BoundaryPoint,
+    /// <https://dom.spec.whatwg.org/#concept-range-end>
+    end: BoundaryPoint,
+}
+
+/// <https://dom.spec.whatwg.org/#concept-range-bp>
+#[derive(Clone, Debug)]
+pub struct BoundaryPoint {
+    /// <https://dom.spec.whatwg.org/#boundary-point-node>
+    pub node: Node,
+    /// <https://dom.spec.whatwg.org/#concept-range-bp-offset>
+    pub offset: usize,
+}
+
+impl BoundaryPoint {
+    /// <https://dom.spec.whatwg.org/#concept-range-bp-position>
+    ///
+    /// Both boundary points must have the same root.
+    fn position(&self, other: &BoundaryPoint, store: impl AsContext) -> Ordering {
+        // 1. Assert: nodeA and nodeB have the same root.
+        // 2. If nodeA is nodeB, then return equal if offsetA is offsetB, before if offsetA is less
+        // than offsetB, and after if offsetA is greater than offsetB.
+        if self.node.is_same_node(&other.node, &store) {
+            return self.offset.cmp(&other.offset);
+        }
+        // 3. If nodeA is following nodeB, then if the position of (nodeB, offsetB) relative to
+        // (nodeA, offsetA) is before, return after, and if it is after, return before.
+        if tree_order_cmp(&self.node, &other.node, &store) == Some(Ordering::Greater) {
+            return other.position(self, &store).reverse();
+        }
+        // 4. If nodeA is an ancestor of nodeB:
+        if self.node.is_inclusive_ancestor_of(&other.node, &store) {
+            // 4.1 Let child be nodeB.
+            let mut child = other.node.clone();
+            // 4.2 While child is not a child of nodeA, set child to its parent.
+            while let Some(parent) = child.parent_node(&store) {
+                if parent.is_same_node(&self.node, &store) {
+                    break;
+                }
+                child = parent;
+            }
+            // 4.3 If child’s index is less than offsetA, then return after.
+            if child.index(&store) < self.offset {
+                return Ordering::Greater;
+            }
+        }
+        // 5. Return before.
+        Ordering::Less
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        CharacterData,
+        testing::{self, element, text},
+    };
+
+    #[test]
+    fn new_ranges_are_collapsed_at_the_document() {
+        let (_user_agent, mut store, document) = testing::document();
+        let range = document.create_range(&mut store).unwrap();
+        let start = range.start(&store);
+        assert!(start.node.is_same_node(&document.into(), &store));
+        assert_eq!(start.offset, 0);
+        assert!(range.collapsed(&store));
+    }
+
+    #[test]
+    fn setting_a_boundary_point_keeps_start_before_end() {
+        let (_user_agent, mut store, document) = testing::document();
+        let node = text(&document, "abcdef", &mut store);
+        let range = document.create_range(&mut store).unwrap();
+        // The text node is not in the document, so the end moves with the start.
+        range.set_start(&node, 2, &mut store).unwrap();
+        assert!(range.end(&store).node.is_same_node(&node, &store));
+        range.set_end(&node, 4, &mut store).unwrap();
+        assert!(!range.collapsed(&store));
+
+        // A start after the end collapses the range to the new start, and an end before the start
+        // to the new end.
+        range.set_start(&node, 5, &mut store).unwrap();
+        assert_eq!(range.end(&store).offset, 5);
+        range.set_end(&node, 1, &mut store).unwrap();
+        assert_eq!(range.start(&store).offset, 1);
+        assert!(range.collapsed(&store));
+    }
+
+    #[test]
+    fn boundary_points_compare_in_tree_order() {
+        let (_user_agent, mut store, document) = testing::document();
+        let parent = Node::from(element(&document, "div", &mut store));
+        let first = text(&document, "a", &mut store);
+        let second = text(&document, "b", &mut store);
+        parent.pre_insert(first.clone(), None, &mut store).unwrap();
+        parent.pre_insert(second.clone(), None, &mut store).unwrap();
+        let range = document.create_range(&mut store).unwrap();
+        range.set_start(&parent, 1, &mut store).unwrap();
+        range.set_end(&second, 1, &mut store).unwrap();
+        // (parent, 1) is before the second child, but after the first one.
+        range.set_end(&first, 1, &mut store).unwrap();
+        assert!(range.start(&store).node.is_same_node(&first, &store));
+        assert!(range.collapsed(&store));
+    }
+
+    #[test]
+    fn invalid_boundary_points_are_rejected() {
+        let (_user_agent, mut store, document) = testing::document();
+        let doctype = document
+            .create_document_type("html".into(), "".into(), "".into(), &mut store)
+            .unwrap();
+        let range = document.create_range(&mut store).unwrap();
+        assert!(matches!(
+            range.set_start(&doctype.into(), 0, &mut store),
+            Err(DomException::InvalidNodeTypeError(_))
+        ));
+        let node = text(&document, "abc", &mut store);
+        assert!(matches!(
+            range.set_end(&node, 4, &mut store),
+            Err(DomException::IndexSizeError(_))
+        ));
+        assert!(
+            range
+                .start(&store)
+                .node
+                .is_same_node(&document.into(), &store)
+        );
+    }
+
+    #[test]
+    fn ranges_moved_to_another_node_stop_following_the_old_one() {
+        let (_user_agent, mut store, document) = testing::document();
+        let old = text(&document, "abc", &mut store);
+        let new = text(&document, "abc", &mut store);
+        let range = document.create_range(&mut store).unwrap();
+        range.set_start(&old, 2, &mut store).unwrap();
+        range.set_start(&new, 2, &mut store).unwrap();
+        assert!(old.data(&store).live_ranges.is_empty());
+        assert_eq!(new.data(&store).live_ranges.len(), 1);
+
+        CharacterData(old.0.clone())
+            .delete_data(0, 3, &mut store)
+            .unwrap();
+        assert_eq!(range.start(&store).offset, 2);
+        CharacterData(new.0.clone())
+            .delete_data(0, 3, &mut store)
+            .unwrap();
+        assert_eq!(range.start(&store).offset, 0);
+        assert_eq!(range.end(&store).offset, 0);
+    }
+}
@@ -0,0 +1,452 @@
+use wasmtime::{AsContext, AsContextMut};
+
+use crate::{
+    ohim::dom::node::{
+        SanitizerConfig as WitSanitizerConfig, UnknownElements as WitUnknownElements,
+    },
+    string::DOMString,
+};
+
+use super::{Attr, Element, Node};
+
+/// Elements kept by the default configuration.
+const DEFAULT_ELEMENTS: &[&str] = &[
+    "a",
+    "abbr",
+    "address",
+    "article",
+    "aside",
+    "b",
+    "bdi",
+    "bdo",
+    "blockquote",
+    "br",
+    "caption",
+    "cite",
+    "code",
+    "col",
+    "colgroup",
+    "data",
+    "dd",
+    "del",
+    "details",
+    "dfn",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "figcaption",
+    "figure",
+    "footer",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hgroup",
+    "hr",
+    "i",
+    "img",
+    "ins",
+    "kbd",
+    "li",
+    "main",
+    "mark",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "q",
+    "rp",
+    "rt",
+    "ruby",
+    "s",
+    "samp",
+    "section",
+    "small",
+    "span",
+    "strong",
+    "sub",
+    "summary",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "time",
+    "tr",
+    "u",
+    "ul",
+    "var",
+    "wbr",
+];
+
+/// Elements removed together with their children by the default configuration.
+const DEFAULT_REMOVE_ELEMENTS: &[&str] = &[
+    "script", "style", "iframe", "frame", "frameset", "object", "embed", "noscript", "template",
+];
+
+/// Attributes kept on every element by the default configuration.
+const DEFAULT_ATTRIBUTES: &[&str] = &[
+    "class",
+    "dir",
+    "hidden",
+    "id",
+    "lang",
+    "role",
+    "title",
+    "translate",
+];
+
+/// Attributes kept on specific elements by the default configuration.
+const DEFAULT_ELEMENT_ATTRIBUTES: &[(&str, &[&str])] = &[
+    ("a", &["href", "hreflang", "rel", "target"]),
+    ("img", &["alt", "height", "src", "width"]),
+    ("ol", &["reversed", "start", "type"]),
+    ("td", &["colspan", "headers", "rowspan"]),
+    ("th", &["abbr", "colspan", "headers", "rowspan", "scope"]),
+    ("time", &["datetime"]),
+];
+
+/// Attributes holding a URL, whose value is checked for `javascript:` and `data:` URLs.
+const URL_ATTRIBUTES: &[&str] = &[
+    "action",
+    "background",
+    "cite",
+    "codebase",
+    "data",
+    "formaction",
+    "href",
+    "poster",
+    "src",
+    "xlink:href",
+];
+
+/// What to do with an element that is neither kept nor removed by a [`Sanitizer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnknownElements {
+    /// Remove the element together with its children.
+    Drop,
+    /// Replace the element with its sanitized children, keeping its text.
+    ReplaceWithChildren,
+}
+
+/// The configuration of [`Element::set_html`], modeled on the
+/// [HTML Sanitizer API](https://wicg.github.io/sanitizer-api/).
+///
+/// Whatever the configuration, `script` elements, event handler attributes (`on*`), `srcdoc`
+/// attributes and `javascript:` URLs in URL attributes like `href`, `src` or `data` are always
+/// removed. So are `meta` elements with `http-equiv="refresh"`, and SVG `animate` and `set` elements
+/// animating an `href`, since they can navigate to a `javascript:` URL held by another attribute.
+///
+/// The default configuration is safe: it keeps a list of common text-level and grouping elements
+/// with a few harmless attributes, removes `script`, `style`, `iframe`, `object`, `embed` and the
+/// like together with their children, replaces other elements with their children, and removes
+/// `data:` URLs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sanitizer {
+    /// Local names of the elements to keep, or `None` to keep every element that is not removed.
+    pub elements: Option<Vec<DOMString>>,
+    /// Local names of the elements removed together with their children.
+    pub remove_elements: Vec<DOMString>,
+    /// Qualified names of the attributes to keep on every element, or `None` to keep every
+    /// attribute.
+    pub attributes: Option<Vec<DOMString>>,
+    /// Qualified names of the attributes to keep on the element with the given local name, in
+    /// addition to `attributes`.
+    pub element_attributes: Vec<(DOMString, Vec<DOMString>)>,
+    /// What to do with the elements that are not in `elements`.
+    pub unknown_elements: UnknownElements,
+    /// Whether `data:` URLs are kept in the `src` attribute of `img` elements. They are removed
+    /// from every other attribute.
+    pub data_urls: bool,
+}
+
+impl Default for Sanitizer {
+    fn default() -> Self {
+        let names = |names: &[&str]| -> Vec<DOMString> {
+            names.iter().copied().map(DOMString::from).collect()
+        };
+        Self {
+            elements: Some(names(DEFAULT_ELEMENTS)),
+            remove_elements: names(DEFAULT_REMOVE_ELEMENTS),
+            attributes: Some(names(DEFAULT_ATTRIBUTES)),
+            element_attributes: DEFAULT_ELEMENT_ATTRIBUTES
+                .iter()
+                .map(|(element, attributes)| (DOMString::from(*element), names(*attributes)))
+                .collect(),
+            unknown_elements: UnknownElements::ReplaceWithChildren,
+            data_urls: false,
+        }
+    }
+}
+
+impl Sanitizer {
+    /// Remove the disallowed nodes and attributes from `nodes` and their descendants. The nodes
+    /// must not have a parent.
+    ///
+    /// Return the nodes to insert in place of `nodes`.
+    pub(crate) fn sanitize(&self, nodes: Vec<Node>, mut store: impl AsContextMut) -> Vec<Node> {
+        let mut result = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            let Some(element) = node.as_element(&store) else {
+                // Text and comments can't run scripts or load resources.
+                result.push(node);
+                continue;
+            };
+            let local_name = element.local_name(&store);
+            if is_always_removed(&element, &local_name, &store)
+                || self.remove_elements.contains(&local_name)
+            {
+                continue;
+            }
+            let children = node.child_nodes(&store);
+            for child in &children {
+                child.remove(true, &mut store);
+            }
+            let children = self.sanitize(children, &mut store);
+            let kept = self
+                .elements
+                .as_ref()
+                .is_none_or(|elements| elements.contains(&local_name));
+            if kept {
+                self.sanitize_attributes(&element, &local_name, &mut store);
+                for child in children {
+                    node.insert(child, None, true, &mut store)
+                        .expect("inserting before null can't fail");
+                }
+                result.push(node);
+            } else if self.unknown_elements == UnknownElements::ReplaceWithChildren {
+                result.extend(children);
+            }
+        }
+        result
+    }
+
+    /// Remove the disallowed attributes of `element`, whose local name is `local_name`.
+    fn sanitize_attributes(
+        &self,
+        element: &Element,
+        local_name: &str,
+        mut store: impl AsContextMut,
+    ) {
+        let attribute_list = &mut element.data_mut(&mut store).as_element_mut().attribute_list;
+        attribute_list.retain(|attr| self.allows_attribute(local_name, attr));
+    }
+
+    /// Check whether `attr` is kept on an element whose local name is `local_name`.
+    fn allows_attribute(&self, local_name: &str, attr: &Attr) -> bool {
+        let name = attr.name();
+        let event_handler = name
+            .get(..2)
+            .is_some_and(|on| on.eq_ignore_ascii_case("on"));
+        if event_handler || name == "srcdoc" {
+            return false;
+        }
+        let allowed =
+            self.attributes
+                .as_ref()
+                .is_none_or(|attributes| attributes.contains(&name))
+                || self.element_attributes.iter().any(|(element, attributes)| {
+                    element == local_name && attributes.contains(&name)
+                });
+        if !allowed {
+            return false;
+        }
+        if !URL_ATTRIBUTES.contains(&&*name) {
+            return true;
+        }
+        match url_scheme(&attr.value).as_deref() {
+            Some("javascript") => false,
+            Some("data") => self.data_urls && local_name == "img" && name == "src",
+            _ => true,
+        }
+    }
+}
+
+/// Check whether `element`, whose local name is `local_name`, is removed together with its children
+/// whatever the configuration, because it can run a script.
+fn is_always_removed(element: &Element, local_name: &str, store: impl AsContext) -> bool {
+    // The name is matched ASCII case-insensitively, since it is not lowercased on SVG elements.
+    let attribute = |name: &str| {
+        element
+            .data(&store)
+            .as_element()
+            .attribute_list
+            .iter()
+            .find(|attr| attr.name().eq_ignore_ascii_case(name))
+            .map(|attr| attr.value().trim().to_owned())
+    };
+    match local_name {
+        "script" => true,
+        // `<meta http-equiv="refresh" content="0;url=javascript:...">` navigates to its URL.
+        "meta" => {
+            attribute("http-equiv").is_some_and(|value| value.eq_ignore_ascii_case("refresh"))
+        }
+        // `<animate attributeName="href" to="javascript:...">` sets the link of its parent.
+        "animate" | "set" => {
+            attribute("attributeName").is_some_and(|name| matches!(&*name, "href" | "xlink:href"))
+        }
+        _ => false,
+    }
+}
+
+/// Get the scheme of `url` in ASCII lowercase, the way the URL parser reads it: leading C0 control
+/// or space characters are skipped and ASCII tab or newlines are ignored.
+fn url_scheme(url: &str) -> Option<String> {
+    let url = url.trim_start_matches(|c: char| c <= ' ');
+    let mut scheme = String::new();
+    for c in url.chars().filter(|c| !matches!(c, '\t' | '\n' | '\r')) {
+        match c {
+            ':' => return (!scheme.is_empty()).then_some(scheme),
+            c if c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.') => {
+                scheme.push(c.to_ascii_lowercase())
+            }
+            _ => return None,
+        }
+    }
+    None
+}
+
+impl From<WitUnknownElements> for UnknownElements {
+    fn from(value: WitUnknownElements) -> Self {
+        match value {
+            WitUnknownElements::Drop => UnknownElements::Drop,
+            WitUnknownElements::ReplaceWithChildren => UnknownElements::ReplaceWithChildren,
+        }
+    }
+}
+
+impl From<WitSanitizerConfig> for Sanitizer {
+    fn from(value: WitSanitizerConfig) -> Self {
+        let names = |names: Vec<String>| -> Vec<DOMString> {
+            names.into_iter().map(DOMString::from).collect()
+        };
+        Self {
+            elements: value.elements.map(names),
+            remove_elements: names(value.remove_elements),
+            attributes: value.attributes.map(names),
+            element_attributes: value
+                .element_attributes
+                .into_iter()
+                .map(|(element, attributes)| (DOMString::from(element), names(attributes)))
+                .collect(),
+            unknown_elements: value.unknown_elements.into(),
+            data_urls: value.data_urls,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn default_config_removes_scripts_and_handlers() {
+        let (_user_agent, mut store, document) = testing::document();
+        let body = document.body(&store).unwrap();
+        body.set_html(
+            "<p onclick=\"steal()\">a<script>alert(1)</script>\
+             <a href=\" JaVa\tscript:alert(1)\">b</a><a href=\"https://example.com/\">c</a>\
+             <iframe srcdoc=\"<script>alert(1)</script>\"></iframe><style>p {}</style>\
+             <img src=\"x.png\" onerror=\"alert(1)\"><svg><script>alert(1)</script></svg></p>",
+            &Sanitizer::default(),
+            &mut store,
+        )
+        .unwrap();
+        assert_eq!(
+            body.inner_html(&store),
+            "<p>a<a>b</a><a href=\"https://example.com/\">c</a><img src=\"x.png\"></p>"
+        );
+        // Nothing that could run a script or load a document reached the tree.
+        let found = document
+            .query_selector_all("script, style, iframe, [onclick], [onerror]", &store)
+            .unwrap();
+        assert!(found.is_empty());
+    }
+
+    #[test]
+    fn data_urls_are_only_kept_in_img_src() {
+        let (_user_agent, mut store, document) = testing::document();
+        let body = document.body(&store).unwrap();
+        let markup = "<img src=\"data:image/png;base64,AA\"><a href=\"data:text/html,x\">d</a>";
+        body.set_html(markup, &Sanitizer::default(), &mut store)
+            .unwrap();
+        assert_eq!(body.inner_html(&store), "<img><a>d</a>");
+
+        let sanitizer = Sanitizer {
+            data_urls: true,
+            ..Sanitizer::default()
+        };
+        body.set_html(markup, &sanitizer, &mut store).unwrap();
+        assert_eq!(
+            body.inner_html(&store),
+            "<img src=\"data:image/png;base64,AA\"><a>d</a>"
+        );
+    }
+
+    #[test]
+    fn unknown_elements() {
+        let (_user_agent, mut store, document) = testing::document();
+        let body = document.body(&store).unwrap();
+        let markup = "<my-element mode=\"on\" onclick=\"x()\">t<b>u</b></my-element>";
+        body.set_html(markup, &Sanitizer::default(), &mut store)
+            .unwrap();
+        assert_eq!(body.inner_html(&store), "t<b>u</b>");
+
+        let sanitizer = Sanitizer {
+            unknown_elements: UnknownElements::Drop,
+            ..Sanitizer::default()
+        };
+        body.set_html(markup, &sanitizer, &mut store).unwrap();
+        assert_eq!(body.inner_html(&store), "");
+
+        let permissive = Sanitizer {
+            elements: None,
+            attributes: None,
+            ..Sanitizer::default()
+        };
+        body.set_html(
+            &format!("{markup}<script></script>"),
+            &permissive,
+            &mut store,
+        )
+        .unwrap();
+        assert_eq!(
+            body.inner_html(&store),
+            "<my-element mode=\"on\">t<b>u</b></my-element>"
+        );
+    }
+
+    #[test]
+    fn javascript_urls_are_removed_whatever_the_config() {
+        let (_user_agent, mut store, document) = testing::document();
+        let body = document.body(&store).unwrap();
+        let permissive = Sanitizer {
+            elements: None,
+            remove_elements: Vec::new(),
+            attributes: None,
+            ..Sanitizer::default()
+        };
+        body.set_html(
+            "<my-element mode=\"on\"><object data=\"javascript:alert(1)\"></object>\
+             <meta http-equiv=\"Refresh\" content=\"0;url=javascript:alert(1)\">\
+             <svg><a><animate attributeName=\"href\" to=\"javascript:alert(1)\"></animate>\
+             <set attributeName=\"xlink:href\" to=\"javascript:alert(1)\"></set>\
+             <animate attributeName=\"width\" to=\"10\"></animate></a></svg></my-element>",
+            &permissive,
+            &mut store,
+        )
+        .unwrap();
+        assert_eq!(
+            body.inner_html(&store),
+            "<my-element mode=\"on\"><object></object><svg><a>\
+             <animate attributeName=\"width\" to=\"10\"></animate></a></svg></my-element>"
+        );
+    }
+}
@@ -0,0 +1,504 @@
+//! A subset of CSS selectors for `querySelector`, `querySelectorAll` and `matches`.
+//!
+//! The supported selectors are:
+//!
+//! - Type selectors such as `div`, and the universal selector `*`.
+//! - ID selectors such as `#main` and class selectors such as `.item`.
+//! - Attribute selectors `[attr]` and `[attr=value]`. The value is an identifier or a string quoted
+//!   with `'` or `"`.
+//! - The descendant combinator (whitespace) and the child combinator `>`.
+//! - Selector lists separated by `,`.
+//!
+//! Anything else, such as pseudo-classes, other combinators or escapes, fails to parse with a
+//! "SyntaxError" [`DomException`].
+
+use std::str::FromStr;
+
+use wasmtime::AsContext;
+
+use crate::{DomException, Element, Node};
+
+/// <https://drafts.csswg.org/selectors-4/#selector-list>
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SelectorList(Vec<ComplexSelector>);
+
+impl SelectorList {
+    /// <https://drafts.csswg.org/selectors-4/#match-a-selector-against-an-element>
+    pub fn matches(&self, element: &Element, store: impl AsContext) -> bool {
+        // A selector list matches an element if any of its complex selectors matches it.
+        self.0
+            .iter()
+            .any(|selector| selector.matches(element, &store))
+    }
+}
+
+impl FromStr for SelectorList {
+    type Err = DomException;
+
+    /// <https://drafts.csswg.org/selectors-4/#parse-a-selector>
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Parser {
+            input: s,
+            position: 0,
+        }
+        .parse()
+    }
+}
+
+/// <https://drafts.csswg.org/selectors-4/#complex>
+///
+/// `combinators[i]` joins `compounds[i]` and `compounds[i + 1]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct ComplexSelector {
+    compounds: Vec<CompoundSelector>,
+    combinators: Vec<Combinator>,
+}
+
+impl ComplexSelector {
+    fn matches(&self, element: &Element, store: impl AsContext) -> bool {
+        // Complex selectors are matched from right to left.
+        self.matches_at(self.compounds.len() - 1, element, &store) == MatchResult::Matched
+    }
+
+    /// Check if the compound selectors up to `index` match with `element` as the subject of
+    /// `compounds[index]`.
+    ///
+    /// A descendant combinator tries every ancestor, which could take exponential time with several
+    /// of them. Once the ancestors run out, no higher element can match either, so the failure is
+    /// returned as [`MatchResult::NotMatchedGlobally`] and stops all the searches below it.
+    fn matches_at(&self, index: usize, element: &Element, store: impl AsContext) -> MatchResult {
+        if !self.compounds[index].matches(element, &store) {
+            return MatchResult::NotMatched;
+        }
+        let Some(previous) = index.checked_sub(1) else {
+            return MatchResult::Matched;
+        };
+        let combinator = self.combinators[previous];
+        let mut ancestor = parent_element(element, &store);
+        while let Some(current) = ancestor {
+            match self.matches_at(previous, &current, &store) {
+                MatchResult::NotMatched if combinator == Combinator::Descendant => {}
+                result => return result,
+            }
+            ancestor = parent_element(&current, &store);
+        }
+        match combinator {
+            Combinator::Descendant => MatchResult::NotMatchedGlobally,
+            // A descendant combinator further right may still find another ancestor.
+            Combinator::Child => MatchResult::NotMatched,
+        }
+    }
+}
+
+/// The result of matching a part of a [`ComplexSelector`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MatchResult {
+    Matched,
+    /// The selector does not match, but it may match with another element as the subject.
+    NotMatched,
+    /// The selector does not match with this element or any of its ancestors as the subject.
+    NotMatchedGlobally,
+}
+
+/// <https://drafts.csswg.org/selectors-4/#selector-combinator>
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Combinator {
+    /// Whitespace
+    Descendant,
+    /// `>`
+    Child,
+}
+
+/// <https://drafts.csswg.org/selectors-4/#compound>
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct CompoundSelector {
+    /// The type selector, or `None` for the universal selector or no type selector at all.
+    type_name: Option<String>,
+    subclasses: Vec<SubclassSelector>,
+}
+
+impl CompoundSelector {
+    fn matches(&self, element: &Element, store: impl AsContext) -> bool {
+        // Type selectors are ASCII case-insensitive for HTML elements in an HTML document.
+        let type_matches = self.type_name.as_ref().is_none_or(|name| {
+            let local_name = element.local_name(&store);
+            if element.is_html_element_in_html_document(&store) {
+                local_name.eq_ignore_ascii_case(name)
+            } else {
+                local_name == name.as_str()
+            }
+        });
+        type_matches
+            && self
+                .subclasses
+                .iter()
+                .all(|subclass| subclass.matches(element, &store))
+    }
+}
+
+/// <https://drafts.csswg.org/selectors-4/#typedef-subclass-selector>
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum SubclassSelector {
+    /// `#id`
+    Id(String),
+    /// `.class`
+    Class(String),
+    /// `[name]` or `[name=value]`
+    Attribute { name: String, value: Option<String> },
+}
+
+impl SubclassSelector {
+    fn matches(&self, element: &Element, store: impl AsContext) -> bool {
+        match self {
+            SubclassSelector::Id(id) => element.get_attribute_value("id", &store) == id.as_str(),
+            SubclassSelector::Class(class) => element
+                .get_attribute_value("class", &store)
+                .split_ascii_whitespace()
+                .any(|token| token == class),
+            SubclassSelector::Attribute { name, value } => element
+                .get_attribute(name, &store)
+                .is_some_and(|v| value.as_ref().is_none_or(|value| v == value.as_str())),
+        }
+    }
+}
+
+/// Get the parent of `element` if it is an element.
+fn parent_element(element: &Element, store: impl AsContext) -> Option<Element> {
+    Node::from(element.clone())
+        .parent_node(&store)
+        .filter(|parent| parent.data(&store).is_element())
+        .map(|parent| Element(parent.0))
+}
+
+/// Parser of [`SelectorList`]. `position` is the byte offset of the next character.
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn parse(mut self) -> Result<SelectorList, DomException> {
+        let mut selectors = vec![self.complex()?];
+        while self.eat(',') {
+            selectors.push(self.complex()?);
+        }
+        match self.peek() {
+            None => Ok(SelectorList(selectors)),
+            Some(c) => Err(self.error(format!("unexpected '{c}'"))),
+        }
+    }
+
+    fn complex(&mut self) -> Result<ComplexSelector, DomException> {
+        self.skip_whitespace();
+        let mut compounds = vec![self.compound()?];
+        let mut combinators = Vec::new();
+        loop {
+            let has_whitespace = self.skip_whitespace();
+            let combinator = match self.peek() {
+                Some('>') => {
+                    self.position += 1;
+                    self.skip_whitespace();
+                    Combinator::Child
+                }
+                None | Some(',') => break,
+                Some(_) if has_whitespace => Combinator::Descendant,
+                Some(c) => return Err(self.error(format!("unexpected '{c}'"))),
+            };
+            combinators.push(combinator);
+            compounds.push(self.compound()?);
+        }
+        Ok(ComplexSelector {
+            compounds,
+            combinators,
+        })
+    }
+
+    fn compound(&mut self) -> Result<CompoundSelector, DomException> {
+        let start = self.position;
+        let type_name = if self.eat('*') {
+            None
+        } else if self.peek().is_some_and(is_name_char) {
+            Some(self.ident()?)
+        } else {
+            None
+        };
+        let mut subclasses = Vec::new();
+        loop {
+            if self.eat('#') {
+                subclasses.push(SubclassSelector::Id(self.ident()?));
+            } else if self.eat('.') {
+                subclasses.push(SubclassSelector::Class(self.ident()?));
+            } else if self.eat('[') {
+                subclasses.push(self.attribute()?);
+            } else {
+                break;
+            }
+        }
+        if self.position == start {
+            return Err(self.error("expected a selector"));
+        }
+        Ok(CompoundSelector {
+            type_name,
+            subclasses,
+        })
+    }
+
+    fn attribute(&mut self) -> Result<SubclassSelector, DomException> {
+        self.skip_whitespace();
+        let name = self.ident()?;
+        self.skip_whitespace();
+        let value = if self.eat('=') {
+            self.skip_whitespace();
+            let value = match self.peek() {
+                Some('\'' | '"') => self.quoted()?,
+                _ => self.ident()?,
+            };
+            self.skip_whitespace();
+            Some(value)
+        } else {
+            None
+        };
+        self.expect(']')?;
+        Ok(SubclassSelector::Attribute { name, value })
+    }
+
+    /// <https://drafts.csswg.org/css-syntax-3/#ident-token-diagram>
+    ///
+    /// TODO: Escapes.
+    fn ident(&mut self) -> Result<String, DomException> {
+        let start = self.position;
+        let ident = self.take_while(is_name_char);
+        let rest = ident.strip_prefix('-').unwrap_or(ident);
+        if ident.is_empty() || ident == "-" || rest.starts_with(|c: char| c.is_ascii_digit()) {
+            self.position = start;
+            return Err(self.error("expected an identifier"));
+        }
+        Ok(ident.to_owned())
+    }
+
+    fn quoted(&mut self) -> Result<String, DomException> {
+        let start = self.position;
+        let Some(quote) = self.peek() else {
+            return Err(self.error("expected a quoted value"));
+        };
+        self.position += 1;
+        let value = self.take_while(|c| c != quote);
+        if !self.eat(quote) {
+            self.position = start;
+            return Err(self.error("unterminated string"));
+        }
+        Ok(value.to_owned())
+    }
+
+    /// Skip ASCII whitespace and return whether there was any.
+    fn skip_whitespace(&mut self) -> bool {
+        !self.take_while(|c| c.is_ascii_whitespace()).is_empty()
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let input: &'a str = self.input;
+        let rest = &input[self.position..];
+        let len = rest.find(|c| !f(c)).unwrap_or(rest.len());
+        self.position += len;
+        &rest[..len]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.position += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), DomException> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected '{c}'")))
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> DomException {
+        DomException::SyntaxError(format!(
+            "{} at position {} of selector \"{}\"",
+            message.into(),
+            self.position,
+            self.input
+        ))
+    }
+}
+
+/// <https://drafts.csswg.org/css-syntax-3/#ident-code-point>
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '_') || !c.is_ascii()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, element};
+
+    fn parse(selectors: &str) -> Result<SelectorList, DomException> {
+        selectors.parse()
+    }
+
+    #[test]
+    fn parse_selectors() {
+        let compound = |type_name: Option<&str>, subclasses| CompoundSelector {
+            type_name: type_name.map(String::from),
+            subclasses,
+        };
+        assert_eq!(
+            parse(" div > p.a#b[c = 'd' ] [e=f], * ").unwrap(),
+            SelectorList(vec![
+                ComplexSelector {
+                    compounds: vec![
+                        compound(Some("div"), vec![]),
+                        compound(
+                            Some("p"),
+                            vec![
+                                SubclassSelector::Class("a".into()),
+                                SubclassSelector::Id("b".into()),
+                                SubclassSelector::Attribute {
+                                    name: "c".into(),
+                                    value: Some("d".into()),
+                                },
+                            ],
+                        ),
+                        compound(
+                            None,
+                            vec![SubclassSelector::Attribute {
+                                name: "e".into(),
+                                value: Some("f".into()),
+                            }],
+                        ),
+                    ],
+                    combinators: vec![Combinator::Child, Combinator::Descendant],
+                },
+                ComplexSelector {
+                    compounds: vec![compound(None, vec![])],
+                    combinators: vec![],
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_errors_are_syntax_errors() {
+        for selectors in [
+            // expected a selector
+            "",
+            " ",
+            "div,",
+            ",div",
+            "div >",
+            "div > > p",
+            "div ~ p",
+            "div :hover",
+            // unexpected character
+            "div:hover",
+            "div+p",
+            "div)",
+            // expected an identifier
+            "#",
+            ".",
+            ".1a",
+            ".-2",
+            "-",
+            "[",
+            "[=a]",
+            "[a=]",
+            "[a=1]",
+            // unterminated string
+            "[a='b]",
+            "[a=\"b']",
+            // expected ']'
+            "[a b]",
+            "[a='b' c]",
+            "[a",
+        ] {
+            assert!(
+                matches!(parse(selectors), Err(DomException::SyntaxError(_))),
+                "{selectors:?}"
+            );
+        }
+        let Err(DomException::SyntaxError(message)) = parse("div:hover") else {
+            unreachable!()
+        };
+        assert_eq!(
+            message,
+            "unexpected ':' at position 3 of selector \"div:hover\""
+        );
+    }
+
+    #[test]
+    fn match_selectors() {
+        let (_user_agent, mut store, document) = testing::document();
+        let body = document.body(&store).unwrap();
+        body.set_inner_html(
+            "<div id=a class='x y'><p data-k=v><span class=x></span></p></div><SPAN></SPAN>",
+            &mut store,
+        )
+        .unwrap();
+        let count = |selectors: &str| body.query_selector_all(selectors, &store).unwrap().len();
+        assert_eq!(count("span"), 2);
+        assert_eq!(count("SPAN"), 2);
+        assert_eq!(count("*"), 4);
+        assert_eq!(count("#a"), 1);
+        assert_eq!(count(".x"), 2);
+        assert_eq!(count(".x.y"), 1);
+        assert_eq!(count(".z"), 0);
+        assert_eq!(count("[data-k]"), 1);
+        assert_eq!(count("[data-k=v]"), 1);
+        assert_eq!(count("[data-k='w']"), 0);
+        assert_eq!(count("div span"), 1);
+        assert_eq!(count("div > span"), 0);
+        assert_eq!(count("div > p > span"), 1);
+        assert_eq!(count("body > span"), 1);
+        assert_eq!(count("html div p span.x"), 1);
+        assert_eq!(count("p div"), 0);
+        assert_eq!(count("#a, span"), 3);
+
+        let span = body.query_selector_all("span.x", &store).unwrap().remove(0);
+        assert!(span.matches("div span", &store).unwrap());
+        assert!(!span.matches("div > span", &store).unwrap());
+    }
+
+    #[test]
+    fn child_combinator_retries_other_ancestors() {
+        let (_user_agent, mut store, document) = testing::document();
+        let body = document.body(&store).unwrap();
+        // The closest span is not a child of the div, but the other one is.
+        body.set_inner_html(
+            "<div><span><b><span><em></em></span></b></span></div>",
+            &mut store,
+        )
+        .unwrap();
+        let em = body.query_selector_all("em", &store).unwrap().remove(0);
+        assert!(em.matches("div > span em", &store).unwrap());
+        assert!(em.matches("div > span > b em", &store).unwrap());
+        assert!(!em.matches("div > b em", &store).unwrap());
+    }
+
+    #[test]
+    fn descendant_combinators_fail_fast() {
+        let (_user_agent, mut store, document) = testing::document();
+        let mut parent = element(&document, "div", &mut store);
+        for _ in 0..200 {
+            let child = element(&document, "div", &mut store);
+            Node::from(parent).append_child(child.clone().into(), &mut store);
+            parent = child;
+        }
+        // Without giving up once the ancestors run out, this tries every way to pick 12 of the 200
+        // ancestors.
+        let selectors = format!("a{}", " div".repeat(12));
+        assert!(!parent.matches(&selectors, &store).unwrap());
+        assert!(parent.matches(&" div".repeat(12), &store).unwrap());
+    }
+}
@@ -0,0 +1,251 @@
+use std::ops::Deref;
+
+use malloc_size_of_derive::MallocSizeOf;
+use wasmtime::{AsContext, AsContextMut, Error, ExternRef, Result, Rooted, component::Resource};
+
+use crate::{
+    DomException, NodeImpl, NodeTypeData, Object, WindowStates,
+    ohim::dom::node::{
+        DomException as WitDomException, HostShadowRoot, NodeOrString as WitNodeOrString,
+        ShadowRootInit as WitShadowRootInit, ShadowRootMode as WitShadowRootMode,
+        SlotAssignmentMode as WitSlotAssignmentMode,
+    },
+};
+
+use super::{Element, Node, NodeOrString};
+
+/// <https://dom.spec.whatwg.org/#shadowroot>
+#[derive(Clone, Debug)]
+pub struct ShadowRoot(pub(crate) Object<NodeImpl>);
+
+impl ShadowRoot {
+    /// Create a new `ShadowRoot` node whose host is `host`, with the same node document as `host`.
+    pub(crate) fn new(
+        host: &Element,
+        mode: ShadowRootMode,
+        delegates_focus: bool,
+        slot_assignment: SlotAssignmentMode,
+        mut store: impl AsContextMut,
+    ) -> Result<Self> {
+        let document = host.data(&store).node_document().cloned();
+        let shadow = ShadowRoot(Object::new(
+            &mut store,
+            NodeImpl::new_with_type(NodeTypeData::ShadowRoot(ShadowRootImpl {
+                mode,
+                host: host.clone(),
+                delegates_focus,
+                slot_assignment,
+            })),
+        )?);
+        shadow.data_mut(&mut store).set_node_document(document);
+        Ok(shadow)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-shadowroot-mode>
+    pub fn mode(&self, store: impl AsContext) -> ShadowRootMode {
+        self.data(&store).as_shadow_root().mode
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-shadowroot-host>
+    pub fn host(&self, store: impl AsContext) -> Element {
+        self.data(&store).as_shadow_root().host.clone()
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-shadowroot-delegatesfocus>
+    pub fn delegates_focus(&self, store: impl AsContext) -> bool {
+        self.data(&store).as_shadow_root().delegates_focus
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-shadowroot-slotassignment>
+    pub fn slot_assignment(&self, store: impl AsContext) -> SlotAssignmentMode {
+        self.data(&store).as_shadow_root().slot_assignment
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-parentnode-append>
+    pub fn append(
+        &self,
+        nodes: Vec<NodeOrString>,
+        store: impl AsContextMut,
+    ) -> Result<(), DomException> {
+        Node::from(self.clone()).append(nodes, store)
+    }
+
+    /// Get `Rooted<ExternRef>` reference of the `Node`.
+    pub fn as_root(&self) -> &Rooted<ExternRef> {
+        self
+    }
+}
+
+impl NodeImpl {
+    /// Get `ShadowRootImpl` shared reference, or `None` if the node is not a `ShadowRoot`.
+    pub fn try_as_shadow_root(&self) -> Option<&ShadowRootImpl> {
+        match self.data {
+            NodeTypeData::ShadowRoot(ref shadow) => Some(shadow),
+            _ => None,
+        }
+    }
+
+    /// Get `ShadowRootImpl` shared reference.
+    fn as_shadow_root(&self) -> &ShadowRootImpl {
+        self.try_as_shadow_root().expect("node is not a ShadowRoot")
+    }
+}
+
+impl Deref for ShadowRoot {
+    type Target = Object<NodeImpl>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<ShadowRoot> for Node {
+    fn from(value: ShadowRoot) -> Self {
+        Self(value.0)
+    }
+}
+
+/// Implementation of acutal `ShadowRoot` object. This can be accessed from `NodeImpl`.
+///
+/// TODO: clonable, serializable and declarative.
+#[derive(Debug, MallocSizeOf)]
+pub struct ShadowRootImpl {
+    /// <https://dom.spec.whatwg.org/#shadowroot-mode>
+    pub(crate) mode: ShadowRootMode,
+    /// <https://dom.spec.whatwg.org/#concept-documentfragment-host>
+    #[ignore_malloc_size_of = "owned by the GC heap"]
+    pub(crate) host: Element,
+    /// <https://dom.spec.whatwg.org/#shadowroot-delegates-focus>
+    pub(crate) delegates_focus: bool,
+    /// <https://dom.spec.whatwg.org/#shadowroot-slot-assignment>
+    pub(crate) slot_assignment: SlotAssignmentMode,
+}
+
+/// <https://dom.spec.whatwg.org/#enumdef-shadowrootmode>
+#[derive(Clone, Copy, Debug, MallocSizeOf, PartialEq, Eq)]
+pub enum ShadowRootMode {
+    /// "open"
+    Open,
+    /// "closed"
+    Closed,
+}
+
+/// <https://dom.spec.whatwg.org/#enumdef-slotassignmentmode>
+#[derive(Clone, Copy, Debug, Default, MallocSizeOf, PartialEq, Eq)]
+pub enum SlotAssignmentMode {
+    /// "named"
+    #[default]
+    Named,
+    /// "manual"
+    Manual,
+}
+
+/// <https://dom.spec.whatwg.org/#dictdef-shadowrootinit>
+#[derive(Clone, Copy, Debug)]
+pub struct ShadowRootInit {
+    /// `mode`
+    pub mode: ShadowRootMode,
+    /// `delegatesFocus`
+    pub delegates_focus: bool,
+    /// `slotAssignment`
+    pub slot_assignment: SlotAssignmentMode,
+    // TODO: clonable, serializable, customElementRegistry
+}
+
+impl From<WitShadowRootMode> for ShadowRootMode {
+    fn from(value: WitShadowRootMode) -> Self {
+        match value {
+            WitShadowRootMode::Open => ShadowRootMode::Open,
+            WitShadowRootMode::Closed => ShadowRootMode::Closed,
+        }
+    }
+}
+
+impl From<ShadowRootMode> for WitShadowRootMode {
+    fn from(value: ShadowRootMode) -> Self {
+        match value {
+            ShadowRootMode::Open => WitShadowRootMode::Open,
+            ShadowRootMode::Closed => WitShadowRootMode::Closed,
+        }
+    }
+}
+
+impl From<WitShadowRootInit> for ShadowRootInit {
+    fn from(value: WitShadowRootInit) -> Self {
+        ShadowRootInit {
+            mode: value.mode.into(),
+            delegates_focus: value.delegates_focus,
+            slot_assignment: value.slot_assignment.into(),
+        }
+    }
+}
+
+impl From<WitSlotAssignmentMode> for SlotAssignmentMode {
+    fn from(value: WitSlotAssignmentMode) -> Self {
+        match value {
+            WitSlotAssignmentMode::Named => SlotAssignmentMode::Named,
+            WitSlotAssignmentMode::Manual => SlotAssignmentMode::Manual,
+        }
+    }
+}
+
+impl From<SlotAssignmentMode> for WitSlotAssignmentMode {
+    fn from(value: SlotAssignmentMode) -> Self {
+        match value {
+            SlotAssignmentMode::Named => WitSlotAssignmentMode::Named,
+            SlotAssignmentMode::Manual => WitSlotAssignmentMode::Manual,
+        }
+    }
+}
+
+impl WindowStates {
+    /// Get the `ShadowRoot` of `resource`.
+    ///
+    /// This returns an error instead of panicking if the object is not a `ShadowRoot`.
+    fn get_shadow_root(&self, resource: &Resource<ShadowRoot>) -> Result<ShadowRoot> {
+        let shadow = self.table.get(resource)?;
+        shadow
+            .try_data(&self.store)?
+            .try_as_shadow_root()
+            .ok_or_else(|| Error::msg("object is not a ShadowRoot"))?;
+        Ok(shadow.clone())
+    }
+}
+
+impl HostShadowRoot for WindowStates {
+    fn mode(&mut self, self_: Resource<ShadowRoot>) -> Result<WitShadowRootMode> {
+        let self_ = self.get_shadow_root(&self_)?;
+        Ok(self_.mode(&self.store).into())
+    }
+
+    fn host(&mut self, self_: Resource<ShadowRoot>) -> Result<Resource<Element>> {
+        let self_ = self.get_shadow_root(&self_)?;
+        let host = self_.host(&self.store);
+        Ok(self.push_resource(host)?)
+    }
+
+    fn delegates_focus(&mut self, self_: Resource<ShadowRoot>) -> Result<bool> {
+        let self_ = self.get_shadow_root(&self_)?;
+        Ok(self_.delegates_focus(&self.store))
+    }
+
+    fn slot_assignment(&mut self, self_: Resource<ShadowRoot>) -> Result<WitSlotAssignmentMode> {
+        let self_ = self.get_shadow_root(&self_)?;
+        Ok(self_.slot_assignment(&self.store).into())
+    }
+
+    fn append(
+        &mut self,
+        self_: Resource<ShadowRoot>,
+        nodes: Vec<WitNodeOrString>,
+    ) -> Result<Result<(), WitDomException>> {
+        let nodes = self.get_nodes_or_strings(nodes)?;
+        let self_ = self.get_shadow_root(&self_)?;
+        Ok(self_.append(nodes, &mut self.store).map_err(Into::into))
+    }
+
+    fn drop(&mut self, rep: Resource<ShadowRoot>) -> Result<()> {
+        self.delete_resource(rep)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,280 @@
+use std::ops::Deref;
+
+use malloc_size_of_derive::MallocSizeOf;
+use wasmtime::{AsContext, AsContextMut, Error, ExternRef, Result, Rooted, component::Resource};
+
+use crate::{
+    DomException, NodeImpl, NodeTypeData, Object, WindowStates,
+    ohim::dom::node::{DomException as WitDomException, HostText},
+    string::DOMString,
+};
+
+use super::{
+    CharacterData, Document, Element, HTMLSlotElement, Node, html_slot_element::find_slot,
+};
+
+/// <https://dom.spec.whatwg.org/#text>
+#[derive(Clone, Debug)]
+pub struct Text(pub(crate) Object<NodeImpl>);
+
+impl Text {
+    /// Create a new `Text` node whose data is `data` and node document is `document`.
+    ///
+    /// <https://dom.spec.whatwg.org/#dom-text-text>
+    pub fn new(document: &Document, data: DOMString, mut store: impl AsContextMut) -> Result<Self> {
+        let text = Text(Object::new(
+            &mut store,
+            NodeImpl::new_with_type(NodeTypeData::Text(TextImpl { data })),
+        )?);
+        text.data_mut(&mut store)
+            .set_node_document(Some(document.clone()));
+        Ok(text)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-characterdata-data>
+    pub fn data(&self, store: impl AsContext) -> DOMString {
+        CharacterData::from(self.clone()).data(store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-characterdata-data>
+    pub fn set_data(&self, data: &str, store: impl AsContextMut) {
+        CharacterData::from(self.clone()).set_data(data, store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-slotable-assignedslot>
+    pub fn assigned_slot(&self, store: impl AsContext) -> Option<HTMLSlotElement> {
+        // The assignedSlot getter steps are to return the result of find a slot given this and
+        // true.
+        find_slot(&self.clone().into(), true, store)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-text-splittext>
+    pub fn split_text(
+        &self,
+        offset: usize,
+        mut store: impl AsContextMut,
+    ) -> Result<Text, DomException> {
+        let character_data = CharacterData::from(self.clone());
+        // The splitText(offset) method steps are to split this with offset offset.
+        // 1. Let length be node’s length.
+        let length = character_data.length(&store);
+        // 2. If offset is greater than length, then throw an "IndexSizeError" DOMException.
+        if offset > length {
+            return Err(DomException::IndexSizeError(format!(
+                "offset {offset} is greater than the length {length}"
+            )));
+        }
+        // 3. Let count be length minus offset.
+        let count = length - offset;
+        // 4. Let new data be the result of substringing data with node node, offset offset, and
+        // count count.
+        let new_data = character_data.substring_data(offset, count, &store)?;
+        // 5. Let new node be a new Text node, with the same node document as node. Set new node’s
+        // data to new data.
+        let document = self
+            .0
+            .data(&store)
+            .node_document()
+            .cloned()
+            .expect("text node has a node document");
+        let new_node = Text::new(&document, new_data, &mut store).expect("Failed to create text");
+        // 6. Let parent be node’s parent.
+        let node = Node::from(self.clone());
+        // 7. If parent is not null:
+        if let Some(parent) = node.parent_node(&store) {
+            // 7.1 Insert new node into parent before node’s next sibling.
+            let next_sibling = node.next_sibling(&store);
+            parent.insert(
+                new_node.clone().into(),
+                next_sibling.as_ref(),
+                false,
+                &mut store,
+            )?;
+            // 7.2 ~ 7.5 TODO: Update live ranges.
+        }
+        // 8. Replace data with node node, offset offset, count count, and data the empty string.
+        character_data.replace_data(offset, count, "", &mut store)?;
+        // 9. Return new node.
+        Ok(new_node)
+    }
+
+    /// Get `Rooted<ExternRef>` reference of the `Node`.
+    pub fn as_root(&self) -> &Rooted<ExternRef> {
+        self
+    }
+}
+
+impl Deref for Text {
+    type Target = Object<NodeImpl>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<Text> for Node {
+    fn from(value: Text) -> Self {
+        Self(value.0)
+    }
+}
+
+/// Implementation of acutal `Text` object. This can be accessed from `NodeImpl`.
+#[derive(Debug, MallocSizeOf)]
+pub struct TextImpl {
+    /// <https://dom.spec.whatwg.org/#concept-cd-data>
+    pub(crate) data: DOMString,
+}
+
+impl WindowStates {
+    /// Get the `Text` of `resource`.
+    ///
+    /// This returns an error instead of panicking if the object is not a `Text`.
+    fn get_text(&self, resource: &Resource<Text>) -> Result<Text> {
+        let text = self.table.get(resource)?;
+        if !text.try_data(&self.store)?.is_text() {
+            return Err(Error::msg("object is not a Text"));
+        }
+        Ok(text.clone())
+    }
+}
+
+impl HostText for WindowStates {
+    fn data(&mut self, self_: Resource<Text>) -> Result<String> {
+        let self_ = self.get_text(&self_)?;
+        Ok(self_.data(&self.store).into())
+    }
+
+    fn set_data(&mut self, self_: Resource<Text>, data: String) -> Result<()> {
+        let self_ = self.get_text(&self_)?;
+        self_.set_data(&data, &mut self.store);
+        Ok(())
+    }
+
+    fn assigned_slot(&mut self, self_: Resource<Text>) -> Result<Option<Resource<Element>>> {
+        let self_ = self.get_text(&self_)?;
+        match self_.assigned_slot(&self.store) {
+            Some(slot) => Ok(Some(self.push_resource(Element::from(slot))?)),
+            None => Ok(None),
+        }
+    }
+
+    fn length(&mut self, self_: Resource<Text>) -> Result<u32> {
+        let self_ = CharacterData::from(self.get_text(&self_)?);
+        Ok(self_.length(&self.store) as u32)
+    }
+
+    fn substring_data(
+        &mut self,
+        self_: Resource<Text>,
+        offset: u32,
+        count: u32,
+    ) -> Result<Result<String, WitDomException>> {
+        let self_ = CharacterData::from(self.get_text(&self_)?);
+        Ok(self_
+            .substring_data(offset as usize, count as usize, &self.store)
+            .map(Into::into)
+            .map_err(Into::into))
+    }
+
+    fn append_data(&mut self, self_: Resource<Text>, data: String) -> Result<()> {
+        let self_ = CharacterData::from(self.get_text(&self_)?);
+        self_.append_data(&data, &mut self.store);
+        Ok(())
+    }
+
+    fn insert_data(
+        &mut self,
+        self_: Resource<Text>,
+        offset: u32,
+        data: String,
+    ) -> Result<Result<(), WitDomException>> {
+        let self_ = CharacterData::from(self.get_text(&self_)?);
+        Ok(self_
+            .insert_data(offset as usize, &data, &mut self.store)
+            .map_err(Into::into))
+    }
+
+    fn delete_data(
+        &mut self,
+        self_: Resource<Text>,
+        offset: u32,
+        count: u32,
+    ) -> Result<Result<(), WitDomException>> {
+        let self_ = CharacterData::from(self.get_text(&self_)?);
+        Ok(self_
+            .delete_data(offset as usize, count as usize, &mut self.store)
+            .map_err(Into::into))
+    }
+
+    fn replace_data(
+        &mut self,
+        self_: Resource<Text>,
+        offset: u32,
+        count: u32,
+        data: String,
+    ) -> Result<Result<(), WitDomException>> {
+        let self_ = CharacterData::from(self.get_text(&self_)?);
+        Ok(self_
+            .replace_data(offset as usize, count as usize, &data, &mut self.store)
+            .map_err(Into::into))
+    }
+
+    fn split_text(
+        &mut self,
+        self_: Resource<Text>,
+        offset: u32,
+    ) -> Result<Result<Resource<Text>, WitDomException>> {
+        let self_ = self.get_text(&self_)?;
+        match self_.split_text(offset as usize, &mut self.store) {
+            Ok(text) => Ok(Ok(self.push_resource(text)?)),
+            Err(e) => Ok(Err(e.into())),
+        }
+    }
+
+    fn drop(&mut self, rep: Resource<Text>) -> Result<()> {
+        self.delete_resource(rep)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, element};
+
+    #[test]
+    fn split_text_inserts_the_rest_after_this() {
+        let (_user_agent, mut store, document) = testing::document();
+        let parent = Node::from(element(&document, "p", &mut store));
+        let text = document
+            .create_text_node("hello".into(), &mut store)
+            .unwrap();
+        parent.append_child(text.clone().into(), &mut store);
+
+        let rest = text.split_text(2, &mut store).unwrap();
+        assert_eq!(text.data(&store), "he");
+        assert_eq!(rest.data(&store), "llo");
+        let children = parent.child_nodes(&store);
+        assert_eq!(children.len(), 2);
+        assert!(children[1].is_same_node(&rest.into(), &store));
+    }
+
+    #[test]
+    fn split_text_checks_the_offset() {
+        let (_user_agent, mut store, document) = testing::document();
+        let text = document.create_text_node("a😀".into(), &mut store).unwrap();
+        assert!(matches!(
+            text.split_text(4, &mut store),
+            Err(DomException::IndexSizeError(_))
+        ));
+        assert!(matches!(
+            text.split_text(2, &mut store),
+            Err(DomException::IndexSizeError(_))
+        ));
+        assert_eq!(text.data(&store), "a😀");
+
+        let rest = text.split_text(1, &mut store).unwrap();
+        assert_eq!(text.data(&store), "a");
+        assert_eq!(rest.data(&store), "😀");
+    }
+}
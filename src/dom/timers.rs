@@ -0,0 +1,227 @@
+use std::collections::{BTreeMap, HashMap};
+
+/// <https://html.spec.whatwg.org/multipage/#timerhandler>
+///
+/// Guests can not pass functions to the host yet, so a handler is identified by an id chosen by the
+/// guest, similar to [`EventListener`](super::EventListener). The host can't invoke it either, so
+/// due handlers are returned by [`Window::run_timers`](super::Window::run_timers) for the embedder
+/// to call into the guest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TimerHandler(pub u32);
+
+/// The timers of a global object.
+///
+/// Time is the number of milliseconds given by the embedder to [`Timers::run`]. A timer started
+/// with a timeout of `ms` is due once the time reaches the time of the last run plus `ms`, so the
+/// embedder can drive it with a real clock or a mock one.
+///
+/// TODO: Timer nesting levels and the 4ms clamping of nested timers.
+#[derive(Debug, Default)]
+pub(crate) struct Timers {
+    /// <https://html.spec.whatwg.org/multipage/#map-of-settimeout-and-setinterval-ids>
+    ///
+    /// The unique handle of a timer is the key of its entry in `pending`.
+    ids: HashMap<u32, TimerKey>,
+    /// Started timers ordered by deadline, then by the order they were started.
+    pending: BTreeMap<TimerKey, Timer>,
+    /// The time of the last run.
+    now: u64,
+    /// The sequence number of the next started timer.
+    sequence: u64,
+    /// The last allocated id.
+    last_id: u32,
+}
+
+/// The deadline of a timer and its sequence number.
+type TimerKey = (u64, u64);
+
+#[derive(Debug)]
+struct Timer {
+    id: u32,
+    handler: TimerHandler,
+    timeout: u64,
+    repeat: bool,
+}
+
+impl Timers {
+    /// <https://html.spec.whatwg.org/multipage/#timer-initialisation-steps>
+    pub(crate) fn initialize(
+        &mut self,
+        handler: TimerHandler,
+        timeout: u64,
+        repeat: bool,
+        previous_id: Option<u32>,
+    ) -> u32 {
+        // 1. TODO: Let thisArg be global if that is a WorkerGlobalScope object; otherwise let thisArg
+        // be the WindowProxy that corresponds to global.
+        // 2. If previousId was given, let id be previousId; otherwise, let id be an
+        // implementation-defined integer that is greater than zero and does not already exist in
+        // global’s map of setTimeout and setInterval IDs.
+        let id = previous_id.unwrap_or_else(|| self.allocate_id());
+        // 3 ~ 12. TODO: Timer nesting levels. The task of the timer is the `Timer` entry, which is
+        // run by `Timers::run`.
+        let timer = Timer {
+            id,
+            handler,
+            timeout,
+            repeat,
+        };
+        // 13. Set uniqueHandle to the result of running steps after a timeout given global,
+        // "setTimeout/setInterval", timeout, and completionStep.
+        // Timers with the same deadline run in the order they were started.
+        let key = (self.now.saturating_add(timeout), self.sequence);
+        self.sequence += 1;
+        self.pending.insert(key, timer);
+        // 14. Set global’s map of setTimeout and setInterval IDs[id] to uniqueHandle.
+        self.ids.insert(id, key);
+        // 15. Return id.
+        id
+    }
+
+    /// Remove `id` from the map of setTimeout and setInterval IDs, which cancels the timer.
+    pub(crate) fn clear(&mut self, id: u32) {
+        if let Some(key) = self.ids.remove(&id) {
+            self.pending.remove(&key);
+        }
+    }
+
+    /// Set the time to `now`, and run the tasks of the timers that are due by then.
+    ///
+    /// This returns the handlers to invoke in order. An interval runs at most once per call, and is
+    /// started again with `now` as its start time.
+    pub(crate) fn run(&mut self, now: u64) -> Vec<TimerHandler> {
+        self.now = self.now.max(now);
+        let mut due = Vec::new();
+        while let Some(entry) = self.pending.first_entry() {
+            if entry.key().0 > self.now {
+                break;
+            }
+            due.push(entry.remove());
+        }
+        let mut handlers = Vec::with_capacity(due.len());
+        for timer in due {
+            if timer.repeat {
+                // 9.9. If repeat is true, then perform the timer initialization steps again, given
+                // global, handler, timeout, arguments, true, and id.
+                self.initialize(timer.handler, timer.timeout, true, Some(timer.id));
+            } else {
+                // 9.10. Otherwise, remove global’s map of setTimeout and setInterval IDs[id].
+                self.ids.remove(&timer.id);
+            }
+            handlers.push(timer.handler);
+        }
+        handlers
+    }
+
+    /// Allocate an id that is greater than zero and not in use.
+    fn allocate_id(&mut self) -> u32 {
+        loop {
+            self.last_id = self.last_id.wrapping_add(1);
+            if self.last_id != 0 && !self.ids.contains_key(&self.last_id) {
+                return self.last_id;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn start(timers: &mut Timers, handler: u32, timeout: u64, repeat: bool) -> u32 {
+        timers.initialize(TimerHandler(handler), timeout, repeat, None)
+    }
+
+    fn run(timers: &mut Timers, now: u64) -> Vec<u32> {
+        timers
+            .run(now)
+            .into_iter()
+            .map(|handler| handler.0)
+            .collect()
+    }
+
+    #[test]
+    fn timeouts_fire_in_deadline_order() {
+        let mut timers = Timers::default();
+        start(&mut timers, 1, 200, false);
+        start(&mut timers, 2, 100, false);
+        start(&mut timers, 3, 100, false);
+        assert!(run(&mut timers, 50).is_empty());
+        // Timers with the same deadline fire in the order they were started.
+        assert_eq!(run(&mut timers, 150), [2, 3]);
+        assert_eq!(run(&mut timers, 250), [1]);
+        assert!(run(&mut timers, 1000).is_empty());
+
+        // All the due timers of one run are returned in order.
+        start(&mut timers, 4, 300, false);
+        start(&mut timers, 5, 100, false);
+        start(&mut timers, 6, 200, false);
+        assert_eq!(run(&mut timers, 2000), [5, 6, 4]);
+        assert!(timers.ids.is_empty());
+    }
+
+    #[test]
+    fn timeouts_count_from_the_last_run() {
+        let mut timers = Timers::default();
+        assert!(run(&mut timers, 100).is_empty());
+        start(&mut timers, 1, 50, false);
+        assert!(run(&mut timers, 149).is_empty());
+        assert_eq!(run(&mut timers, 150), [1]);
+        // Time never goes back.
+        assert!(run(&mut timers, 10).is_empty());
+        start(&mut timers, 2, 10, false);
+        assert!(run(&mut timers, 155).is_empty());
+        assert_eq!(run(&mut timers, 160), [2]);
+    }
+
+    #[test]
+    fn zero_timeouts_fire_on_the_next_run() {
+        let mut timers = Timers::default();
+        start(&mut timers, 1, 0, false);
+        assert_eq!(run(&mut timers, 0), [1]);
+        assert!(run(&mut timers, 0).is_empty());
+
+        // An interval of 0 runs once per call.
+        start(&mut timers, 2, 0, true);
+        assert_eq!(run(&mut timers, 0), [2]);
+        assert_eq!(run(&mut timers, 0), [2]);
+    }
+
+    #[test]
+    fn intervals_are_started_again_from_the_run() {
+        let mut timers = Timers::default();
+        let id = start(&mut timers, 1, 100, true);
+        assert!(run(&mut timers, 99).is_empty());
+        assert_eq!(run(&mut timers, 100), [1]);
+        assert!(run(&mut timers, 150).is_empty());
+        assert_eq!(run(&mut timers, 200), [1]);
+        // A late run fires the interval once, and it is due again a timeout after that run.
+        assert_eq!(run(&mut timers, 1000), [1]);
+        assert!(run(&mut timers, 1099).is_empty());
+        assert_eq!(run(&mut timers, 1100), [1]);
+        // The interval keeps its id.
+        assert_eq!(timers.ids.keys().collect::<Vec<_>>(), [&id]);
+    }
+
+    #[test]
+    fn cleared_timers_do_not_fire() {
+        let mut timers = Timers::default();
+        let timeout = start(&mut timers, 1, 100, false);
+        let interval = start(&mut timers, 2, 100, true);
+        let other = start(&mut timers, 3, 100, false);
+        assert!(timeout > 0);
+        assert_ne!(timeout, interval);
+        assert_ne!(interval, other);
+
+        timers.clear(timeout);
+        assert_eq!(run(&mut timers, 100), [2, 3]);
+        // An interval can be cleared after it fired.
+        timers.clear(interval);
+        assert!(run(&mut timers, 1000).is_empty());
+        // Clearing an unknown or finished timer does nothing.
+        timers.clear(other);
+        timers.clear(12345);
+        assert!(timers.ids.is_empty());
+        assert!(timers.pending.is_empty());
+    }
+}
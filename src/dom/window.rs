@@ -1,6 +1,21 @@
-use wasmtime::{AsContextMut, Result};
+use std::collections::VecDeque;
 
-use super::Object;
+use wasmtime::{AsContext, AsContextMut, Error, Result, component::Resource};
+
+use crate::{
+    DomException, WindowStates,
+    browsing_context::BrowsingContextID,
+    ohim::dom::{
+        node::DomException as WitDomException,
+        window::{Host, HostWindow},
+    },
+    structured_clone::SerializedValue,
+    url::{ImmutableOrigin, parse_origin_string},
+};
+
+use super::{
+    CustomElementRegistry, Document, Location, MessageEvent, Object, TimerHandler, Timers,
+};
 
 /// <https://html.spec.whatwg.org/multipage/#window>
 #[derive(Clone, Debug)]
@@ -9,13 +24,340 @@ pub struct Window(Object<WindowImpl>);
 impl Window {
     /// Create a `Window` object.
     pub fn new(store: impl AsContextMut) -> Result<Self> {
-        Ok(Window(Object::new(store, WindowImpl {})?))
+        Ok(Window(Object::new(
+            store,
+            WindowImpl {
+                document: None,
+                timers: Timers::default(),
+                posted_messages: VecDeque::new(),
+                origin_keyed: false,
+            },
+        )?))
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#concept-document-window>
+    pub fn document(&self, store: impl AsContext) -> Option<Document> {
+        self.0.data(&store).document.clone()
+    }
+
+    /// Set the `Window`'s associated `Document`.
+    pub(crate) fn set_document(&self, document: Document, mut store: impl AsContextMut) {
+        self.0.data_mut(&mut store).document = Some(document);
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#is-origin-keyed>
+    ///
+    /// Whether the agent cluster of the agent of this window is origin-keyed.
+    pub fn is_origin_keyed(&self, store: impl AsContext) -> bool {
+        self.0.data(&store).origin_keyed
+    }
+
+    /// Set whether the agent cluster of the agent of this window is origin-keyed, which is known
+    /// once the agent is obtained for its browsing context.
+    pub(crate) fn set_origin_keyed(&self, origin_keyed: bool, mut store: impl AsContextMut) {
+        self.0.data_mut(&mut store).origin_keyed = origin_keyed;
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-window-customelements>
+    pub fn custom_elements(&self, store: impl AsContext) -> Option<CustomElementRegistry> {
+        // 1. Assert: this’s associated Document’s custom element registry is a
+        // CustomElementRegistry object.
+        // 2. Return this’s associated Document’s custom element registry.
+        self.document(&store)
+            .and_then(|document| document.custom_element_registry(&store))
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-location>
+    pub fn location(&self, store: impl AsContext) -> Option<Location> {
+        self.document(store).map(Location::new)
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-settimeout>
+    pub fn set_timeout(
+        &self,
+        handler: TimerHandler,
+        timeout: u64,
+        mut store: impl AsContextMut,
+    ) -> u32 {
+        // The setTimeout(handler, timeout, ...arguments) method steps are to return the result of
+        // running the timer initialization steps given this, handler, timeout, arguments, and
+        // false.
+        self.0
+            .data_mut(&mut store)
+            .timers
+            .initialize(handler, timeout, false, None)
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-setinterval>
+    pub fn set_interval(
+        &self,
+        handler: TimerHandler,
+        timeout: u64,
+        mut store: impl AsContextMut,
+    ) -> u32 {
+        // The setInterval(handler, timeout, ...arguments) method steps are to return the result of
+        // running the timer initialization steps given this, handler, timeout, arguments, and
+        // true.
+        self.0
+            .data_mut(&mut store)
+            .timers
+            .initialize(handler, timeout, true, None)
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-cleartimeout>
+    pub fn clear_timeout(&self, id: u32, mut store: impl AsContextMut) {
+        // The clearTimeout(id) and clearInterval(id) method steps are to remove this’s map of
+        // setTimeout and setInterval IDs[id].
+        self.0.data_mut(&mut store).timers.clear(id);
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-clearinterval>
+    pub fn clear_interval(&self, id: u32, store: impl AsContextMut) {
+        self.clear_timeout(id, store);
+    }
+
+    /// Advance the time of the timers to `now` milliseconds, and return the handlers of the timers
+    /// that are due, in the order they should be invoked.
+    ///
+    /// Timers are due in deadline order, and timers with the same deadline in the order they were
+    /// started. Intervals are started again from `now`, and run at most once per call. The deadline
+    /// of a new timer is counted from the last `now` passed here, so the embedder's event loop
+    /// should call this with a monotonic clock, or a mock clock when testing.
+    pub fn run_timers(&self, now: u64, mut store: impl AsContextMut) -> Vec<TimerHandler> {
+        self.0.data_mut(&mut store).timers.run(now)
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#window-post-message-steps>
+    ///
+    /// Post `message` from this window to `target`, which must be in the same `Store`. Use
+    /// [`UserAgent::post_message`](crate::user_agent::UserAgent::post_message) to post to a window
+    /// of another `Store`.
+    /// TODO: transfer
+    pub fn post_message(
+        &self,
+        target: &Window,
+        message: SerializedValue,
+        target_origin: &str,
+        mut store: impl AsContextMut,
+    ) -> Result<(), DomException> {
+        let message = self.prepare_message(message, target_origin, &store)?;
+        target.queue_message(message, &mut store);
+        Ok(())
+    }
+
+    /// Run the window post message steps up to queuing the task, with this window as the incumbent
+    /// settings object.
+    pub(crate) fn prepare_message(
+        &self,
+        message: SerializedValue,
+        target_origin: &str,
+        store: impl AsContext,
+    ) -> Result<PostedMessage, DomException> {
+        // 1. TODO: Let targetRealm be targetWindow’s realm.
+        // 2. Let incumbentSettings be the incumbent settings object.
+        let document = self.document(&store).ok_or_else(|| {
+            DomException::InvalidStateError("window has no associated document".into())
+        })?;
+        let incumbent_origin = document.origin(&store);
+        // 3. Let targetOrigin be options["targetOrigin"].
+        let target_origin = match target_origin {
+            "*" => None,
+            // 4. If targetOrigin is a single U+002F SOLIDUS character (/), then set targetOrigin to
+            // incumbentSettings’s origin.
+            "/" => Some(incumbent_origin.clone()),
+            // 5. Otherwise, if targetOrigin is not a single U+002A ASTERISK character (*), then:
+            target_origin => {
+                // 5.1 Let parsedURL be the result of running the URL parser on targetOrigin.
+                // 5.2 If parsedURL is failure, then throw a "SyntaxError" DOMException.
+                // 5.3 Set targetOrigin to parsedURL’s origin.
+                let origin = parse_origin_string(target_origin).map_err(|e| {
+                    DomException::SyntaxError(format!(
+                        "{target_origin:?} is not a valid target origin: {e}"
+                    ))
+                })?;
+                Some(origin)
+            }
+        };
+        // 6 ~ 7. TODO: Let serializeWithTransferResult be StructuredSerializeWithTransfer(message,
+        // transfer). The message is serialized by the caller for now.
+        Ok(PostedMessage {
+            message,
+            target_origin,
+            // 8.2 Let origin be the serialization of incumbentSettings’s origin.
+            origin: incumbent_origin.ascii_serialization(),
+            // 8.3 Let source be the WindowProxy object corresponding to incumbentSettings’s global
+            // object (a Window object).
+            source: document.browsing_context(&store),
+        })
+    }
+
+    /// Queue a global task on the posted message task source given this window to deliver
+    /// `message`, as step 8 of the window post message steps.
+    pub(crate) fn queue_message(&self, message: PostedMessage, mut store: impl AsContextMut) {
+        self.0
+            .data_mut(&mut store)
+            .posted_messages
+            .push_back(message);
+    }
+
+    /// Run the queued tasks of posted messages, and return the `MessageEvent`s to fire at this
+    /// window in order.
+    ///
+    /// Messages whose target origin doesn't match the origin of the associated `Document` are
+    /// dropped silently. The embedder's event loop should call this and deliver the events to the
+    /// guest.
+    pub fn take_messages(&self, mut store: impl AsContextMut) -> Vec<MessageEvent> {
+        let messages = std::mem::take(&mut self.0.data_mut(&mut store).posted_messages);
+        let origin = self
+            .document(&store)
+            .map(|document| document.origin(&store));
+        messages
+            .into_iter()
+            .filter_map(|message| {
+                // 8.1 If the targetOrigin argument is not a single literal U+002A ASTERISK
+                // character (*) and targetWindow’s associated Document’s origin is not same origin
+                // with targetOrigin, then return.
+                if message
+                    .target_origin
+                    .as_ref()
+                    .is_some_and(|target_origin| origin.as_ref() != Some(target_origin))
+                {
+                    return None;
+                }
+                // 8.4 ~ 8.6. TODO: Deserialize the message in targetRealm, and fire "messageerror"
+                // if it fails. The data is kept serialized for the embedder to deserialize.
+                // 8.7 Fire an event named message at targetWindow, using MessageEvent, with the
+                // origin attribute initialized to origin, the source attribute initialized to
+                // source, the data attribute initialized to messageClone, and the ports attribute
+                // initialized to newPorts.
+                Some(MessageEvent::new(
+                    message.message,
+                    message.origin,
+                    message.source,
+                ))
+            })
+            .collect()
+    }
+}
+
+impl Host for WindowStates {
+    fn same_origin(&mut self, a: String, b: String) -> Result<bool> {
+        // Strings which aren't valid URLs, including "null", are never same origin.
+        Ok(match (parse_origin_string(&a), parse_origin_string(&b)) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        })
+    }
+}
+
+impl WindowStates {
+    /// Get the `Window` of `resource`.
+    ///
+    /// This returns an error instead of panicking if the object is not a `Window`.
+    fn get_window(&self, resource: &Resource<Window>) -> Result<Window> {
+        let window = self.table.get(resource)?;
+        window.0.try_data(&self.store)?;
+        Ok(window.clone())
+    }
+}
+
+impl HostWindow for WindowStates {
+    fn current(&mut self) -> Result<Resource<Window>> {
+        Ok(self.push_resource(self.window.clone())?)
+    }
+
+    fn document(&mut self, self_: Resource<Window>) -> Result<Resource<Document>> {
+        let self_ = self.get_window(&self_)?;
+        let document = self_
+            .document(&self.store)
+            .ok_or_else(|| Error::msg("window has no associated document"))?;
+        Ok(self.push_resource(document)?)
+    }
+
+    fn location(&mut self, self_: Resource<Window>) -> Result<String> {
+        let self_ = self.get_window(&self_)?;
+        let location = self_
+            .location(&self.store)
+            .ok_or_else(|| Error::msg("window has no associated document"))?;
+        Ok(location.href(&self.store).to_string())
+    }
+
+    fn custom_elements(
+        &mut self,
+        self_: Resource<Window>,
+    ) -> Result<Resource<CustomElementRegistry>> {
+        let self_ = self.get_window(&self_)?;
+        let registry = self_
+            .custom_elements(&self.store)
+            .ok_or_else(|| Error::msg("window has no custom element registry"))?;
+        Ok(self.push_resource(registry)?)
+    }
+
+    fn set_timeout(&mut self, self_: Resource<Window>, handler: u32, timeout: u64) -> Result<u32> {
+        let self_ = self.get_window(&self_)?;
+        Ok(self_.set_timeout(TimerHandler(handler), timeout, &mut self.store))
+    }
+
+    fn set_interval(&mut self, self_: Resource<Window>, handler: u32, timeout: u64) -> Result<u32> {
+        let self_ = self.get_window(&self_)?;
+        Ok(self_.set_interval(TimerHandler(handler), timeout, &mut self.store))
+    }
+
+    fn clear_timeout(&mut self, self_: Resource<Window>, id: u32) -> Result<()> {
+        let self_ = self.get_window(&self_)?;
+        self_.clear_timeout(id, &mut self.store);
+        Ok(())
+    }
+
+    fn clear_interval(&mut self, self_: Resource<Window>, id: u32) -> Result<()> {
+        let self_ = self.get_window(&self_)?;
+        self_.clear_interval(id, &mut self.store);
+        Ok(())
+    }
+
+    fn post_message(
+        &mut self,
+        self_: Resource<Window>,
+        message: Vec<u8>,
+        target_origin: String,
+    ) -> Result<Result<(), WitDomException>> {
+        let self_ = self.get_window(&self_)?;
+        let result = SerializedValue::decode(&message).and_then(|message| {
+            self.window
+                .post_message(&self_, message, &target_origin, &mut self.store)
+        });
+        Ok(result.map_err(Into::into))
+    }
+
+    fn drop(&mut self, rep: Resource<Window>) -> Result<()> {
+        self.delete_resource(rep)?;
+        Ok(())
     }
 }
 
 /// Implementation of acutal `Window` object.
 #[derive(Debug)]
-struct WindowImpl {}
+struct WindowImpl {
+    /// <https://html.spec.whatwg.org/multipage/#concept-document-window>
+    document: Option<Document>,
+    /// <https://html.spec.whatwg.org/multipage/#map-of-settimeout-and-setinterval-ids>
+    timers: Timers,
+    /// Tasks queued on the posted message task source, which are run by `Window::take_messages`.
+    posted_messages: VecDeque<PostedMessage>,
+    /// Whether the agent cluster of the agent of the window is origin-keyed.
+    origin_keyed: bool,
+}
+
+/// A message posted to a window, waiting for its task to run.
+#[derive(Debug)]
+pub(crate) struct PostedMessage {
+    message: SerializedValue,
+    /// The target origin, or `None` for "*".
+    target_origin: Option<ImmutableOrigin>,
+    /// The serialization of the origin of the incumbent settings object.
+    origin: String,
+    source: Option<BrowsingContextID>,
+}
 
 /// <https://html.spec.whatwg.org/multipage/#windowproxy>
 #[derive(Clone, Debug)]
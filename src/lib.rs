@@ -7,21 +7,93 @@
     unreachable_pub
 )]
 
-use std::fmt::Debug;
+use std::{any::type_name, collections::BTreeMap, fmt::Debug};
 
 pub use bindings::{Imports, ohim};
 
 pub use dom::*;
 use ohim::dom::node::Host;
-use wasmtime::{Store, component::ResourceTable};
+use wasmtime::{
+    Store,
+    component::{Resource, ResourceTable},
+};
 use wasmtime_wasi::p2::{IoView, WasiCtx, WasiView};
 
+use crate::{
+    browsing_context::BrowsingContextID,
+    navigible::{Navigable, NavigableID},
+    string::DOMString,
+    url::DOMUrl,
+    user_agent::UserAgent,
+};
+
+/// Define an ID type allocated from a process-wide counter.
+///
+/// Each call of `new` takes the next value with a single atomic `fetch_add`, so IDs stay unique
+/// even when they are allocated from several threads at the same time.
+macro_rules! define_id {
+    ($(#[$attr:meta])* $name:ident) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name(pub usize);
+
+        impl $name {
+            /// Allocate a new unique ID.
+            #[allow(clippy::new_without_default)]
+            pub fn new() -> Self {
+                Self(Self::count().fetch_add(1, std::sync::atomic::Ordering::Relaxed))
+            }
+
+            /// Restart the allocation from 0, so a test sees the same IDs on every run.
+            ///
+            /// IDs allocated before the reset are handed out again, so this must only be called
+            /// while no other thread allocates this type of ID.
+            #[cfg(test)]
+            #[allow(dead_code)]
+            pub(crate) fn reset() {
+                Self::count().store(0, std::sync::atomic::Ordering::Relaxed);
+            }
+
+            fn count() -> &'static std::sync::atomic::AtomicUsize {
+                static COUNT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+                &COUNT
+            }
+        }
+
+        impl std::ops::Deref for $name {
+            type Target = usize;
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+    };
+}
+
+/// Create a "NotSupportedError" [`DomException`] for a `feature` that is not implemented yet.
+///
+/// Host functions that guests can reach should return this instead of calling `todo!()`, which
+/// traps the whole instance. The message names the feature, so the embedder can report it when the
+/// exception reaches the guest.
+macro_rules! not_supported {
+    ($feature:expr) => {
+        $crate::DomException::NotSupportedError(format!("{} is not supported yet", $feature))
+    };
+}
+
 pub mod agent;
 pub mod browsing_context;
 pub mod dom;
 pub mod navigible;
+pub mod path;
+pub mod permissions_policy;
 pub mod string;
+pub mod structured_clone;
+#[cfg(test)]
+mod testing;
+pub mod time;
 pub mod url;
+pub mod user_agent;
 
 #[allow(missing_debug_implementations, missing_docs, unreachable_pub)]
 mod bindings {
@@ -30,41 +102,186 @@ mod bindings {
         path: "wit",
         world: "ohim:dom/imports",
         with: {
+            "ohim:dom/event-target/event-target": EventTarget,
             "ohim:dom/node/node": Node,
             "ohim:dom/node/document": Document,
             "ohim:dom/node/element": Element,
+            "ohim:dom/node/attr": Attr,
+            "ohim:dom/node/comment": Comment,
+            "ohim:dom/node/document-type": DocumentType,
+            "ohim:dom/node/dom-token-list": DOMTokenList,
+            "ohim:dom/node/mutation-observer": MutationObserver,
+            "ohim:dom/node/text": Text,
+            "ohim:dom/node/shadow-root": ShadowRoot,
+            "ohim:dom/window/window": Window,
+            "ohim:dom/window/custom-element-registry": CustomElementRegistry,
         },
         trappable_imports: true,
     });
 }
 
 /// `Store` states to use when `[Exposed=Window]`
+///
+/// Each `WindowStates` belongs to one window. Its inner `Store` holds the DOM objects of the window.
+/// Use [`UserAgent`](user_agent::UserAgent) to host more than one window.
 pub struct WindowStates {
     table: ResourceTable,
+    /// Number of live resources in `table` for each resource type.
+    resources: BTreeMap<&'static str, usize>,
     ctx: WasiCtx,
     store: Store<()>,
+    traversable: NavigableID,
+    window: Window,
 }
 
 impl WindowStates {
     /// Create `WindowStates` data for initializing a new `Store`.
-    pub fn create() -> Self {
+    ///
+    /// This creates a fresh top-level traversable navigated to `about:blank` in `user_agent`. The
+    /// `Window` of its active document is the global of the guest.
+    pub fn create(user_agent: &mut UserAgent) -> Self {
+        let mut store = Store::<()>::default();
+        let url = DOMUrl::parse("about:blank").unwrap();
+        let traversable =
+            Navigable::create_fresh_top_traversable(user_agent, url, None, &mut store);
+        Self::with_traversable(user_agent, traversable, store)
+    }
+
+    /// Create `WindowStates` data for a window opened by the window of the top-level traversable
+    /// `opener`, whose active browsing context is `context`.
+    ///
+    /// The new top-level traversable's browsing context is an auxiliary browsing context in the
+    /// browsing context group of `context`. It stays on its initial `about:blank` document.
+    pub(crate) fn create_auxiliary(
+        user_agent: &mut UserAgent,
+        opener: NavigableID,
+        context: BrowsingContextID,
+    ) -> Self {
+        let mut store = Store::<()>::default();
+        let traversable = Navigable::create_top_traversable(
+            user_agent,
+            Some(context),
+            String::new(),
+            Some(opener),
+            &mut store,
+        );
+        Self::with_traversable(user_agent, traversable, store)
+    }
+
+    fn with_traversable(
+        user_agent: &UserAgent,
+        traversable: NavigableID,
+        store: Store<()>,
+    ) -> Self {
+        let window = Navigable::top_level_active_document(user_agent, traversable)
+            .and_then(|document| document.relevant_global_object(user_agent, &store))
+            .expect("Failed to get the window of the top-level traversable");
         Self {
             table: ResourceTable::new(),
+            resources: BTreeMap::new(),
             ctx: WasiCtx::builder().inherit_stdout().build(),
-            store: Store::<()>::default(),
+            store,
+            traversable,
+            window,
         }
     }
+
+    /// Get the ID of the top-level traversable of this `Store`.
+    pub fn traversable(&self) -> NavigableID {
+        self.traversable
+    }
+
+    /// Get the `Window` of this `Store`.
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+
+    /// Run the timers of the window up to `now` milliseconds and return the due handlers in order.
+    ///
+    /// See [`Window::run_timers`].
+    pub fn run_timers(&mut self, now: u64) -> Vec<TimerHandler> {
+        self.window.run_timers(now, &mut self.store)
+    }
+
+    /// Run the tasks of the messages posted to the window and return the `MessageEvent`s to fire.
+    ///
+    /// See [`Window::take_messages`].
+    pub fn take_messages(&mut self) -> Vec<MessageEvent> {
+        self.window.take_messages(&mut self.store)
+    }
+
+    /// Take the `whenDefined` promises of the custom element registry of the window that were
+    /// resolved since the last call.
+    ///
+    /// See [`CustomElementRegistry::take_resolved`].
+    pub fn take_resolved_when_defined(&mut self) -> Vec<(DOMString, CustomElementConstructor)> {
+        self.window
+            .custom_elements(&self.store)
+            .map(|registry| registry.take_resolved(&mut self.store))
+            .unwrap_or_default()
+    }
+
+    /// Get the number of DOM resources the guest holds and hasn't dropped yet.
+    ///
+    /// A count that keeps growing usually means the guest never drops its `Node`, `Document` or
+    /// `Element` handles. Resources created by WASI are not counted.
+    pub fn resource_count(&self) -> usize {
+        self.resources.values().sum()
+    }
+
+    /// Get the number of live DOM resources for each resource type, such as `"Element"`.
+    ///
+    /// Types without live resources are left out. The same breakdown is part of the `Debug`
+    /// output of `WindowStates`.
+    pub fn resource_counts(&self) -> &BTreeMap<&'static str, usize> {
+        &self.resources
+    }
+
+    /// Push `value` to the resource table and count it as a live resource.
+    pub(crate) fn push_resource<T: Send + 'static>(
+        &mut self,
+        value: T,
+    ) -> wasmtime::Result<Resource<T>> {
+        let resource = self.table.push(value)?;
+        *self.resources.entry(resource_type::<T>()).or_default() += 1;
+        Ok(resource)
+    }
+
+    /// Delete `resource` from the resource table and stop counting it as a live resource.
+    pub(crate) fn delete_resource<T: 'static>(
+        &mut self,
+        resource: Resource<T>,
+    ) -> wasmtime::Result<T> {
+        let value = self.table.delete(resource)?;
+        let name = resource_type::<T>();
+        if let Some(count) = self.resources.get_mut(name) {
+            *count -= 1;
+            if *count == 0 {
+                self.resources.remove(name);
+            }
+        }
+        Ok(value)
+    }
 }
 
 impl Debug for WindowStates {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("WindowStates")
             .field("table", &self.table)
+            .field("resources", &self.resources)
             .field("store", &self.store)
+            .field("traversable", &self.traversable)
+            .field("window", &self.window)
             .finish()
     }
 }
 
+/// Get the name of resource type `T` without its module path.
+fn resource_type<T>() -> &'static str {
+    let name = type_name::<T>();
+    name.rsplit("::").next().unwrap_or(name)
+}
+
 impl Host for WindowStates {}
 
 impl IoView for WindowStates {
@@ -78,3 +295,60 @@ impl WasiView for WindowStates {
         &mut self.ctx
     }
 }
+
+#[cfg(test)]
+// The ID types defined by `define_id!` are `pub`.
+#[allow(unreachable_pub)]
+mod tests {
+    use std::{collections::HashSet, thread};
+
+    use crate::DomException;
+
+    define_id! {
+        /// An ID only allocated by the stress test.
+        StressID
+    }
+
+    define_id! {
+        /// An ID only allocated by the reset test.
+        ResetID
+    }
+
+    #[test]
+    fn ids_are_unique_across_threads() {
+        const THREADS: usize = 8;
+        const IDS: usize = 5000;
+        let handles: Vec<_> = (0..THREADS)
+            .map(|_| thread::spawn(|| (0..IDS).map(|_| StressID::new()).collect::<Vec<_>>()))
+            .collect();
+        let mut ids = HashSet::new();
+        for handle in handles {
+            for id in handle.join().unwrap() {
+                assert!(ids.insert(id), "{id:?} was allocated twice");
+            }
+        }
+        assert_eq!(ids.len(), THREADS * IDS);
+    }
+
+    #[test]
+    fn reset_restarts_the_allocation() {
+        let first = [ResetID::new(), ResetID::new()];
+        ResetID::reset();
+        assert_eq!([ResetID::new(), ResetID::new()], [ResetID(0), ResetID(1)]);
+        assert_eq!(first[1].0, first[0].0 + 1);
+    }
+
+    #[test]
+    fn not_supported_names_the_feature() {
+        let exception = not_supported!("cloning a Document");
+        assert_eq!(exception.name(), "NotSupportedError");
+        assert_eq!(
+            exception.message(),
+            "cloning a Document is not supported yet"
+        );
+        assert!(matches!(
+            not_supported!(format!("namespace {:?}", "urn:x")),
+            DomException::NotSupportedError(message) if message == "namespace \"urn:x\" is not supported yet"
+        ));
+    }
+}
@@ -1,26 +1,17 @@
 //! A navigable presents a Document to the user via its active session history entry.
 
-use std::{
-    collections::HashMap,
-    ops::Deref,
-    sync::{
-        Arc, LazyLock, Mutex,
-        atomic::{AtomicUsize, Ordering},
-    },
-};
+use std::collections::HashMap;
 
-use wasmtime::AsContextMut;
+use wasmtime::{AsContext, AsContextMut};
 
 use crate::{
     Document,
-    browsing_context::BrowsingContext,
+    browsing_context::{BrowsingContext, BrowsingContextID},
+    structured_clone::SerializedValue,
     url::{DOMUrl, ImmutableOrigin},
+    user_agent::UserAgent,
 };
 
-/// <https://html.spec.whatwg.org/multipage/#top-level-traversable-set>
-static TOP_LEVEL_TRAVERSABLE_SET: LazyLock<Arc<Mutex<HashMap<NavigableID, Navigable>>>> =
-    LazyLock::new(|| Arc::new(Mutex::new(HashMap::new())));
-
 /// <https://html.spec.whatwg.org/multipage/#traversable-navigable>
 #[derive(Debug, Default)]
 pub struct Traversable {
@@ -28,7 +19,7 @@ pub struct Traversable {
 }
 
 /// <https://html.spec.whatwg.org/multipage/document-sequences.html#navigable>
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct Navigable {
     id: NavigableID,
     parent: Option<NavigableID>,
@@ -37,49 +28,91 @@ pub struct Navigable {
     traversable: Option<Traversable>,
 }
 
+/// An observer of the navigables of a [`UserAgent`], registered with
+/// [`UserAgent::add_navigable_observer`].
+///
+/// Embedders can use it to manage their window UI, and it's where WebDriver BiDi hooks in.
+pub trait NavigableObserver {
+    /// <https://w3c.github.io/webdriver-bidi/#webdriver-bidi-navigable-created>
+    ///
+    /// Called after `navigable` is created. `opener` is the navigable which opened it, if any.
+    fn navigable_created(&mut self, navigable: &Navigable, opener: Option<NavigableID>);
+}
+
 impl Navigable {
+    /// Get the top-level traversable `id` in the user agent's top-level traversable set.
+    pub fn get(user_agent: &UserAgent, id: NavigableID) -> Option<&Navigable> {
+        user_agent.top_level_traversable_set.get(&id)
+    }
+
+    /// Get the ID of the navigable.
+    pub fn id(&self) -> NavigableID {
+        self.id
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#nav-parent>
+    pub fn parent(&self) -> Option<NavigableID> {
+        self.parent
+    }
+
     /// <https://html.spec.whatwg.org/multipage/document-sequences.html#create-a-fresh-top-level-traversable>
     /// TODO: implement POST resource
     pub fn create_fresh_top_traversable(
-        _url: DOMUrl,
+        user_agent: &mut UserAgent,
+        url: DOMUrl,
         _resource: Option<bool>,
-        store: impl AsContextMut,
-    ) -> Self {
+        mut store: impl AsContextMut,
+    ) -> NavigableID {
         // 1. Let traversable be the result of creating a new top-level traversable given null and the empty string.
-        let _traversable = Navigable::create_top_traversable(None, String::from(""), None, store);
+        let traversable =
+            Navigable::create_top_traversable(user_agent, None, String::from(""), None, &mut store);
         // 2. Navigate traversable to initialNavigationURL using traversable's active document,
         // with documentResource set to initialNavigationPostResource.
-        todo!()
+        if let Some(document) = Navigable::top_level_active_document(user_agent, traversable) {
+            Navigable::navigate(&document, url, NavigationHistoryBehavior::Auto, store);
+        }
+        // 3. Return traversable.
+        traversable
     }
 
     /// <https://html.spec.whatwg.org/multipage/document-sequences.html#creating-a-new-top-level-traversable>
     pub fn create_top_traversable(
-        opener: Option<bool>,
+        user_agent: &mut UserAgent,
+        opener: Option<BrowsingContextID>,
         target: String,
-        _navigable: Option<Navigable>,
+        opener_navigable: Option<NavigableID>,
         mut store: impl AsContextMut,
     ) -> NavigableID {
         // 5. Let traversable be a new traversable navigable.
-        let mut traversable = Self::default();
+        let mut traversable = Self {
+            id: NavigableID::new(),
+            parent: None,
+            current_entry: None,
+            active_entry: None,
+            traversable: None,
+        };
         // 1. Let document be null.
         let document = match opener {
             // 2. If opener is null, then set document to the second return value of creating a new top-level browsing
             // context and document.
             None => {
-                let (_context, document) = BrowsingContext::new_top_browsing_context(&mut store);
+                let (_group, _context, document) =
+                    BrowsingContext::new_top_browsing_context(user_agent, &mut store);
                 document
             }
             // 3. Otherwise, set document to the second return value of creating a new auxiliary browsing context and
             // document given opener.
-            Some(_) => {
-                todo!()
+            Some(opener) => {
+                let (_context, document) =
+                    BrowsingContext::new_auxiliary_browsing_context(user_agent, opener, &mut store);
+                document
             }
         };
         // 4. Let documentState be a new document state
         let url = document.url(&store);
         let state = DocumentState {
-            // TODO: null if opener is null; otherwise, document's origin
-            initiator_origin: None,
+            // null if opener is null; otherwise, document's origin
+            initiator_origin: opener.map(|_| document.origin(&store)),
             origin: Some(document.origin(&store)),
             target,
             about_base_url: document.about_base_url(&store),
@@ -102,16 +135,73 @@ impl Navigable {
         // top-level traversable and traversable.
         // 11. Append traversable to the user agent's top-level traversable set.
         let id = traversable.id;
-        TOP_LEVEL_TRAVERSABLE_SET
-            .lock()
-            .unwrap()
-            .insert(id, traversable);
-        // 12. TODO: Invoke WebDriver BiDi navigable created with traversable and openerNavigableForWebDriver.
+        user_agent.top_level_traversable_set.insert(id, traversable);
+        // 12. Invoke WebDriver BiDi navigable created with traversable and openerNavigableForWebDriver.
+        let traversable = &user_agent.top_level_traversable_set[&id];
+        for observer in &mut user_agent.navigable_observers {
+            observer.navigable_created(traversable, opener_navigable);
+        }
 
         // 13. Return traversable.
         id
     }
 
+    /// <https://html.spec.whatwg.org/multipage/#destroy-a-top-level-traversable>
+    pub fn destroy_top_traversable(
+        user_agent: &mut UserAgent,
+        id: NavigableID,
+        store: impl AsContext,
+    ) {
+        let Some(traversable) = user_agent.top_level_traversable_set.remove(&id) else {
+            return;
+        };
+        // 1. Let browsingContext be traversable's active browsing context.
+        let browsing_context = traversable
+            .active_document()
+            .and_then(|document| document.browsing_context(&store));
+        // 2. For each historyEntry in traversable's session history entries:
+        for entry in traversable
+            .traversable
+            .iter()
+            .flat_map(|traversable| traversable.history_entries.values())
+        {
+            // 2.1 Let document be historyEntry's document.
+            // 2.2 If document is not null, then destroy a document given document.
+            // TODO: Destroy the document. Discarding it is enough to release its realm for now.
+            if let Some(document) = &entry.state.document {
+                document.discard(user_agent, &store);
+            }
+        }
+        // 3. Remove browsingContext.
+        if let Some(browsing_context) = browsing_context {
+            BrowsingContext::remove(user_agent, browsing_context);
+        }
+        // 4. TODO: Remove traversable from the user interface.
+        // 5. Remove traversable from the user agent's top-level traversable set.
+        // This is done at the beginning to take the traversable.
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#nav-document>
+    pub fn active_document(&self) -> Option<Document> {
+        let entry = self.active_entry?;
+        self.traversable
+            .as_ref()?
+            .history_entries
+            .get(&entry)?
+            .state
+            .document
+            .clone()
+    }
+
+    /// Get the active document of the top-level traversable `id` in the user agent's top-level
+    /// traversable set.
+    pub fn top_level_active_document(user_agent: &UserAgent, id: NavigableID) -> Option<Document> {
+        user_agent
+            .top_level_traversable_set
+            .get(&id)
+            .and_then(Navigable::active_document)
+    }
+
     /// <https://html.spec.whatwg.org/multipage/#initialize-the-navigable>
     fn initialize(
         &mut self,
@@ -121,9 +211,10 @@ impl Navigable {
     ) -> SessionHistory {
         // 2. Let entry be a new session history entry
         let entry = SessionHistory {
-            id: SessionHistoryID::default(),
+            id: SessionHistoryID::new(),
             step: None,
             url,
+            serialized_state: None,
             state,
         };
         // 3. Set navigable's current session history entry to entry.
@@ -135,48 +226,61 @@ impl Navigable {
         entry
     }
 
-    // /// <https://html.spec.whatwg.org/multipage/#navigate>
-    // /// TODO: response, navigationAPIState, formDataEntryList, userInvolvement
-    // #[allow(clippy::too_many_arguments)]
-    // pub fn navigate(
-    //     &self,
-    //     url: DOMUrl,
-    //     documet: Option<Document>,
-    //     resource: Option<bool>,
-    //     response: Option<bool>,
-    //     exception: bool,
-    //     history_handling: NavigationHistoryBehavior,
-    //     api_state: Option<bool>,
-    //     entry_list: Option<bool>,
-    //     referer_policy: ReferrerPolicy,
-    //     involvement: Option<bool>,
-    //     element: Option<Element>,
-    //     initial_insertion: bool,
-    // ) {
-    //     // 1. Let cspNavigationType be "form-submission" if formDataEntryList is non-null; otherwise "other".
-    //     let csp_type = entry_list.is_some();
-    // }
-}
-
-/// ID of `Navigable`.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct NavigableID(pub usize);
+    /// <https://html.spec.whatwg.org/multipage/#navigate>
+    ///
+    /// The navigable is represented by its active `Document` until documents can look up their node
+    /// navigable.
+    /// TODO: documentResource, response, exceptionsEnabled, navigationAPIState, formDataEntryList,
+    /// referrerPolicy, userInvolvement, sourceElement, initialInsertion
+    pub fn navigate(
+        document: &Document,
+        url: DOMUrl,
+        history_handling: NavigationHistoryBehavior,
+        store: impl AsContextMut,
+    ) {
+        // 14. If documentResource is null, url equals navigable's active document's URL with exclude
+        // fragments set to true, and url's fragment is non-null, then:
+        let current_url = document.url(&store);
+        if url.fragment().is_some() && equals_exclude_fragments(&url, &current_url) {
+            // 14.1 Navigate to a fragment given navigable, url, historyHandling, userInvolvement,
+            // sourceElement, navigationAPIState, and navigationId.
+            Self::navigate_to_fragment(document, url, history_handling, store);
+            // 14.2 Return.
+            return;
+        }
+        // TODO: Fetch url and load the response into a new Document with
+        // `Document::parse_html_document`. Until responses can be fetched, the active document is
+        // kept and its URL is updated to url.
+        document.set_url(url, store);
+    }
 
-impl Default for NavigableID {
-    fn default() -> Self {
-        static COUNT: LazyLock<AtomicUsize> = LazyLock::new(|| AtomicUsize::new(0));
-        let id = Self(COUNT.load(Ordering::Relaxed));
-        COUNT.fetch_add(1, Ordering::Relaxed);
-        id
+    /// <https://html.spec.whatwg.org/multipage/#navigate-fragid>
+    fn navigate_to_fragment(
+        document: &Document,
+        url: DOMUrl,
+        _history_handling: NavigationHistoryBehavior,
+        store: impl AsContextMut,
+    ) {
+        // TODO: Create a session history entry for url and append or replace it according to
+        // historyHandling.
+        // Update document for history step application, which sets the document's URL to url.
+        document.set_url(url, store);
+        // TODO: Update the navigation API entries and scroll to the fragment.
     }
 }
 
-impl Deref for NavigableID {
-    type Target = usize;
+/// <https://url.spec.whatwg.org/#concept-url-equals>
+fn equals_exclude_fragments(a: &DOMUrl, b: &DOMUrl) -> bool {
+    let mut a = a.clone();
+    let mut b = b.clone();
+    a.set_fragment(None);
+    b.set_fragment(None);
+    a == b
+}
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
+define_id! {
+    /// ID of `Navigable`.
+    NavigableID
 }
 
 /// <https://html.spec.whatwg.org/multipage/#session-history-entry>
@@ -188,29 +292,15 @@ pub struct SessionHistory {
     pub step: Option<usize>,
     /// <https://html.spec.whatwg.org/multipage/#she-url>
     pub url: DOMUrl,
+    /// <https://html.spec.whatwg.org/multipage/#she-serialized-state>
+    pub serialized_state: Option<SerializedValue>,
     /// <https://html.spec.whatwg.org/multipage/#she-document-state>
     pub state: DocumentState,
 }
 
-/// ID of `SessionHistory`.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct SessionHistoryID(pub usize);
-
-impl Default for SessionHistoryID {
-    fn default() -> Self {
-        static COUNT: LazyLock<AtomicUsize> = LazyLock::new(|| AtomicUsize::new(0));
-        let id = Self(COUNT.load(Ordering::Relaxed));
-        COUNT.fetch_add(1, Ordering::Relaxed);
-        id
-    }
-}
-
-impl Deref for SessionHistoryID {
-    type Target = usize;
-
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
+define_id! {
+    /// ID of `SessionHistory`.
+    SessionHistoryID
 }
 
 /// <https://html.spec.whatwg.org/multipage/browsing-the-web.html#document-state-2>
@@ -265,3 +355,46 @@ pub enum ReferrerPolicy {
     /// "unsafe-url"
     UnsafeUrl,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use wasmtime::Engine;
+
+    use super::*;
+
+    /// Record the created navigables and their openers.
+    struct Recorder(Rc<RefCell<Vec<(NavigableID, Option<NavigableID>)>>>);
+
+    impl NavigableObserver for Recorder {
+        fn navigable_created(&mut self, navigable: &Navigable, opener: Option<NavigableID>) {
+            self.0.borrow_mut().push((navigable.id(), opener));
+        }
+    }
+
+    #[test]
+    fn observers_are_notified_of_created_traversables() {
+        let mut user_agent = UserAgent::new(Engine::default());
+        let created = Rc::new(RefCell::new(Vec::new()));
+        user_agent.add_navigable_observer(Box::new(Recorder(created.clone())));
+        let second_created = Rc::new(RefCell::new(Vec::new()));
+        user_agent.add_navigable_observer(Box::new(Recorder(second_created.clone())));
+
+        let first = user_agent.open_window();
+        let second = user_agent.open_window();
+        let opened = user_agent.open_window_with_opener(first).unwrap();
+        let expected = [(first, None), (second, None), (opened, Some(first))];
+        assert_eq!(*created.borrow(), expected);
+        assert_eq!(*second_created.borrow(), expected);
+
+        let mut traversables = user_agent.top_level_traversables().collect::<Vec<_>>();
+        traversables.sort();
+        let mut ids = vec![first, second, opened];
+        ids.sort();
+        assert_eq!(traversables, ids);
+        for id in ids {
+            assert_eq!(Navigable::get(&user_agent, id).unwrap().id(), id);
+        }
+    }
+}
@@ -0,0 +1,519 @@
+//! A small path language to address nodes from host-side tooling.
+//!
+//! This is not XPath and it is not exposed to guests. A path is absolute and made of steps
+//! separated by `/`:
+//!
+//! - `div` selects element children whose qualified name is `div`. Like `getElementsByTagName`,
+//!   HTML elements in an HTML document also match if `div` in ASCII lowercase is their qualified
+//!   name, so `/HTML/BODY` selects the body.
+//! - `text()` selects text children.
+//! - `comment()` selects comment children.
+//! - `doctype()` selects doctype children.
+//!
+//! Each step can be followed by predicates, which are applied from left to right:
+//!
+//! - `[2]` keeps the second node selected so far. Indices start at 1.
+//! - `[@id='x']` keeps elements having an attribute `id` whose value is exactly `x`. Both `'` and `"`
+//!   can be used as quotes.
+//!
+//! The first remaining node of a step is used as the context of the next step. For example,
+//! `/html/body/div[2]/span[@id='x']/text()[1]` is the first text child of the `span` with id `x`
+//! in the second `div` of the body.
+//!
+//! [`Node::path_from_root`] generates the canonical path of a node, where every step has an index,
+//! e.g. `/html[1]/body[1]/div[2]`. Evaluating it with [`Document::query_path`] returns the same node.
+//! The path of a `Document` is `/`. Nodes whose root is not a document, i.e. detached nodes and nodes
+//! in a shadow tree, have no path.
+
+use std::{error::Error, fmt, str::FromStr};
+
+use wasmtime::AsContext;
+
+use crate::{Document, Node};
+
+/// A parsed path. See the [module documentation](self) for the syntax.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Path {
+    steps: Vec<Step>,
+}
+
+impl Path {
+    /// Evaluate the path from `document`, returning the first node it addresses.
+    pub fn evaluate(&self, document: &Document, store: impl AsContext) -> Option<Node> {
+        let is_html = document.is_html(&store);
+        let mut current = Node::from(document.clone());
+        for step in &self.steps {
+            current = step.select(&current, is_html, &store)?;
+        }
+        Some(current)
+    }
+}
+
+impl FromStr for Path {
+    type Err = PathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Parser {
+            input: s,
+            position: 0,
+        }
+        .parse()
+    }
+}
+
+/// A step of a [`Path`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Step {
+    test: NodeTest,
+    predicates: Vec<Predicate>,
+}
+
+impl Step {
+    /// Select the first child of `parent` matching this step.
+    fn select(&self, parent: &Node, is_html: bool, store: impl AsContext) -> Option<Node> {
+        let mut nodes: Vec<Node> = parent
+            .child_nodes(&store)
+            .into_iter()
+            .filter(|node| self.test.matches(node, is_html, &store))
+            .collect();
+        for predicate in &self.predicates {
+            nodes = match predicate {
+                Predicate::Position(index) => nodes.into_iter().skip(index - 1).take(1).collect(),
+                Predicate::Attribute { name, value } => nodes
+                    .into_iter()
+                    .filter(|node| {
+                        node.as_element(&store).is_some_and(|element| {
+                            element
+                                .get_attribute(name, &store)
+                                .is_some_and(|v| v == value.as_str())
+                        })
+                    })
+                    .collect(),
+            };
+        }
+        nodes.into_iter().next()
+    }
+}
+
+/// The kind of nodes a [`Step`] selects.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum NodeTest {
+    /// Elements whose qualified name matches the name.
+    Element(String),
+    /// Text nodes.
+    Text,
+    /// Comment nodes.
+    Comment,
+    /// Doctype nodes.
+    DocumentType,
+}
+
+impl NodeTest {
+    /// Create the test of the step selecting `node`, or `None` if no step can select it.
+    fn of(node: &Node, store: impl AsContext) -> Option<Self> {
+        if let Some(element) = node.as_element(&store) {
+            return Some(NodeTest::Element(element.qualified_name(&store).into()));
+        }
+        let data = node.data(&store);
+        if data.is_text() {
+            Some(NodeTest::Text)
+        } else if data.is_comment() {
+            Some(NodeTest::Comment)
+        } else if data.is_document_type() {
+            Some(NodeTest::DocumentType)
+        } else {
+            None
+        }
+    }
+
+    fn matches(&self, node: &Node, is_html: bool, store: impl AsContext) -> bool {
+        match self {
+            NodeTest::Element(name) => node.as_element(&store).is_some_and(|element| {
+                // An exact match is needed by elements created with createElementNS, whose
+                // qualified name may have uppercase letters even in the HTML namespace.
+                let tag = element.tag(&store);
+                tag.qualified_name() == name.as_str() || tag.matches_qualified_name(name, is_html)
+            }),
+            NodeTest::Text => node.data(&store).is_text(),
+            NodeTest::Comment => node.data(&store).is_comment(),
+            NodeTest::DocumentType => node.data(&store).is_document_type(),
+        }
+    }
+}
+
+impl fmt::Display for NodeTest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeTest::Element(name) => f.write_str(name),
+            NodeTest::Text => f.write_str("text()"),
+            NodeTest::Comment => f.write_str("comment()"),
+            NodeTest::DocumentType => f.write_str("doctype()"),
+        }
+    }
+}
+
+/// A predicate of a [`Step`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Predicate {
+    /// `[n]`, keep the n-th node. It starts at 1.
+    Position(usize),
+    /// `[@name='value']`, keep elements with the attribute.
+    Attribute { name: String, value: String },
+}
+
+/// An error while parsing a [`Path`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathError {
+    position: usize,
+    message: String,
+}
+
+impl PathError {
+    /// Get the byte offset in the path where the error occurred.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Get the message describing the error.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl Error for PathError {}
+
+/// Parser of [`Path`]. `position` is the byte offset of the next character.
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn parse(mut self) -> Result<Path, PathError> {
+        if !self.eat('/') {
+            return Err(self.error("path must start with '/'"));
+        }
+        let mut steps = Vec::new();
+        // The path of the document itself.
+        if self.peek().is_none() {
+            return Ok(Path { steps });
+        }
+        loop {
+            steps.push(self.step()?);
+            match self.peek() {
+                None => return Ok(Path { steps }),
+                Some('/') => self.position += 1,
+                Some(c) => return Err(self.error(format!("unexpected '{c}'"))),
+            }
+        }
+    }
+
+    fn step(&mut self) -> Result<Step, PathError> {
+        let start = self.position;
+        let name = self.name()?;
+        let test = if self.eat('(') {
+            self.expect(')')?;
+            match name.as_str() {
+                "text" => NodeTest::Text,
+                "comment" => NodeTest::Comment,
+                "doctype" => NodeTest::DocumentType,
+                _ => {
+                    return Err(PathError {
+                        position: start,
+                        message: format!("unknown node test {name}()"),
+                    });
+                }
+            }
+        } else {
+            NodeTest::Element(name)
+        };
+        let mut predicates = Vec::new();
+        while self.eat('[') {
+            predicates.push(self.predicate()?);
+            self.expect(']')?;
+        }
+        Ok(Step { test, predicates })
+    }
+
+    fn predicate(&mut self) -> Result<Predicate, PathError> {
+        if self.eat('@') {
+            let name = self.name()?;
+            self.expect('=')?;
+            let value = self.quoted()?;
+            return Ok(Predicate::Attribute { name, value });
+        }
+        let start = self.position;
+        let digits = self.take_while(|c| c.is_ascii_digit());
+        if digits.is_empty() {
+            return Err(self.error("expected an index or an attribute"));
+        }
+        match digits.parse::<usize>() {
+            Ok(index) if index > 0 => Ok(Predicate::Position(index)),
+            _ => Err(PathError {
+                position: start,
+                message: format!("invalid index {digits}, indices start at 1"),
+            }),
+        }
+    }
+
+    fn name(&mut self) -> Result<String, PathError> {
+        let name = self.take_while(|c| {
+            !c.is_ascii_whitespace()
+                && !matches!(c, '/' | '[' | ']' | '(' | ')' | '@' | '=' | '\'' | '"')
+        });
+        if name.is_empty() {
+            return Err(self.error("expected a name"));
+        }
+        Ok(name.to_owned())
+    }
+
+    fn quoted(&mut self) -> Result<String, PathError> {
+        let start = self.position;
+        let quote = match self.peek() {
+            Some(c @ ('\'' | '"')) => c,
+            _ => return Err(self.error("expected a quoted value")),
+        };
+        self.position += 1;
+        let value = self.take_while(|c| c != quote);
+        if !self.eat(quote) {
+            return Err(PathError {
+                position: start,
+                message: "unterminated quoted value".into(),
+            });
+        }
+        Ok(value.to_owned())
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let input: &'a str = self.input;
+        let rest = &input[self.position..];
+        let len = rest.find(|c| !f(c)).unwrap_or(rest.len());
+        self.position += len;
+        &rest[..len]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.position += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), PathError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected '{c}'")))
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> PathError {
+        PathError {
+            position: self.position,
+            message: message.into(),
+        }
+    }
+}
+
+/// Generate the canonical path of `node`. See [`Node::path_from_root`].
+pub(crate) fn path_from_root(node: &Node, store: impl AsContext) -> Option<String> {
+    let is_html = node
+        .data(&store)
+        .node_document()
+        .is_some_and(|document| document.is_html(&store));
+    let mut steps = Vec::new();
+    let mut current = node.clone();
+    while let Some(parent) = current.parent_node(&store) {
+        let test = NodeTest::of(&current, &store)?;
+        // The index counts the previous siblings matched by the same step, including the node itself.
+        let index = parent
+            .child_nodes(&store)
+            .into_iter()
+            .take_while(|sibling| !sibling.is_same_node(&current, &store))
+            .filter(|sibling| test.matches(sibling, is_html, &store))
+            .count()
+            + 1;
+        steps.push(format!("/{test}[{index}]"));
+        current = parent;
+    }
+    // Every path starts from a document, which contributes no step.
+    if current.as_document(&store).is_none() {
+        return None;
+    }
+    if steps.is_empty() {
+        return Some(String::from("/"));
+    }
+    Some(steps.into_iter().rev().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use wasmtime::Store;
+
+    use super::*;
+    use crate::{
+        ShadowRootInit, ShadowRootMode, SlotAssignmentMode,
+        testing::{self, HTML, element, text},
+    };
+
+    /// Build `<!DOCTYPE html><html><head></head><body>` followed by the children used by the tests.
+    fn tree() -> (crate::user_agent::UserAgent, Store<()>, Document) {
+        let (user_agent, mut store, document) = testing::document();
+        let root = Node::from(document.clone());
+        let doctype = document
+            .create_document_type("html".into(), "".into(), "".into(), &mut store)
+            .unwrap();
+        let html = root.first_child(&store);
+        root.pre_insert(doctype.into(), html.as_ref(), &mut store)
+            .unwrap();
+
+        let body = Node::from(document.body(&store).unwrap());
+        let comment = document.create_comment("c".into(), &mut store).unwrap();
+        body.append_child(comment.into(), &mut store);
+        body.append_child(element(&document, "div", &mut store).into(), &mut store);
+        let div = Node::from(element(&document, "div", &mut store));
+        body.append_child(div.clone(), &mut store);
+        let upper = document
+            .create_element_ns(Some(HTML.into()), "DIV".into(), &mut store)
+            .unwrap();
+        body.append_child(upper.into(), &mut store);
+
+        for id in ["y", "x"] {
+            let span = element(&document, "span", &mut store);
+            span.set_attribute("id", id, &mut store).unwrap();
+            Node::from(span.clone()).append_child(text(&document, id, &mut store), &mut store);
+            Node::from(span.clone()).append_child(text(&document, "!", &mut store), &mut store);
+            div.append_child(span.into(), &mut store);
+        }
+        (user_agent, store, document)
+    }
+
+    #[test]
+    fn paths_round_trip() {
+        let (_user_agent, store, document) = tree();
+        let mut stack = vec![Node::from(document.clone())];
+        let mut count = 0;
+        while let Some(node) = stack.pop() {
+            let path = node.path_from_root(&store).unwrap();
+            let found = document.query_path(&path, &store).unwrap().unwrap();
+            assert!(found.is_same_node(&node, &store), "{path}");
+            stack.extend(node.child_nodes(&store));
+            count += 1;
+        }
+        // The document, the doctype, html, head, body and its comment, three divs, two spans and
+        // their four text nodes.
+        assert_eq!(count, 15);
+    }
+
+    #[test]
+    fn canonical_paths() {
+        let (_user_agent, store, document) = tree();
+        let path = |path: &str| {
+            document
+                .query_path(path, &store)
+                .unwrap()
+                .and_then(|node| node.path_from_root(&store))
+        };
+        assert_eq!(path("/").as_deref(), Some("/"));
+        assert_eq!(path("/doctype()").as_deref(), Some("/doctype()[1]"));
+        assert_eq!(
+            path("/html/body/comment()").as_deref(),
+            Some("/html[1]/body[1]/comment()[1]")
+        );
+        assert_eq!(
+            path("/HTML/BODY/div[2]").as_deref(),
+            Some("/html[1]/body[1]/div[2]")
+        );
+        // Like getElementsByTagName, a `DIV` step selects `div` elements too.
+        assert_eq!(
+            path("/html/body/DIV").as_deref(),
+            Some("/html[1]/body[1]/div[1]")
+        );
+        assert_eq!(path("/html/body/div[3]"), None);
+        assert_eq!(
+            path("/html/body/div[2]/span[@id='x']/text()[2]").as_deref(),
+            Some("/html[1]/body[1]/div[2]/span[2]/text()[2]")
+        );
+        assert_eq!(
+            path("/html/body/div/span[@id=\"x\"]"),
+            None,
+            "the first div has no span"
+        );
+        assert_eq!(path("/html/body/div[2]/span[@id='z']"), None);
+    }
+
+    /// An HTML element created by createElementNS with an uppercase name is matched exactly, and the
+    /// `DIV` step matches the `div` elements before it too.
+    #[test]
+    fn uppercase_html_element_has_a_path() {
+        let (_user_agent, store, document) = tree();
+        let body = Node::from(document.body(&store).unwrap());
+        let upper = body.last_child(&store).unwrap();
+        assert_eq!(
+            upper.path_from_root(&store).as_deref(),
+            Some("/html[1]/body[1]/DIV[3]")
+        );
+    }
+
+    #[test]
+    fn nodes_outside_a_document_have_no_path() {
+        let (_user_agent, mut store, document) = tree();
+        let detached = Node::from(element(&document, "div", &mut store));
+        let child = text(&document, "x", &mut store);
+        detached.append_child(child.clone(), &mut store);
+        assert_eq!(detached.path_from_root(&store), None);
+        assert_eq!(child.path_from_root(&store), None);
+
+        let host = document.body(&store).unwrap();
+        let init = ShadowRootInit {
+            mode: ShadowRootMode::Open,
+            delegates_focus: false,
+            slot_assignment: SlotAssignmentMode::Named,
+        };
+        let shadow = Node::from(host.attach_shadow(init, &mut store).unwrap());
+        let inner = Node::from(element(&document, "span", &mut store));
+        shadow.append_child(inner.clone(), &mut store);
+        assert_eq!(shadow.path_from_root(&store), None);
+        assert_eq!(inner.path_from_root(&store), None);
+    }
+
+    #[test]
+    fn parse_errors_point_at_the_offending_position() {
+        let error = |path: &str| {
+            let error = path.parse::<Path>().unwrap_err();
+            (error.position(), error.message().to_owned())
+        };
+        assert_eq!(error("html"), (0, "path must start with '/'".into()));
+        assert_eq!(error("//"), (1, "expected a name".into()));
+        assert_eq!(error("/div]"), (4, "unexpected ']'".into()));
+        assert_eq!(
+            error("/div["),
+            (5, "expected an index or an attribute".into())
+        );
+        assert_eq!(
+            error("/div[0]"),
+            (5, "invalid index 0, indices start at 1".into())
+        );
+        assert_eq!(error("/div[1"), (6, "expected ']'".into()));
+        assert_eq!(error("/div[@id]"), (8, "expected '='".into()));
+        assert_eq!(error("/div[@id=x]"), (9, "expected a quoted value".into()));
+        assert_eq!(
+            error("/div[@id='x]"),
+            (9, "unterminated quoted value".into())
+        );
+        assert_eq!(error("/text("), (6, "expected ')'".into()));
+        assert_eq!(error("/node()"), (1, "unknown node test node()".into()));
+    }
+}
@@ -0,0 +1,215 @@
+//! A permissions policy controls which policy-controlled features a document and its embedded
+//! content can use.
+
+use std::collections::{HashMap, HashSet};
+
+use wasmtime::AsContext;
+
+use crate::{Element, Node, url::ImmutableOrigin};
+
+/// <https://w3c.github.io/webappsec-permissions-policy/#policy-controlled-feature>
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Feature {
+    /// "autoplay"
+    Autoplay,
+    /// "camera"
+    Camera,
+    /// "display-capture"
+    DisplayCapture,
+    /// "document-domain"
+    DocumentDomain,
+    /// "fullscreen"
+    Fullscreen,
+    /// "geolocation"
+    Geolocation,
+    /// "microphone"
+    Microphone,
+    /// "payment"
+    Payment,
+    /// "picture-in-picture"
+    PictureInPicture,
+}
+
+impl Feature {
+    /// All features supported by the user agent.
+    pub const ALL: [Feature; 9] = [
+        Feature::Autoplay,
+        Feature::Camera,
+        Feature::DisplayCapture,
+        Feature::DocumentDomain,
+        Feature::Fullscreen,
+        Feature::Geolocation,
+        Feature::Microphone,
+        Feature::Payment,
+        Feature::PictureInPicture,
+    ];
+
+    /// The token that names the feature in policy directives.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Feature::Autoplay => "autoplay",
+            Feature::Camera => "camera",
+            Feature::DisplayCapture => "display-capture",
+            Feature::DocumentDomain => "document-domain",
+            Feature::Fullscreen => "fullscreen",
+            Feature::Geolocation => "geolocation",
+            Feature::Microphone => "microphone",
+            Feature::Payment => "payment",
+            Feature::PictureInPicture => "picture-in-picture",
+        }
+    }
+
+    /// <https://w3c.github.io/webappsec-permissions-policy/#default-allowlist>
+    pub fn default_allowlist(&self) -> DefaultAllowlist {
+        match self {
+            Feature::DocumentDomain | Feature::PictureInPicture => DefaultAllowlist::Any,
+            _ => DefaultAllowlist::SelfOrigin,
+        }
+    }
+}
+
+/// <https://w3c.github.io/webappsec-permissions-policy/#default-allowlist>
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DefaultAllowlist {
+    /// `*`
+    Any,
+    /// `'self'`
+    SelfOrigin,
+}
+
+/// <https://w3c.github.io/webappsec-permissions-policy/#allowlists>
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Allowlist {
+    /// The special value `*`.
+    Any,
+    /// An ordered set of origins.
+    Origins(HashSet<ImmutableOrigin>),
+}
+
+impl Allowlist {
+    /// <https://w3c.github.io/webappsec-permissions-policy/#matches>
+    pub fn matches(&self, origin: &ImmutableOrigin) -> bool {
+        match self {
+            // 1. If allowlist is the special value *, then return true.
+            Allowlist::Any => true,
+            // 2. Otherwise, for each item in allowlist: if item is same origin-domain with origin,
+            // then return true.
+            // 3. Return false.
+            Allowlist::Origins(origins) => origins.contains(origin),
+        }
+    }
+}
+
+/// <https://w3c.github.io/webappsec-permissions-policy/#permissions-policy>
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PermissionsPolicy {
+    /// <https://w3c.github.io/webappsec-permissions-policy/#inherited-policy>
+    ///
+    /// A feature missing from the map is "Enabled".
+    inherited_policy: HashMap<Feature, bool>,
+    /// <https://w3c.github.io/webappsec-permissions-policy/#declared-policy>
+    declared_policy: HashMap<Feature, Allowlist>,
+    /// The origin of the document the policy is for.
+    origin: ImmutableOrigin,
+}
+
+impl PermissionsPolicy {
+    /// Create a permissions policy for `origin` which enables every feature it inherits.
+    pub fn new(origin: ImmutableOrigin) -> Self {
+        PermissionsPolicy {
+            inherited_policy: HashMap::new(),
+            declared_policy: HashMap::new(),
+            origin,
+        }
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#creating-a-permissions-policy>
+    pub fn create(
+        embedder: Option<&Element>,
+        origin: ImmutableOrigin,
+        store: impl AsContext,
+    ) -> Self {
+        // 1. Let inherited policy be a new ordered map.
+        let mut inherited_policy = HashMap::new();
+        // 2. For each feature supported, let isInherited be the result of running define an
+        // inherited policy for feature in container at origin, and set inherited policy[feature]
+        // to isInherited.
+        for feature in Feature::ALL {
+            let is_inherited = Self::define_inherited_policy(feature, embedder, &origin, &store);
+            inherited_policy.insert(feature, is_inherited);
+        }
+        // 3. Let policy be a new permissions policy, with inherited policy inherited policy,
+        // declared policy a new ordered map, and self-origin origin.
+        // 4. Return policy.
+        PermissionsPolicy {
+            inherited_policy,
+            declared_policy: HashMap::new(),
+            origin,
+        }
+    }
+
+    /// <https://w3c.github.io/webappsec-permissions-policy/#define-inherited-policy-in-container>
+    ///
+    /// This returns true for "Enabled" and false for "Disabled".
+    fn define_inherited_policy(
+        feature: Feature,
+        container: Option<&Element>,
+        origin: &ImmutableOrigin,
+        store: impl AsContext,
+    ) -> bool {
+        // 1. If container is null, return "Enabled".
+        let Some(document) =
+            container.and_then(|container| Node::from(container.clone()).owner_document(&store))
+        else {
+            return true;
+        };
+        // 2. If the result of executing Is feature enabled in document for origin? on feature,
+        // container’s node document, and container’s node document’s origin is "Disabled", return
+        // "Disabled".
+        let document_origin = document.origin(&store);
+        if !document.is_feature_enabled(feature, &document_origin, &store) {
+            return false;
+        }
+        // 3. If the result of executing Is feature enabled in document for origin? on feature,
+        // container’s node document, and origin is "Disabled", return "Disabled".
+        if !document.is_feature_enabled(feature, origin, &store) {
+            return false;
+        }
+        // 4 ~ 5. TODO: Process the permissions policy attributes of container, which is the
+        // container policy of the `allow` attribute.
+        // 6. If feature’s default allowlist is *, return "Enabled".
+        // 7. If feature’s default allowlist is 'self', and origin is same origin with container’s
+        // node document’s origin, return "Enabled".
+        // 8. Otherwise return "Disabled".
+        match feature.default_allowlist() {
+            DefaultAllowlist::Any => true,
+            DefaultAllowlist::SelfOrigin => *origin == document_origin,
+        }
+    }
+
+    /// <https://w3c.github.io/webappsec-permissions-policy/#is-feature-enabled>
+    ///
+    /// Check if `feature` is enabled for `origin` in the document this policy is for.
+    pub fn is_feature_enabled(&self, feature: Feature, origin: &ImmutableOrigin) -> bool {
+        // 1. Let policy be document’s permissions policy.
+        // 2. If policy’s inherited policy for feature is "Disabled", return "Disabled".
+        if self.inherited_policy.get(&feature) == Some(&false) {
+            return false;
+        }
+        // 3. If feature is present in policy’s declared policy:
+        if let Some(allowlist) = self.declared_policy.get(&feature) {
+            // 3.1 If the allowlist for feature in policy’s declared policy matches origin, then
+            // return "Enabled".
+            // 3.2 Otherwise return "Disabled".
+            return allowlist.matches(origin);
+        }
+        // 4. If feature’s default allowlist is *, return "Enabled".
+        // 5. If feature’s default allowlist is 'self', and origin is same origin with document’s
+        // origin, return "Enabled".
+        // 6. Return "Disabled".
+        match feature.default_allowlist() {
+            DefaultAllowlist::Any => true,
+            DefaultAllowlist::SelfOrigin => *origin == self.origin,
+        }
+    }
+}
@@ -4,16 +4,30 @@ use std::{
     borrow::{Borrow, Cow},
     fmt,
     ops::{Deref, DerefMut},
+    str::EncodeUtf16,
     sync::LazyLock,
 };
 
+use html5ever::{LocalName, Namespace};
 use malloc_size_of_derive::MallocSizeOf;
 use regex::Regex;
+use unicode_normalization::{UnicodeNormalization, is_nfc};
+
+use crate::DomException;
 
 /// A DOMString.
 ///
 /// This type corresponds to the [`DOMString`] type in WebIDL.
 ///
+/// A WebIDL `DOMString` is a sequence of UTF-16 code units, while this type stores UTF-8. Lengths
+/// and offsets seen by scripts are in code units, so use [`DOMString::len_utf16`] and
+/// [`DOMString::substring_utf16`] for them.
+///
+/// A lone surrogate can not be represented. An offset between the two code units of a surrogate
+/// pair is an "IndexSizeError" rather than splitting the pair, which is what
+/// [`DOMString::substring_utf16`] and the `CharacterData` methods do. Lone surrogates coming from
+/// UTF-16 become U+FFFD REPLACEMENT CHARACTER, see [`DOMString::from_utf16_lossy`].
+///
 /// [`DOMString`]: https://webidl.spec.whatwg.org/#idl-DOMString
 #[derive(Clone, Debug, Default, Eq, Hash, MallocSizeOf, Ord, PartialEq, PartialOrd)]
 pub struct DOMString(String);
@@ -34,6 +48,47 @@ impl DOMString {
         &self.0
     }
 
+    /// Creates a new `DOMString` from UTF-16 code units. Lone surrogates are replaced with
+    /// U+FFFD REPLACEMENT CHARACTER.
+    pub fn from_utf16_lossy(code_units: &[u16]) -> DOMString {
+        DOMString(String::from_utf16_lossy(code_units))
+    }
+
+    /// Get the UTF-16 code units of this `DOMString`.
+    pub fn to_utf16(&self) -> Vec<u16> {
+        self.code_units().collect()
+    }
+
+    /// Iterate over the UTF-16 code units of this `DOMString`.
+    pub fn code_units(&self) -> EncodeUtf16<'_> {
+        self.0.encode_utf16()
+    }
+
+    /// <https://infra.spec.whatwg.org/#string-length>
+    ///
+    /// The length of this `DOMString` in UTF-16 code units.
+    pub fn len_utf16(&self) -> usize {
+        self.code_units().count()
+    }
+
+    /// <https://infra.spec.whatwg.org/#code-unit-substring>
+    ///
+    /// Get the `count` code units from the `offset`th code unit, or until the end if there are
+    /// fewer. An "IndexSizeError" is returned if `offset` is greater than the length, or if either
+    /// end splits a surrogate pair.
+    pub fn substring_utf16(&self, offset: usize, count: usize) -> Result<DOMString, DomException> {
+        let code_units = self.to_utf16();
+        let length = code_units.len();
+        if offset > length {
+            return Err(DomException::IndexSizeError(format!(
+                "offset {offset} is greater than the length {length}"
+            )));
+        }
+        let end = offset.saturating_add(count).min(length);
+        check_surrogate_boundaries(&code_units, offset, end)?;
+        Ok(DOMString::from_utf16_lossy(&code_units[offset..end]))
+    }
+
     /// Appends a given string slice onto the end of this String.
     pub fn push_str(&mut self, string: &str) {
         self.0.push_str(string)
@@ -49,6 +104,17 @@ impl DOMString {
         self.0.truncate(new_len);
     }
 
+    /// Normalizes the string to Unicode Normalization Form C, so canonically equivalent strings
+    /// like a decomposed and a precomposed "é" compare equal.
+    ///
+    /// This is never done implicitly. Strings keep their code points as provided unless this is
+    /// called.
+    pub fn normalize_nfc(&mut self) {
+        if !is_nfc(&self.0) {
+            self.0 = self.0.nfc().collect();
+        }
+    }
+
     /// Removes newline characters according to <https://infra.spec.whatwg.org/#strip-newlines>.
     pub fn strip_newlines(&mut self) {
         self.0.retain(|c| c != '\r' && c != '\n');
@@ -74,6 +140,19 @@ impl DOMString {
         self.0.replace_range(0..first_non_whitespace, "");
     }
 
+    /// Replaces each run of ASCII whitespace with a single space, and removes leading and trailing
+    /// ASCII whitespace according to
+    /// <https://infra.spec.whatwg.org/#strip-and-collapse-ascii-whitespace>.
+    pub fn strip_and_collapse_ascii_whitespace(&mut self) {
+        let collapsed = self
+            .0
+            .split(|c: char| c.is_ascii_whitespace())
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        self.0 = collapsed;
+    }
+
     /// <https://html.spec.whatwg.org/multipage/#valid-floating-point-number>
     pub fn is_valid_floating_point_number_string(&self) -> bool {
         static RE: LazyLock<Regex> = LazyLock::new(|| {
@@ -103,6 +182,60 @@ impl DOMString {
         None
     }
 
+    /// <https://html.spec.whatwg.org/multipage/#rules-for-parsing-integers>
+    ///
+    /// An integer that does not fit in an `i64` is an error.
+    pub fn parse_integer(&self) -> Option<i64> {
+        // 1. Let input be the string being parsed.
+        // 2. Let position be a pointer into input, initially pointing at the start of the string.
+        // 3. Let sign have the value "positive".
+        // 4. Skip ASCII whitespace within input given position.
+        let input = self.0.trim_start_matches(|c: char| c.is_ascii_whitespace());
+        // 5. If position is past the end of input, return an error.
+        // 6. If the character indicated by position (the first character) is a U+002D HYPHEN-MINUS
+        // character (-): let sign be "negative" and advance position to the next character.
+        // Otherwise, if the character indicated by position (the first character) is a U+002B PLUS
+        // SIGN character (+): advance position to the next character.
+        // If position is past the end of input, return an error.
+        let (negative, input) = match input.as_bytes().first()? {
+            b'-' => (true, &input[1..]),
+            b'+' => (false, &input[1..]),
+            _ => (false, input),
+        };
+        // 7. If the character indicated by position is not an ASCII digit, then return an error.
+        // 8. Collect a sequence of code points that are ASCII digits from input given position, and
+        // interpret the resulting sequence as a base-ten integer. Let value be that integer.
+        let digits = input
+            .find(|c: char| !c.is_ascii_digit())
+            .map_or(input, |end| &input[..end]);
+        if digits.is_empty() {
+            return None;
+        }
+        let value = digits.bytes().try_fold(0i64, |value, digit| {
+            let digit = i64::from(digit - b'0');
+            // The value is accumulated as a negative number, so the minimum of `i64` is allowed.
+            value.checked_mul(10)?.checked_sub(digit)
+        })?;
+        // 9. If sign is "positive", return value, otherwise return the result of subtracting value
+        // from zero.
+        if negative {
+            Some(value)
+        } else {
+            value.checked_neg()
+        }
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#rules-for-parsing-non-negative-integers>
+    pub fn parse_non_negative_integer(&self) -> Option<u64> {
+        // 1. Let input be the string being parsed.
+        // 2. Let value be the result of parsing input using the rules for parsing integers.
+        // 3. If value is an error, return an error.
+        let value = self.parse_integer()?;
+        // 4. If value is less than zero, return an error.
+        // 5. Return value.
+        u64::try_from(value).ok()
+    }
+
     /// Applies the same processing as `parse_floating_point_number` with some additional handling
     /// according to ECMA's string conversion steps.
     ///
@@ -194,19 +327,19 @@ impl<'a> From<Cow<'a, str>> for DOMString {
     }
 }
 
-// TODO: implement this when html5ever is imported.
-// impl From<DOMString> for LocalName {
-//     fn from(contents: DOMString) -> LocalName {
-//         LocalName::from(contents.0)
-//     }
-// }
-//
-// impl From<DOMString> for Namespace {
-//     fn from(contents: DOMString) -> Namespace {
-//         Namespace::from(contents.0)
-//     }
-// }
-//
+impl From<DOMString> for LocalName {
+    fn from(contents: DOMString) -> LocalName {
+        LocalName::from(contents.0)
+    }
+}
+
+impl From<DOMString> for Namespace {
+    fn from(contents: DOMString) -> Namespace {
+        Namespace::from(contents.0)
+    }
+}
+
+// TODO: implement this when servo_atoms and cssparser are imported.
 // impl From<DOMString> for Atom {
 //     fn from(contents: DOMString) -> Atom {
 //         Atom::from(contents.0)
@@ -245,3 +378,161 @@ impl Extend<char> for DOMString {
         self.0.extend(iterable)
     }
 }
+
+/// Return an "IndexSizeError" if `start` or `end` falls between the two code units of a surrogate
+/// pair in `code_units`.
+pub(crate) fn check_surrogate_boundaries(
+    code_units: &[u16],
+    start: usize,
+    end: usize,
+) -> Result<(), DomException> {
+    // The code units come from valid UTF-8, so a trail surrogate is always preceded by a lead
+    // surrogate.
+    for index in [start, end] {
+        if code_units
+            .get(index)
+            .is_some_and(|unit| (0xDC00..=0xDFFF).contains(unit))
+        {
+            return Err(DomException::IndexSizeError(format!(
+                "offset {index} splits a surrogate pair"
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_nfc_composes_a_decomposed_e_acute() {
+        let precomposed = DOMString::from("caf\u{e9}");
+        let mut decomposed = DOMString::from("cafe\u{301}");
+        // Nothing is normalized until asked.
+        assert_ne!(decomposed, precomposed);
+        assert_eq!(decomposed.len_utf16(), 5);
+
+        decomposed.normalize_nfc();
+        assert_eq!(decomposed, precomposed);
+        assert_eq!(decomposed.len_utf16(), 4);
+
+        // Normalizing a string already in NFC keeps it as is.
+        let mut normalized = precomposed.clone();
+        normalized.normalize_nfc();
+        assert_eq!(normalized, precomposed);
+    }
+
+    #[test]
+    fn lengths_and_code_units_are_utf16() {
+        let string = DOMString::from("a\u{e9}\u{1F600}");
+        assert_eq!(string.len(), 7);
+        assert_eq!(string.len_utf16(), 4);
+        assert_eq!(
+            string.code_units().collect::<Vec<_>>(),
+            [0x61, 0xE9, 0xD83D, 0xDE00]
+        );
+        assert_eq!(string.to_utf16(), string.code_units().collect::<Vec<_>>());
+        assert_eq!(DOMString::new().len_utf16(), 0);
+    }
+
+    #[test]
+    fn substrings_are_taken_in_code_units() {
+        let string = DOMString::from("a\u{1F600}b");
+        assert_eq!(string.substring_utf16(1, 2).unwrap(), "\u{1F600}");
+        assert_eq!(string.substring_utf16(0, 1).unwrap(), "a");
+        assert_eq!(string.substring_utf16(3, 10).unwrap(), "b");
+        assert_eq!(string.substring_utf16(4, 1).unwrap(), "");
+        assert_eq!(string.substring_utf16(0, usize::MAX).unwrap(), string);
+        assert!(matches!(
+            string.substring_utf16(5, 0),
+            Err(DomException::IndexSizeError(_))
+        ));
+    }
+
+    #[test]
+    fn substrings_do_not_split_surrogate_pairs() {
+        let string = DOMString::from("a\u{1F600}b");
+        // The start, the end, or both would fall between the surrogates.
+        for (offset, count) in [(2, 1), (0, 2), (2, 0)] {
+            assert!(
+                matches!(
+                    string.substring_utf16(offset, count),
+                    Err(DomException::IndexSizeError(_))
+                ),
+                "{offset}, {count}"
+            );
+        }
+    }
+
+    #[test]
+    fn lone_surrogates_from_utf16_are_replaced() {
+        let emoji = [0xD83D, 0xDE00];
+        assert_eq!(DOMString::from_utf16_lossy(&emoji), "\u{1F600}");
+        assert_eq!(DOMString::from_utf16_lossy(&emoji[..1]), "\u{FFFD}");
+        assert_eq!(
+            DOMString::from_utf16_lossy(&[0x61, 0xDE00, 0x62]),
+            "a\u{FFFD}b"
+        );
+        // A lead surrogate followed by another lead surrogate is lone too.
+        assert_eq!(
+            DOMString::from_utf16_lossy(&[0xD83D, 0xD83D, 0xDE00]),
+            "\u{FFFD}\u{1F600}"
+        );
+        assert_eq!(DOMString::from_utf16_lossy(&[]), "");
+    }
+
+    #[test]
+    fn parse_integer_follows_the_html_rules() {
+        let cases = [
+            ("42", Some(42)),
+            ("+42", Some(42)),
+            ("-42", Some(-42)),
+            ("-0", Some(0)),
+            ("  \t\n\x0C\r42", Some(42)),
+            ("42px", Some(42)),
+            ("007", Some(7)),
+            ("9223372036854775807", Some(i64::MAX)),
+            ("-9223372036854775808", Some(i64::MIN)),
+            ("9223372036854775808", None),
+            ("-9223372036854775809", None),
+            ("99999999999999999999", None),
+            ("", None),
+            ("   ", None),
+            ("-", None),
+            ("+-1", None),
+            ("- 1", None),
+            ("px42", None),
+            // U+00A0 NO-BREAK SPACE is not ASCII whitespace.
+            ("\u{A0}42", None),
+            ("\u{1F600}", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                DOMString::from(input).parse_integer(),
+                expected,
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_non_negative_integer_rejects_negative_values() {
+        let cases = [
+            ("42", Some(42)),
+            ("-0", Some(0)),
+            (" 7", Some(7)),
+            ("-1", None),
+            ("9223372036854775807", Some(i64::MAX as u64)),
+            ("18446744073709551615", None),
+            ("", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                DOMString::from(input).parse_non_negative_integer(),
+                expected,
+                "{input:?}"
+            );
+        }
+    }
+}
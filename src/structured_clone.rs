@@ -0,0 +1,772 @@
+//! Structured serialization of values passed between realms.
+//!
+//! Guests hand their values to the host as a graph of [`Value`] nodes, which is serialized to a
+//! [`SerializedValue`]. A serialized value doesn't belong to any realm or `Store`, so it can be kept
+//! by session history entries or carried to another window, then deserialized there.
+//!
+//! An object reachable more than once is serialized once, and its other occurrences refer back to
+//! it, so its identity survives the round trip. Cycles fail with a "DataCloneError".
+
+use std::collections::HashMap;
+
+use wasmtime::Result;
+
+use crate::{
+    DomException, WindowStates,
+    ohim::dom::{
+        node::DomException as WitDomException,
+        structured_clone::{Host, Value as WitValue},
+    },
+};
+
+/// The maximum depth of nested values, so a deeply nested value can't overflow the call stack.
+const MAX_DEPTH: usize = 1024;
+
+/// A node of the value graph of a guest.
+///
+/// Composite values refer to their items by the index of the item in the graph, so a graph can
+/// share and cycle like JavaScript objects do.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// `undefined`
+    Undefined,
+    /// `null`
+    Null,
+    /// A boolean
+    Boolean(bool),
+    /// A number
+    Number(f64),
+    /// A string
+    String(String),
+    /// The bytes of an `ArrayBuffer`
+    ArrayBuffer(Vec<u8>),
+    /// An array of the values at these indices
+    Array(Vec<usize>),
+    /// A plain object with these properties, in order
+    Object(Vec<(String, usize)>),
+    /// A `Map` with these entries, in order
+    Map(Vec<(usize, usize)>),
+}
+
+/// The serialized form of a value.
+///
+/// It can be stored anywhere and round-trips through [`SerializedValue::encode`] and
+/// [`SerializedValue::decode`] byte for byte.
+///
+/// Array buffers, arrays, objects and maps are numbered in the order they first appear, depth
+/// first. A [`SerializedValue::Reference`] stands for another occurrence of the one with its number.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SerializedValue {
+    /// `undefined`
+    Undefined,
+    /// `null`
+    Null,
+    /// A boolean
+    Boolean(bool),
+    /// A number
+    Number(f64),
+    /// A string
+    String(String),
+    /// The bytes of an `ArrayBuffer`
+    ArrayBuffer(Vec<u8>),
+    /// An array
+    Array(Vec<SerializedValue>),
+    /// A plain object with its properties in order
+    Object(Vec<(String, SerializedValue)>),
+    /// A `Map` with its entries in order
+    Map(Vec<(SerializedValue, SerializedValue)>),
+    /// The array buffer, array, object or map with this number, which appears before
+    Reference(usize),
+}
+
+/// <https://html.spec.whatwg.org/multipage/#structuredserialize>
+///
+/// Serialize the value at index `root` of `graph`. This throws a "DataCloneError" if the value has
+/// a cycle or refers to an index out of `graph`.
+pub fn structured_serialize(graph: &[Value], root: usize) -> Result<SerializedValue, DomException> {
+    Serializer {
+        graph,
+        memory: HashMap::new(),
+        ancestors: Vec::new(),
+    }
+    .serialize_internal(root)
+}
+
+/// The state of one [`structured_serialize`] call.
+struct Serializer<'a> {
+    graph: &'a [Value],
+    /// <https://html.spec.whatwg.org/multipage/#structuredserializeinternal>
+    ///
+    /// The memory, from the index of each array buffer, array, object or map serialized so far to
+    /// its number.
+    memory: HashMap<usize, usize>,
+    /// The indices of the composite values being serialized, which contain the current one.
+    ancestors: Vec<usize>,
+}
+
+impl Serializer<'_> {
+    /// <https://html.spec.whatwg.org/multipage/#structuredserializeinternal>
+    fn serialize_internal(&mut self, index: usize) -> Result<SerializedValue, DomException> {
+        let graph = self.graph;
+        let value = graph.get(index).ok_or_else(|| {
+            DomException::DataCloneError(format!("value {index} is not in the value graph"))
+        })?;
+        if self.ancestors.contains(&index) {
+            return Err(DomException::DataCloneError(format!(
+                "value {index} is part of a cycle"
+            )));
+        }
+        // If memory[value] exists, then return memory[value].
+        if let Some(&number) = self.memory.get(&index) {
+            return Ok(SerializedValue::Reference(number));
+        }
+        if self.ancestors.len() >= MAX_DEPTH {
+            return Err(DomException::DataCloneError(format!(
+                "value {index} is nested too deeply"
+            )));
+        }
+        // Primitives are serialized as { [[Type]]: "primitive", [[Value]]: value }, and are not
+        // kept in the memory.
+        let serialized = match value {
+            Value::Undefined => return Ok(SerializedValue::Undefined),
+            Value::Null => return Ok(SerializedValue::Null),
+            Value::Boolean(b) => return Ok(SerializedValue::Boolean(*b)),
+            Value::Number(n) => return Ok(SerializedValue::Number(*n)),
+            Value::String(s) => return Ok(SerializedValue::String(s.clone())),
+            // Set memory[value] to serialized.
+            _ => {
+                self.memory.insert(index, self.memory.len());
+                self.ancestors.push(index);
+                self.serialize_object(value)?
+            }
+        };
+        self.ancestors.pop();
+        Ok(serialized)
+    }
+
+    /// Serialize the array buffer, array, object or map `value`, whose items are serialized
+    /// deeply, in order.
+    fn serialize_object(&mut self, value: &Value) -> Result<SerializedValue, DomException> {
+        Ok(match value {
+            Value::ArrayBuffer(bytes) => SerializedValue::ArrayBuffer(bytes.clone()),
+            Value::Array(items) => SerializedValue::Array(
+                items
+                    .iter()
+                    .map(|&item| self.serialize_internal(item))
+                    .collect::<Result<_, DomException>>()?,
+            ),
+            Value::Object(properties) => SerializedValue::Object(
+                properties
+                    .iter()
+                    .map(|(key, value)| Ok((key.clone(), self.serialize_internal(*value)?)))
+                    .collect::<Result<_, DomException>>()?,
+            ),
+            Value::Map(entries) => SerializedValue::Map(
+                entries
+                    .iter()
+                    .map(|&(key, value)| {
+                        Ok((
+                            self.serialize_internal(key)?,
+                            self.serialize_internal(value)?,
+                        ))
+                    })
+                    .collect::<Result<_, DomException>>()?,
+            ),
+            Value::Undefined
+            | Value::Null
+            | Value::Boolean(_)
+            | Value::Number(_)
+            | Value::String(_) => unreachable!("primitives are serialized by serialize_internal"),
+        })
+    }
+}
+
+/// <https://html.spec.whatwg.org/multipage/#structureddeserialize>
+///
+/// Deserialize `serialized` into a new value graph, whose root is at index 0. Every occurrence of
+/// an object deserializes to the same index. This throws a "DataCloneError" if a
+/// [`SerializedValue::Reference`] doesn't refer to an object that appears before it.
+pub fn structured_deserialize(serialized: &SerializedValue) -> Result<Vec<Value>, DomException> {
+    let mut graph = Vec::new();
+    deserialize_internal(serialized, &mut graph, &mut Vec::new())?;
+    Ok(graph)
+}
+
+/// Append `serialized` to `graph` and return its index.
+///
+/// `memory` holds the index of each object deserialized so far, by number.
+fn deserialize_internal(
+    serialized: &SerializedValue,
+    graph: &mut Vec<Value>,
+    memory: &mut Vec<usize>,
+) -> Result<usize, DomException> {
+    // If memory[serialized] exists, then return memory[serialized].
+    if let SerializedValue::Reference(number) = serialized {
+        return memory.get(*number).copied().ok_or_else(|| {
+            DomException::DataCloneError(format!(
+                "object {number} is referred to before it appears"
+            ))
+        });
+    }
+    let index = graph.len();
+    // The node is replaced once its items are deserialized, so the root of each subtree comes
+    // before its items.
+    graph.push(Value::Undefined);
+    let value = match serialized {
+        SerializedValue::Undefined | SerializedValue::Reference(_) => Value::Undefined,
+        SerializedValue::Null => Value::Null,
+        SerializedValue::Boolean(b) => Value::Boolean(*b),
+        SerializedValue::Number(n) => Value::Number(*n),
+        SerializedValue::String(s) => Value::String(s.clone()),
+        SerializedValue::ArrayBuffer(bytes) => {
+            memory.push(index);
+            Value::ArrayBuffer(bytes.clone())
+        }
+        SerializedValue::Array(items) => {
+            memory.push(index);
+            Value::Array(
+                items
+                    .iter()
+                    .map(|item| deserialize_internal(item, graph, memory))
+                    .collect::<Result<_, DomException>>()?,
+            )
+        }
+        SerializedValue::Object(properties) => {
+            memory.push(index);
+            Value::Object(
+                properties
+                    .iter()
+                    .map(|(key, value)| {
+                        Ok((key.clone(), deserialize_internal(value, graph, memory)?))
+                    })
+                    .collect::<Result<_, DomException>>()?,
+            )
+        }
+        SerializedValue::Map(entries) => {
+            memory.push(index);
+            Value::Map(
+                entries
+                    .iter()
+                    .map(|(key, value)| {
+                        let key = deserialize_internal(key, graph, memory)?;
+                        Ok((key, deserialize_internal(value, graph, memory)?))
+                    })
+                    .collect::<Result<_, DomException>>()?,
+            )
+        }
+    };
+    graph[index] = value;
+    Ok(index)
+}
+
+/// Tags of the byte format, one for each variant of `SerializedValue`.
+mod tag {
+    pub(super) const UNDEFINED: u8 = 0;
+    pub(super) const NULL: u8 = 1;
+    pub(super) const FALSE: u8 = 2;
+    pub(super) const TRUE: u8 = 3;
+    pub(super) const NUMBER: u8 = 4;
+    pub(super) const STRING: u8 = 5;
+    pub(super) const ARRAY_BUFFER: u8 = 6;
+    pub(super) const ARRAY: u8 = 7;
+    pub(super) const OBJECT: u8 = 8;
+    pub(super) const MAP: u8 = 9;
+    pub(super) const REFERENCE: u8 = 10;
+}
+
+impl SerializedValue {
+    /// Encode this value to bytes.
+    ///
+    /// Each value is a tag byte followed by its payload. Numbers are 8 bytes of little-endian
+    /// IEEE 754, and lengths are unsigned LEB128. Strings and array buffers are a length followed by
+    /// their bytes, composite values are a length followed by their items, and references are the
+    /// number of the object they refer to, as a LEB128 length.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.encode_to(&mut bytes);
+        bytes
+    }
+
+    fn encode_to(&self, bytes: &mut Vec<u8>) {
+        match self {
+            SerializedValue::Undefined => bytes.push(tag::UNDEFINED),
+            SerializedValue::Null => bytes.push(tag::NULL),
+            SerializedValue::Boolean(false) => bytes.push(tag::FALSE),
+            SerializedValue::Boolean(true) => bytes.push(tag::TRUE),
+            SerializedValue::Number(n) => {
+                bytes.push(tag::NUMBER);
+                bytes.extend_from_slice(&n.to_le_bytes());
+            }
+            SerializedValue::String(s) => {
+                bytes.push(tag::STRING);
+                encode_bytes(s.as_bytes(), bytes);
+            }
+            SerializedValue::ArrayBuffer(buffer) => {
+                bytes.push(tag::ARRAY_BUFFER);
+                encode_bytes(buffer, bytes);
+            }
+            SerializedValue::Array(items) => {
+                bytes.push(tag::ARRAY);
+                encode_length(items.len(), bytes);
+                for item in items {
+                    item.encode_to(bytes);
+                }
+            }
+            SerializedValue::Object(properties) => {
+                bytes.push(tag::OBJECT);
+                encode_length(properties.len(), bytes);
+                for (key, value) in properties {
+                    encode_bytes(key.as_bytes(), bytes);
+                    value.encode_to(bytes);
+                }
+            }
+            SerializedValue::Map(entries) => {
+                bytes.push(tag::MAP);
+                encode_length(entries.len(), bytes);
+                for (key, value) in entries {
+                    key.encode_to(bytes);
+                    value.encode_to(bytes);
+                }
+            }
+            SerializedValue::Reference(number) => {
+                bytes.push(tag::REFERENCE);
+                encode_length(*number, bytes);
+            }
+        }
+    }
+
+    /// Decode a value encoded by [`SerializedValue::encode`].
+    ///
+    /// This throws a "DataCloneError" if `bytes` is not exactly one encoded value, as `encode`
+    /// writes it. Lengths must be in their shortest form, and references must refer to an object
+    /// that was decoded before, not to one that contains them.
+    pub fn decode(bytes: &[u8]) -> Result<Self, DomException> {
+        let mut decoder = Decoder {
+            bytes,
+            position: 0,
+            depth: 0,
+            objects: Vec::new(),
+        };
+        let value = decoder.value()?;
+        if decoder.position != bytes.len() {
+            return Err(decoder.error("trailing bytes"));
+        }
+        Ok(value)
+    }
+}
+
+fn encode_length(mut length: usize, bytes: &mut Vec<u8>) {
+    loop {
+        let byte = (length & 0x7f) as u8;
+        length >>= 7;
+        if length == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+fn encode_bytes(data: &[u8], bytes: &mut Vec<u8>) {
+    encode_length(data.len(), bytes);
+    bytes.extend_from_slice(data);
+}
+
+/// Decoder of the byte format. `position` is the offset of the next byte, and `depth` is the number
+/// of composite values being decoded. `objects` tells, for each object numbered so far, whether it
+/// is fully decoded.
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    depth: usize,
+    objects: Vec<bool>,
+}
+
+impl<'a> Decoder<'a> {
+    fn value(&mut self) -> Result<SerializedValue, DomException> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error("value nested too deeply"));
+        }
+        self.depth += 1;
+        let tag = self.take(1)?[0];
+        let object =
+            matches!(tag, tag::ARRAY_BUFFER | tag::ARRAY | tag::OBJECT | tag::MAP).then(|| {
+                self.objects.push(false);
+                self.objects.len() - 1
+            });
+        let value = match tag {
+            tag::UNDEFINED => SerializedValue::Undefined,
+            tag::NULL => SerializedValue::Null,
+            tag::FALSE => SerializedValue::Boolean(false),
+            tag::TRUE => SerializedValue::Boolean(true),
+            tag::NUMBER => {
+                let mut number = [0; 8];
+                number.copy_from_slice(self.take(8)?);
+                SerializedValue::Number(f64::from_le_bytes(number))
+            }
+            tag::STRING => SerializedValue::String(self.string()?),
+            tag::ARRAY_BUFFER => {
+                let length = self.length()?;
+                SerializedValue::ArrayBuffer(self.take(length)?.to_vec())
+            }
+            tag::ARRAY => {
+                let length = self.length()?;
+                let mut items = Vec::new();
+                for _ in 0..length {
+                    items.push(self.value()?);
+                }
+                SerializedValue::Array(items)
+            }
+            tag::OBJECT => {
+                let length = self.length()?;
+                let mut properties = Vec::new();
+                for _ in 0..length {
+                    properties.push((self.string()?, self.value()?));
+                }
+                SerializedValue::Object(properties)
+            }
+            tag::MAP => {
+                let length = self.length()?;
+                let mut entries = Vec::new();
+                for _ in 0..length {
+                    entries.push((self.value()?, self.value()?));
+                }
+                SerializedValue::Map(entries)
+            }
+            tag::REFERENCE => {
+                let start = self.position;
+                let number = self.length()?;
+                if !self.objects.get(number).is_some_and(|decoded| *decoded) {
+                    self.position = start;
+                    return Err(self.error(format!("invalid reference to object {number}")));
+                }
+                SerializedValue::Reference(number)
+            }
+            tag => {
+                self.position -= 1;
+                return Err(self.error(format!("unknown tag {tag}")));
+            }
+        };
+        if let Some(number) = object {
+            self.objects[number] = true;
+        }
+        self.depth -= 1;
+        Ok(value)
+    }
+
+    fn string(&mut self) -> Result<String, DomException> {
+        let length = self.length()?;
+        let start = self.position;
+        let string = std::str::from_utf8(self.take(length)?).map_err(|_| {
+            self.position = start;
+            self.error("invalid UTF-8 string")
+        })?;
+        Ok(string.to_owned())
+    }
+
+    fn length(&mut self) -> Result<usize, DomException> {
+        let mut length: usize = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.take(1)?[0];
+            let bits = usize::from(byte & 0x7f);
+            if shift >= usize::BITS || (bits << shift) >> shift != bits {
+                return Err(self.error("length overflow"));
+            }
+            length |= bits << shift;
+            if byte & 0x80 == 0 {
+                // A last byte of 0 would make a longer encoding of the same length.
+                if byte == 0 && shift > 0 {
+                    return Err(self.error("length not in its shortest form"));
+                }
+                return Ok(length);
+            }
+            shift += 7;
+        }
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], DomException> {
+        let bytes: &'a [u8] = self.bytes;
+        let taken = self
+            .position
+            .checked_add(length)
+            .and_then(|end| bytes.get(self.position..end))
+            .ok_or_else(|| self.error("unexpected end of input"))?;
+        self.position += length;
+        Ok(taken)
+    }
+
+    fn error(&self, message: impl Into<String>) -> DomException {
+        DomException::DataCloneError(format!(
+            "{} at byte {} of serialized value",
+            message.into(),
+            self.position
+        ))
+    }
+}
+
+impl From<WitValue> for Value {
+    fn from(value: WitValue) -> Self {
+        let index = |index: u32| index as usize;
+        match value {
+            WitValue::Undefined => Value::Undefined,
+            WitValue::Null => Value::Null,
+            WitValue::Boolean(b) => Value::Boolean(b),
+            WitValue::Number(n) => Value::Number(n),
+            WitValue::String(s) => Value::String(s),
+            WitValue::ArrayBuffer(bytes) => Value::ArrayBuffer(bytes),
+            WitValue::Array(items) => Value::Array(items.into_iter().map(index).collect()),
+            WitValue::Object(properties) => Value::Object(
+                properties
+                    .into_iter()
+                    .map(|(key, value)| (key, index(value)))
+                    .collect(),
+            ),
+            WitValue::Map(entries) => Value::Map(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (index(key), index(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl TryFrom<Value> for WitValue {
+    type Error = DomException;
+
+    /// This throws a "DataCloneError" if an index doesn't fit in `u32`.
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let index = |index: usize| {
+            u32::try_from(index).map_err(|_| {
+                DomException::DataCloneError(format!("value {index} is out of the WIT index range"))
+            })
+        };
+        Ok(match value {
+            Value::Undefined => WitValue::Undefined,
+            Value::Null => WitValue::Null,
+            Value::Boolean(b) => WitValue::Boolean(b),
+            Value::Number(n) => WitValue::Number(n),
+            Value::String(s) => WitValue::String(s),
+            Value::ArrayBuffer(bytes) => WitValue::ArrayBuffer(bytes),
+            Value::Array(items) => {
+                WitValue::Array(items.into_iter().map(index).collect::<Result<_, _>>()?)
+            }
+            Value::Object(properties) => WitValue::Object(
+                properties
+                    .into_iter()
+                    .map(|(key, value)| Ok((key, index(value)?)))
+                    .collect::<Result<_, DomException>>()?,
+            ),
+            Value::Map(entries) => WitValue::Map(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| Ok((index(key)?, index(value)?)))
+                    .collect::<Result<_, DomException>>()?,
+            ),
+        })
+    }
+}
+
+impl Host for WindowStates {
+    fn serialize(&mut self, graph: Vec<WitValue>) -> Result<Result<Vec<u8>, WitDomException>> {
+        let graph: Vec<Value> = graph.into_iter().map(Into::into).collect();
+        Ok(structured_serialize(&graph, 0)
+            .map(|serialized| serialized.encode())
+            .map_err(Into::into))
+    }
+
+    fn deserialize(&mut self, bytes: Vec<u8>) -> Result<Result<Vec<WitValue>, WitDomException>> {
+        Ok(SerializedValue::decode(&bytes)
+            .and_then(|serialized| {
+                structured_deserialize(&serialized)?
+                    .into_iter()
+                    .map(TryInto::try_into)
+                    .collect()
+            })
+            .map_err(Into::into))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_data_clone_error<T: std::fmt::Debug>(result: Result<T, DomException>) -> bool {
+        matches!(result, Err(DomException::DataCloneError(_)))
+    }
+
+    #[test]
+    fn nested_values_round_trip() {
+        let graph = vec![
+            Value::Object(vec![("list".into(), 1), ("map".into(), 4)]),
+            Value::Array(vec![2, 3]),
+            Value::Number(-0.5),
+            Value::ArrayBuffer(vec![0, 255]),
+            Value::Map(vec![(5, 6)]),
+            Value::String("key".into()),
+            Value::Array(vec![7]),
+            Value::Boolean(true),
+        ];
+        let serialized = structured_serialize(&graph, 0).unwrap();
+        assert_eq!(
+            serialized,
+            SerializedValue::Object(vec![
+                (
+                    "list".into(),
+                    SerializedValue::Array(vec![
+                        SerializedValue::Number(-0.5),
+                        SerializedValue::ArrayBuffer(vec![0, 255]),
+                    ])
+                ),
+                (
+                    "map".into(),
+                    SerializedValue::Map(vec![(
+                        SerializedValue::String("key".into()),
+                        SerializedValue::Array(vec![SerializedValue::Boolean(true)]),
+                    )])
+                ),
+            ])
+        );
+        let decoded = SerializedValue::decode(&serialized.encode()).unwrap();
+        assert_eq!(decoded, serialized);
+        // The graph is listed depth first, which is the order it is deserialized in.
+        assert_eq!(structured_deserialize(&decoded).unwrap(), graph);
+    }
+
+    #[test]
+    fn cycles_are_data_clone_errors() {
+        let itself = [Value::Array(vec![0])];
+        assert!(is_data_clone_error(structured_serialize(&itself, 0)));
+        let through_an_object = [
+            Value::Map(vec![(1, 2)]),
+            Value::Null,
+            Value::Object(vec![("parent".into(), 0)]),
+        ];
+        assert!(is_data_clone_error(structured_serialize(
+            &through_an_object,
+            0
+        )));
+        let out_of_the_graph = [Value::Array(vec![1])];
+        assert!(is_data_clone_error(structured_serialize(
+            &out_of_the_graph,
+            0
+        )));
+    }
+
+    #[test]
+    fn shared_values_keep_their_identity() {
+        let graph = vec![
+            Value::Array(vec![1, 1, 2]),
+            Value::ArrayBuffer(vec![7]),
+            Value::Object(vec![("buffer".into(), 1)]),
+        ];
+        let serialized = structured_serialize(&graph, 0).unwrap();
+        assert_eq!(
+            serialized,
+            SerializedValue::Array(vec![
+                SerializedValue::ArrayBuffer(vec![7]),
+                SerializedValue::Reference(1),
+                SerializedValue::Object(vec![("buffer".into(), SerializedValue::Reference(1))]),
+            ])
+        );
+        assert_eq!(structured_deserialize(&serialized).unwrap(), graph);
+    }
+
+    #[test]
+    fn shared_values_are_serialized_once() {
+        // Each array lists the next one twice, so the value has 2^60 paths to the last array.
+        let mut graph: Vec<_> = (1..60).map(|next| Value::Array(vec![next, next])).collect();
+        graph.push(Value::Array(Vec::new()));
+        let serialized = structured_serialize(&graph, 0).unwrap();
+        let bytes = serialized.encode();
+        assert!(bytes.len() < 60 * 8);
+        assert_eq!(
+            structured_deserialize(&SerializedValue::decode(&bytes).unwrap()).unwrap(),
+            graph
+        );
+    }
+
+    #[test]
+    fn encoding_is_byte_exact() {
+        let value = SerializedValue::Object(vec![
+            (
+                "a".into(),
+                SerializedValue::Array(vec![
+                    SerializedValue::Number(1.0),
+                    SerializedValue::ArrayBuffer(vec![9]),
+                    SerializedValue::Reference(2),
+                ]),
+            ),
+            ("b".into(), SerializedValue::String("é".into())),
+        ]);
+        #[rustfmt::skip]
+        let bytes = [
+            tag::OBJECT, 2,
+            1, b'a', tag::ARRAY, 3,
+            tag::NUMBER, 0, 0, 0, 0, 0, 0, 0xf0, 0x3f,
+            tag::ARRAY_BUFFER, 1, 9,
+            tag::REFERENCE, 2,
+            1, b'b', tag::STRING, 2, 0xc3, 0xa9,
+        ];
+        assert_eq!(value.encode(), bytes);
+        let decoded = SerializedValue::decode(&bytes).unwrap();
+        assert_eq!(decoded, value);
+        assert_eq!(decoded.encode(), bytes);
+
+        // Lengths of 128 and more take several bytes.
+        let long = SerializedValue::ArrayBuffer(vec![1; 300]);
+        let bytes = long.encode();
+        assert_eq!(bytes[..3], [tag::ARRAY_BUFFER, 0xac, 0x02]);
+        assert_eq!(SerializedValue::decode(&bytes).unwrap(), long);
+    }
+
+    #[test]
+    fn malformed_input_is_rejected() {
+        let cases: &[(&str, &[u8])] = &[
+            ("empty input", &[]),
+            ("trailing bytes", &[tag::NULL, tag::NULL]),
+            ("unknown tag", &[11]),
+            ("truncated number", &[tag::NUMBER, 0, 0, 0]),
+            ("truncated string", &[tag::STRING, 3, b'a']),
+            ("missing items", &[tag::ARRAY, 2, tag::NULL]),
+            ("invalid UTF-8", &[tag::STRING, 1, 0xff]),
+            (
+                "length overflow",
+                &[
+                    tag::STRING,
+                    0xff,
+                    0xff,
+                    0xff,
+                    0xff,
+                    0xff,
+                    0xff,
+                    0xff,
+                    0xff,
+                    0xff,
+                    0xff,
+                    0x01,
+                ],
+            ),
+            ("unterminated length", &[tag::ARRAY, 0x80]),
+            ("length not in its shortest form", &[tag::ARRAY, 0x80, 0x00]),
+            ("reference before its object", &[tag::REFERENCE, 0]),
+            (
+                "reference to a primitive",
+                &[tag::ARRAY, 2, tag::NULL, tag::REFERENCE, 1],
+            ),
+        ];
+        for (case, bytes) in cases {
+            assert!(
+                is_data_clone_error(SerializedValue::decode(bytes)),
+                "{case} was decoded"
+            );
+        }
+
+        let too_deep = [tag::ARRAY, 1].repeat(MAX_DEPTH + 1);
+        assert!(is_data_clone_error(SerializedValue::decode(&too_deep)));
+        assert!(is_data_clone_error(structured_deserialize(
+            &SerializedValue::Reference(0)
+        )));
+    }
+}
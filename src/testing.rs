@@ -0,0 +1,32 @@
+//! Fixtures shared by unit tests.
+
+use wasmtime::{Engine, Store};
+
+use crate::{Document, Element, Node, browsing_context::BrowsingContext, user_agent::UserAgent};
+
+/// The HTML namespace.
+pub(crate) const HTML: &str = "http://www.w3.org/1999/xhtml";
+
+/// Create a top-level browsing context and return its document, which has `<html>`, `<head>` and
+/// `<body>`, along with the store holding the DOM.
+pub(crate) fn document() -> (UserAgent, Store<()>, Document) {
+    let mut user_agent = UserAgent::new(Engine::default());
+    let mut store = Store::<()>::default();
+    let (_, _, document) = BrowsingContext::new_top_browsing_context(&mut user_agent, &mut store);
+    (user_agent, store, document)
+}
+
+/// Create an HTML element named `name` in `document`.
+pub(crate) fn element(document: &Document, name: &str, store: &mut Store<()>) -> Element {
+    document
+        .create_element_ns(Some(HTML.into()), name.into(), store)
+        .expect("failed to create an element")
+}
+
+/// Create a Text node with `data` in `document`.
+pub(crate) fn text(document: &Document, data: &str, store: &mut Store<()>) -> Node {
+    document
+        .create_text_node(data.into(), store)
+        .expect("failed to create a Text node")
+        .into()
+}
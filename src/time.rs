@@ -0,0 +1,78 @@
+//! Moments on the shared monotonic clock of the High Resolution Time specification.
+
+use std::{sync::LazyLock, time::Instant};
+
+/// <https://w3c.github.io/hr-time/#dom-domhighrestimestamp>
+///
+/// A time in milliseconds.
+pub type DOMHighResTimeStamp = f64;
+
+/// <https://w3c.github.io/hr-time/#dfn-estimated-monotonic-time-of-the-unix-epoch>
+///
+/// Timestamps are relative to the instant the clock is first read instead of the Unix epoch,
+/// since `Instant` can't be related to the wall clock.
+static CLOCK_ORIGIN: LazyLock<Instant> = LazyLock::new(Instant::now);
+
+/// <https://w3c.github.io/hr-time/#dfn-unsafe-shared-current-time>
+pub fn unsafe_shared_current_time() -> Instant {
+    // Start the clock before reading it, so the moment is never before the origin.
+    LazyLock::force(&CLOCK_ORIGIN);
+    Instant::now()
+}
+
+/// <https://w3c.github.io/hr-time/#dfn-coarsen-time>
+///
+/// This returns the coarsened duration from the origin of the shared clock to `timestamp`.
+pub fn coarsen_time(
+    timestamp: Instant,
+    cross_origin_isolated_capability: bool,
+) -> DOMHighResTimeStamp {
+    // 1. Let time resolution be 100 microseconds, or 5 microseconds if
+    // crossOriginIsolatedCapability is true.
+    let resolution: u128 = if cross_origin_isolated_capability {
+        5_000
+    } else {
+        100_000
+    };
+    // 2. Let coarse time be the result of calling some implementation-defined coarsening function
+    // on timestamp with time resolution, which is rounding down to a multiple of the resolution
+    // here.
+    let nanos = timestamp
+        .saturating_duration_since(*CLOCK_ORIGIN)
+        .as_nanos();
+    let coarse_time = nanos - nanos % resolution;
+    // 3. Return coarse time.
+    coarse_time as f64 / 1_000_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn coarsen_time_rounds_down_to_the_resolution() {
+        let at = |nanos| *CLOCK_ORIGIN + Duration::from_nanos(nanos);
+        assert_eq!(coarsen_time(at(123_456_789), true), 123.455);
+        assert_eq!(coarsen_time(at(123_456_789), false), 123.4);
+        // Values already on the grid are kept.
+        assert_eq!(coarsen_time(at(5_000), true), 0.005);
+        assert_eq!(coarsen_time(at(100_000), false), 0.1);
+        // Anything below one step is zero.
+        assert_eq!(coarsen_time(at(4_999), true), 0.0);
+        assert_eq!(coarsen_time(at(99_999), false), 0.0);
+    }
+
+    #[test]
+    fn coarsen_time_starts_at_the_clock_origin() {
+        let now = unsafe_shared_current_time();
+        assert!(now >= *CLOCK_ORIGIN);
+        // Moments before the origin saturate to zero.
+        if let Some(before) = CLOCK_ORIGIN.checked_sub(Duration::from_millis(1)) {
+            assert_eq!(coarsen_time(before, true), 0.0);
+        }
+        let later = unsafe_shared_current_time();
+        assert!(coarsen_time(later, true) >= coarsen_time(now, true));
+    }
+}
@@ -1,20 +1,22 @@
 //! URL related types
-use std::cell::RefCell;
+use std::borrow::Cow;
 use std::collections::hash_map::DefaultHasher;
 use std::fmt;
 use std::hash::Hasher;
 use std::net::IpAddr;
 use std::ops::{Index, Range, RangeFrom, RangeFull, RangeTo};
 use std::path::Path;
-use std::rc::Rc;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 use malloc_size_of::malloc_size_of_is_0;
 use malloc_size_of_derive::MallocSizeOf;
+use percent_encoding::{AsciiSet, CONTROLS, percent_decode_str, utf8_percent_encode};
 pub use url::Host;
 use url::{Origin, Position, Url};
 use uuid::Uuid;
 
+use crate::{browsing_context::obtain_site, user_agent::UserAgent};
+
 const DATA_URL_DISPLAY_LENGTH: usize = 40;
 
 /// Error type of `DOMUrl`.
@@ -32,6 +34,63 @@ pub enum UrlError {
     FromFilePath,
 }
 
+/// <https://url.spec.whatwg.org/#fragment-percent-encode-set>
+const FRAGMENT: &AsciiSet = &CONTROLS.add(b' ').add(b'"').add(b'<').add(b'>').add(b'`');
+
+/// <https://url.spec.whatwg.org/#query-percent-encode-set>
+const QUERY: &AsciiSet = &CONTROLS.add(b' ').add(b'"').add(b'#').add(b'<').add(b'>');
+
+/// <https://url.spec.whatwg.org/#path-percent-encode-set>
+const PATH: &AsciiSet = &QUERY.add(b'?').add(b'^').add(b'`').add(b'{').add(b'}');
+
+/// <https://url.spec.whatwg.org/#userinfo-percent-encode-set>
+const USERINFO: &AsciiSet = &PATH
+    .add(b'/')
+    .add(b':')
+    .add(b';')
+    .add(b'=')
+    .add(b'@')
+    .add(b'[')
+    .add(b'\\')
+    .add(b']')
+    .add(b'^')
+    .add(b'|');
+
+/// <https://url.spec.whatwg.org/#component-percent-encode-set>
+const COMPONENT: &AsciiSet = &USERINFO.add(b'$').add(b'%').add(b'&').add(b'+').add(b',');
+
+/// The percent-encode sets defined by the URL Standard.
+///
+/// Code points above U+007E are percent-encoded by every set.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EncodeSet {
+    /// <https://url.spec.whatwg.org/#c0-control-percent-encode-set>
+    C0Control,
+    /// <https://url.spec.whatwg.org/#fragment-percent-encode-set>
+    Fragment,
+    /// <https://url.spec.whatwg.org/#query-percent-encode-set>
+    Query,
+    /// <https://url.spec.whatwg.org/#path-percent-encode-set>
+    Path,
+    /// <https://url.spec.whatwg.org/#userinfo-percent-encode-set>
+    Userinfo,
+    /// <https://url.spec.whatwg.org/#component-percent-encode-set>
+    Component,
+}
+
+impl EncodeSet {
+    fn ascii_set(self) -> &'static AsciiSet {
+        match self {
+            EncodeSet::C0Control => CONTROLS,
+            EncodeSet::Fragment => FRAGMENT,
+            EncodeSet::Query => QUERY,
+            EncodeSet::Path => PATH,
+            EncodeSet::Userinfo => USERINFO,
+            EncodeSet::Component => COMPONENT,
+        }
+    }
+}
+
 /// A URL type used in DOM context.
 #[derive(Clone, Eq, Hash, MallocSizeOf, Ord, PartialEq, PartialOrd)]
 pub struct DOMUrl(#[conditional_malloc_size_of] Arc<Url>);
@@ -294,6 +353,21 @@ impl DOMUrl {
             .map_err(|_| UrlError::FromFilePath)
     }
 
+    /// <https://url.spec.whatwg.org/#string-utf-8-percent-encode>
+    ///
+    /// Percent-encode the UTF-8 bytes of `input` which are in the percent-encode `set`.
+    pub fn percent_encode(input: &str, set: EncodeSet) -> String {
+        utf8_percent_encode(input, set.ascii_set()).to_string()
+    }
+
+    /// <https://url.spec.whatwg.org/#string-percent-decode>
+    ///
+    /// Percent-decode `input`. Byte sequences which are not valid UTF-8 after decoding are
+    /// replaced with U+FFFD, the same as UTF-8 decode without BOM does.
+    pub fn percent_decode(input: &str) -> Cow<'_, str> {
+        percent_decode_str(input).decode_utf8_lossy()
+    }
+
     /// Return a non-standard shortened form of the URL. Mainly intended to be
     /// used for debug printing in a constrained space (e.g., thread names).
     pub fn debug_compact(&self) -> impl std::fmt::Display + '_ {
@@ -327,10 +401,34 @@ impl DOMUrl {
         }
     }
 
+    /// <https://html.spec.whatwg.org/multipage/#matches-about:blank>
+    ///
+    /// The query and fragment of the URL are ignored.
+    pub fn matches_about_blank(&self) -> bool {
+        self.matches_about("blank")
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#matches-about:srcdoc>
+    ///
+    /// The fragment of the URL is ignored, but its query must be null.
+    pub fn is_about_srcdoc(&self) -> bool {
+        self.matches_about("srcdoc") && self.query().is_none()
+    }
+
+    /// Check if the URL’s scheme is "about", its path contains the single string `path`, its
+    /// username and password are the empty string, and its host is null.
+    fn matches_about(&self, path: &str) -> bool {
+        self.scheme() == "about"
+            && self.path() == path
+            && self.username().is_empty()
+            && self.password().is_none_or(str::is_empty)
+            && self.host().is_none()
+    }
+
     /// <https://w3c.github.io/webappsec-secure-contexts/#potentially-trustworthy-url>
     pub fn is_potentially_trustworthy(&self) -> bool {
         // Step 1
-        if self.as_str() == "about:blank" || self.as_str() == "about:srcdoc" {
+        if self.matches_about_blank() || self.is_about_srcdoc() {
             return true;
         }
         // Step 2
@@ -338,8 +436,42 @@ impl DOMUrl {
             return true;
         }
         // Step 3
+        // The origin of a blob: or filesystem: URL is the origin of its inner URL. A malformed inner
+        // URL is not trustworthy.
+        if self.scheme() == "blob" || self.scheme() == "filesystem" {
+            return DOMUrl::parse(self.path())
+                .is_ok_and(|inner| inner.origin().is_potentially_trustworthy());
+        }
         self.origin().is_potentially_trustworthy()
     }
+
+    /// <https://w3c.github.io/FileAPI/#unicodeBlobURL>
+    ///
+    /// Generate a new `blob:` URL for `origin`, like `blob:https://example.com/{uuid}`.
+    pub fn new_blob(origin: &ImmutableOrigin) -> DOMUrl {
+        // 1. Let result be the empty string.
+        // 2. Append the string "blob:" to result.
+        // 3. Let settings be the current settings object.
+        // 4. Let origin be settings’s origin.
+        // 5. Let serialized be the ASCII serialization of origin.
+        // 6. If serialized is "null", set it to an implementation-defined value.
+        // 7. Append serialized to result.
+        // 8. Append U+0024 SOLIDUS (/) to result.
+        // 9. Generate a UUID [RFC4122] as a string and append it to result.
+        // 10. Return result.
+        let serialized = origin.ascii_serialization();
+        DOMUrl::parse(&format!("blob:{serialized}/{}", Uuid::new_v4()))
+            .expect("Failed to parse blob URL")
+    }
+
+    /// Get the UUID identifying a `blob:` URL in the blob URL store.
+    fn blob_id(&self) -> Option<Uuid> {
+        if self.scheme() != "blob" {
+            return None;
+        }
+        let id = self.path().rsplit('/').next()?;
+        Uuid::parse_str(id).ok()
+    }
 }
 
 impl fmt::Display for DOMUrl {
@@ -410,6 +542,74 @@ impl From<Arc<Url>> for DOMUrl {
     }
 }
 
+/// <https://w3c.github.io/FileAPI/#blob-url-entry>
+///
+/// The environment is represented by its origin, which is all resolution needs from it.
+#[derive(Debug)]
+pub(crate) struct BlobUrlEntry {
+    bytes: Arc<Vec<u8>>,
+    origin: ImmutableOrigin,
+}
+
+/// <https://w3c.github.io/FileAPI/#add-an-entry>
+///
+/// Store `bytes` in the blob URL store of `user_agent` and return a new `blob:` URL of `origin` to
+/// resolve them.
+pub fn register_blob(
+    user_agent: &mut UserAgent,
+    origin: &ImmutableOrigin,
+    bytes: Vec<u8>,
+) -> DOMUrl {
+    // 3. Let url be the result of generating a new blob URL.
+    let url = DOMUrl::new_blob(origin);
+    // 4. Let entry be a new blob URL entry consisting of object and environment.
+    // 5. Set store[url] to entry.
+    let id = url.blob_id().expect("Failed to get the id of blob URL");
+    user_agent.blob_url_store.insert(
+        id,
+        BlobUrlEntry {
+            bytes: Arc::new(bytes),
+            origin: origin.clone(),
+        },
+    );
+    // 6. Return url.
+    url
+}
+
+/// <https://w3c.github.io/FileAPI/#blob-url-resolve>
+///
+/// Get the bytes stored for a `blob:` URL, or `None` if it is not in the blob URL store of
+/// `user_agent`. If the `origin` of the environment resolving the URL is provided, it must be same
+/// origin with the one the URL was registered for.
+/// TODO: Partitioning by storage key.
+pub fn resolve_blob(
+    user_agent: &UserAgent,
+    url: &DOMUrl,
+    origin: Option<&ImmutableOrigin>,
+) -> Option<Arc<Vec<u8>>> {
+    let id = url.blob_id()?;
+    // 4. If store[url string] exists, then:
+    // 4.1 Let entry be store[url string].
+    let entry = user_agent.blob_url_store.get(&id)?;
+    // 4.2 Let isAuthorized be true.
+    // 4.3 If environment is not null, then set isAuthorized to true if environment’s origin is same
+    // origin with entry’s environment’s origin.
+    let is_authorized = origin.is_none_or(|origin| *origin == entry.origin);
+    // 4.4 If isAuthorized is false, then return failure.
+    // 4.5 Return entry.
+    is_authorized.then(|| entry.bytes.clone())
+}
+
+/// <https://w3c.github.io/FileAPI/#removeTheEntry>
+///
+/// Remove a `blob:` URL from the blob URL store of `user_agent`. Later resolutions of the URL return
+/// `None`.
+pub fn revoke_blob(user_agent: &mut UserAgent, url: &DOMUrl) {
+    if let Some(id) = url.blob_id() {
+        user_agent.blob_url_store.remove(&id);
+    }
+}
+
 /// The origin of an URL
 #[derive(Clone, Debug, Eq, Hash, MallocSizeOf, PartialEq)]
 pub enum ImmutableOrigin {
@@ -493,8 +693,11 @@ impl ImmutableOrigin {
     /// <https://w3c.github.io/webappsec-secure-contexts/#is-origin-trustworthy>
     pub fn is_potentially_trustworthy(&self) -> bool {
         // 1. If origin is an opaque origin return "Not Trustworthy"
-        if matches!(self, ImmutableOrigin::Opaque(_)) {
-            return false;
+        // Workers created from `data:` URLs inherit the secure context they were created in.
+        match self {
+            ImmutableOrigin::Opaque(OpaqueOrigin::SecureWorkerFromDataUrl(_)) => return true,
+            ImmutableOrigin::Opaque(_) => return false,
+            ImmutableOrigin::Tuple(..) => {}
         }
 
         if let ImmutableOrigin::Tuple(scheme, host, _) = self {
@@ -531,6 +734,96 @@ impl ImmutableOrigin {
     pub fn ascii_serialization(&self) -> String {
         self.clone().into_url_origin().ascii_serialization()
     }
+
+    /// <https://html.spec.whatwg.org/multipage/#unicode-serialisation-of-an-origin>
+    pub fn unicode_serialization(&self) -> String {
+        self.clone().into_url_origin().unicode_serialization()
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#same-site>
+    ///
+    /// TODO: Hosts are compared as is until registrable domains are supported by `obtain_site`.
+    pub fn same_site(&self, other: &ImmutableOrigin) -> bool {
+        // A and B are schemelessly same site, and A and B are either both opaque origins, or both
+        // tuple origins with the same scheme.
+        obtain_site(self) == obtain_site(other)
+    }
+
+    /// Parse the output of [`ImmutableOrigin::ascii_serialization`] back into an origin.
+    ///
+    /// `"null"` returns a new opaque origin, since an opaque origin’s identity can’t be recovered
+    /// from its serialization. Otherwise `serialization` must be exactly the serialization of a
+    /// tuple origin, like `https://example.com:8080`, or `None` is returned.
+    pub fn from_ascii_serialization(serialization: &str) -> Option<ImmutableOrigin> {
+        if serialization == "null" {
+            return Some(ImmutableOrigin::new_opaque());
+        }
+        let origin = Url::parse(serialization).ok()?.origin();
+        if !origin.is_tuple() || origin.ascii_serialization() != serialization {
+            return None;
+        }
+        Some(ImmutableOrigin::new(origin))
+    }
+}
+
+/// Parse an origin string like `https://example.com:8443` as the origin of the URL it represents,
+/// which is how `targetOrigin` of `postMessage` is parsed.
+pub fn parse_origin_string(input: &str) -> Result<ImmutableOrigin, url::ParseError> {
+    DOMUrl::parse(input).map(|url| url.origin())
+}
+
+/// <https://html.spec.whatwg.org/multipage/#is-a-registrable-domain-suffix-of-or-is-equal-to>
+pub fn is_registrable_domain_suffix_of_or_is_equal_to(
+    host_suffix_string: &str,
+    original_host: &Host,
+) -> bool {
+    // 1. If hostSuffixString is the empty string, then return false.
+    if host_suffix_string.is_empty() {
+        return false;
+    }
+    // 2. Let hostSuffix be the result of parsing hostSuffixString.
+    // 3. If hostSuffix is failure, then return false.
+    let Ok(host_suffix) = Host::parse(host_suffix_string) else {
+        return false;
+    };
+    // 4. If hostSuffix does not equal originalHost, then:
+    if host_suffix != *original_host {
+        // 4.1 If hostSuffix or originalHost is not a domain, then return false.
+        let (Host::Domain(host_suffix), Host::Domain(original_host)) =
+            (&host_suffix, original_host)
+        else {
+            return false;
+        };
+        // 4.2 If hostSuffix, prefixed by U+002E (.), does not exactly match the end of
+        // originalHost, then return false.
+        if !original_host
+            .strip_suffix(host_suffix.as_str())
+            .is_some_and(|prefix| prefix.ends_with('.'))
+        {
+            return false;
+        }
+        // 4.3 If any of the following are true:
+        // * hostSuffix equals hostSuffix’s public suffix; or
+        // * hostSuffix, prefixed by U+002E (.), matches the end of originalHost’s public suffix,
+        // then return false.
+        if host_suffix == public_suffix(host_suffix)
+            || public_suffix(original_host).ends_with(&format!(".{host_suffix}"))
+        {
+            return false;
+        }
+        // 4.4 Assert: originalHost’s registrable domain equals hostSuffix’s registrable domain.
+    }
+    // 5. Return true.
+    true
+}
+
+/// <https://url.spec.whatwg.org/#host-public-suffix>
+///
+/// The rules of the public suffix list are compiled into the `psl` crate. A domain no rule matches
+/// gets its last label, which is what the list’s default rule `*` gives.
+fn public_suffix(domain: &str) -> &str {
+    let domain = domain.strip_suffix('.').unwrap_or(domain);
+    psl::suffix_str(domain).unwrap_or(domain)
 }
 
 /// Opaque identifier for URLs that have file or other schemes
@@ -547,14 +840,14 @@ malloc_size_of_is_0!(OpaqueOrigin);
 
 /// A representation of an [origin](https://html.spec.whatwg.org/multipage/#origin-2).
 #[derive(Clone, Debug)]
-pub struct MutableOrigin(Rc<(ImmutableOrigin, RefCell<Option<Host>>)>);
+pub struct MutableOrigin(Arc<(ImmutableOrigin, RwLock<Option<Host>>)>);
 
 malloc_size_of_is_0!(MutableOrigin);
 
 impl MutableOrigin {
     /// Create a `MutableOrigin` from `ImmutableOrigin`.
     pub fn new(origin: ImmutableOrigin) -> MutableOrigin {
-        MutableOrigin(Rc::new((origin, RefCell::new(None))))
+        MutableOrigin(Arc::new((origin, RwLock::new(None))))
     }
 
     /// Get the reference of `ImmutableOrigin`.
@@ -590,8 +883,8 @@ impl MutableOrigin {
 
     /// Check if `other` has the same origin domain.
     pub fn same_origin_domain(&self, other: &MutableOrigin) -> bool {
-        if let Some(ref self_domain) = *(self.0).1.borrow() {
-            if let Some(ref other_domain) = *(other.0).1.borrow() {
+        if let Some(ref self_domain) = self.domain() {
+            if let Some(ref other_domain) = other.domain() {
                 self_domain == other_domain
                     && self.immutable().scheme() == other.immutable().scheme()
             } else {
@@ -604,17 +897,17 @@ impl MutableOrigin {
 
     /// Get the domain of the origin.
     pub fn domain(&self) -> Option<Host> {
-        (self.0).1.borrow().clone()
+        (self.0).1.read().unwrap().clone()
     }
 
     /// Set the domain of the origin.
     pub fn set_domain(&self, domain: Host) {
-        *(self.0).1.borrow_mut() = Some(domain);
+        *(self.0).1.write().unwrap() = Some(domain);
     }
 
     /// Check if the origin has domain.
     pub fn has_domain(&self) -> bool {
-        (self.0).1.borrow().is_some()
+        (self.0).1.read().unwrap().is_some()
     }
 
     /// Get the effective domain of the origin.
@@ -624,3 +917,197 @@ impl MutableOrigin {
             .map(|host| self.domain().unwrap_or_else(|| host.clone()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use wasmtime::Engine;
+
+    use super::*;
+
+    fn origin(url: &str) -> ImmutableOrigin {
+        DOMUrl::parse(url).unwrap().origin()
+    }
+
+    #[test]
+    fn blob_urls_resolve_for_their_origin() {
+        let mut user_agent = UserAgent::new(Engine::default());
+        let example = origin("https://example.com/");
+        let url = register_blob(&mut user_agent, &example, b"hello".to_vec());
+        assert_eq!(url.scheme(), "blob");
+        assert!(url.as_str().starts_with("blob:https://example.com/"));
+        assert_eq!(url.origin(), example);
+
+        assert_eq!(
+            resolve_blob(&user_agent, &url, None).as_deref(),
+            Some(&b"hello".to_vec())
+        );
+        assert!(resolve_blob(&user_agent, &url, Some(&example)).is_some());
+        let other = origin("https://example.org/");
+        assert!(resolve_blob(&user_agent, &url, Some(&other)).is_none());
+        let opaque = ImmutableOrigin::new_opaque();
+        assert!(resolve_blob(&user_agent, &url, Some(&opaque)).is_none());
+
+        revoke_blob(&mut user_agent, &url);
+        assert!(resolve_blob(&user_agent, &url, Some(&example)).is_none());
+    }
+
+    #[test]
+    fn blob_urls_of_opaque_origins_only_resolve_for_the_same_origin() {
+        let mut user_agent = UserAgent::new(Engine::default());
+        let origin = ImmutableOrigin::new_opaque();
+        let url = register_blob(&mut user_agent, &origin, Vec::new());
+        assert!(resolve_blob(&user_agent, &url, Some(&origin)).is_some());
+        let other = ImmutableOrigin::new_opaque();
+        assert!(resolve_blob(&user_agent, &url, Some(&other)).is_none());
+    }
+
+    #[test]
+    fn blob_url_stores_are_per_user_agent() {
+        let mut user_agent = UserAgent::new(Engine::default());
+        let other = UserAgent::new(Engine::default());
+        let example = origin("https://example.com/");
+        let url = register_blob(&mut user_agent, &example, b"hello".to_vec());
+        assert!(resolve_blob(&user_agent, &url, Some(&example)).is_some());
+        assert!(resolve_blob(&other, &url, Some(&example)).is_none());
+    }
+
+    #[test]
+    fn tuple_origins_round_trip_through_their_serialization() {
+        for url in [
+            "https://example.com/a?b#c",
+            "http://example.com:8080/",
+            "http://[::1]:3000/",
+        ] {
+            let origin = origin(url);
+            let serialization = origin.ascii_serialization();
+            assert_eq!(
+                ImmutableOrigin::from_ascii_serialization(&serialization),
+                Some(origin)
+            );
+        }
+        // Only the exact serialization of a tuple origin is accepted.
+        for input in [
+            "https://example.com/",
+            "https://example.com:443",
+            "HTTPS://example.com",
+            "data:text/plain,x",
+            "",
+        ] {
+            assert_eq!(ImmutableOrigin::from_ascii_serialization(input), None);
+        }
+    }
+
+    #[test]
+    fn null_parses_to_a_new_opaque_origin() {
+        let first = ImmutableOrigin::from_ascii_serialization("null").unwrap();
+        let second = ImmutableOrigin::from_ascii_serialization("null").unwrap();
+        assert!(!first.is_tuple());
+        assert_eq!(first.ascii_serialization(), "null");
+        // The identity of an opaque origin is not kept in its serialization.
+        assert_ne!(first, second);
+        assert_ne!(first, ImmutableOrigin::new_opaque());
+    }
+
+    #[test]
+    fn public_suffixes_are_not_registrable_domain_suffixes() {
+        let cases = [
+            ("example.com", "www.example.com", true),
+            ("example.com", "example.com", true),
+            ("com", "example.com", false),
+            ("ample.com", "example.com", false),
+            ("example.co.uk", "www.example.co.uk", true),
+            ("co.uk", "example.co.uk", false),
+            ("uk", "example.co.uk", false),
+            ("", "example.com", false),
+        ];
+        for (suffix, host, expected) in cases {
+            let host = Host::parse(host).unwrap();
+            assert_eq!(
+                is_registrable_domain_suffix_of_or_is_equal_to(suffix, &host),
+                expected,
+                "{suffix:?} of {host}"
+            );
+        }
+    }
+
+    #[test]
+    fn about_blank_ignores_the_query_and_fragment() {
+        let cases = [
+            ("about:blank", true),
+            ("about:blank#frag", true),
+            ("about:blank?q", true),
+            ("about:blank?q#frag", true),
+            // The scheme is ASCII lowercased by the parser, but the path is not.
+            ("ABOUT:blank", true),
+            ("about:BLANK", false),
+            ("about:blank/", false),
+            ("about://host/blank", false),
+            ("about:srcdoc", false),
+            ("https://example.com/blank", false),
+        ];
+        for (url, expected) in cases {
+            let url = DOMUrl::parse(url).unwrap();
+            assert_eq!(url.matches_about_blank(), expected, "{url}");
+        }
+    }
+
+    #[test]
+    fn about_srcdoc_ignores_only_the_fragment() {
+        let cases = [
+            ("about:srcdoc", true),
+            ("about:srcdoc#frag", true),
+            ("ABOUT:srcdoc", true),
+            ("about:srcdoc?q", false),
+            ("about:srcdoc?", false),
+            ("about:blank", false),
+        ];
+        for (url, expected) in cases {
+            let url = DOMUrl::parse(url).unwrap();
+            assert_eq!(url.is_about_srcdoc(), expected, "{url}");
+        }
+    }
+
+    #[test]
+    fn origins_serialize_without_default_ports() {
+        assert_eq!(ImmutableOrigin::new_opaque().ascii_serialization(), "null");
+        assert_eq!(
+            ImmutableOrigin::new_opaque().unicode_serialization(),
+            "null"
+        );
+        let https = origin("https://example.com:443/");
+        assert_eq!(https.ascii_serialization(), "https://example.com");
+        assert_eq!(https.unicode_serialization(), "https://example.com");
+        let http = origin("http://example.com:443/");
+        assert_eq!(http.ascii_serialization(), "http://example.com:443");
+    }
+
+    #[test]
+    fn only_the_ascii_serialization_punycodes_hosts() {
+        let idna = origin("https://b\u{FC}cher.example/");
+        assert_eq!(idna.ascii_serialization(), "https://xn--bcher-kva.example");
+        assert_eq!(idna.unicode_serialization(), "https://b\u{FC}cher.example");
+    }
+
+    #[test]
+    fn same_site_ignores_ports_but_not_schemes() {
+        let example = origin("https://example.com/");
+        assert!(example.same_site(&origin("https://example.com:8443/")));
+        assert!(!example.same_site(&origin("http://example.com/")));
+        assert!(!example.same_site(&origin("https://example.org/")));
+
+        let opaque = ImmutableOrigin::new_opaque();
+        assert!(opaque.same_site(&opaque.clone()));
+        assert!(!opaque.same_site(&ImmutableOrigin::new_opaque()));
+        assert!(!opaque.same_site(&example));
+    }
+
+    #[test]
+    fn data_url_worker_origins_are_opaque_but_potentially_trustworthy() {
+        let worker = ImmutableOrigin::new_opaque_data_url_worker();
+        assert!(!worker.is_tuple());
+        assert_eq!(worker.ascii_serialization(), "null");
+        assert!(worker.is_potentially_trustworthy());
+        assert_ne!(worker, ImmutableOrigin::new_opaque_data_url_worker());
+        assert!(!ImmutableOrigin::new_opaque().is_potentially_trustworthy());
+    }
+}
@@ -0,0 +1,360 @@
+//! The user agent hosting the windows of an embedder.
+
+use std::{collections::HashMap, fmt};
+
+use uuid::Uuid;
+use wasmtime::{Engine, Store};
+
+use crate::{
+    DomException, WindowStates,
+    agent::{Agent, AgentID, Realm, RealmID},
+    browsing_context::{
+        BrowsingContext, BrowsingContextGroup, BrowsingContextGroupID, BrowsingContextID,
+    },
+    navigible::{Navigable, NavigableID, NavigableObserver},
+    structured_clone::SerializedValue,
+    url::BlobUrlEntry,
+};
+
+/// <https://infra.spec.whatwg.org/#user-agent>
+///
+/// Each window of the user agent is a top-level traversable with its own `Store`, whose data is
+/// the [`WindowStates`] of the window. DOM objects live in the store of their window, so they are
+/// never reachable from another window.
+///
+/// It also owns the sets and maps the specifications keep per user agent, so two `UserAgent`s are
+/// independent of each other.
+pub struct UserAgent {
+    engine: Engine,
+    windows: HashMap<NavigableID, Store<WindowStates>>,
+    /// <https://html.spec.whatwg.org/multipage/#browsing-context-set>
+    pub(crate) browsing_context_set: HashMap<BrowsingContextID, BrowsingContext>,
+    /// <https://html.spec.whatwg.org/multipage/#browsing-context-group-set>
+    pub(crate) browsing_context_group_set: HashMap<BrowsingContextGroupID, BrowsingContextGroup>,
+    /// <https://html.spec.whatwg.org/multipage/#top-level-traversable-set>
+    pub(crate) top_level_traversable_set: HashMap<NavigableID, Navigable>,
+    /// The observers notified when a navigable is created.
+    pub(crate) navigable_observers: Vec<Box<dyn NavigableObserver>>,
+    /// <https://html.spec.whatwg.org/multipage/#relevant-agent>
+    pub(crate) agents: HashMap<AgentID, Agent>,
+    /// <https://html.spec.whatwg.org/multipage/#concept-relevant-realm>
+    pub(crate) realms: HashMap<RealmID, Realm>,
+    /// <https://w3c.github.io/FileAPI/#BlobURLStore>
+    pub(crate) blob_url_store: HashMap<Uuid, BlobUrlEntry>,
+}
+
+impl UserAgent {
+    /// Create a `UserAgent` whose windows use `engine`.
+    pub fn new(engine: Engine) -> Self {
+        Self {
+            engine,
+            windows: HashMap::new(),
+            browsing_context_set: HashMap::new(),
+            browsing_context_group_set: HashMap::new(),
+            top_level_traversable_set: HashMap::new(),
+            navigable_observers: Vec::new(),
+            agents: HashMap::new(),
+            realms: HashMap::new(),
+            blob_url_store: HashMap::new(),
+        }
+    }
+
+    /// Get the `Engine` used by the windows.
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    /// Register `observer` to be notified when a navigable is created.
+    pub fn add_navigable_observer(&mut self, observer: Box<dyn NavigableObserver>) {
+        self.navigable_observers.push(observer);
+    }
+
+    /// Get the IDs of the top-level traversables in the user agent's top-level traversable set.
+    pub fn top_level_traversables(&self) -> impl Iterator<Item = NavigableID> + '_ {
+        self.top_level_traversable_set.keys().copied()
+    }
+
+    /// Open a new window with a fresh top-level traversable and return the ID of the traversable.
+    pub fn open_window(&mut self) -> NavigableID {
+        let states = WindowStates::create(self);
+        let id = states.traversable();
+        self.windows.insert(id, Store::new(&self.engine, states));
+        id
+    }
+
+    /// Open a new window from the window of the top-level traversable `opener`, and return the ID
+    /// of its top-level traversable.
+    ///
+    /// Its browsing context is an auxiliary browsing context in the browsing context group of the
+    /// opener, so the two windows can [post messages](Self::post_message) to each other. Returns
+    /// `None` if `opener` is not an open window.
+    pub fn open_window_with_opener(&mut self, opener: NavigableID) -> Option<NavigableID> {
+        let context = self.active_browsing_context(opener)?;
+        let states = WindowStates::create_auxiliary(self, opener, context);
+        let id = states.traversable();
+        self.windows.insert(id, Store::new(&self.engine, states));
+        Some(id)
+    }
+
+    /// Get the `Store` of the window of the top-level traversable `id`.
+    pub fn window(&self, id: NavigableID) -> Option<&Store<WindowStates>> {
+        self.windows.get(&id)
+    }
+
+    /// Get the mutable `Store` of the window of the top-level traversable `id`.
+    pub fn window_mut(&mut self, id: NavigableID) -> Option<&mut Store<WindowStates>> {
+        self.windows.get_mut(&id)
+    }
+
+    /// Close the window of the top-level traversable `id` and return its `Store`.
+    ///
+    /// The traversable is destroyed, which removes its browsing context and the realms of its
+    /// documents from the user agent. Once its browsing context group is empty, the group and the
+    /// agents of its agent clusters are removed too.
+    pub fn close_window(&mut self, id: NavigableID) -> Option<Store<WindowStates>> {
+        let window = self.windows.remove(&id)?;
+        Navigable::destroy_top_traversable(self, id, &window.data().store);
+        Some(window)
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#window-post-message-steps>
+    ///
+    /// Post `message` from the window of the top-level traversable `source` to the window of
+    /// `target`. The windows must be in the same browsing context group, since a window can only
+    /// reach the `WindowProxy` of another window in its group. Windows opened with
+    /// [`UserAgent::open_window_with_opener`] share the group of their opener. The message is delivered when the
+    /// target's embedder calls [`WindowStates::take_messages`].
+    pub fn post_message(
+        &mut self,
+        source: NavigableID,
+        target: NavigableID,
+        message: SerializedValue,
+        target_origin: &str,
+    ) -> Result<(), DomException> {
+        let group = |id| {
+            self.active_browsing_context(id)
+                .and_then(|context| self.browsing_context_set.get(&context))
+                .and_then(BrowsingContext::group)
+        };
+        let source_group = group(source);
+        if source_group.is_none() || source_group != group(target) {
+            return Err(DomException::NotFoundError(
+                "target window is not in the browsing context group of the source window".into(),
+            ));
+        }
+        let message = {
+            let store = &self.windows[&source];
+            store
+                .data()
+                .window()
+                .prepare_message(message, target_origin, store)?
+        };
+        let store = self
+            .windows
+            .get_mut(&target)
+            .expect("target window is checked above");
+        let window = store.data().window().clone();
+        window.queue_message(message, store);
+        Ok(())
+    }
+
+    /// Get the IDs of the top-level traversables of all windows.
+    pub fn windows(&self) -> impl Iterator<Item = NavigableID> + '_ {
+        self.windows.keys().copied()
+    }
+
+    /// Get the active browsing context of the window of the top-level traversable `id`.
+    fn active_browsing_context(&self, id: NavigableID) -> Option<BrowsingContextID> {
+        Navigable::top_level_active_document(self, id)
+            .zip(self.windows.get(&id))
+            .and_then(|(document, store)| document.browsing_context(store))
+    }
+}
+
+impl fmt::Debug for UserAgent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UserAgent")
+            .field("windows", &self.windows)
+            .field("browsing_context_set", &self.browsing_context_set)
+            .field(
+                "browsing_context_group_set",
+                &self.browsing_context_group_set,
+            )
+            .field("top_level_traversable_set", &self.top_level_traversable_set)
+            .field("navigable_observers", &self.navigable_observers.len())
+            .field("agents", &self.agents)
+            .field("realms", &self.realms)
+            .field("blob_url_store", &self.blob_url_store)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Node, testing::HTML, url::DOMUrl};
+
+    /// Replace the document of the window `id` with an empty document loaded from `url`.
+    fn load(user_agent: &mut UserAgent, id: NavigableID, url: &str) {
+        let states = user_agent.window_mut(id).unwrap().data_mut();
+        let document = states.window.document(&states.store).unwrap();
+        let loaded = document
+            .parse_html_document(b"", DOMUrl::parse(url).unwrap(), &mut states.store)
+            .unwrap();
+        states.window.set_document(loaded, &mut states.store);
+    }
+
+    #[test]
+    fn windows_do_not_share_their_dom() {
+        let mut user_agent = UserAgent::new(Engine::default());
+        let first = user_agent.open_window();
+        let second = user_agent.open_window();
+        assert_ne!(first, second);
+
+        let states = user_agent.window_mut(first).unwrap().data_mut();
+        let document = states.window.document(&states.store).unwrap();
+        let paragraph = document
+            .create_element_ns(Some(HTML.into()), "p".into(), &mut states.store)
+            .unwrap();
+        let body = Node::from(document.body(&states.store).unwrap());
+        body.append_child(paragraph.into(), &mut states.store);
+        assert_eq!(body.child_nodes(&states.store).len(), 1);
+
+        // The other window has its own store and document, which the mutation didn't reach.
+        let states = user_agent.window(second).unwrap().data();
+        let document = states.window.document(&states.store).unwrap();
+        let body = Node::from(document.body(&states.store).unwrap());
+        assert!(body.child_nodes(&states.store).is_empty());
+
+        // Closing one window leaves the other open.
+        assert!(user_agent.close_window(first).is_some());
+        assert_eq!(user_agent.windows().collect::<Vec<_>>(), [second]);
+        assert!(user_agent.window(second).is_some());
+    }
+
+    #[test]
+    fn closing_windows_empties_the_registries() {
+        let mut user_agent = UserAgent::new(Engine::default());
+        for _ in 0..10 {
+            let windows = [user_agent.open_window(), user_agent.open_window()];
+            for window in windows {
+                assert!(user_agent.close_window(window).is_some());
+            }
+        }
+        assert_eq!(user_agent.windows().count(), 0);
+        assert!(user_agent.browsing_context_set.is_empty());
+        assert!(user_agent.browsing_context_group_set.is_empty());
+        assert!(user_agent.top_level_traversable_set.is_empty());
+        assert!(user_agent.agents.is_empty());
+        assert!(user_agent.realms.is_empty());
+    }
+
+    #[test]
+    fn opened_windows_share_the_group_of_their_opener() {
+        let mut user_agent = UserAgent::new(Engine::default());
+        let opener = user_agent.open_window();
+        let opened = user_agent.open_window_with_opener(opener).unwrap();
+        let other = user_agent.open_window();
+
+        let opener_context = user_agent.active_browsing_context(opener).unwrap();
+        let opened_context = user_agent.active_browsing_context(opened).unwrap();
+        let context = &user_agent.browsing_context_set[&opened_context];
+        assert_eq!(context.opener(), Some(opener_context));
+        assert_eq!(
+            context.group(),
+            user_agent.browsing_context_set[&opener_context].group()
+        );
+
+        // Windows of other groups are out of reach.
+        let error = user_agent
+            .post_message(opener, other, SerializedValue::Null, "*")
+            .unwrap_err();
+        assert!(matches!(error, DomException::NotFoundError(_)));
+        assert!(
+            user_agent
+                .open_window_with_opener(NavigableID(usize::MAX))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn messages_are_delivered_when_the_target_origin_matches() {
+        let mut user_agent = UserAgent::new(Engine::default());
+        let source = user_agent.open_window();
+        let target = user_agent.open_window_with_opener(source).unwrap();
+        load(&mut user_agent, source, "https://sender.example/page");
+        load(&mut user_agent, target, "https://receiver.example/page");
+
+        let message = SerializedValue::String("hello".into());
+        user_agent
+            .post_message(source, target, message.clone(), "https://receiver.example")
+            .unwrap();
+        let events = user_agent
+            .window_mut(target)
+            .unwrap()
+            .data_mut()
+            .take_messages();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data(), &message);
+        assert_eq!(
+            events[0].source(),
+            user_agent.active_browsing_context(source)
+        );
+    }
+
+    #[test]
+    fn messages_are_dropped_when_the_target_origin_mismatches() {
+        let mut user_agent = UserAgent::new(Engine::default());
+        let source = user_agent.open_window();
+        let target = user_agent.open_window_with_opener(source).unwrap();
+        load(&mut user_agent, source, "https://sender.example/page");
+        load(&mut user_agent, target, "https://receiver.example/page");
+
+        for target_origin in [
+            "https://other.example",
+            "https://receiver.example:8443",
+            "/",
+        ] {
+            user_agent
+                .post_message(source, target, SerializedValue::Null, target_origin)
+                .unwrap();
+        }
+        let events = user_agent
+            .window_mut(target)
+            .unwrap()
+            .data_mut()
+            .take_messages();
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn message_events_carry_the_origin_of_the_sender() {
+        let mut user_agent = UserAgent::new(Engine::default());
+        let source = user_agent.open_window();
+        let target = user_agent.open_window_with_opener(source).unwrap();
+        load(&mut user_agent, source, "https://sender.example:8080/page");
+        load(&mut user_agent, target, "https://receiver.example/page");
+
+        user_agent
+            .post_message(source, target, SerializedValue::Null, "*")
+            .unwrap();
+        // And the other way around, to the opener.
+        user_agent
+            .post_message(target, source, SerializedValue::Null, "*")
+            .unwrap();
+        let events = user_agent
+            .window_mut(target)
+            .unwrap()
+            .data_mut()
+            .take_messages();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].origin(), "https://sender.example:8080");
+        let events = user_agent
+            .window_mut(source)
+            .unwrap()
+            .data_mut()
+            .take_messages();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].origin(), "https://receiver.example");
+    }
+}
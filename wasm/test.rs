@@ -1,4 +1,4 @@
-use ohim::dom::node::Document;
+use ohim::dom::window::Window;
 
 // cargo component build
 wit_bindgen::generate!({
@@ -13,7 +13,7 @@ export!(GuestComponent);
 impl Guest for GuestComponent {
     #[allow(async_fn_in_trait)]
     fn test() -> String {
-        let document = Document::new();
+        let document = Window::current().document();
         let element = document.document_element();
         format!(
             "Document has url: {} with element has attributes: {}",